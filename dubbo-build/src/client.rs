@@ -15,7 +15,7 @@
  * limitations under the License.
  */
 
-use super::{generate_doc_comments, naive_snake_case, Attributes};
+use super::{generate_doc_comment, generate_doc_comments, naive_snake_case, Attributes};
 use crate::{Method, Service};
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
@@ -82,6 +82,21 @@ pub fn generate<T: Service>(
                     }
                 }
 
+                pub fn from_reference(name: &str) -> Result<Self, dubbo::config::consumer::ReferenceError> {
+                    Ok(Self::new(ClientBuilder::from_reference(name)?))
+                }
+
+                /// Run `interceptor` on the metadata of each call, it may add
+                /// headers or reject the call.
+                pub fn with_interceptor<F>(self, interceptor: F) -> Self
+                where
+                    F: Filter + Send + 'static,
+                {
+                    Self {
+                        inner: self.inner.with_interceptor(interceptor),
+                    }
+                }
+
                 #methods
 
             }
@@ -160,6 +175,11 @@ fn generate_unary<T: Method>(
     path: String,
 ) -> TokenStream {
     let ident = format_ident!("{}", method.name());
+    let with_options = format_ident!("{}_with_options", method.name());
+    let options_doc = generate_doc_comment(format!(
+        "Like [`Self::{}`], with the options of this call.",
+        method.name()
+    ));
     let (request, response) = method.request_response_name(proto_path, compile_well_known_types);
     let method_name = method.identifier();
 
@@ -168,11 +188,21 @@ fn generate_unary<T: Method>(
             &mut self,
             request: Request<#request>,
         ) -> Result<Response<#response>, dubbo::status::Status> {
-           let invocation = RpcInvocation::default()
-            .with_service_unique_name(String::from(#service_unique_name))
-            .with_method_name(String::from(#method_name));
-           let path = http::uri::PathAndQuery::from_static(#path);
-           self.inner.unary(
+            self.#with_options(request, CallOptions::default()).await
+        }
+
+        #options_doc
+        pub async fn #with_options(
+            &mut self,
+            mut request: Request<#request>,
+            options: CallOptions,
+        ) -> Result<Response<#response>, dubbo::status::Status> {
+            request.extensions_mut().insert(options);
+            let invocation = RpcInvocation::default()
+             .with_service_unique_name(String::from(#service_unique_name))
+             .with_method_name(String::from(#method_name));
+            let path = http::uri::PathAndQuery::from_static(#path);
+            self.inner.unary(
                 request,
                 path,
                 invocation,
@@ -189,6 +219,11 @@ fn generate_server_streaming<T: Method>(
     path: String,
 ) -> TokenStream {
     let ident = format_ident!("{}", method.name());
+    let with_options = format_ident!("{}_with_options", method.name());
+    let options_doc = generate_doc_comment(format!(
+        "Like [`Self::{}`], with the options of this call.",
+        method.name()
+    ));
     let (request, response) = method.request_response_name(proto_path, compile_well_known_types);
     let method_name = method.identifier();
 
@@ -197,6 +232,16 @@ fn generate_server_streaming<T: Method>(
             &mut self,
             request: Request<#request>,
         ) -> Result<Response<Decoding<#response>>, dubbo::status::Status> {
+            self.#with_options(request, CallOptions::default()).await
+        }
+
+        #options_doc
+        pub async fn #with_options(
+            &mut self,
+            mut request: Request<#request>,
+            options: CallOptions,
+        ) -> Result<Response<Decoding<#response>>, dubbo::status::Status> {
+            request.extensions_mut().insert(options);
             let invocation = RpcInvocation::default()
              .with_service_unique_name(String::from(#service_unique_name))
              .with_method_name(String::from(#method_name));
@@ -218,14 +263,30 @@ fn generate_client_streaming<T: Method>(
     path: String,
 ) -> TokenStream {
     let ident = format_ident!("{}", method.name());
+    let with_options = format_ident!("{}_with_options", method.name());
+    let options_doc = generate_doc_comment(format!(
+        "Like [`Self::{}`], with the options of this call.",
+        method.name()
+    ));
     let (request, response) = method.request_response_name(proto_path, compile_well_known_types);
     let method_name = method.identifier();
 
     quote! {
         pub async fn #ident(
             &mut self,
-            request: impl IntoStreamingRequest<Message = #request>
+            request: impl IntoStreamingRequest<Message = #request>,
         ) -> Result<Response<#response>, dubbo::status::Status> {
+            self.#with_options(request, CallOptions::default()).await
+        }
+
+        #options_doc
+        pub async fn #with_options(
+            &mut self,
+            request: impl IntoStreamingRequest<Message = #request>,
+            options: CallOptions,
+        ) -> Result<Response<#response>, dubbo::status::Status> {
+            let mut request = request.into_streaming_request();
+            request.extensions_mut().insert(options);
             let invocation = RpcInvocation::default()
              .with_service_unique_name(String::from(#service_unique_name))
              .with_method_name(String::from(#method_name));
@@ -247,14 +308,30 @@ fn generate_streaming<T: Method>(
     path: String,
 ) -> TokenStream {
     let ident = format_ident!("{}", method.name());
+    let with_options = format_ident!("{}_with_options", method.name());
+    let options_doc = generate_doc_comment(format!(
+        "Like [`Self::{}`], with the options of this call.",
+        method.name()
+    ));
     let (request, response) = method.request_response_name(proto_path, compile_well_known_types);
     let method_name = method.identifier();
 
     quote! {
         pub async fn #ident(
             &mut self,
-            request: impl IntoStreamingRequest<Message = #request>
+            request: impl IntoStreamingRequest<Message = #request>,
+        ) -> Result<Response<Decoding<#response>>, dubbo::status::Status> {
+            self.#with_options(request, CallOptions::default()).await
+        }
+
+        #options_doc
+        pub async fn #with_options(
+            &mut self,
+            request: impl IntoStreamingRequest<Message = #request>,
+            options: CallOptions,
         ) -> Result<Response<Decoding<#response>>, dubbo::status::Status> {
+            let mut request = request.into_streaming_request();
+            request.extensions_mut().insert(options);
             let invocation = RpcInvocation::default()
              .with_service_unique_name(String::from(#service_unique_name))
              .with_method_name(String::from(#method_name));
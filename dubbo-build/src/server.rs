@@ -159,6 +159,37 @@ pub fn generate<T: Service>(
                         dubbo::utils::boxed_clone::BoxCloneService::new(s),
                     );
             }
+
+            /// Register `server` as the `version` of the service in `group`,
+            /// several of them can be exported on the same port.
+            pub fn register_versioned_server<T: #server_trait>(server: T, group: &str, version: &str) {
+                let s = #server_service::new(server);
+                let key = dubbo::protocol::triple::service_key::ServiceKey::new(#service_name)
+                    .with_group(group)
+                    .with_version(version);
+                dubbo::protocol::triple::register_service(
+                    &key,
+                    dubbo::utils::boxed_clone::BoxCloneService::new(s),
+                );
+            }
+
+            /// Register `server` behind `interceptor`, run on the metadata of
+            /// each call before it reaches the service. It may add to the
+            /// extensions of the request or reject the call.
+            pub fn register_server_with_interceptor<T, F>(server: T, interceptor: F)
+            where
+                T: #server_trait,
+                F: Filter + Clone + Send + Sync + 'static,
+            {
+                let s = #server_service::with_filter(server, interceptor);
+                dubbo::protocol::triple::TRIPLE_SERVICES
+                    .write()
+                    .unwrap()
+                    .insert(
+                        #service_name.to_string(),
+                        dubbo::utils::boxed_clone::BoxCloneService::new(s),
+                    );
+            }
         }
     }
 }
@@ -0,0 +1,83 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use argh::FromArgs;
+use dubbo::StdError;
+use hyper::{Body, Client, Method, Request};
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "offline")]
+/// Unregister the providers of an application through its admin port.
+pub struct OfflineCommand {
+    /// admin address of the application, as in `127.0.0.1:22222`
+    #[argh(positional)]
+    addr: String,
+    /// interface or `group/interface:version` key of the service, all by default
+    #[argh(option)]
+    service: Option<String>,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "online")]
+/// Register again the providers taken offline.
+pub struct OnlineCommand {
+    /// admin address of the application, as in `127.0.0.1:22222`
+    #[argh(positional)]
+    addr: String,
+    /// interface or `group/interface:version` key of the service, all by default
+    #[argh(option)]
+    service: Option<String>,
+}
+
+impl OfflineCommand {
+    pub async fn run(self) -> Result<(), StdError> {
+        post(&self.addr, "offline", self.service.as_deref()).await
+    }
+}
+
+impl OnlineCommand {
+    pub async fn run(self) -> Result<(), StdError> {
+        post(&self.addr, "online", self.service.as_deref()).await
+    }
+}
+
+/// Post to the `path` endpoint of the admin server, printing the services
+/// changed.
+async fn post(addr: &str, path: &str, service: Option<&str>) -> Result<(), StdError> {
+    let mut uri = format!("http://{}/{}", addr, path);
+    if let Some(service) = service {
+        uri.push_str(&format!("?service={}", urlencoding::encode(service)));
+    }
+    let req = Request::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .body(Body::empty())?;
+    let resp = Client::new()
+        .request(req)
+        .await
+        .map_err(|err| format!("no admin server on {}: {}", addr, err))?;
+    let status = resp.status();
+    let body = hyper::body::to_bytes(resp.into_body()).await?;
+    let body = String::from_utf8_lossy(&body);
+    if !status.is_success() {
+        return Err(format!("{} failed: {} {}", path, status, body.trim()).into());
+    }
+    for service in body.lines() {
+        println!("{} {}", service, path);
+    }
+    Ok(())
+}
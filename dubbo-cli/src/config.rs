@@ -0,0 +1,86 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use argh::FromArgs;
+use dubbo::{
+    config::loader::{ConfigLoader, DUBBO_ENV_PREFIX},
+    StdError,
+};
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "config")]
+/// Inspect the configuration.
+pub struct ConfigCommand {
+    #[argh(subcommand)]
+    command: ConfigSubcommand,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum ConfigSubcommand {
+    Check(CheckCommand),
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "check")]
+/// Load the configuration and report every problem found.
+struct CheckCommand {
+    /// config file, repeatable, the files of DUBBO_CONFIG_PATH by default
+    #[argh(option, short = 'f')]
+    file: Vec<String>,
+    /// active profile, repeatable
+    #[argh(option, short = 'p')]
+    profile: Vec<String>,
+    /// override a key, as in `protocols.triple.port=8889`
+    #[argh(option)]
+    set: Vec<String>,
+}
+
+impl ConfigCommand {
+    pub async fn run(self) -> Result<(), StdError> {
+        match self.command {
+            ConfigSubcommand::Check(check) => check.run().await,
+        }
+    }
+}
+
+impl CheckCommand {
+    async fn run(self) -> Result<(), StdError> {
+        let mut loader = if self.file.is_empty() {
+            ConfigLoader::from_env()
+        } else {
+            self.file.into_iter().fold(
+                ConfigLoader::new().with_env(DUBBO_ENV_PREFIX),
+                |loader, file| loader.with_file(file),
+            )
+        };
+        for profile in self.profile {
+            loader = loader.with_profile(profile);
+        }
+        for set in self.set {
+            let (key, value) = set
+                .split_once('=')
+                .ok_or_else(|| format!("invalid --set {:?}, expected key=value", set))?;
+            loader = loader.with_override(key, value);
+        }
+
+        let config = loader.load()?;
+        config.validate_with_extensions().await?;
+        println!("config ok");
+        Ok(())
+    }
+}
@@ -0,0 +1,301 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use argh::FromArgs;
+use dubbo::{
+    codegen::ClientBuilder,
+    generic::{reflection::REFLECTION_SERVICE, Descriptors},
+    StdError,
+};
+use prost_reflect::{
+    DescriptorPool, EnumDescriptor, FieldDescriptor, Kind, MessageDescriptor, MethodDescriptor,
+};
+
+use crate::target;
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "describe")]
+/// Describe a service, method or message with its protobuf descriptors.
+pub struct DescribeCommand {
+    /// full name of a service, a message, or a method as in `pkg.Service/Method`
+    #[argh(positional)]
+    symbol: String,
+    /// descriptor set file, as written by `protoc --include_imports --descriptor_set_out`
+    #[argh(option)]
+    descriptor_set: Option<String>,
+    /// provider serving the server reflection, as in `127.0.0.1:8888`
+    #[argh(option)]
+    url: Option<String>,
+}
+
+impl DescribeCommand {
+    pub async fn run(self) -> Result<(), StdError> {
+        let descriptors = load_descriptors(
+            self.descriptor_set.as_deref(),
+            self.url.as_deref(),
+            &self.symbol,
+        )
+        .await?
+        .ok_or("one of --descriptor-set and --url is required")?;
+        let description = describe(descriptors.pool(), &self.symbol)
+            .ok_or_else(|| format!("symbol {} not found", self.symbol))?;
+        print!("{}", description);
+        Ok(())
+    }
+}
+
+/// Descriptors of the descriptor set file, or of `symbol` fetched from the
+/// server reflection of the provider at `url`.
+pub async fn load_descriptors(
+    descriptor_set: Option<&str>,
+    url: Option<&str>,
+    symbol: &str,
+) -> Result<Option<Descriptors>, StdError> {
+    if let Some(file) = descriptor_set {
+        return Ok(Some(Descriptors::from_file(file)?));
+    }
+    let Some(url) = url else {
+        return Ok(None);
+    };
+    let builder = ClientBuilder::from_static(&format!(
+        "{}?interface={}",
+        target::provider_url(url)?,
+        REFLECTION_SERVICE
+    ));
+    // the reflection knows services and messages, not methods
+    let symbol = split_method(symbol).map_or(symbol, |(service, _)| service);
+    Ok(Some(Descriptors::from_reflection(builder, symbol).await?))
+}
+
+/// Service and method of `pkg.Service/Method` or `pkg.Service.Method`.
+fn split_method(symbol: &str) -> Option<(&str, &str)> {
+    symbol.rsplit_once('/').or_else(|| symbol.rsplit_once('.'))
+}
+
+/// Method of `pkg.Service/Method` or `pkg.Service.Method`.
+fn find_method(pool: &DescriptorPool, symbol: &str) -> Option<MethodDescriptor> {
+    let (service, method) = split_method(symbol)?;
+    pool.get_service_by_name(service)?
+        .methods()
+        .find(|m| m.name() == method)
+}
+
+/// Proto like description of `symbol` and of the messages it uses.
+fn describe(pool: &DescriptorPool, symbol: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut messages = Vec::new();
+    if let Some(service) = pool.get_service_by_name(symbol) {
+        let _ = writeln!(out, "service {} {{", service.full_name());
+        for method in service.methods() {
+            let _ = writeln!(out, "  {}", rpc(&method));
+            messages.extend([method.input(), method.output()]);
+        }
+        let _ = writeln!(out, "}}");
+    } else if let Some(method) = find_method(pool, symbol) {
+        let _ = writeln!(out, "{}", rpc(&method));
+        messages.extend([method.input(), method.output()]);
+    } else if let Some(message) = pool.get_message_by_name(symbol) {
+        messages.push(message);
+    } else {
+        let enum_desc = pool.get_enum_by_name(symbol)?;
+        write_enum(&mut out, &enum_desc);
+        return Some(out);
+    }
+
+    // the messages and enums used, each once
+    let mut types = BTreeMap::new();
+    while let Some(message) = messages.pop() {
+        if types.contains_key(message.full_name()) {
+            continue;
+        }
+        let mut text = String::new();
+        write_message(&mut text, &message);
+        types.insert(message.full_name().to_string(), text);
+        for field in message.fields() {
+            match field_kind(&field) {
+                Kind::Message(message) => messages.push(message),
+                Kind::Enum(enum_desc) => {
+                    let mut text = String::new();
+                    write_enum(&mut text, &enum_desc);
+                    types.insert(enum_desc.full_name().to_string(), text);
+                }
+                _ => {}
+            }
+        }
+    }
+    for text in types.values() {
+        let _ = writeln!(out);
+        out.push_str(text);
+    }
+    Some(out)
+}
+
+fn rpc(method: &MethodDescriptor) -> String {
+    let stream = |streaming: bool| if streaming { "stream " } else { "" };
+    format!(
+        "rpc {}({}{}) returns ({}{});",
+        method.name(),
+        stream(method.is_client_streaming()),
+        method.input().full_name(),
+        stream(method.is_server_streaming()),
+        method.output().full_name()
+    )
+}
+
+fn write_message(out: &mut String, message: &MessageDescriptor) {
+    let _ = writeln!(out, "message {} {{", message.full_name());
+    for field in message.fields() {
+        let ty = if field.is_map() {
+            let Kind::Message(entry) = field.kind() else {
+                unreachable!("map fields are messages");
+            };
+            format!(
+                "map<{}, {}>",
+                kind_name(&entry.map_entry_key_field().kind()),
+                kind_name(&entry.map_entry_value_field().kind())
+            )
+        } else if field.is_list() {
+            format!("repeated {}", kind_name(&field.kind()))
+        } else {
+            kind_name(&field.kind())
+        };
+        let _ = writeln!(out, "  {} {} = {};", ty, field.name(), field.number());
+    }
+    let _ = writeln!(out, "}}");
+}
+
+fn write_enum(out: &mut String, enum_desc: &EnumDescriptor) {
+    let _ = writeln!(out, "enum {} {{", enum_desc.full_name());
+    for value in enum_desc.values() {
+        let _ = writeln!(out, "  {} = {};", value.name(), value.number());
+    }
+    let _ = writeln!(out, "}}");
+}
+
+/// Kind of the values of `field`, those of the map values for maps.
+fn field_kind(field: &FieldDescriptor) -> Kind {
+    match field.kind() {
+        Kind::Message(entry) if field.is_map() => entry.map_entry_value_field().kind(),
+        kind => kind,
+    }
+}
+
+fn kind_name(kind: &Kind) -> String {
+    match kind {
+        Kind::Message(message) => message.full_name().to_string(),
+        Kind::Enum(enum_desc) => enum_desc.full_name().to_string(),
+        scalar => format!("{:?}", scalar).to_lowercase(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use argh::FromArgs;
+    use prost_reflect::prost_types::{
+        field_descriptor_proto::{Label, Type},
+        DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+        MethodDescriptorProto, ServiceDescriptorProto,
+    };
+
+    use super::*;
+
+    /// Pool of `test.Greeter`, whose `SayHello` takes a `HelloRequest { name }`
+    /// and streams `HelloReply { message }`.
+    fn greeter() -> DescriptorPool {
+        let message = |name: &str, field: &str| DescriptorProto {
+            name: Some(name.to_string()),
+            field: vec![FieldDescriptorProto {
+                name: Some(field.to_string()),
+                number: Some(1),
+                label: Some(Label::Optional as i32),
+                r#type: Some(Type::String as i32),
+                json_name: Some(field.to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("greeter.proto".to_string()),
+            package: Some("test".to_string()),
+            message_type: vec![
+                message("HelloRequest", "name"),
+                message("HelloReply", "message"),
+            ],
+            service: vec![ServiceDescriptorProto {
+                name: Some("Greeter".to_string()),
+                method: vec![MethodDescriptorProto {
+                    name: Some("SayHello".to_string()),
+                    input_type: Some(".test.HelloRequest".to_string()),
+                    output_type: Some(".test.HelloReply".to_string()),
+                    server_streaming: Some(true),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            syntax: Some("proto3".to_string()),
+            ..Default::default()
+        };
+        DescriptorPool::from_file_descriptor_set(FileDescriptorSet { file: vec![file] }).unwrap()
+    }
+
+    #[test]
+    fn test_describe() {
+        let command = DescribeCommand::from_args(
+            &["describe"],
+            &["test.Greeter/SayHello", "--url", "127.0.0.1:8888"],
+        )
+        .unwrap();
+        assert_eq!(command.symbol, "test.Greeter/SayHello");
+        assert_eq!(command.url.as_deref(), Some("127.0.0.1:8888"));
+        assert!(command.descriptor_set.is_none());
+        assert!(DescribeCommand::from_args(&["describe"], &[]).is_err());
+
+        let pool = greeter();
+        let messages = "
+message test.HelloReply {
+  string message = 1;
+}
+
+message test.HelloRequest {
+  string name = 1;
+}
+";
+        assert_eq!(
+            describe(&pool, "test.Greeter").unwrap(),
+            format!(
+                "service test.Greeter {{\n  rpc SayHello(test.HelloRequest) returns (stream test.HelloReply);\n}}\n{}",
+                messages
+            )
+        );
+        for method in ["test.Greeter/SayHello", "test.Greeter.SayHello"] {
+            assert_eq!(
+                describe(&pool, method).unwrap(),
+                format!(
+                    "rpc SayHello(test.HelloRequest) returns (stream test.HelloReply);\n{}",
+                    messages
+                )
+            );
+        }
+        assert_eq!(
+            describe(&pool, "test.HelloRequest").unwrap(),
+            "\nmessage test.HelloRequest {\n  string name = 1;\n}\n"
+        );
+        assert!(describe(&pool, "test.Unknown").is_none());
+    }
+}
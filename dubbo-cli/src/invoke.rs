@@ -0,0 +1,246 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{io, time::Instant};
+
+use argh::FromArgs;
+use dubbo::{
+    codegen::{Metadata, Request},
+    generic::GenericClient,
+    StdError,
+};
+use serde_json::Value;
+
+use crate::{describe::load_descriptors, target};
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "invoke")]
+/// Call a method with json messages, printing the responses to stdout, and
+/// the headers, trailers and timings to stderr.
+pub struct InvokeCommand {
+    /// interface of the service
+    #[argh(positional)]
+    service: String,
+    /// name of the method
+    #[argh(positional)]
+    method: String,
+    /// request message as json, repeatable for streaming methods, `@` reading
+    /// them from stdin, `{}` by default
+    #[argh(option, short = 'd')]
+    data: Vec<String>,
+    /// provider url, as in `127.0.0.1:8888`
+    #[argh(option)]
+    url: Option<String>,
+    /// registry url finding the providers, as in `nacos://127.0.0.1:8848`
+    #[argh(option, short = 'r')]
+    registry: Option<String>,
+    /// group of the called service
+    #[argh(option, default = "String::new()")]
+    group: String,
+    /// version of the called service
+    #[argh(option, default = "String::new()")]
+    version: String,
+    /// attachment sent as a header, as in `-H key=value`, repeatable
+    #[argh(option, short = 'H')]
+    header: Vec<String>,
+    /// descriptor set file, sending protobuf instead of json
+    #[argh(option)]
+    descriptor_set: Option<String>,
+    /// fetch the descriptors from the server reflection of the --url provider
+    #[argh(switch)]
+    reflect: bool,
+    /// timeout of the call in milliseconds
+    #[argh(option)]
+    timeout: Option<u64>,
+}
+
+impl InvokeCommand {
+    pub async fn run(self) -> Result<(), StdError> {
+        let args = self.messages()?;
+        let mut metadata = Metadata::new();
+        for header in &self.header {
+            let (key, value) = header
+                .split_once('=')
+                .ok_or_else(|| format!("invalid header {:?}, expected key=value", header))?;
+            metadata = metadata.insert(key.to_lowercase(), value.to_string());
+        }
+
+        let reflection_url = match (self.reflect, &self.url) {
+            (false, _) => None,
+            (true, Some(url)) => Some(url.as_str()),
+            (true, None) => return Err("--reflect requires --url".into()),
+        };
+        let descriptors = load_descriptors(
+            self.descriptor_set.as_deref(),
+            reflection_url,
+            &self.service,
+        )
+        .await?;
+
+        let mut builder =
+            target::client_builder(self.url.as_deref(), self.registry.as_deref(), &self.service)?
+                .with_group(self.group)
+                .with_version(self.version);
+        if let Some(timeout) = self.timeout {
+            builder = builder.with_timeout(timeout);
+        }
+        let mut client = GenericClient::new(builder);
+        if let Some(descriptors) = descriptors {
+            client = client.with_descriptors(descriptors);
+        }
+
+        let start = Instant::now();
+        let response = client
+            .invoke_streaming(
+                &self.service,
+                &self.method,
+                Request::from_parts(metadata, args),
+            )
+            .await?;
+        let (headers, mut responses) = response.into_parts();
+        eprintln!("headers after {:?}:", start.elapsed());
+        print_metadata(&headers);
+
+        let mut count = 0;
+        while let Some(message) = responses.message().await? {
+            count += 1;
+            eprintln!("message {} after {:?}", count, start.elapsed());
+            println!("{}", serde_json::to_string_pretty(&message)?);
+        }
+        let trailers = responses.trailer().await?;
+        if let Some(trailers) = &trailers {
+            eprintln!("trailers after {:?}:", start.elapsed());
+            print_metadata(trailers);
+        }
+        eprintln!("{} messages in {:?}", count, start.elapsed());
+        // failures without a message only have headers
+        check_status(trailers.as_ref().unwrap_or(&headers))
+    }
+
+    /// The request messages of `--data`.
+    fn messages(&self) -> Result<Vec<Value>, StdError> {
+        if self.data.is_empty() {
+            return Ok(vec![Value::Object(Default::default())]);
+        }
+        let mut messages = Vec::with_capacity(self.data.len());
+        for data in &self.data {
+            if data == "@" {
+                for message in serde_json::Deserializer::from_reader(io::stdin()).into_iter() {
+                    messages.push(message?);
+                }
+            } else {
+                messages.push(
+                    serde_json::from_str(data)
+                        .map_err(|err| format!("invalid json {:?}: {}", data, err))?,
+                );
+            }
+        }
+        Ok(messages)
+    }
+}
+
+/// Fails unless the `grpc-status` of `metadata` is ok.
+fn check_status(metadata: &Metadata) -> Result<(), StdError> {
+    match metadata.get("grpc-status").map(String::as_str) {
+        None | Some("0") => Ok(()),
+        Some(code) => {
+            let message = metadata
+                .get("grpc-message")
+                .map_or(String::new(), |message| {
+                    urlencoding::decode(message)
+                        .map_or(message.clone(), |message| message.into_owned())
+                });
+            Err(format!("call failed with grpc-status {}: {}", code, message).into())
+        }
+    }
+}
+
+fn print_metadata(metadata: &Metadata) {
+    let headers = metadata.into_headers();
+    let mut names: Vec<_> = headers.keys().map(|name| name.as_str()).collect();
+    names.sort_unstable();
+    for name in names {
+        for value in headers.get_all(name) {
+            eprintln!("  {}: {}", name, String::from_utf8_lossy(value.as_bytes()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use argh::FromArgs;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_invoke_args() {
+        let command = InvokeCommand::from_args(
+            &["invoke"],
+            &[
+                "org.apache.dubbo.sample.tri.Greeter",
+                "greet",
+                "--url",
+                "127.0.0.1:8888",
+                "-d",
+                r#"{"name": "a"}"#,
+                "-d",
+                r#"{"name": "b"}"#,
+                "-H",
+                "x-tag=gray",
+                "--timeout",
+                "500",
+            ],
+        )
+        .unwrap();
+        assert_eq!(command.service, "org.apache.dubbo.sample.tri.Greeter");
+        assert_eq!(command.method, "greet");
+        assert_eq!(command.header, ["x-tag=gray"]);
+        assert_eq!(command.timeout, Some(500));
+        assert!(!command.reflect);
+        assert_eq!(
+            command.messages().unwrap(),
+            [json!({"name": "a"}), json!({"name": "b"})]
+        );
+
+        let command =
+            InvokeCommand::from_args(&["invoke"], &["test.Greeter", "greet", "-d", "{"]).unwrap();
+        let err = command.messages().unwrap_err();
+        assert!(err.to_string().starts_with("invalid json \"{\""), "{}", err);
+
+        let command = InvokeCommand::from_args(&["invoke"], &["test.Greeter", "greet"]).unwrap();
+        assert_eq!(command.messages().unwrap(), [json!({})]);
+        assert!(InvokeCommand::from_args(&["invoke"], &["test.Greeter"]).is_err());
+    }
+
+    #[test]
+    fn test_check_status() {
+        assert!(check_status(&Metadata::new()).is_ok());
+        assert!(
+            check_status(&Metadata::new().insert("grpc-status".to_string(), "0".to_string()))
+                .is_ok()
+        );
+
+        let metadata = Metadata::new()
+            .insert("grpc-status".to_string(), "5".to_string())
+            .insert("grpc-message".to_string(), "no%20such%20user".to_string());
+        assert_eq!(
+            check_status(&metadata).unwrap_err().to_string(),
+            "call failed with grpc-status 5: no such user"
+        );
+    }
+}
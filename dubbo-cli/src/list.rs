@@ -0,0 +1,149 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{collections::BTreeSet, time::Duration};
+
+use argh::FromArgs;
+use dubbo::{
+    extension::registry_extension::{Registry, ServiceChange},
+    params::registry_param::InterfaceName,
+    protocol::triple::service_key::ServiceSelector,
+    StdError, Url,
+};
+
+use crate::target;
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "list")]
+/// List the services of a registry, or the providers of a service.
+pub struct ListCommand {
+    /// interface of the service whose providers are listed
+    #[argh(positional)]
+    service: Option<String>,
+    /// registry url, as in `nacos://127.0.0.1:8848`
+    #[argh(option, short = 'r')]
+    registry: String,
+    /// only list the providers of these groups, comma separated
+    #[argh(option, default = "String::new()")]
+    group: String,
+    /// only list the providers of these versions, comma separated
+    #[argh(option, default = "String::new()")]
+    version: String,
+    /// seconds waiting for the providers to be discovered, 2 by default
+    #[argh(option, default = "2")]
+    wait: u64,
+}
+
+impl ListCommand {
+    pub async fn run(self) -> Result<(), StdError> {
+        for line in self.list().await? {
+            println!("{}", line);
+        }
+        Ok(())
+    }
+
+    /// The services of the registry, or the providers of the service.
+    async fn list(self) -> Result<Vec<String>, StdError> {
+        let registry = target::load_registry(&self.registry).await?;
+        let Some(service) = self.service else {
+            return registry.services().await;
+        };
+
+        let selector = ServiceSelector::new(self.group, self.version);
+        let mut subscribe_url: Url = format!("consumer://127.0.0.1/{}", service).parse()?;
+        subscribe_url.add_query_param(InterfaceName::new(service));
+        selector.add_url_params(&mut subscribe_url);
+        let mut changes = registry.subscribe(subscribe_url.clone()).await?;
+
+        // registries only tell the changes, collect them for a while
+        let mut providers = BTreeSet::new();
+        let deadline = tokio::time::sleep(Duration::from_secs(self.wait));
+        tokio::pin!(deadline);
+        loop {
+            tokio::select! {
+                _ = &mut deadline => break,
+                change = changes.recv() => match change {
+                    Some(Ok(ServiceChange::Insert(url, _))) => {
+                        providers.insert(url);
+                    }
+                    Some(Ok(ServiceChange::Remove(url))) => {
+                        providers.remove(&url);
+                    }
+                    Some(Err(err)) => return Err(err),
+                    None => break,
+                },
+            }
+        }
+        let _ = registry.unsubscribe(subscribe_url).await;
+
+        Ok(providers
+            .into_iter()
+            .filter(|provider| {
+                provider
+                    .parse::<Url>()
+                    .map_or(true, |url| selector.matches_url(&url))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use argh::FromArgs;
+    use dubbo::extension::registry_extension::Registry;
+
+    use super::*;
+
+    const GREETER: &str = "org.apache.dubbo.sample.tri.Greeter";
+
+    fn parse(args: &[&str]) -> ListCommand {
+        ListCommand::from_args(&["list"], args).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_list() {
+        let command = parse(&["-r", "static://127.0.0.1", GREETER, "--group", "a,b"]);
+        assert_eq!(command.service.as_deref(), Some(GREETER));
+        assert_eq!(command.registry, "static://127.0.0.1");
+        assert_eq!(command.group, "a,b");
+        assert_eq!(command.version, "");
+        assert_eq!(command.wait, 2);
+        assert!(ListCommand::from_args(&["list"], &[GREETER]).is_err());
+
+        // the static registry lives in memory, shared by the loads of its url
+        let registry = target::load_registry("static://127.0.0.1").await.unwrap();
+        let providers = [
+            format!("tri://127.0.0.1:8881/{0}?interface={0}&group=a", GREETER),
+            format!("tri://127.0.0.1:8882/{0}?interface={0}&group=b", GREETER),
+            format!("tri://127.0.0.1:8883/{0}?interface={0}&group=c", GREETER),
+        ];
+        for provider in &providers {
+            registry.register(provider.parse().unwrap()).await.unwrap();
+        }
+
+        let services = parse(&["-r", "static://127.0.0.1"]).list().await.unwrap();
+        assert_eq!(services, [GREETER]);
+
+        let args = ["-r", "static://127.0.0.1", GREETER, "--wait", "1"];
+        let listed = parse(&args).list().await.unwrap();
+        assert_eq!(listed, providers);
+
+        let args = [&args[..], &["--group", "a,b"]].concat();
+        let listed = parse(&args).list().await.unwrap();
+        assert_eq!(listed, providers[..2]);
+    }
+}
@@ -0,0 +1,68 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+mod admin;
+mod config;
+mod describe;
+mod invoke;
+mod list;
+mod target;
+
+use argh::FromArgs;
+use dubbo::extension::{registry_extension::RegistryExtension, EXTENSIONS};
+use registry_nacos::NacosRegistry;
+
+#[derive(FromArgs)]
+/// Command line tool of dubbo-rust.
+struct Cli {
+    #[argh(subcommand)]
+    command: Command,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum Command {
+    Config(config::ConfigCommand),
+    List(list::ListCommand),
+    Describe(describe::DescribeCommand),
+    Invoke(invoke::InvokeCommand),
+    Offline(admin::OfflineCommand),
+    Online(admin::OnlineCommand),
+}
+
+#[tokio::main]
+async fn main() {
+    let cli: Cli = argh::from_env();
+
+    // registries known to the tool, beside the builtin static one
+    let _ = EXTENSIONS
+        .register::<RegistryExtension<NacosRegistry>>()
+        .await;
+
+    let result = match cli.command {
+        Command::Config(command) => command.run().await,
+        Command::List(command) => command.run().await,
+        Command::Describe(command) => command.run().await,
+        Command::Invoke(command) => command.run().await,
+        Command::Offline(command) => command.run().await,
+        Command::Online(command) => command.run().await,
+    };
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
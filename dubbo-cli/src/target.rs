@@ -0,0 +1,64 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use dubbo::{
+    codegen::ClientBuilder, extension, extension::registry_extension::proxy::RegistryProxy,
+    StdError, Url,
+};
+
+/// Url of a provider given as `host:port` or `http://host:port`.
+pub fn provider_url(addr: &str) -> Result<String, StdError> {
+    let url = if addr.contains("://") {
+        addr.trim_end_matches('/').to_string()
+    } else {
+        format!("http://{}", addr.trim_end_matches('/'))
+    };
+    url.parse::<Url>()
+        .map_err(|err| format!("invalid provider url {:?}: {}", addr, err))?;
+    Ok(url)
+}
+
+/// Url of a registry such as `nacos://127.0.0.1:8848`.
+pub fn registry_url(registry: &str) -> Result<Url, StdError> {
+    Ok(registry
+        .parse()
+        .map_err(|err| format!("invalid registry url {:?}: {}", registry, err))?)
+}
+
+/// Builder of the clients calling `interface`, on the provider at `url` or
+/// the providers found in `registry`.
+pub fn client_builder(
+    url: Option<&str>,
+    registry: Option<&str>,
+    interface: &str,
+) -> Result<ClientBuilder, StdError> {
+    match (url, registry) {
+        (Some(url), None) => Ok(ClientBuilder::from_static(&format!(
+            "{}?interface={}",
+            provider_url(url)?,
+            interface
+        ))),
+        (None, Some(registry)) => Ok(ClientBuilder::new().with_registry(registry_url(registry)?)),
+        _ => Err("exactly one of --url and --registry is required".into()),
+    }
+}
+
+/// Load the registry extension of `registry`.
+pub async fn load_registry(registry: &str) -> Result<RegistryProxy, StdError> {
+    let url = extension::registry_extension::to_extension_url(registry_url(registry)?);
+    extension::EXTENSIONS.load_registry(url).await
+}
@@ -0,0 +1,68 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Procedural macros of dubbo, re-exported by the `dubbo` crate.
+
+mod service;
+
+use proc_macro::TokenStream;
+
+/// Define a dubbo service with a rust trait instead of a `.proto` file.
+///
+/// The methods of the trait are `async`, take `&self` and return a
+/// `Result<T, E>` where `E: Into<Status>`. Their arguments and results are
+/// any serde types and are encoded as json: a single argument as itself,
+/// several as an array and none as `null`.
+///
+/// A method is client streaming if its only argument is a `Decoding<T>` and
+/// server streaming if it returns a `ResponseStream<T>`, both found in
+/// `dubbo::codegen`.
+///
+/// Beside the trait, the macro generates `{Trait}Server<T>`, dispatching the
+/// calls of `/{interface}/{method}` to an implementation of the trait, and
+/// `{Trait}Client` to call them. The method name is the lower camel case of
+/// the rust one as used by java interfaces, `#[dubbo(name = "...")]` on a
+/// method overrides it. The interface defaults to the name of the trait.
+///
+/// ```ignore
+/// #[dubbo::service(interface = "org.apache.dubbo.sample.Greeter")]
+/// pub trait Greeter {
+///     async fn say_hello(&self, name: String) -> Result<String, Status>;
+///
+///     async fn watch(&self, name: String) -> Result<ResponseStream<String>, Status>;
+/// }
+///
+/// struct GreeterImpl;
+///
+/// #[dubbo::codegen::async_trait]
+/// impl Greeter for GreeterImpl {
+///     ...
+/// }
+///
+/// GreeterServer::register(GreeterImpl);
+///
+/// let mut client = GreeterClient::new(ClientBuilder::from_static("http://127.0.0.1:8888"));
+/// let reply = client.say_hello("dubbo".to_string()).await?;
+/// ```
+#[proc_macro_attribute]
+pub fn service(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = syn::parse_macro_input!(attr as syn::AttributeArgs);
+    let item = syn::parse_macro_input!(item as syn::ItemTrait);
+    service::expand(args, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
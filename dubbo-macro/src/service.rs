@@ -0,0 +1,689 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::{
+    spanned::Spanned, AttributeArgs, FnArg, GenericArgument, ItemTrait, Lit, Meta, NestedMeta,
+    PathArguments, ReturnType, TraitItem, TraitItemMethod, Type,
+};
+
+struct Method {
+    ident: Ident,
+    name: String,
+    args: Vec<(Ident, Type)>,
+    // message type of the request, the item type for client streaming
+    request: Type,
+    // message type of the response, the item type for server streaming
+    response: Type,
+    client_streaming: bool,
+    // the declared stream type of a server streaming method
+    response_stream: Option<Type>,
+}
+
+pub(crate) fn expand(args: AttributeArgs, mut item: ItemTrait) -> syn::Result<TokenStream> {
+    let mut interface = item.ident.to_string();
+    for arg in args {
+        match arg {
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("interface") => {
+                interface = lit_str(&nv.lit)?;
+            }
+            other => {
+                return Err(syn::Error::new(
+                    other.span(),
+                    "expected `interface = \"...\"`",
+                ))
+            }
+        }
+    }
+
+    let mut methods = Vec::new();
+    for trait_item in item.items.iter_mut() {
+        if let TraitItem::Method(method) = trait_item {
+            methods.push(parse_method(method)?);
+        }
+    }
+
+    item.supertraits
+        .push(syn::parse_quote!(::std::marker::Send));
+    item.supertraits
+        .push(syn::parse_quote!(::std::marker::Sync));
+    item.supertraits.push(syn::parse_quote!('static));
+
+    let server = expand_server(&item, &interface, &methods);
+    let client = expand_client(&item, &interface, &methods);
+
+    Ok(quote! {
+        #[::dubbo::codegen::async_trait]
+        #item
+
+        #server
+
+        #client
+    })
+}
+
+fn parse_method(method: &mut TraitItemMethod) -> syn::Result<Method> {
+    let sig = &method.sig;
+    if sig.asyncness.is_none() {
+        return Err(syn::Error::new(
+            sig.span(),
+            "service methods must be `async`",
+        ));
+    }
+    if !matches!(sig.inputs.first(), Some(FnArg::Receiver(r)) if r.reference.is_some() && r.mutability.is_none())
+    {
+        return Err(syn::Error::new(
+            sig.span(),
+            "service methods must take `&self`",
+        ));
+    }
+
+    let mut name = lower_camel_case(&sig.ident.to_string());
+    let mut attrs = Vec::new();
+    for attr in method.attrs.drain(..) {
+        if !attr.path.is_ident("dubbo") {
+            attrs.push(attr);
+            continue;
+        }
+        match attr.parse_meta()? {
+            Meta::List(list) => {
+                for nested in list.nested {
+                    match nested {
+                        NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("name") => {
+                            name = lit_str(&nv.lit)?;
+                        }
+                        other => {
+                            return Err(syn::Error::new(other.span(), "expected `name = \"...\"`"))
+                        }
+                    }
+                }
+            }
+            other => {
+                return Err(syn::Error::new(
+                    other.span(),
+                    "expected `#[dubbo(name = \"...\")]`",
+                ))
+            }
+        }
+    }
+    method.attrs = attrs;
+
+    let sig = &method.sig;
+    let mut args = Vec::new();
+    for (i, input) in sig.inputs.iter().skip(1).enumerate() {
+        let FnArg::Typed(pat) = input else {
+            continue;
+        };
+        if let Type::Reference(_) = *pat.ty {
+            return Err(syn::Error::new(
+                pat.ty.span(),
+                "service method arguments must be owned",
+            ));
+        }
+        let ident = match &*pat.pat {
+            syn::Pat::Ident(p) => p.ident.clone(),
+            _ => format_ident!("arg{}", i),
+        };
+        args.push((ident, (*pat.ty).clone()));
+    }
+
+    let streaming_arg = args.iter().find_map(|(_, ty)| generic_of(ty, "Decoding"));
+    let (request, client_streaming) = match (streaming_arg, args.len()) {
+        (Some(item), 1) => (item, true),
+        (Some(_), _) => {
+            return Err(syn::Error::new(
+                sig.inputs.span(),
+                "a client streaming method takes a single `Decoding<T>` argument",
+            ))
+        }
+        (None, 1) => (args[0].1.clone(), false),
+        (None, _) => {
+            let types = args.iter().map(|(_, ty)| ty);
+            (syn::parse_quote!((#(#types),*)), false)
+        }
+    };
+
+    let ok = match &sig.output {
+        ReturnType::Type(_, ty) => result_ok(ty),
+        ReturnType::Default => None,
+    }
+    .ok_or_else(|| {
+        syn::Error::new(
+            sig.output.span(),
+            "service methods must return `Result<T, E>`",
+        )
+    })?;
+    let (response, response_stream) = match generic_of(&ok, "ResponseStream") {
+        Some(item) => (item, Some(ok)),
+        None => (ok, None),
+    };
+
+    Ok(Method {
+        ident: sig.ident.clone(),
+        name,
+        args,
+        request,
+        response,
+        client_streaming,
+        response_stream,
+    })
+}
+
+fn expand_server(item: &ItemTrait, interface: &str, methods: &[Method]) -> TokenStream {
+    let vis = &item.vis;
+    let service = &item.ident;
+    let server = format_ident!("{}Server", service);
+    let doc = format!(
+        "Dispatches the calls of `{}` to a [`{}`].",
+        interface, service
+    );
+
+    let arms = methods.iter().map(|method| {
+        let path = format!("/{}/{}", interface, method.name);
+        let ident = &method.ident;
+        let svc = format_ident!("{}Svc", upper_camel_case(&ident.to_string()));
+        let request = &method.request;
+        let response = &method.response;
+
+        let (pattern, call) = if method.client_streaming {
+            (quote!(arg), quote!(inner.#ident(arg)))
+        } else {
+            let idents: Vec<Ident> = (0..method.args.len()).map(|i| format_ident!("arg{}", i)).collect();
+            let pattern = match idents.len() {
+                1 => quote!(#(#idents)*),
+                _ => quote!((#(#idents),*)),
+            };
+            (pattern, quote!(inner.#ident(#(#idents),*)))
+        };
+        let body = quote! {
+            let inner = self.inner.clone();
+            let #pattern = request.message;
+            Box::pin(async move {
+                #call
+                    .await
+                    .map(::dubbo::codegen::Response::new)
+                    .map_err(::std::convert::Into::into)
+            })
+        };
+
+        let (svc_impl, serve) = match (method.client_streaming, &method.response_stream) {
+            (false, None) => (
+                quote! {
+                    impl<T: #service> ::dubbo::codegen::UnarySvc<#request> for #svc<T> {
+                        type Response = #response;
+                        type Future = ::dubbo::codegen::BoxFuture<
+                            ::dubbo::codegen::Response<Self::Response>,
+                            ::dubbo::status::Status,
+                        >;
+                        fn call(&mut self, request: ::dubbo::codegen::Request<#request>) -> Self::Future {
+                            #body
+                        }
+                    }
+                },
+                quote!(unary),
+            ),
+            (false, Some(stream)) => (
+                quote! {
+                    impl<T: #service> ::dubbo::codegen::ServerStreamingSvc<#request> for #svc<T> {
+                        type Response = #response;
+                        type ResponseStream = #stream;
+                        type Future = ::dubbo::codegen::BoxFuture<
+                            ::dubbo::codegen::Response<Self::ResponseStream>,
+                            ::dubbo::status::Status,
+                        >;
+                        fn call(&mut self, request: ::dubbo::codegen::Request<#request>) -> Self::Future {
+                            #body
+                        }
+                    }
+                },
+                quote!(server_streaming),
+            ),
+            (true, None) => (
+                quote! {
+                    impl<T: #service> ::dubbo::codegen::ClientStreamingSvc<#request> for #svc<T> {
+                        type Response = #response;
+                        type Future = ::dubbo::codegen::BoxFuture<
+                            ::dubbo::codegen::Response<Self::Response>,
+                            ::dubbo::status::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: ::dubbo::codegen::Request<::dubbo::codegen::Decoding<#request>>,
+                        ) -> Self::Future {
+                            #body
+                        }
+                    }
+                },
+                quote!(client_streaming),
+            ),
+            (true, Some(stream)) => (
+                quote! {
+                    impl<T: #service> ::dubbo::codegen::StreamingSvc<#request> for #svc<T> {
+                        type Response = #response;
+                        type ResponseStream = #stream;
+                        type Future = ::dubbo::codegen::BoxFuture<
+                            ::dubbo::codegen::Response<Self::ResponseStream>,
+                            ::dubbo::status::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: ::dubbo::codegen::Request<::dubbo::codegen::Decoding<#request>>,
+                        ) -> Self::Future {
+                            #body
+                        }
+                    }
+                },
+                quote!(bidi_streaming),
+            ),
+        };
+
+        quote! {
+            #path => {
+                struct #svc<T> {
+                    inner: ::std::sync::Arc<T>,
+                }
+                #svc_impl
+                Box::pin(async move {
+                    let mut server = ::dubbo::codegen::TripleServer::<
+                        #request,
+                        #response,
+                        ::dubbo::codegen::JsonCodec,
+                    >::new();
+                    Ok(server.#serve(#svc { inner }, req).await)
+                })
+            }
+        }
+    });
+
+    quote! {
+        #[doc = #doc]
+        #vis struct #server<T> {
+            inner: ::std::sync::Arc<T>,
+        }
+
+        impl<T: #service> #server<T> {
+            #vis fn new(inner: T) -> Self {
+                Self {
+                    inner: ::std::sync::Arc::new(inner),
+                }
+            }
+
+            #vis fn with_filter<F>(inner: T, filter: F) -> ::dubbo::codegen::FilterService<Self, F>
+            where
+                F: ::dubbo::codegen::Filter,
+            {
+                ::dubbo::codegen::FilterService::new(Self::new(inner), filter)
+            }
+
+            /// Register the service to be exported by the triple protocol.
+            #vis fn register(inner: T) {
+                ::dubbo::protocol::triple::TRIPLE_SERVICES
+                    .write()
+                    .unwrap()
+                    .insert(
+                        #interface.to_string(),
+                        ::dubbo::utils::boxed_clone::BoxCloneService::new(Self::new(inner)),
+                    );
+            }
+
+            /// Register the service as the `version` of the interface in
+            /// `group`, see [`::dubbo::protocol::triple::service_key::ServiceKey`].
+            #vis fn register_versioned(inner: T, group: &str, version: &str) {
+                let key = ::dubbo::protocol::triple::service_key::ServiceKey::new(#interface)
+                    .with_group(group)
+                    .with_version(version);
+                ::dubbo::protocol::triple::register_service(
+                    &key,
+                    ::dubbo::utils::boxed_clone::BoxCloneService::new(Self::new(inner)),
+                );
+            }
+        }
+
+        impl<T> ::std::clone::Clone for #server<T> {
+            fn clone(&self) -> Self {
+                Self {
+                    inner: self.inner.clone(),
+                }
+            }
+        }
+
+        impl<T, B> ::dubbo::codegen::Service<::dubbo::codegen::http::Request<B>> for #server<T>
+        where
+            T: #service,
+            B: ::dubbo::codegen::Body + ::std::marker::Send + 'static,
+            B::Error: ::std::convert::Into<::dubbo::codegen::StdError> + ::std::marker::Send + 'static,
+        {
+            type Response = ::dubbo::codegen::http::Response<::dubbo::codegen::BoxBody>;
+            type Error = ::std::convert::Infallible;
+            type Future = ::dubbo::codegen::BoxFuture<Self::Response, Self::Error>;
+
+            fn poll_ready(
+                &mut self,
+                _cx: &mut ::dubbo::codegen::Context<'_>,
+            ) -> ::dubbo::codegen::Poll<::std::result::Result<(), Self::Error>> {
+                ::dubbo::codegen::Poll::Ready(Ok(()))
+            }
+
+            fn call(&mut self, req: ::dubbo::codegen::http::Request<B>) -> Self::Future {
+                let inner = self.inner.clone();
+                match req.uri().path() {
+                    #(#arms)*
+                    _ => Box::pin(async move {
+                        Ok(::dubbo::codegen::http::Response::builder()
+                            .status(200)
+                            .header("grpc-status", "12")
+                            .header("content-type", "application/grpc")
+                            .body(::dubbo::codegen::empty_body())
+                            .unwrap())
+                    }),
+                }
+            }
+        }
+    }
+}
+
+fn expand_client(item: &ItemTrait, interface: &str, methods: &[Method]) -> TokenStream {
+    let vis = &item.vis;
+    let client = format_ident!("{}Client", item.ident);
+    let doc = format!("Client of `{}`.", interface);
+
+    let methods = methods.iter().map(|method| {
+        let ident = &method.ident;
+        let name = &method.name;
+        let path = format!("/{}/{}", interface, method.name);
+        let request = &method.request;
+        let response = &method.response;
+        let idents: Vec<&Ident> = method.args.iter().map(|(ident, _)| ident).collect();
+        let message = match idents.len() {
+            1 => quote!(#(#idents)*),
+            _ => quote!((#(#idents),*)),
+        };
+
+        let (inputs, output, call) = match (method.client_streaming, &method.response_stream) {
+            (false, None) => {
+                let args = method.args.iter().map(|(ident, ty)| quote!(#ident: #ty));
+                (
+                    quote!(#(#args),*),
+                    quote!(#response),
+                    quote!(unary(::dubbo::codegen::Request::new(#message), path, invocation)),
+                )
+            }
+            (false, Some(_)) => {
+                let args = method.args.iter().map(|(ident, ty)| quote!(#ident: #ty));
+                (
+                    quote!(#(#args),*),
+                    quote!(::dubbo::codegen::Decoding<#response>),
+                    quote!(server_streaming(::dubbo::codegen::Request::new(#message), path, invocation)),
+                )
+            }
+            (true, None) => (
+                quote!(#message: impl ::dubbo::codegen::IntoStreamingRequest<Message = #request>),
+                quote!(#response),
+                quote!(client_streaming(#message, path, invocation)),
+            ),
+            (true, Some(_)) => (
+                quote!(#message: impl ::dubbo::codegen::IntoStreamingRequest<Message = #request>),
+                quote!(::dubbo::codegen::Decoding<#response>),
+                quote!(bidi_streaming(#message, path, invocation)),
+            ),
+        };
+
+        quote! {
+            #vis async fn #ident(&mut self, #inputs) -> ::std::result::Result<#output, ::dubbo::status::Status> {
+                let invocation = ::dubbo::codegen::RpcInvocation::default()
+                    .with_service_unique_name(::std::string::String::from(#interface))
+                    .with_method_name(::std::string::String::from(#name));
+                let path = ::dubbo::codegen::http::uri::PathAndQuery::from_static(#path);
+                self.inner
+                    .#call
+                    .await
+                    .map(::dubbo::codegen::Response::into_inner)
+            }
+        }
+    });
+
+    quote! {
+        #[doc = #doc]
+        #[derive(Clone)]
+        #vis struct #client {
+            inner: ::dubbo::codegen::TripleClient<::dubbo::codegen::JsonCodec>,
+        }
+
+        impl #client {
+            #vis fn connect(host: ::std::string::String) -> Self {
+                Self {
+                    inner: ::dubbo::codegen::TripleClient::connect(host),
+                }
+            }
+
+            #vis fn new(builder: ::dubbo::codegen::ClientBuilder) -> Self {
+                Self {
+                    inner: ::dubbo::codegen::TripleClient::new(builder),
+                }
+            }
+
+            /// Client of the reference `name` of the global config.
+            #vis fn from_reference(
+                name: &str,
+            ) -> ::std::result::Result<Self, ::dubbo::config::consumer::ReferenceError> {
+                ::dubbo::codegen::ClientBuilder::from_reference(name).map(Self::new)
+            }
+
+            #(#methods)*
+        }
+    }
+}
+
+fn lit_str(lit: &Lit) -> syn::Result<String> {
+    match lit {
+        Lit::Str(s) => Ok(s.value()),
+        _ => Err(syn::Error::new(lit.span(), "expected a string literal")),
+    }
+}
+
+/// `T` of a `Result<T, E>`.
+fn result_ok(ty: &Type) -> Option<Type> {
+    let args = generic_args(ty, "Result")?;
+    match args.as_slice() {
+        [ok, _] => Some(ok.clone()),
+        _ => None,
+    }
+}
+
+/// `T` of a `{name}<T>`, whatever its path.
+fn generic_of(ty: &Type, name: &str) -> Option<Type> {
+    let args = generic_args(ty, name)?;
+    match args.as_slice() {
+        [ty] => Some(ty.clone()),
+        _ => None,
+    }
+}
+
+fn generic_args(ty: &Type, name: &str) -> Option<Vec<Type>> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != name {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    Some(
+        args.args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty.clone()),
+                _ => None,
+            })
+            .collect(),
+    )
+}
+
+fn lower_camel_case(name: &str) -> String {
+    let upper = upper_camel_case(name);
+    let mut chars = upper.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => upper,
+    }
+}
+
+fn upper_camel_case(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_method() {
+        assert_eq!(lower_camel_case("say_hello"), "sayHello");
+        assert_eq!(upper_camel_case("say_hello"), "SayHello");
+
+        let mut method: TraitItemMethod = syn::parse_quote! {
+            #[dubbo(name = "SayHello")]
+            async fn say_hello(&self, name: String, age: u32) -> Result<String, Status>;
+        };
+        let method = parse_method(&mut method).unwrap();
+        assert_eq!(method.name, "SayHello");
+        assert!(!method.client_streaming && method.response_stream.is_none());
+        let request = &method.request;
+        assert_eq!(quote!(#request).to_string(), "(String , u32)");
+
+        let mut method: TraitItemMethod = syn::parse_quote! {
+            async fn chat(&self, items: Decoding<Item>) -> Result<ResponseStream<Item>, Status>;
+        };
+        let method = parse_method(&mut method).unwrap();
+        assert_eq!(method.name, "chat");
+        assert!(method.client_streaming && method.response_stream.is_some());
+        let (request, response) = (&method.request, &method.response);
+        assert_eq!(quote!(#request).to_string(), "Item");
+        assert_eq!(quote!(#response).to_string(), "Item");
+    }
+
+    fn expand_err(args: TokenStream, item: TokenStream) -> String {
+        let args = syn::parse::Parser::parse2(
+            syn::punctuated::Punctuated::<NestedMeta, syn::Token![,]>::parse_terminated,
+            args,
+        )
+        .unwrap();
+        let item = syn::parse2(item).unwrap();
+        match expand(args.into_iter().collect(), item) {
+            Ok(_) => panic!("expected the expansion to fail"),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_rejected_signatures() {
+        let cases = [
+            (
+                quote!(
+                    trait Greeter {
+                        fn greet(&self, name: String) -> Result<String, Status>;
+                    }
+                ),
+                "service methods must be `async`",
+            ),
+            (
+                quote!(
+                    trait Greeter {
+                        async fn greet(&mut self, name: String) -> Result<String, Status>;
+                    }
+                ),
+                "service methods must take `&self`",
+            ),
+            (
+                quote!(
+                    trait Greeter {
+                        async fn greet(name: String) -> Result<String, Status>;
+                    }
+                ),
+                "service methods must take `&self`",
+            ),
+            (
+                quote!(
+                    trait Greeter {
+                        async fn greet(&self, name: &str) -> Result<String, Status>;
+                    }
+                ),
+                "service method arguments must be owned",
+            ),
+            (
+                quote!(
+                    trait Greeter {
+                        async fn greet(&self, name: String) -> String;
+                    }
+                ),
+                "service methods must return `Result<T, E>`",
+            ),
+            (
+                quote!(
+                    trait Greeter {
+                        async fn greet(&self, name: String);
+                    }
+                ),
+                "service methods must return `Result<T, E>`",
+            ),
+            (
+                quote!(
+                    trait Greeter {
+                        async fn greet(
+                            &self,
+                            id: u32,
+                            names: Decoding<String>,
+                        ) -> Result<String, Status>;
+                    }
+                ),
+                "a client streaming method takes a single `Decoding<T>` argument",
+            ),
+            (
+                quote!(
+                    trait Greeter {
+                        #[dubbo(rename = "Greet")]
+                        async fn greet(&self, name: String) -> Result<String, Status>;
+                    }
+                ),
+                "expected `name = \"...\"`",
+            ),
+        ];
+        for (item, message) in cases {
+            assert_eq!(expand_err(quote!(), item), message);
+        }
+
+        let item = quote!(
+            trait Greeter {}
+        );
+        assert_eq!(
+            expand_err(quote!(name = "Greeter"), item),
+            "expected `interface = \"...\"`"
+        );
+    }
+}
@@ -0,0 +1,124 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Calls a local provider over 1 and 4 pooled http/2 connections. The
+//! provider accepts 100 concurrent streams per connection, as many http/2
+//! servers and proxies do, and answers after 10ms: a single connection keeps
+//! the other calls waiting.
+//!
+//! ```sh
+//! cargo bench -p dubbo --bench connection_pool
+//! ```
+
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use dubbo::{
+    invoker::clone_body::CloneBody,
+    triple::transport::{
+        connector::get_connector,
+        pool::{ConnectionPool, PoolConfig},
+    },
+};
+use futures::future;
+use tokio::{net::TcpListener, sync::Semaphore};
+
+const CALLS: usize = 10000;
+const CONCURRENCY: usize = 256;
+const RESPONSE_SIZE: usize = 16 * 1024;
+const MAX_STREAMS: u32 = 100;
+const LATENCY: Duration = Duration::from_millis(10);
+
+async fn serve() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let body = Bytes::from(vec![7u8; RESPONSE_SIZE]);
+    tokio::spawn(async move {
+        while let Ok((io, _)) = listener.accept().await {
+            let body = body.clone();
+            let service = hyper::service::service_fn(move |_| {
+                let body = body.clone();
+                async move {
+                    tokio::time::sleep(LATENCY).await;
+                    Ok::<_, Infallible>(http::Response::new(hyper::Body::from(body)))
+                }
+            });
+            tokio::spawn(
+                hyper::server::conn::Http::new()
+                    .http2_only(true)
+                    .http2_max_concurrent_streams(MAX_STREAMS)
+                    .serve_connection(io, service),
+            );
+        }
+    });
+    addr
+}
+
+async fn run(addr: SocketAddr, connections: usize) -> Duration {
+    let pool = ConnectionPool::new(
+        format!("http://{}", addr).parse().unwrap(),
+        get_connector("http"),
+        hyper::client::conn::Builder::new()
+            .http2_only(true)
+            .to_owned(),
+        PoolConfig::default()
+            .min_connections(connections)
+            .max_connections(connections),
+    );
+    let permits = Arc::new(Semaphore::new(CONCURRENCY));
+    let start = Instant::now();
+    let calls = (0..CALLS).map(|_| {
+        let pool = pool.clone();
+        let permits = permits.clone();
+        tokio::spawn(async move {
+            let _permit = permits.acquire().await.unwrap();
+            let req = http::Request::builder()
+                .uri(format!("http://{}/", addr))
+                .body(CloneBody::new(hyper::Body::empty()))
+                .unwrap();
+            let resp = pool.send(req).await.unwrap();
+            hyper::body::to_bytes(resp.into_body()).await.unwrap().len()
+        })
+    });
+    for len in future::join_all(calls).await {
+        assert_eq!(len.unwrap(), RESPONSE_SIZE);
+    }
+    start.elapsed()
+}
+
+#[tokio::main]
+async fn main() {
+    let addr = serve().await;
+    // warm up the server and the allocator
+    run(addr, 1).await;
+    for connections in [1, 4] {
+        let elapsed = run(addr, connections).await;
+        println!(
+            "{} connection(s): {} calls of {} KiB in {:?}, {:.0} calls/s",
+            connections,
+            CALLS,
+            RESPONSE_SIZE / 1024,
+            elapsed,
+            CALLS as f64 / elapsed.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,184 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{convert::Infallible, net::SocketAddr};
+
+use futures_core::Future;
+use http::{Method, Request, Response, StatusCode};
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Server,
+};
+
+use crate::{directory::health, logger::tracing::info, metrics, registry::exported};
+
+/// Http server for operational endpoints, separated from the rpc port.
+///
+/// Routes:
+/// - `GET /metrics`: metrics in the prometheus text format
+/// - `GET /services`: the exported services, with whether they are online
+/// - `GET|POST /offline?service=..`: unregister the providers of a service,
+///   all of them without `service`
+/// - `GET|POST /online?service=..`: register them again
+/// - `GET /providers`: the providers called by the consumers, with whether
+///   they are healthy
+pub struct AdminServer {
+    addr: SocketAddr,
+}
+
+impl AdminServer {
+    pub fn new(addr: SocketAddr) -> Self {
+        AdminServer { addr }
+    }
+
+    pub async fn serve(self) -> Result<(), crate::Error> {
+        self.serve_with_graceful(futures_util::future::pending())
+            .await
+    }
+
+    pub async fn serve_with_graceful(
+        self,
+        signal: impl Future<Output = ()>,
+    ) -> Result<(), crate::Error> {
+        let make_svc = make_service_fn(|_conn| async { Ok::<_, Infallible>(service_fn(handle)) });
+
+        info!("admin server listening on {}", self.addr);
+        Server::try_bind(&self.addr)?
+            .serve(make_svc)
+            .with_graceful_shutdown(signal)
+            .await?;
+        Ok(())
+    }
+}
+
+async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let resp = match (req.method(), req.uri().path()) {
+        (&Method::GET, "/metrics") => render_metrics(),
+        (&Method::GET, "/services") => render_services(),
+        (&Method::GET, "/providers") => render_providers(),
+        (&Method::GET | &Method::POST, "/offline") => {
+            set_online(service_param(&req).as_deref(), false).await
+        }
+        (&Method::GET | &Method::POST, "/online") => {
+            set_online(service_param(&req).as_deref(), true).await
+        }
+        _ => text(StatusCode::NOT_FOUND, "not found".to_string()),
+    };
+    Ok(resp)
+}
+
+fn render_metrics() -> Response<Body> {
+    metrics::record_runtime_metrics();
+    match metrics::recorder().render() {
+        Some(text) => {
+            let mut resp = Response::new(Body::from(text));
+            resp.headers_mut().insert(
+                http::header::CONTENT_TYPE,
+                http::HeaderValue::from_static("text/plain; version=0.0.4"),
+            );
+            resp
+        }
+        None => text(
+            StatusCode::NOT_FOUND,
+            "metrics recorder does not support scraping".to_string(),
+        ),
+    }
+}
+
+fn render_services() -> Response<Body> {
+    let services: String = exported::services()
+        .into_iter()
+        .map(|(key, online)| {
+            let status = if online { "online" } else { "offline" };
+            format!("{} {}\n", key, status)
+        })
+        .collect();
+    text(StatusCode::OK, services)
+}
+
+fn render_providers() -> Response<Body> {
+    let providers: String = health::providers()
+        .into_iter()
+        .map(|(service, url, healthy)| {
+            let status = if healthy { "healthy" } else { "unhealthy" };
+            format!("{} {} {}\n", service, url, status)
+        })
+        .collect();
+    text(StatusCode::OK, providers)
+}
+
+async fn set_online(service: Option<&str>, online: bool) -> Response<Body> {
+    let result = if online {
+        exported::online(service).await
+    } else {
+        exported::offline(service).await
+    };
+    match result {
+        Ok(keys) if keys.is_empty() => text(
+            StatusCode::NOT_FOUND,
+            format!("no service {} to change", service.unwrap_or("")),
+        ),
+        Ok(keys) => text(
+            StatusCode::OK,
+            keys.into_iter().map(|key| key + "\n").collect(),
+        ),
+        Err(err) => text(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
+
+/// The `service` query parameter of `req`.
+fn service_param(req: &Request<Body>) -> Option<String> {
+    url::form_urlencoded::parse(req.uri().query()?.as_bytes())
+        .find(|(key, _)| key == "service")
+        .map(|(_, value)| value.into_owned())
+        .filter(|value| !value.is_empty())
+}
+
+fn text(status: StatusCode, body: String) -> Response<Body> {
+    let mut resp = Response::new(Body::from(body));
+    *resp.status_mut() = status;
+    resp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_metrics_endpoint() {
+        let req = Request::get("/metrics").body(Body::empty()).unwrap();
+        let resp = handle(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("# TYPE dubbo_runtime_workers gauge"));
+
+        let req = Request::post("/offline?service=org.apache.dubbo.admin.Unknown")
+            .body(Body::empty())
+            .unwrap();
+        let resp = handle(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = Request::get("/providers").body(Body::empty()).unwrap();
+        let resp = handle(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = Request::get("/unknown").body(Body::empty()).unwrap();
+        let resp = handle(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+}
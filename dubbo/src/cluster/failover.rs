@@ -17,7 +17,14 @@
 
 use std::task::Poll;
 
-use crate::StdError;
+use crate::{
+    config::{
+        dynamic::{self, Side, RETRIES_KEY},
+        method::MethodConfig,
+    },
+    triple::client::CallOptions,
+    StdError,
+};
 use futures_util::future;
 use http::Request;
 use tower::{retry::Retry, util::Oneshot, ServiceExt};
@@ -25,14 +32,18 @@ use tower_service::Service;
 
 pub struct Failover<N> {
     inner: N, // loadbalancer service
+    retries: u32,
 }
 
+/// Retries a failed call until `retries` attempts are spent.
 #[derive(Clone)]
-pub struct FailoverPolicy;
+pub struct FailoverPolicy {
+    retries: u32,
+}
 
 impl<N> Failover<N> {
-    pub fn new(inner: N) -> Self {
-        Self { inner }
+    pub fn new(inner: N, retries: u32) -> Self {
+        Self { inner, retries }
     }
 }
 
@@ -46,7 +57,10 @@ where
         //TODO some error handling or logging
         match result {
             Ok(_) => None,
-            Err(_) => Some(future::ready(self.clone())),
+            Err(_) if self.retries == 0 => None,
+            Err(_) => Some(future::ready(FailoverPolicy {
+                retries: self.retries - 1,
+            })),
         }
     }
 
@@ -81,7 +95,30 @@ where
     }
 
     fn call(&mut self, req: Request<B>) -> Self::Future {
-        let retry = Retry::new(FailoverPolicy, self.inner.clone());
+        // the path header stays when the uri is rewritten for the provider
+        let path = req
+            .headers()
+            .get("path")
+            .and_then(|path| path.to_str().ok())
+            .unwrap_or(req.uri().path());
+        let (service, method) = dynamic::service_method(path);
+        let retries = req
+            .extensions()
+            .get::<CallOptions>()
+            .and_then(|options| options.retries)
+            .or_else(|| {
+                dynamic::dynamic_config().parameter_as(
+                    Side::Consumer,
+                    service,
+                    method,
+                    "",
+                    RETRIES_KEY,
+                )
+            })
+            .or_else(|| req.extensions().get::<MethodConfig>()?.retries)
+            .unwrap_or(self.retries);
+        let policy = FailoverPolicy { retries };
+        let retry = Retry::new(policy, self.inner.clone());
         retry.oneshot(req)
     }
 }
@@ -0,0 +1,529 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Hedging of unary calls: the call is sent again to another provider when
+//! no response came after a delay, the first response winning and the other
+//! calls being cancelled.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use bytes::{Bytes, BytesMut};
+use futures_util::{stream::FuturesUnordered, FutureExt, StreamExt};
+use http::{HeaderValue, Request, Response};
+use http_body::Body;
+use tokio::time::Instant;
+use tower::ServiceExt;
+use tower_service::Service;
+
+use crate::{
+    config::method::{HedgingConfig, MethodConfig, MAX_HEDGING_ATTEMPTS},
+    invoker::clone_body::CloneBody,
+    metrics::rpc::RequestSize,
+    status::Code,
+    triple::{client::CallOptions, decode::failed_status},
+    StdError,
+};
+
+/// Number of the attempts sent before this one, on the requests and the
+/// response of a hedged call.
+pub const GRPC_PREVIOUS_RPC_ATTEMPTS: &str = "grpc-previous-rpc-attempts";
+
+/// Delay before the next attempt of a method without a fixed delay, until
+/// enough of its calls were timed.
+pub const DEFAULT_HEDGING_DELAY: Duration = Duration::from_millis(100);
+
+/// Latencies of the last calls of a method kept to compute their p95.
+const LATENCY_SAMPLES: usize = 100;
+
+/// Calls of a method timed before their p95 is trusted.
+const MIN_LATENCY_SAMPLES: usize = 20;
+
+/// Latencies of the last successful calls of each method, the hedging
+/// delay of the methods without a fixed one.
+#[derive(Clone, Default)]
+pub struct Latencies(Arc<Mutex<HashMap<String, VecDeque<Duration>>>>);
+
+impl Latencies {
+    fn record(&self, path: &str, latency: Duration) {
+        let mut latencies = self.0.lock().unwrap();
+        let samples = latencies.entry(path.to_string()).or_default();
+        if samples.len() == LATENCY_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back(latency);
+    }
+
+    /// The p95 latency of the calls of `path`, once enough were timed.
+    fn p95(&self, path: &str) -> Option<Duration> {
+        let latencies = self.0.lock().unwrap();
+        let samples = latencies.get(path)?;
+        if samples.len() < MIN_LATENCY_SAMPLES {
+            return None;
+        }
+        let mut sorted: Vec<_> = samples.iter().copied().collect();
+        sorted.sort();
+        Some(sorted[(sorted.len() * 95).div_ceil(100) - 1])
+    }
+}
+
+/// Marks the requests of unary calls, the only ones hedged: the request is
+/// read whole before the first attempt.
+#[derive(Debug, Clone, Copy)]
+pub struct UnaryCall;
+
+/// Addresses of the providers called by the attempts of a hedged call, the
+/// load balancer avoiding them for the next attempts.
+#[derive(Clone, Default)]
+pub(crate) struct AttemptedProviders(Arc<Mutex<Vec<String>>>);
+
+impl AttemptedProviders {
+    pub(crate) fn add(&self, address: String) {
+        self.0.lock().unwrap().push(address);
+    }
+
+    pub(crate) fn contains(&self, address: &str) -> bool {
+        self.0.lock().unwrap().iter().any(|a| a == address)
+    }
+}
+
+/// Sends the unary calls of the methods with a [`HedgingConfig`] through the
+/// load balancer `N` as hedged calls.
+#[derive(Clone)]
+pub struct Hedging<N> {
+    inner: N,
+    latencies: Latencies,
+    buffer_size: usize,
+}
+
+impl<N> Hedging<N> {
+    /// Hedging of the calls whose request body fits in `buffer_size` bytes,
+    /// the others being sent once.
+    pub fn new(inner: N, buffer_size: usize) -> Self {
+        Self {
+            inner,
+            latencies: Latencies::default(),
+            buffer_size,
+        }
+    }
+
+    /// Share the latencies of the methods with other calls.
+    pub fn with_latencies(self, latencies: Latencies) -> Self {
+        Self { latencies, ..self }
+    }
+}
+
+impl<N, B> Hedging<N>
+where
+    N: Service<Request<CloneBody>, Response = Response<B>> + Clone + Send + 'static,
+    N::Error: Into<StdError>,
+    N::Future: Send,
+    B: Send + 'static,
+{
+    pub fn call(
+        &mut self,
+        req: Request<hyper::Body>,
+        hedging: &HedgingConfig,
+    ) -> crate::BoxFuture<Response<B>, StdError> {
+        let call = HedgedCall {
+            inner: self.inner.clone(),
+            latencies: self.latencies.clone(),
+            max_attempts: hedging.max_attempts.clamp(1, MAX_HEDGING_ATTEMPTS),
+            delay: hedging.hedging_delay.map(Duration::from_millis),
+            non_fatal: hedging.non_fatal_codes().unwrap_or_default(),
+        };
+        let buffer_size = self.buffer_size;
+        Box::pin(async move {
+            let (parts, body) = req.into_parts();
+            match buffer(body, buffer_size).await? {
+                Buffered::Full(body) => call.send(parts, body).await,
+                Buffered::Partial(body) => {
+                    // too large to be sent again
+                    let req = Request::from_parts(parts, CloneBody::with_capacity(body, 0));
+                    call.inner.oneshot(req).await.map_err(Into::into)
+                }
+            }
+        })
+    }
+}
+
+struct HedgedCall<N> {
+    inner: N,
+    latencies: Latencies,
+    max_attempts: u32,
+    delay: Option<Duration>,
+    non_fatal: Vec<Code>,
+}
+
+impl<N, B> HedgedCall<N>
+where
+    N: Service<Request<CloneBody>, Response = Response<B>> + Clone + Send + 'static,
+    N::Error: Into<StdError>,
+    N::Future: Send,
+{
+    async fn send(self, parts: http::request::Parts, body: Bytes) -> Result<Response<B>, StdError> {
+        let path = parts
+            .headers
+            .get("path")
+            .and_then(|path| path.to_str().ok())
+            .unwrap_or(parts.uri.path())
+            .to_string();
+        let delay = self
+            .delay
+            .or_else(|| self.latencies.p95(&path))
+            .unwrap_or(DEFAULT_HEDGING_DELAY);
+        let attempted = AttemptedProviders::default();
+        let (inner, parts, body, attempted) = (self.inner.clone(), &parts, &body, &attempted);
+        let attempt = move |n: u32| {
+            let req = attempt_request(parts, body, n, attempted);
+            let started = Instant::now();
+            inner
+                .clone()
+                .oneshot(req)
+                .map(move |result| (n, started.elapsed(), result))
+        };
+
+        let mut in_flight = FuturesUnordered::new();
+        in_flight.push(attempt(0));
+        let mut sent = 1;
+        let next = tokio::time::sleep(delay);
+        tokio::pin!(next);
+        loop {
+            tokio::select! {
+                Some((n, latency, result)) = in_flight.next() => {
+                    let failed = match result {
+                        Ok(resp) => match failed_status(resp.headers()).map(|status| status.code()) {
+                            None => {
+                                self.latencies.record(&path, latency);
+                                return Ok(with_previous_attempts(resp, n));
+                            }
+                            Some(code) if !self.non_fatal.contains(&code) => {
+                                return Ok(with_previous_attempts(resp, n));
+                            }
+                            Some(_) => Ok(with_previous_attempts(resp, n)),
+                        },
+                        // the provider could not be reached
+                        Err(err) if self.non_fatal.contains(&Code::Unavailable) => Err(err.into()),
+                        Err(err) => return Err(err.into()),
+                    };
+                    // a non fatal failure lets the next attempt go right away
+                    if sent < self.max_attempts {
+                        in_flight.push(attempt(sent));
+                        sent += 1;
+                        next.as_mut().reset(Instant::now() + delay);
+                    } else if in_flight.is_empty() {
+                        // the last failure of the call
+                        return failed;
+                    }
+                }
+                _ = next.as_mut(), if sent < self.max_attempts => {
+                    in_flight.push(attempt(sent));
+                    sent += 1;
+                    next.as_mut().reset(Instant::now() + delay);
+                }
+            }
+        }
+    }
+}
+
+/// Request of the attempt `n` of a hedged call, carrying the extensions
+/// read by the load balancer and the invokers.
+fn attempt_request(
+    parts: &http::request::Parts,
+    body: &Bytes,
+    n: u32,
+    attempted: &AttemptedProviders,
+) -> Request<CloneBody> {
+    let mut req = Request::new(CloneBody::new(hyper::Body::from(body.clone())));
+    *req.method_mut() = parts.method.clone();
+    *req.uri_mut() = parts.uri.clone();
+    *req.version_mut() = parts.version;
+    *req.headers_mut() = parts.headers.clone();
+    if n > 0 {
+        req.headers_mut()
+            .insert(GRPC_PREVIOUS_RPC_ATTEMPTS, HeaderValue::from(n));
+    }
+    let extensions = req.extensions_mut();
+    if let Some(method) = parts.extensions.get::<MethodConfig>() {
+        extensions.insert(method.clone());
+    }
+    if let Some(options) = parts.extensions.get::<CallOptions>() {
+        extensions.insert(options.clone());
+    }
+    if let Some(size) = parts.extensions.get::<RequestSize>() {
+        extensions.insert(size.clone());
+    }
+    if let Some(context) = parts.extensions.get::<opentelemetry::Context>() {
+        extensions.insert(context.clone());
+    }
+    extensions.insert(attempted.clone());
+    req
+}
+
+fn with_previous_attempts<B>(mut resp: Response<B>, n: u32) -> Response<B> {
+    if n > 0 {
+        resp.headers_mut()
+            .insert(GRPC_PREVIOUS_RPC_ATTEMPTS, HeaderValue::from(n));
+    }
+    resp
+}
+
+enum Buffered {
+    Full(Bytes),
+    /// the body outgrew the buffer, its data read so far put back in front
+    Partial(hyper::Body),
+}
+
+async fn buffer(mut body: hyper::Body, limit: usize) -> Result<Buffered, StdError> {
+    let mut chunks = Vec::new();
+    let mut size = 0;
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        size += chunk.len();
+        chunks.push(chunk);
+        if size > limit {
+            let read = futures_util::stream::iter(chunks.into_iter().map(Ok));
+            return Ok(Buffered::Partial(hyper::Body::wrap_stream(
+                read.chain(body),
+            )));
+        }
+    }
+    let mut buf = BytesMut::with_capacity(size);
+    for chunk in chunks {
+        buf.extend_from_slice(&chunk);
+    }
+    Ok(Buffered::Full(buf.freeze()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        task::{Context, Poll},
+    };
+
+    use super::*;
+    use crate::cluster::{failover::Failover, Cluster};
+
+    /// Invoker answering the attempt `n` after `replies[n]`, with the given
+    /// status code.
+    #[derive(Clone)]
+    struct FakeInvoker {
+        replies: Arc<Vec<(Duration, Code)>>,
+        bodies: Arc<Mutex<Vec<Bytes>>>,
+        cancelled: Arc<AtomicUsize>,
+        hedged: Arc<AtomicUsize>,
+    }
+
+    impl FakeInvoker {
+        fn new(replies: Vec<(u64, Code)>) -> Self {
+            Self {
+                replies: Arc::new(
+                    replies
+                        .into_iter()
+                        .map(|(ms, code)| (Duration::from_millis(ms), code))
+                        .collect(),
+                ),
+                bodies: Default::default(),
+                cancelled: Default::default(),
+                hedged: Default::default(),
+            }
+        }
+
+        fn attempts(&self) -> usize {
+            self.bodies.lock().unwrap().len()
+        }
+    }
+
+    /// Counts the attempts dropped before their response.
+    struct Cancelled(Option<Arc<AtomicUsize>>);
+
+    impl Cancelled {
+        fn answered(&mut self) {
+            self.0 = None;
+        }
+    }
+
+    impl Drop for Cancelled {
+        fn drop(&mut self) {
+            if let Some(cancelled) = self.0.take() {
+                cancelled.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    impl Service<Request<CloneBody>> for FakeInvoker {
+        type Response = Response<()>;
+        type Error = StdError;
+        type Future = crate::BoxFuture<Self::Response, Self::Error>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: Request<CloneBody>) -> Self::Future {
+            let n: usize = req
+                .headers()
+                .get(GRPC_PREVIOUS_RPC_ATTEMPTS)
+                .map(|n| n.to_str().unwrap().parse().unwrap())
+                .unwrap_or(0);
+            if req.extensions().get::<AttemptedProviders>().is_some() {
+                self.hedged.fetch_add(1, Ordering::SeqCst);
+            }
+            let (latency, code) = self.replies[n];
+            let bodies = self.bodies.clone();
+            let mut cancelled = Cancelled(Some(self.cancelled.clone()));
+            Box::pin(async move {
+                let body = hyper::body::to_bytes(req.into_body()).await?;
+                bodies.lock().unwrap().push(body);
+                tokio::time::sleep(latency).await;
+                cancelled.answered();
+                let mut resp = Response::new(());
+                if code != Code::Ok {
+                    resp.headers_mut()
+                        .insert("grpc-status", code.to_http_header_value());
+                }
+                Ok(resp)
+            })
+        }
+    }
+
+    async fn hedge(
+        invoker: &FakeInvoker,
+        hedging: HedgingConfig,
+    ) -> (Duration, Result<Response<()>, StdError>) {
+        let mut hedging_svc = Hedging::new(invoker.clone(), 1024);
+        let req = Request::builder()
+            .header("path", "/demo.Greeter/SayHello")
+            .body(hyper::Body::from("hello"))
+            .unwrap();
+        let started = Instant::now();
+        let result = hedging_svc.call(req, &hedging).await;
+        tokio::task::yield_now().await;
+        (started.elapsed(), result)
+    }
+
+    fn previous_attempts(resp: &Response<()>) -> Option<&str> {
+        resp.headers()
+            .get(GRPC_PREVIOUS_RPC_ATTEMPTS)
+            .map(|n| n.to_str().unwrap())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_hedging() {
+        // the slow first provider is beaten by the hedged call
+        let invoker = FakeInvoker::new(vec![(500, Code::Ok), (20, Code::Ok), (20, Code::Ok)]);
+        let (elapsed, resp) = hedge(&invoker, HedgingConfig::new(3).hedging_delay(50)).await;
+        assert_eq!(previous_attempts(&resp.unwrap()), Some("1"));
+        assert_eq!(elapsed, Duration::from_millis(70));
+        assert_eq!(invoker.attempts(), 2);
+        assert_eq!(invoker.cancelled.load(Ordering::SeqCst), 1);
+        assert_eq!(invoker.hedged.load(Ordering::SeqCst), 2);
+        assert!(invoker.bodies.lock().unwrap().iter().all(|b| b == "hello"));
+
+        // a non fatal failure sends the next attempt right away
+        let invoker = FakeInvoker::new(vec![(10, Code::Unavailable), (30, Code::Ok)]);
+        let hedging = HedgingConfig::new(2)
+            .hedging_delay(50)
+            .non_fatal_status_codes(vec!["UNAVAILABLE".to_string()]);
+        let (elapsed, resp) = hedge(&invoker, hedging).await;
+        assert_eq!(previous_attempts(&resp.unwrap()), Some("1"));
+        assert_eq!(elapsed, Duration::from_millis(40));
+
+        // a fatal one ends the call
+        let invoker = FakeInvoker::new(vec![(10, Code::InvalidArgument), (30, Code::Ok)]);
+        let (elapsed, resp) = hedge(&invoker, HedgingConfig::new(2).hedging_delay(50)).await;
+        let resp = resp.unwrap();
+        assert_eq!(
+            failed_status(resp.headers()).unwrap().code(),
+            Code::InvalidArgument
+        );
+        assert_eq!(previous_attempts(&resp), None);
+        assert_eq!(elapsed, Duration::from_millis(10));
+        assert_eq!(invoker.attempts(), 1);
+
+        // no more than max_attempts are sent, the last failure wins
+        let invoker = FakeInvoker::new(vec![
+            (100, Code::Unavailable),
+            (100, Code::Unavailable),
+            (100, Code::Unavailable),
+        ]);
+        let hedging = HedgingConfig::new(3)
+            .hedging_delay(10)
+            .non_fatal_status_codes(vec!["14".to_string()]);
+        let (elapsed, resp) = hedge(&invoker, hedging).await;
+        assert_eq!(previous_attempts(&resp.unwrap()), Some("2"));
+        assert_eq!(elapsed, Duration::from_millis(120));
+        assert_eq!(invoker.attempts(), 3);
+        assert_eq!(invoker.cancelled.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_hedging_delay_from_latencies() {
+        let latencies = Latencies::default();
+        assert_eq!(latencies.p95("/demo.Greeter/SayHello"), None);
+        for ms in 1..=100 {
+            latencies.record("/demo.Greeter/SayHello", Duration::from_millis(ms));
+        }
+        assert_eq!(
+            latencies.p95("/demo.Greeter/SayHello"),
+            Some(Duration::from_millis(95))
+        );
+
+        let invoker = FakeInvoker::new(vec![(500, Code::Ok), (10, Code::Ok)]);
+        let mut hedging_svc = Hedging::new(invoker.clone(), 1024).with_latencies(latencies);
+        let req = Request::builder()
+            .header("path", "/demo.Greeter/SayHello")
+            .body(hyper::Body::from("hello"))
+            .unwrap();
+        let started = Instant::now();
+        hedging_svc.call(req, &HedgingConfig::new(2)).await.unwrap();
+        assert_eq!(started.elapsed(), Duration::from_millis(105));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_only_unary_calls_hedged() {
+        let hedging = HedgingConfig::new(3).hedging_delay(50);
+        let method = MethodConfig::default().hedging(hedging);
+        for unary in [true, false] {
+            let invoker = FakeInvoker::new(vec![(500, Code::Ok), (20, Code::Ok)]);
+            let mut cluster = Cluster {
+                failover: Failover::new(invoker.clone(), 0),
+                hedging: Hedging::new(invoker.clone(), 1024),
+                retry_buffer_size: 1024,
+            };
+            let mut req = Request::builder()
+                .header("path", "/demo.Greeter/SayHello")
+                .body(hyper::Body::from("hello"))
+                .unwrap();
+            req.extensions_mut().insert(method.clone());
+            if unary {
+                req.extensions_mut().insert(UnaryCall);
+            }
+            cluster.call(req).await.unwrap();
+
+            // streaming calls, whose request is not read whole, are sent once
+            let attempts = if unary { 2 } else { 1 };
+            assert_eq!(invoker.attempts(), attempts);
+            let hedged = if unary { 2 } else { 0 };
+            assert_eq!(invoker.hedged.load(Ordering::SeqCst), hedged);
+        }
+    }
+}
@@ -19,26 +19,64 @@ use http::Request;
 use tower_service::Service;
 
 use crate::{
-    codegen::RpcInvocation, invoker::clone_body::CloneBody, param::Param, svc::NewService,
+    codegen::RpcInvocation,
+    config::method::MethodConfig,
+    invoker::clone_body::{CloneBody, DEFAULT_RETRY_BUFFER_SIZE},
+    metrics::rpc::RequestSize,
+    param::Param,
+    svc::NewService,
+    StdError,
 };
 
-use self::failover::Failover;
+use self::{
+    failover::Failover,
+    hedging::{Hedging, Latencies, UnaryCall},
+};
 
 mod failover;
+pub mod hedging;
+pub mod router;
+
+/// Times a failed call is retried on another provider.
+pub const DEFAULT_RETRIES: u32 = 2;
+
+/// Names accepted by the `cluster` of a reference.
+pub const CLUSTERS: &[&str] = &["failover", "failfast"];
 
 pub struct NewCluster<N> {
     inner: N, // new loadbalancer service
+    retries: u32,
+    retry_buffer_size: usize,
+    latencies: Latencies,
 }
 
 pub struct Cluster<S> {
-    inner: S, // failover service
+    failover: Failover<S>,
+    hedging: Hedging<S>,
+    retry_buffer_size: usize,
 }
 
 impl<N> NewCluster<N> {
     pub fn layer() -> impl tower_layer::Layer<N, Service = Self> {
-        tower_layer::layer_fn(|inner: N| {
+        Self::layer_with_retries(DEFAULT_RETRIES)
+    }
+
+    pub fn layer_with_retries(retries: u32) -> impl tower_layer::Layer<N, Service = Self> {
+        Self::layer_with_retry_buffer(retries, DEFAULT_RETRY_BUFFER_SIZE)
+    }
+
+    /// Layer retrying failed calls `retries` times, as long as their request
+    /// body fits in `retry_buffer_size` bytes.
+    pub fn layer_with_retry_buffer(
+        retries: u32,
+        retry_buffer_size: usize,
+    ) -> impl tower_layer::Layer<N, Service = Self> {
+        tower_layer::layer_fn(move |inner: N| {
             NewCluster {
                 inner, // new loadbalancer service
+                retries,
+                retry_buffer_size,
+                latencies: Latencies::default(),
             }
         })
     }
@@ -49,37 +87,63 @@ where
     T: Param<RpcInvocation>,
     // new loadbalancer service
     S: NewService<T>,
+    S::Service: Clone,
 {
-    type Service = Cluster<Failover<S::Service>>;
+    type Service = Cluster<S::Service>;
 
     fn new_service(&self, target: T) -> Self::Service {
+        let loadbalancer = self.inner.new_service(target);
         Cluster {
-            inner: Failover::new(self.inner.new_service(target)),
+            failover: Failover::new(loadbalancer.clone(), self.retries),
+            hedging: Hedging::new(loadbalancer, self.retry_buffer_size)
+                .with_latencies(self.latencies.clone()),
+            retry_buffer_size: self.retry_buffer_size,
         }
     }
 }
 
-impl<S> Service<Request<hyper::Body>> for Cluster<S>
+impl<S, B> Service<Request<hyper::Body>> for Cluster<S>
 where
-    S: Service<Request<CloneBody>>,
+    // loadbalancer service
+    S: Service<Request<CloneBody>, Response = http::Response<B>> + Clone + Send + 'static,
+    S::Error: Into<StdError>,
+    S::Future: Send,
+    B: Send + 'static,
 {
     type Response = S::Response;
 
-    type Error = S::Error;
+    type Error = StdError;
 
-    type Future = S::Future;
+    type Future = crate::BoxFuture<Self::Response, StdError>;
 
     fn poll_ready(
         &mut self,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Result<(), Self::Error>> {
-        self.inner.poll_ready(cx)
+        self.failover.poll_ready(cx).map_err(Into::into)
     }
 
     fn call(&mut self, req: Request<hyper::Body>) -> Self::Future {
-        let (parts, body) = req.into_parts();
-        let clone_body = CloneBody::new(body);
+        let (mut parts, body) = req.into_parts();
+        let request_size = RequestSize::default();
+        let body = request_size.count(body);
+        parts.extensions.insert(request_size);
+        // the invoker may be polled on another task, carry the caller's trace context
+        parts.extensions.insert(opentelemetry::Context::current());
+        let hedging = parts
+            .extensions
+            .get::<MethodConfig>()
+            .and_then(|method| method.hedging.clone())
+            .filter(|hedging| hedging.max_attempts > 1)
+            .filter(|_| parts.extensions.get::<UnaryCall>().is_some());
+        if let Some(hedging) = hedging {
+            return self
+                .hedging
+                .call(Request::from_parts(parts, body), &hedging);
+        }
+        let clone_body = CloneBody::with_capacity(body, self.retry_buffer_size);
         let req = Request::from_parts(parts, clone_body);
-        self.inner.call(req)
+        let fut = self.failover.call(req);
+        Box::pin(async move { fut.await.map_err(Into::into) })
     }
 }
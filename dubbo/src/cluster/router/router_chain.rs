@@ -15,59 +15,130 @@
  * limitations under the License.
  */
 
-use crate::{cluster::router::BoxRouter, invocation::RpcInvocation};
-use url::Url;
-use std::{collections::HashMap, sync::Arc};
+use crate::Url;
+use crate::{
+    cluster::router::{zone::zone_router::ZoneRouter, BoxRouter},
+    config::router::ZoneRouterConfig,
+    invocation::RpcInvocation,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct RouterChain {
-    pub routers: HashMap<String, BoxRouter>,
+    // routers run in the order they are added
+    pub routers: Vec<BoxRouter>,
     pub self_url: Url,
 }
 
 impl RouterChain {
     pub fn new() -> Self {
         RouterChain {
-            routers: HashMap::new(),
-            self_url: Url::new(),
+            routers: Vec::new(),
+            self_url: Url::empty(),
         }
     }
 
     pub fn route(&self, mut invokers: Vec<Url>, invocation: Arc<RpcInvocation>) -> Vec<Url> {
-        for (_, value) in self.routers.iter() {
-            invokers = value.route(invokers, self.self_url.clone(), invocation.clone())
+        for router in self.routers.iter() {
+            invokers = router.route(invokers, self.self_url.clone(), invocation.clone())
         }
         invokers
     }
 
-    pub fn add_router(&mut self, key: String, router: BoxRouter) {
-        self.routers.insert(key, router);
+    pub fn add_router(&mut self, router: BoxRouter) {
+        self.routers.push(router);
     }
 }
 
-#[test]
-fn test() {
-    use crate::cluster::router::manager::router_manager::get_global_router_manager;
+impl Default for RouterChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Router chains of the services called by a client, each built on the
+/// first call of its service.
+#[derive(Debug, Clone, Default)]
+pub struct RouterChains {
+    zone: Option<ZoneRouterConfig>,
+    chains: Arc<RwLock<HashMap<String, Arc<RouterChain>>>>,
+}
+
+impl RouterChains {
+    /// Chains routing by `zone` when it is set.
+    pub fn new(zone: Option<ZoneRouterConfig>) -> Self {
+        RouterChains {
+            zone,
+            chains: Default::default(),
+        }
+    }
+
+    pub fn get(&self, service: &str) -> Arc<RouterChain> {
+        if let Some(chain) = self.chains.read().unwrap().get(service) {
+            return chain.clone();
+        }
+        let mut chain = RouterChain::new();
+        let zone_router = self
+            .zone
+            .as_ref()
+            .and_then(|config| ZoneRouter::from_config(config, service));
+        if let Some(zone_router) = zone_router {
+            chain.add_router(Box::new(zone_router));
+        }
+        let chain = Arc::new(chain);
+        self.chains
+            .write()
+            .unwrap()
+            .insert(service.to_string(), chain.clone());
+        chain
+    }
+}
 
-    let u1 = Url::from_url("tri://127.0.0.1:8888/org.apache.dubbo.sample.tri.Greeter").unwrap();
-    let u2 = Url::from_url("tri://127.0.0.1:8889/org.apache.dubbo.sample.tri.Greeter").unwrap();
-    let u3 = Url::from_url("tri://127.0.0.1:8800/org.apache.dubbo.sample.tri.Greeter").unwrap();
-    let u4 = Url::from_url("tri://127.0.2.1:8880/org.apache.dubbo.sample.tri.Greeter").unwrap();
-    let u5 = Url::from_url("tri://127.0.1.1:8882/org.apache.dubbo.sample.tri.Greeter").unwrap();
-    let u6 = Url::from_url("tri://213.0.1.1:8888/org.apache.dubbo.sample.tri.Greeter").unwrap();
-    let u7 = Url::from_url("tri://169.0.1.1:8887/org.apache.dubbo.sample.tri.Greeter").unwrap();
-    let invs = vec![u1, u2, u3, u4, u5, u6, u7];
-    let len = invs.len().clone();
-    let inv = Arc::new(
-        RpcInvocation::default()
+#[cfg(test)]
+mod tests {
+    use crate::config::router::ZoneServiceConfig;
+
+    use super::*;
+
+    #[test]
+    fn test_router_chains() {
+        let service = "org.apache.dubbo.sample.tri.Greeter";
+        let mut zone = ZoneRouterConfig {
+            enabled: true,
+            zone: "hz-a".to_string(),
+            ..Default::default()
+        };
+        zone.services.insert(
+            "test.Other".to_string(),
+            ZoneServiceConfig {
+                enabled: Some(false),
+                ..Default::default()
+            },
+        );
+        let chains = RouterChains::new(Some(zone));
+        assert!(chains.get("test.Other").routers.is_empty());
+        assert!(RouterChains::default().get(service).routers.is_empty());
+
+        let chain = chains.get(service);
+        assert_eq!(chain.routers.len(), 1);
+        assert!(Arc::ptr_eq(&chain, &chains.get(service)));
+        let invokers: Vec<Url> = ["hz-b", "hz-a", "hz-a"]
+            .iter()
+            .enumerate()
+            .map(|(i, zone)| {
+                format!("tri://127.0.0.1:{}/{}?zone={}", 8888 + i, service, zone)
+                    .parse()
+                    .unwrap()
+            })
+            .collect();
+        let invocation = RpcInvocation::default()
             .with_method_name("greet".to_string())
-            .with_service_unique_name("org.apache.dubbo.sample.tri.Greeter".to_string()),
-    );
-    let x = get_global_router_manager()
-        .read()
-        .unwrap()
-        .get_router_chain(inv.get_target_service_unique_name());
-    let result = x.route(invs, inv.clone());
-    println!("total:{},result:{}", len, result.len().clone());
-    dbg!(result);
+            .with_service_unique_name(service.to_string());
+        let routed = chain.route(invokers, Arc::new(invocation));
+        let ports: Vec<_> = routed.iter().map(|url| url.port().unwrap()).collect();
+        assert_eq!(ports, vec![8889, 8890]);
+    }
 }
@@ -0,0 +1,274 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use crate::{
+    cluster::router::Router, codegen::RpcInvocation, config::router::ZoneRouterConfig,
+    invocation::Invocation, logger::tracing::debug, Url,
+};
+
+/// Url param naming the zone of a provider.
+pub const ZONE_KEY: &str = "zone";
+
+/// Url param naming the region of a provider.
+pub const REGION_KEY: &str = "region";
+
+/// Header of a call sent only to the providers of the zone it names, to
+/// debug a zone.
+pub const FORCE_ZONE_HEADER: &str = "dubbo-force-zone";
+
+/// Prefers the providers in the zone of the consumer, see
+/// [`ZoneRouterConfig`].
+#[derive(Debug, Clone)]
+pub struct ZoneRouter {
+    zone: String,
+    region: String,
+    min_healthy_ratio: f64,
+}
+
+impl ZoneRouter {
+    pub fn new(zone: String, region: String, min_healthy_ratio: f64) -> Self {
+        ZoneRouter {
+            zone,
+            region,
+            min_healthy_ratio,
+        }
+    }
+
+    /// Router of `service`, none when zone aware routing is off for it.
+    pub fn from_config(config: &ZoneRouterConfig, service: &str) -> Option<Self> {
+        let overrides = config.services.get(service);
+        let enabled = overrides
+            .and_then(|service| service.enabled)
+            .unwrap_or(config.enabled);
+        if !enabled || config.zone.is_empty() {
+            return None;
+        }
+        let min_healthy_ratio = overrides
+            .and_then(|service| service.min_healthy_ratio)
+            .unwrap_or(config.min_healthy_ratio);
+        Some(ZoneRouter::new(
+            config.zone.clone(),
+            config.region.clone(),
+            min_healthy_ratio,
+        ))
+    }
+}
+
+/// Count of the providers of a directory in each zone, healthy or not, kept
+/// by the directory as they register.
+#[derive(Debug, Clone, Default)]
+pub struct ZoneProviders {
+    counts: Arc<RwLock<HashMap<String, usize>>>,
+}
+
+impl ZoneProviders {
+    pub fn add(&self, url: &Url) {
+        if let Some(zone) = param(url, ZONE_KEY) {
+            *self.counts.write().unwrap().entry(zone).or_default() += 1;
+        }
+    }
+
+    pub fn remove(&self, url: &Url) {
+        let Some(zone) = param(url, ZONE_KEY) else {
+            return;
+        };
+        let mut counts = self.counts.write().unwrap();
+        if let Some(count) = counts.get_mut(&zone) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(&zone);
+            }
+        }
+    }
+
+    /// Providers in `zone`.
+    pub fn count(&self, zone: &str) -> usize {
+        self.counts.read().unwrap().get(zone).copied().unwrap_or(0)
+    }
+}
+
+fn param(url: &Url, key: &str) -> Option<String> {
+    url.query_param_by_key(key)
+}
+
+impl Router for ZoneRouter {
+    fn route(&self, invokers: Vec<Url>, _url: Url, invocation: Arc<RpcInvocation>) -> Vec<Url> {
+        if let Some(zone) = invocation.get_metadata().get(FORCE_ZONE_HEADER) {
+            return invokers
+                .into_iter()
+                .filter(|url| param(url, ZONE_KEY).as_ref() == Some(zone))
+                .collect();
+        }
+        let (mut local, others): (Vec<_>, Vec<_>) = invokers
+            .into_iter()
+            .partition(|url| param(url, ZONE_KEY).as_deref() == Some(self.zone.as_str()));
+        if !local.is_empty() {
+            // providers left out by the health checks are not in `invokers`
+            let registered = invocation
+                .get_zone_providers()
+                .map_or(0, |zones| zones.count(&self.zone))
+                .max(local.len());
+            if local.len() as f64 >= self.min_healthy_ratio * registered as f64 {
+                return local;
+            }
+            debug!(
+                "{} of {} providers of {} healthy in zone {}, spilling over",
+                local.len(),
+                registered,
+                invocation.get_target_service_unique_name(),
+                self.zone
+            );
+        }
+        let (region, rest): (Vec<_>, Vec<_>) = others.into_iter().partition(|url| {
+            !self.region.is_empty()
+                && param(url, REGION_KEY).as_deref() == Some(self.region.as_str())
+        });
+        if region.is_empty() {
+            local.extend(rest);
+        } else {
+            local.extend(region);
+        }
+        local
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cluster::router::router_chain::RouterChains, config::router::ZoneServiceConfig,
+        invocation::Metadata,
+    };
+
+    use super::*;
+
+    fn urls(service: &str, zones: &[(&str, &str)]) -> Vec<Url> {
+        zones
+            .iter()
+            .enumerate()
+            .map(|(i, (region, zone))| {
+                format!(
+                    "tri://127.0.0.1:{}/{}?region={}&zone={}",
+                    9000 + i,
+                    service,
+                    region,
+                    zone
+                )
+                .parse()
+                .unwrap()
+            })
+            .collect()
+    }
+
+    fn ports(urls: Vec<Url>) -> Vec<u16> {
+        let mut ports: Vec<_> = urls.iter().map(|url| url.port().unwrap()).collect();
+        ports.sort();
+        ports
+    }
+
+    fn invocation(service: &str, force_zone: Option<&str>) -> RpcInvocation {
+        let mut metadata = Metadata::new();
+        if let Some(zone) = force_zone {
+            metadata = metadata.insert(FORCE_ZONE_HEADER.to_string(), zone.to_string());
+        }
+        RpcInvocation::default()
+            .with_service_unique_name(service.to_string())
+            .with_metadata(metadata)
+    }
+
+    #[test]
+    fn test_zone_router() {
+        let service = "test.ZoneRouter";
+        let providers = urls(
+            service,
+            &[
+                ("hz", "hz-a"),
+                ("hz", "hz-a"),
+                ("hz", "hz-b"),
+                ("sh", "sh-a"),
+            ],
+        );
+        let zones = ZoneProviders::default();
+        providers.iter().for_each(|url| zones.add(url));
+        let with_zones = |force_zone| {
+            Arc::new(invocation(service, force_zone).with_zone_providers(zones.clone()))
+        };
+        let router = ZoneRouter::new("hz-a".to_string(), "hz".to_string(), 0.5);
+        let route = |invokers: Vec<Url>, force_zone| {
+            ports(router.route(invokers, Url::empty(), with_zones(force_zone)))
+        };
+        assert_eq!(route(providers.clone(), None), vec![9000, 9001]);
+        assert_eq!(route(providers.clone(), Some("sh-a")), vec![9003]);
+        assert!(route(providers.clone(), Some("bj-a")).is_empty());
+
+        // one of the two providers of the zone is enough at a 0.5 ratio
+        let without_9001: Vec<_> = providers
+            .iter()
+            .filter(|url| url.port() != Some(9001))
+            .cloned()
+            .collect();
+        assert_eq!(route(without_9001.clone(), None), vec![9000]);
+
+        // then it spills over to the region, and to any zone without one
+        let router = ZoneRouter::new("hz-a".to_string(), "hz".to_string(), 0.8);
+        let spilled = router.route(without_9001.clone(), Url::empty(), with_zones(None));
+        assert_eq!(ports(spilled), vec![9000, 9002]);
+        let router = ZoneRouter::new("hz-a".to_string(), String::new(), 0.8);
+        let spilled = router.route(without_9001.clone(), Url::empty(), with_zones(None));
+        assert_eq!(ports(spilled), vec![9000, 9002, 9003]);
+
+        // unless the provider left out has left the directory too
+        zones.remove(&providers[1]);
+        assert_eq!(zones.count("hz-a"), 1);
+        let kept = router.route(without_9001, Url::empty(), with_zones(None));
+        assert_eq!(ports(kept), vec![9000]);
+        let no_local: Vec<_> = providers.into_iter().skip(2).collect();
+        assert_eq!(route(no_local, None), vec![9002]);
+    }
+
+    #[test]
+    fn test_zone_router_chain() {
+        let service = "test.ZoneRouterChain";
+        let mut config = ZoneRouterConfig {
+            enabled: true,
+            zone: "hz-a".to_string(),
+            ..Default::default()
+        };
+        assert!(ZoneRouter::from_config(&config, service).is_some());
+        config.services.insert(
+            "test.Other".to_string(),
+            ZoneServiceConfig {
+                enabled: Some(false),
+                ..Default::default()
+            },
+        );
+        assert!(ZoneRouter::from_config(&config, "test.Other").is_none());
+
+        let chains = RouterChains::new(Some(config));
+        let route = |service| {
+            let providers = urls(service, &[("hz", "hz-b"), ("hz", "hz-a")]);
+            let chain = chains.get(service);
+            ports(chain.route(providers, Arc::new(invocation(service, None))))
+        };
+        assert_eq!(route(service), vec![9001]);
+        assert_eq!(route("test.Other"), vec![9000, 9001]);
+    }
+}
@@ -23,6 +23,7 @@ pub use std::{
 pub use crate::StdError;
 pub use async_trait::async_trait;
 pub use bytes::Bytes;
+pub use http;
 pub use http_body::Body;
 pub use hyper::Body as hyperBody;
 pub use tower_service::Service;
@@ -32,8 +33,11 @@ pub use super::{
     invocation::{IntoStreamingRequest, Metadata, Request, Response, RpcInvocation},
     protocol::{triple::triple_invoker::TripleInvoker, Invoker},
     triple::{
-        client::TripleClient,
-        codec::{prost::ProstCodec, serde_codec::SerdeCodec, Codec},
+        client::{CallOptions, TripleClient},
+        codec::{
+            prost::ProstCodec, serde_codec::SerdeCodec, Codec, DefaultCodec, JsonCodec,
+            MessageCodec,
+        },
         decode::Decoding,
         server::{
             service::{ClientStreamingSvc, ServerStreamingSvc, StreamingSvc, UnarySvc},
@@ -49,3 +53,90 @@ pub use crate::{
         transport::connection::Connection,
     },
 };
+
+/// Response stream of a server streaming method defined with
+/// `#[dubbo::service]`.
+pub type ResponseStream<T> =
+    std::pin::Pin<Box<dyn futures_core::Stream<Item = Result<T, crate::status::Status>> + Send>>;
+
+#[cfg(test)]
+mod tests {
+    use futures_util::{stream, TryStreamExt};
+
+    use super::{async_trait, Decoding, ResponseStream};
+    use crate::{status::Status, testing};
+
+    const ECHO: &str = "org.apache.dubbo.sample.Echo";
+
+    #[crate::service(interface = "org.apache.dubbo.sample.Echo")]
+    pub trait Echo {
+        async fn echo(&self, message: String) -> Result<String, Status>;
+
+        async fn add(&self, a: i32, b: i32) -> Result<i32, Status>;
+
+        #[dubbo(name = "Repeat")]
+        async fn repeat(
+            &self,
+            message: String,
+            times: usize,
+        ) -> Result<ResponseStream<String>, Status>;
+
+        async fn concat(&self, parts: Decoding<String>) -> Result<String, Status>;
+
+        async fn upper(&self, parts: Decoding<String>) -> Result<ResponseStream<String>, Status>;
+    }
+
+    struct EchoImpl;
+
+    #[async_trait]
+    impl Echo for EchoImpl {
+        async fn echo(&self, message: String) -> Result<String, Status> {
+            Ok(message)
+        }
+
+        async fn add(&self, a: i32, b: i32) -> Result<i32, Status> {
+            Ok(a + b)
+        }
+
+        async fn repeat(
+            &self,
+            message: String,
+            times: usize,
+        ) -> Result<ResponseStream<String>, Status> {
+            Ok(Box::pin(stream::iter(vec![Ok(message); times])))
+        }
+
+        async fn concat(&self, parts: Decoding<String>) -> Result<String, Status> {
+            let parts: Vec<String> = parts.try_collect().await?;
+            Ok(parts.concat())
+        }
+
+        async fn upper(&self, parts: Decoding<String>) -> Result<ResponseStream<String>, Status> {
+            Ok(Box::pin(parts.map_ok(|part| part.to_uppercase())))
+        }
+    }
+
+    #[test]
+    fn test_service_macro_round_trip() {
+        testing::block_on(async {
+            EchoServer::register(EchoImpl);
+            let server = testing::serve(vec![ECHO.to_string()]).await;
+            let mut client = EchoClient::new(server.client(ECHO));
+
+            assert_eq!(client.echo("dubbo".to_string()).await.unwrap(), "dubbo");
+            assert_eq!(client.add(1, 2).await.unwrap(), 3);
+
+            let replies = client.repeat("hi".to_string(), 3).await.unwrap();
+            let replies: Vec<String> = replies.try_collect().await.unwrap();
+            assert_eq!(replies, vec!["hi", "hi", "hi"]);
+
+            let parts = stream::iter(vec!["a".to_string(), "b".to_string()]);
+            assert_eq!(client.concat(parts).await.unwrap(), "ab");
+
+            let parts = stream::iter(vec!["a".to_string(), "b".to_string()]);
+            let replies = client.upper(parts).await.unwrap();
+            let replies: Vec<String> = replies.try_collect().await.unwrap();
+            assert_eq!(replies, vec!["A", "B"]);
+        })
+    }
+}
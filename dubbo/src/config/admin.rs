@@ -0,0 +1,66 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_ADMIN_PORT: u16 = 22222;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AdminConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_ip")]
+    pub ip: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        AdminConfig {
+            enabled: false,
+            ip: default_ip(),
+            port: default_port(),
+        }
+    }
+}
+
+impl AdminConfig {
+    pub fn enabled(self, enabled: bool) -> Self {
+        Self { enabled, ..self }
+    }
+
+    pub fn ip(self, ip: String) -> Self {
+        Self { ip, ..self }
+    }
+
+    pub fn port(self, port: u16) -> Self {
+        Self { port, ..self }
+    }
+
+    pub fn to_addr(&self) -> String {
+        format!("{}:{}", self.ip, self.port)
+    }
+}
+
+fn default_ip() -> String {
+    "0.0.0.0".to_string()
+}
+
+fn default_port() -> u16 {
+    DEFAULT_ADMIN_PORT
+}
@@ -0,0 +1,85 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct AuthConfig {
+    /// bearer jwt required by every provider service when set
+    #[serde(default)]
+    pub jwt: Option<JwtConfig>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct JwtConfig {
+    /// accepted `iss` claims, any issuer if empty
+    #[serde(default)]
+    pub issuers: Vec<String>,
+    /// accepted `aud` claims, the audience is not checked if empty
+    #[serde(default)]
+    pub audiences: Vec<String>,
+    #[serde(default)]
+    pub keys: Vec<JwtKeyConfig>,
+}
+
+impl JwtConfig {
+    pub fn issuers(self, issuers: Vec<String>) -> Self {
+        Self { issuers, ..self }
+    }
+
+    pub fn audiences(self, audiences: Vec<String>) -> Self {
+        Self { audiences, ..self }
+    }
+
+    pub fn key(mut self, key: JwtKeyConfig) -> Self {
+        self.keys.push(key);
+        self
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JwtKeyConfig {
+    /// matched against the `kid` of the token header, the key is tried for
+    /// every token of its algorithm if empty
+    #[serde(default)]
+    pub kid: Option<String>,
+    #[serde(default = "default_algorithm")]
+    pub algorithm: String,
+    /// shared secret for the HS algorithms, PEM encoded public key otherwise
+    pub key: String,
+}
+
+impl JwtKeyConfig {
+    pub fn new(algorithm: String, key: String) -> Self {
+        JwtKeyConfig {
+            kid: None,
+            algorithm,
+            key,
+        }
+    }
+
+    pub fn kid(self, kid: String) -> Self {
+        Self {
+            kid: Some(kid),
+            ..self
+        }
+    }
+}
+
+fn default_algorithm() -> String {
+    "HS256".to_string()
+}
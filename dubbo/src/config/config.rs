@@ -15,14 +15,22 @@
  * limitations under the License.
  */
 
-use std::{collections::HashMap, env, path::PathBuf};
-
-use super::{protocol::Protocol, registry::RegistryConfig, router::RouterConfig};
-use crate::{
-    logger::tracing::{debug, error, info, warn},
-    utils::yaml_utils::yaml_file_parser,
+use std::{collections::HashMap, env};
+
+use super::{
+    admin::AdminConfig,
+    application::ApplicationConfig,
+    auth::AuthConfig,
+    config_center::ConfigCenterConfig,
+    consumer::ConsumerConfig,
+    loader::{ConfigError, ConfigLoader},
+    protocol::Protocol,
+    registry::RegistryConfig,
+    router::RouterConfig,
+    tracing::TracingConfig,
 };
-use once_cell::sync::OnceCell;
+use crate::logger::tracing::{debug, error};
+use once_cell::sync::{Lazy, OnceCell};
 use serde::{Deserialize, Serialize};
 
 use super::{protocol::ProtocolConfig, provider::ProviderConfig, service::ServiceConfig};
@@ -30,6 +38,9 @@ use super::{protocol::ProtocolConfig, provider::ProviderConfig, service::Service
 pub const DUBBO_CONFIG_PATH: &str = "application.yaml";
 
 pub static GLOBAL_ROOT_CONFIG: OnceCell<RootConfig> = OnceCell::new();
+// the defaults of `get_global_config` when loading fails, kept out of the
+// global config for `try_get_global_config` to go on reporting the error
+static DEFAULT_ROOT_CONFIG: Lazy<RootConfig> = Lazy::new(RootConfig::new);
 pub const DUBBO_CONFIG_PREFIX: &str = "dubbo";
 
 /// used to storage all structed config, from some source: cmd, file..;
@@ -37,65 +48,83 @@ pub const DUBBO_CONFIG_PREFIX: &str = "dubbo";
 #[allow(dead_code)]
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct RootConfig {
+    #[serde(default)]
+    pub application: ApplicationConfig,
+
     #[serde(default)]
     pub protocols: ProtocolConfig,
 
     #[serde(default)]
     pub provider: ProviderConfig,
 
+    #[serde(default)]
+    pub consumer: ConsumerConfig,
+
     #[serde(default)]
     pub registries: HashMap<String, RegistryConfig>,
 
     #[serde(default)]
     pub routers: RouterConfig,
 
+    #[serde(default)]
+    pub admin: AdminConfig,
+
+    #[serde(default)]
+    pub tracing: TracingConfig,
+
+    #[serde(default)]
+    pub auth: AuthConfig,
+
+    #[serde(default)]
+    pub config_center: ConfigCenterConfig,
+
     #[serde(default)]
     pub data: HashMap<String, String>,
 }
 
+/// The global config, loaded by [`RootConfig::load`] on first use. A config
+/// that fails to load is logged and replaced by the defaults, use
+/// [`try_get_global_config`] to handle the error.
 pub fn get_global_config() -> &'static RootConfig {
-    GLOBAL_ROOT_CONFIG.get_or_init(|| {
+    match try_get_global_config() {
+        Ok(config) => config,
+        Err(err) => {
+            error!("Failed to load global config, use the defaults: {}", err);
+            &DEFAULT_ROOT_CONFIG
+        }
+    }
+}
+
+pub fn try_get_global_config() -> Result<&'static RootConfig, ConfigError> {
+    GLOBAL_ROOT_CONFIG.get_or_try_init(|| {
         debug!("current path: {:?}", env::current_dir());
-        RootConfig::new()
-            .load()
-            .unwrap_or_else(|err| panic!("Failed to load global config, error: {}", err))
+        RootConfig::new().load()
     })
 }
 
 impl RootConfig {
     pub fn new() -> Self {
         Self {
+            application: ApplicationConfig::default(),
             protocols: HashMap::new(),
             registries: HashMap::new(),
             provider: ProviderConfig::new(),
+            consumer: ConsumerConfig::new(),
             routers: RouterConfig::default(),
+            admin: AdminConfig::default(),
+            tracing: TracingConfig::default(),
+            auth: AuthConfig::default(),
+            config_center: ConfigCenterConfig::default(),
             data: HashMap::new(),
         }
     }
 
-    pub fn load(&self) -> std::io::Result<Self> {
-        let config_path = match env::var("DUBBO_CONFIG_PATH") {
-            Ok(v) => {
-                info!("read config_path from env: {:?}", v);
-                v
-            }
-            Err(err) => {
-                warn!(
-                    "error loading config_path: {:?}, use default path: {:?}",
-                    err, DUBBO_CONFIG_PATH
-                );
-                crate::app_root_dir()
-                    .join(DUBBO_CONFIG_PATH)
-                    .to_str()
-                    .unwrap()
-                    .to_string()
-            }
-        };
-
-        let conf: HashMap<String, RootConfig> =
-            yaml_file_parser(PathBuf::new().join(config_path)).unwrap();
-        let root_config: RootConfig = conf.get(DUBBO_CONFIG_PREFIX).unwrap().clone();
-        debug!("origin config: {:?}", conf);
+    /// Load the layers of [`ConfigLoader::from_env`] on top of `self`.
+    pub fn load(&self) -> Result<Self, ConfigError> {
+        let root_config = ConfigLoader::from_env()
+            .with_defaults(self.clone())
+            .load()?;
+        debug!("origin config: {:?}", root_config);
         Ok(root_config)
     }
 
@@ -0,0 +1,85 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{path::PathBuf, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+
+use super::dynamic;
+
+pub const DEFAULT_REFRESH_INTERVAL: u64 = 5000;
+
+/// Protocols of [`ConfigCenterConfig`] served by dubbo itself, other config
+/// centers push their rules through [`dynamic::update`].
+pub const CONFIG_CENTERS: &[&str] = &["file"];
+
+/// Source of the dynamic config rules, see [`dynamic`].
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConfigCenterConfig {
+    /// `file` watches the yaml rules at `address`, empty disables the
+    /// config center
+    #[serde(default)]
+    pub protocol: String,
+    #[serde(default)]
+    pub address: String,
+    /// interval in milliseconds between two checks of the source
+    #[serde(default = "default_refresh_interval")]
+    pub refresh_interval: u64,
+}
+
+impl Default for ConfigCenterConfig {
+    fn default() -> Self {
+        ConfigCenterConfig {
+            protocol: String::new(),
+            address: String::new(),
+            refresh_interval: default_refresh_interval(),
+        }
+    }
+}
+
+impl ConfigCenterConfig {
+    pub fn protocol(self, protocol: String) -> Self {
+        Self { protocol, ..self }
+    }
+
+    pub fn address(self, address: String) -> Self {
+        Self { address, ..self }
+    }
+
+    pub fn refresh_interval(self, refresh_interval: u64) -> Self {
+        Self {
+            refresh_interval,
+            ..self
+        }
+    }
+
+    /// Start watching the source, `None` when there is nothing to watch.
+    pub fn watch(&self) -> Option<JoinHandle<()>> {
+        match self.protocol.as_str() {
+            "file" => Some(dynamic::watch_file(
+                PathBuf::from(&self.address),
+                Duration::from_millis(self.refresh_interval.max(1)),
+            )),
+            _ => None,
+        }
+    }
+}
+
+fn default_refresh_interval() -> u64 {
+    DEFAULT_REFRESH_INTERVAL
+}
@@ -0,0 +1,252 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{
+    health_check::HealthCheckConfig, http2::Http2Config, method::MethodConfig, tls::TlsConfig,
+};
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct ConsumerConfig {
+    /// registries used by the references that name none
+    #[serde(default)]
+    pub registry_ids: Vec<String>,
+    #[serde(default)]
+    pub references: HashMap<String, ReferenceConfig>,
+}
+
+impl ConsumerConfig {
+    pub fn new() -> Self {
+        ConsumerConfig {
+            registry_ids: vec![],
+            references: HashMap::new(),
+        }
+    }
+
+    pub fn with_registry_ids(mut self, registry_ids: Vec<String>) -> Self {
+        self.registry_ids = registry_ids;
+        self
+    }
+
+    pub fn with_references(mut self, references: HashMap<String, ReferenceConfig>) -> Self {
+        self.references = references;
+        self
+    }
+}
+
+/// A service consumed by the application, see
+/// [`ClientBuilder::from_reference`](crate::codegen::ClientBuilder::from_reference).
+///
+/// The providers are either the direct `url` or discovered in the registry
+/// named by `registry_ids`.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct ReferenceConfig {
+    #[serde(default)]
+    pub interface: String,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub registry_ids: Vec<String>,
+    #[serde(default)]
+    pub protocol: String,
+    #[serde(default)]
+    pub group: String,
+    #[serde(default)]
+    pub version: String,
+    /// timeout of a call in milliseconds
+    #[serde(default)]
+    pub timeout: Option<u64>,
+    #[serde(default)]
+    pub retries: Option<u32>,
+    /// `failover` (default) retries on another provider, `failfast` never
+    /// retries
+    #[serde(default)]
+    pub cluster: String,
+    #[serde(default)]
+    pub loadbalance: String,
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    #[serde(default)]
+    pub http2: Option<Http2Config>,
+    /// comma separated encodings of the requests, the first one compressing
+    /// them, or `identity` to send them uncompressed
+    #[serde(default)]
+    pub compression: String,
+    /// size in bytes below which requests are sent uncompressed
+    #[serde(default)]
+    pub compression_min_size: Option<usize>,
+    /// largest response in bytes, 4 MiB by default
+    #[serde(default)]
+    pub max_message_size: Option<usize>,
+    /// largest request in bytes
+    #[serde(default)]
+    pub max_encode_message_size: Option<usize>,
+    /// connections kept open to every provider, 1 by default
+    #[serde(default)]
+    pub min_connections: Option<usize>,
+    /// connections opened to a provider once the others are busy, 1 by
+    /// default
+    #[serde(default)]
+    pub max_connections: Option<usize>,
+    #[serde(default)]
+    pub methods: HashMap<String, MethodConfig>,
+    #[serde(default)]
+    pub health_check: Option<HealthCheckConfig>,
+}
+
+impl ReferenceConfig {
+    pub fn interface(self, interface: String) -> Self {
+        Self { interface, ..self }
+    }
+
+    pub fn url(self, url: String) -> Self {
+        Self { url, ..self }
+    }
+
+    pub fn registry_ids(self, registry_ids: Vec<String>) -> Self {
+        Self {
+            registry_ids,
+            ..self
+        }
+    }
+
+    pub fn protocol(self, protocol: String) -> Self {
+        Self { protocol, ..self }
+    }
+
+    pub fn group(self, group: String) -> Self {
+        Self { group, ..self }
+    }
+
+    pub fn version(self, version: String) -> Self {
+        Self { version, ..self }
+    }
+
+    pub fn timeout(self, timeout: u64) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    pub fn retries(self, retries: u32) -> Self {
+        Self {
+            retries: Some(retries),
+            ..self
+        }
+    }
+
+    pub fn cluster(self, cluster: String) -> Self {
+        Self { cluster, ..self }
+    }
+
+    pub fn loadbalance(self, loadbalance: String) -> Self {
+        Self {
+            loadbalance,
+            ..self
+        }
+    }
+
+    pub fn tls(self, tls: TlsConfig) -> Self {
+        Self {
+            tls: Some(tls),
+            ..self
+        }
+    }
+
+    pub fn http2(self, http2: Http2Config) -> Self {
+        Self {
+            http2: Some(http2),
+            ..self
+        }
+    }
+
+    pub fn health_check(self, health_check: HealthCheckConfig) -> Self {
+        Self {
+            health_check: Some(health_check),
+            ..self
+        }
+    }
+
+    pub fn compression(self, compression: String) -> Self {
+        Self {
+            compression,
+            ..self
+        }
+    }
+
+    pub fn compression_min_size(self, compression_min_size: usize) -> Self {
+        Self {
+            compression_min_size: Some(compression_min_size),
+            ..self
+        }
+    }
+
+    pub fn max_message_size(self, max_message_size: usize) -> Self {
+        Self {
+            max_message_size: Some(max_message_size),
+            ..self
+        }
+    }
+
+    pub fn max_encode_message_size(self, max_encode_message_size: usize) -> Self {
+        Self {
+            max_encode_message_size: Some(max_encode_message_size),
+            ..self
+        }
+    }
+
+    pub fn min_connections(self, min_connections: usize) -> Self {
+        Self {
+            min_connections: Some(min_connections),
+            ..self
+        }
+    }
+
+    pub fn max_connections(self, max_connections: usize) -> Self {
+        Self {
+            max_connections: Some(max_connections),
+            ..self
+        }
+    }
+
+    pub fn method(mut self, name: String, method: MethodConfig) -> Self {
+        self.methods.insert(name, method);
+        self
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ReferenceError {
+    #[error("reference {0:?} is not configured in consumer.references")]
+    NotFound(String),
+    #[error("consumer.references.{reference}: {message}")]
+    Invalid { reference: String, message: String },
+}
+
+impl ReferenceError {
+    pub(crate) fn invalid(reference: &str, message: impl Into<String>) -> Self {
+        ReferenceError::Invalid {
+            reference: reference.to_string(),
+            message: message.into(),
+        }
+    }
+}
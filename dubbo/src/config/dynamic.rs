@@ -0,0 +1,397 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Dynamic config: rules changing the behaviour of running services without
+//! a restart, following the configurators of dubbo.
+//!
+//! ```yaml
+//! configVersion: v3.0
+//! scope: service
+//! key: org.apache.dubbo.sample.UserService
+//! configs:
+//!   - side: consumer
+//!     parameters:
+//!       timeout: 3000
+//!       getUser.retries: 0
+//!   - side: provider
+//!     addresses: [10.0.0.1:20000]
+//!     parameters:
+//!       weight: 200
+//! ```
+//!
+//! `scope: application` rules are keyed by the application name and apply
+//! to its `services`, or to every service when none is listed. A
+//! `{method}.{key}` parameter wins over `key`, and service rules win over
+//! application rules.
+//!
+//! Rules are grouped by source and replaced a source at a time, so a change
+//! is seen all at once: the client reads `timeout`, `retries` and the
+//! provider `weight` and `disabled`, the server reads `tps` and
+//! `tps.interval` on every call.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Deserializer, Serialize};
+use tokio::{sync::watch, task::JoinHandle};
+
+use crate::logger::tracing::{error, info};
+
+pub const TIMEOUT_KEY: &str = "timeout";
+pub const RETRIES_KEY: &str = "retries";
+pub const WEIGHT_KEY: &str = "weight";
+pub const DISABLED_KEY: &str = "disabled";
+pub const TPS_KEY: &str = "tps";
+pub const TPS_INTERVAL_KEY: &str = "tps.interval";
+
+/// Addresses matching every instance.
+const ANY_ADDRESSES: &[&str] = &["0.0.0.0", "*"];
+
+static DYNAMIC_CONFIG: Lazy<watch::Sender<Arc<DynamicConfig>>> =
+    Lazy::new(|| watch::channel(Arc::new(DynamicConfig::default())).0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Consumer,
+    Provider,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    Application,
+    #[default]
+    Service,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfiguratorConfig {
+    #[serde(default)]
+    pub config_version: String,
+    #[serde(default)]
+    pub scope: Scope,
+    /// application name or service interface, by `scope`
+    pub key: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub configs: Vec<ConfigItem>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfigItem {
+    /// both sides when empty
+    #[serde(default)]
+    pub side: Option<Side>,
+    /// provider addresses as `ip` or `ip:port`, every provider when empty
+    #[serde(default)]
+    pub addresses: Vec<String>,
+    /// services of an application scoped rule, every service when empty
+    #[serde(default)]
+    pub services: Vec<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default, deserialize_with = "deserialize_parameters")]
+    pub parameters: HashMap<String, String>,
+}
+
+/// A snapshot of every dynamic rule, see [`dynamic_config`].
+#[derive(Debug, Clone, Default)]
+pub struct DynamicConfig {
+    application: String,
+    sources: BTreeMap<String, Vec<ConfiguratorConfig>>,
+}
+
+impl DynamicConfig {
+    pub fn application(&self) -> &str {
+        &self.application
+    }
+
+    pub fn rules(&self) -> impl Iterator<Item = &ConfiguratorConfig> {
+        self.sources.values().flatten()
+    }
+
+    /// Value of `key` for a call of `service` and `method` on `side`.
+    ///
+    /// `method` and `address` may be empty when they are unknown, items
+    /// limited to some addresses only match a known `address`.
+    pub fn parameter(
+        &self,
+        side: Side,
+        service: &str,
+        method: &str,
+        address: &str,
+        key: &str,
+    ) -> Option<&str> {
+        let items: Vec<&ConfigItem> = [Scope::Service, Scope::Application]
+            .into_iter()
+            .flat_map(|scope| {
+                // later rules win, search them first
+                let mut items: Vec<_> = self
+                    .rules()
+                    .filter(|rule| rule.enabled && rule.scope == scope)
+                    .filter(|rule| self.rule_matches(rule, service))
+                    .flat_map(|rule| {
+                        rule.configs
+                            .iter()
+                            .filter(|item| item_matches(rule, item, side, service, address))
+                    })
+                    .collect();
+                items.reverse();
+                items
+            })
+            .collect();
+
+        let method_key = format!("{}.{}", method, key);
+        let keys = if method.is_empty() {
+            vec![key]
+        } else {
+            vec![method_key.as_str(), key]
+        };
+        keys.into_iter().find_map(|key| {
+            items
+                .iter()
+                .find_map(|item| item.parameters.get(key).map(String::as_str))
+        })
+    }
+
+    /// [`DynamicConfig::parameter`] parsed as `T`, invalid values are
+    /// ignored.
+    pub fn parameter_as<T: FromStr>(
+        &self,
+        side: Side,
+        service: &str,
+        method: &str,
+        address: &str,
+        key: &str,
+    ) -> Option<T> {
+        self.parameter(side, service, method, address, key)
+            .and_then(|value| value.parse().ok())
+    }
+
+    fn rule_matches(&self, rule: &ConfiguratorConfig, service: &str) -> bool {
+        match rule.scope {
+            Scope::Service => service_name(&rule.key) == service,
+            Scope::Application => !self.application.is_empty() && rule.key == self.application,
+        }
+    }
+}
+
+fn item_matches(
+    rule: &ConfiguratorConfig,
+    item: &ConfigItem,
+    side: Side,
+    service: &str,
+    address: &str,
+) -> bool {
+    if !item.enabled || item.side.is_some_and(|s| s != side) {
+        return false;
+    }
+    if rule.scope == Scope::Application
+        && !item.services.is_empty()
+        && !item.services.iter().any(|s| service_name(s) == service)
+    {
+        return false;
+    }
+    item.addresses.is_empty()
+        || item.addresses.iter().any(|a| {
+            ANY_ADDRESSES.contains(&a.as_str())
+                || (!address.is_empty()
+                    && (a == address || address.rsplit_once(':').map(|(ip, _)| ip) == Some(a)))
+        })
+}
+
+/// Interface of a service key, `group/interface:version` or `interface`.
+fn service_name(key: &str) -> &str {
+    let key = key.rsplit_once('/').map_or(key, |(_, key)| key);
+    key.split_once(':').map_or(key, |(key, _)| key)
+}
+
+/// Service and method of a call from its path, `/{service}/{method}`.
+pub fn service_method(path: &str) -> (&str, &str) {
+    path.trim_start_matches('/')
+        .split_once('/')
+        .unwrap_or((path.trim_start_matches('/'), ""))
+}
+
+/// The current dynamic config.
+pub fn dynamic_config() -> Arc<DynamicConfig> {
+    DYNAMIC_CONFIG.borrow().clone()
+}
+
+/// Be notified of every change of the dynamic config.
+pub fn subscribe() -> watch::Receiver<Arc<DynamicConfig>> {
+    DYNAMIC_CONFIG.subscribe()
+}
+
+/// Name the application for `scope: application` rules.
+pub fn set_application(application: &str) {
+    DYNAMIC_CONFIG.send_modify(|config| {
+        Arc::make_mut(config).application = application.to_string();
+    });
+}
+
+/// Replace every rule of `source`, config centers call it on each change.
+pub fn update(source: &str, rules: Vec<ConfiguratorConfig>) {
+    DYNAMIC_CONFIG.send_modify(|config| {
+        Arc::make_mut(config)
+            .sources
+            .insert(source.to_string(), rules);
+    });
+    info!("updated dynamic config from {}", source);
+}
+
+/// Drop every rule of `source`.
+pub fn remove(source: &str) {
+    DYNAMIC_CONFIG.send_modify(|config| {
+        Arc::make_mut(config).sources.remove(source);
+    });
+}
+
+/// Parse the rules of a yaml document, or a stream of them.
+pub fn parse_rules(content: &str) -> Result<Vec<ConfiguratorConfig>, serde_yaml::Error> {
+    serde_yaml::Deserializer::from_str(content)
+        .map(Option::<ConfiguratorConfig>::deserialize)
+        .filter_map(Result::transpose)
+        .collect()
+}
+
+/// Keep the rules of the yaml file at `path` as the source named after the
+/// path, reloading it when it changes. A missing file has no rules.
+pub fn watch_file(path: PathBuf, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let source = path.display().to_string();
+        let mut modified: Option<Option<SystemTime>> = None;
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let latest = fs::metadata(&path).and_then(|m| m.modified()).ok();
+            if modified == Some(latest) {
+                continue;
+            }
+            if latest.is_none() {
+                remove(&source);
+                modified = Some(latest);
+                continue;
+            }
+            match fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|content| parse_rules(&content).map_err(|err| err.to_string()))
+            {
+                Ok(rules) => {
+                    update(&source, rules);
+                    modified = Some(latest);
+                }
+                // the file may be half written, try again on the next tick
+                Err(err) => error!("error loading dynamic config {}: {}", source, err),
+            }
+        }
+    })
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn deserialize_parameters<'de, D>(deserializer: D) -> Result<HashMap<String, String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let values = HashMap::<String, serde_yaml::Value>::deserialize(deserializer)?;
+    values
+        .into_iter()
+        .map(|(key, value)| match value {
+            serde_yaml::Value::String(value) => Ok((key, value)),
+            serde_yaml::Value::Bool(value) => Ok((key, value.to_string())),
+            serde_yaml::Value::Number(value) => Ok((key, value.to_string())),
+            _ => Err(serde::de::Error::custom(format!(
+                "parameter {} is not a scalar",
+                key
+            ))),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parameter() {
+        let rules = parse_rules(
+            r#"
+scope: application
+key: demo
+configs:
+  - side: consumer
+    parameters:
+      timeout: 1000
+      retries: 1
+---
+scope: service
+key: group/demo.UserService:1.0.0
+configs:
+  - side: consumer
+    parameters:
+      timeout: 3000
+      getUser.timeout: 500
+  - side: provider
+    addresses: [10.0.0.1]
+    parameters:
+      weight: 200
+"#,
+        )
+        .unwrap();
+        let config = DynamicConfig {
+            application: "demo".to_string(),
+            sources: BTreeMap::from([("test".to_string(), rules)]),
+        };
+
+        let timeout = |method| {
+            config.parameter_as::<u64>(Side::Consumer, "demo.UserService", method, "", TIMEOUT_KEY)
+        };
+        assert_eq!(timeout("getUser"), Some(500));
+        assert_eq!(timeout("listUsers"), Some(3000));
+        assert_eq!(
+            config.parameter(Side::Consumer, "demo.OrderService", "", "", TIMEOUT_KEY),
+            Some("1000")
+        );
+        assert_eq!(
+            config.parameter(Side::Consumer, "demo.UserService", "", "", RETRIES_KEY),
+            Some("1")
+        );
+        assert_eq!(
+            config.parameter(Side::Provider, "demo.UserService", "", "", TIMEOUT_KEY),
+            None
+        );
+
+        let weight =
+            |address| config.parameter(Side::Provider, "demo.UserService", "", address, WEIGHT_KEY);
+        assert_eq!(weight("10.0.0.1:20000"), Some("200"));
+        assert_eq!(weight("10.0.0.2:20000"), None);
+        assert_eq!(weight(""), None);
+    }
+}
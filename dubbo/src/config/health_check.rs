@@ -0,0 +1,137 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Names accepted by the `check` of a [`HealthCheckConfig`].
+pub const HEALTH_CHECKS: &[&str] = &["tcp", "h2", "grpc"];
+
+/// Health checks of the providers of a consumer, the unhealthy ones being
+/// left out of the load balancing until they are healthy again.
+///
+/// `check` actively probes every provider each `interval`: `tcp` connects
+/// to it, `h2` opens a new connection for an http/2 handshake and ping and
+/// `grpc` calls `grpc.health.v1.Health/Check`. The connection carrying the
+/// calls is not probed by `h2`, the http/2 keepalive watching it instead.
+/// `passive` counts the failed calls too; without an active check, a
+/// provider found unhealthy by them is tried again after `interval`.
+///
+/// ```yaml
+/// health_check:
+///   check: grpc
+///   interval: 5000
+///   timeout: 1000
+///   unhealthy_threshold: 3
+///   healthy_threshold: 2
+///   passive: true
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthCheckConfig {
+    /// `tcp`, `h2` or `grpc`, no active check when empty
+    #[serde(default)]
+    pub check: String,
+    /// milliseconds between two checks of a provider
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+    /// milliseconds waited for a check
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+    /// failures in a row making a provider unhealthy
+    #[serde(default = "default_unhealthy_threshold")]
+    pub unhealthy_threshold: u32,
+    /// successful checks in a row making it healthy again
+    #[serde(default = "default_healthy_threshold")]
+    pub healthy_threshold: u32,
+    /// count the calls failing to reach a provider
+    #[serde(default = "default_passive")]
+    pub passive: bool,
+}
+
+impl Default for HealthCheckConfig {
+    fn default() -> Self {
+        HealthCheckConfig {
+            check: String::new(),
+            interval: default_interval(),
+            timeout: default_timeout(),
+            unhealthy_threshold: default_unhealthy_threshold(),
+            healthy_threshold: default_healthy_threshold(),
+            passive: default_passive(),
+        }
+    }
+}
+
+impl HealthCheckConfig {
+    pub fn check(self, check: String) -> Self {
+        Self { check, ..self }
+    }
+
+    pub fn interval(self, interval: Duration) -> Self {
+        Self {
+            interval: interval.as_millis() as u64,
+            ..self
+        }
+    }
+
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: timeout.as_millis() as u64,
+            ..self
+        }
+    }
+
+    pub fn thresholds(self, unhealthy_threshold: u32, healthy_threshold: u32) -> Self {
+        Self {
+            unhealthy_threshold,
+            healthy_threshold,
+            ..self
+        }
+    }
+
+    pub fn passive(self, passive: bool) -> Self {
+        Self { passive, ..self }
+    }
+
+    pub fn interval_duration(&self) -> Duration {
+        Duration::from_millis(self.interval)
+    }
+
+    pub fn timeout_duration(&self) -> Duration {
+        Duration::from_millis(self.timeout)
+    }
+}
+
+fn default_interval() -> u64 {
+    5000
+}
+
+fn default_timeout() -> u64 {
+    1000
+}
+
+fn default_unhealthy_threshold() -> u32 {
+    3
+}
+
+fn default_healthy_threshold() -> u32 {
+    2
+}
+
+fn default_passive() -> bool {
+    true
+}
@@ -0,0 +1,130 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Http/2 settings of the connections of a consumer to its providers, unset
+/// fields keeping the defaults of hyper.
+///
+/// ```yaml
+/// http2:
+///   initial_stream_window_size: 1048576
+///   adaptive_window: true
+///   keepalive_interval: 10000
+///   keepalive_timeout: 5000
+///   connect_timeout: 3000
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Http2Config {
+    /// flow control window of a stream in bytes
+    #[serde(default)]
+    pub initial_stream_window_size: Option<u32>,
+    /// flow control window of a connection in bytes
+    #[serde(default)]
+    pub initial_connection_window_size: Option<u32>,
+    /// grow the windows with the measured bandwidth, overriding the
+    /// initial window sizes
+    #[serde(default)]
+    pub adaptive_window: bool,
+    /// largest frame in bytes, from 16384 to 16777215
+    #[serde(default)]
+    pub max_frame_size: Option<u32>,
+    /// milliseconds between pings checking the connection is alive
+    #[serde(default)]
+    pub keepalive_interval: Option<u64>,
+    /// milliseconds waited for a ping acknowledgement before closing the
+    /// connection, 20s by default
+    #[serde(default)]
+    pub keepalive_timeout: Option<u64>,
+    /// ping connections that carry no call too
+    #[serde(default)]
+    pub keepalive_while_idle: bool,
+    /// streams a connection carries before another one is opened, see
+    /// `max_connections`
+    #[serde(default)]
+    pub max_concurrent_streams: Option<usize>,
+    /// milliseconds to establish a connection
+    #[serde(default)]
+    pub connect_timeout: Option<u64>,
+}
+
+impl Http2Config {
+    pub fn initial_stream_window_size(self, size: u32) -> Self {
+        Self {
+            initial_stream_window_size: Some(size),
+            ..self
+        }
+    }
+
+    pub fn initial_connection_window_size(self, size: u32) -> Self {
+        Self {
+            initial_connection_window_size: Some(size),
+            ..self
+        }
+    }
+
+    pub fn adaptive_window(self, adaptive_window: bool) -> Self {
+        Self {
+            adaptive_window,
+            ..self
+        }
+    }
+
+    pub fn max_frame_size(self, max_frame_size: u32) -> Self {
+        Self {
+            max_frame_size: Some(max_frame_size),
+            ..self
+        }
+    }
+
+    pub fn keepalive(self, interval: Duration, timeout: Duration, while_idle: bool) -> Self {
+        Self {
+            keepalive_interval: Some(interval.as_millis() as u64),
+            keepalive_timeout: Some(timeout.as_millis() as u64),
+            keepalive_while_idle: while_idle,
+            ..self
+        }
+    }
+
+    pub fn max_concurrent_streams(self, max_concurrent_streams: usize) -> Self {
+        Self {
+            max_concurrent_streams: Some(max_concurrent_streams),
+            ..self
+        }
+    }
+
+    pub fn connect_timeout(self, connect_timeout: Duration) -> Self {
+        Self {
+            connect_timeout: Some(connect_timeout.as_millis() as u64),
+            ..self
+        }
+    }
+
+    pub fn keepalive_interval_duration(&self) -> Option<Duration> {
+        self.keepalive_interval.map(Duration::from_millis)
+    }
+
+    pub fn keepalive_timeout_duration(&self) -> Option<Duration> {
+        self.keepalive_timeout.map(Duration::from_millis)
+    }
+
+    pub fn connect_timeout_duration(&self) -> Option<Duration> {
+        self.connect_timeout.map(Duration::from_millis)
+    }
+}
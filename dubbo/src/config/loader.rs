@@ -0,0 +1,508 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+use serde_yaml::{Mapping, Value};
+use thiserror::Error;
+
+use super::{
+    consumer::ReferenceConfig, method::MethodConfig, protocol::Protocol, registry::RegistryConfig,
+    service::ServiceConfig, RootConfig, DUBBO_CONFIG_PREFIX,
+};
+use crate::logger::tracing::debug;
+
+pub const DUBBO_ENV_PREFIX: &str = "DUBBO_";
+pub const DUBBO_CONFIG_PATH_ENV: &str = "DUBBO_CONFIG_PATH";
+pub const DUBBO_PROFILE_ENV: &str = "DUBBO_PROFILE";
+
+/// Prefix of the command line arguments overriding a key, as in
+/// `--dubbo.protocols.triple.port=8889`.
+const ARG_PREFIX: &str = "--dubbo.";
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read config file {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to parse config file {path:?}: {message}")]
+    Parse { path: PathBuf, message: String },
+    #[error("{key}: {message}")]
+    Invalid { key: String, message: String },
+    #[error("{key}: environment variable {name} is not set and has no default")]
+    Placeholder { key: String, name: String },
+}
+
+/// Builds a [`RootConfig`] out of layers, each one overriding the keys of the
+/// previous ones:
+///
+/// 1. the defaults,
+/// 2. the yaml or toml files, each followed by its `{name}-{profile}.{ext}`
+///    variants,
+/// 3. the environment variables such as `DUBBO_PROTOCOLS_TRIPLE_PORT`,
+/// 4. the overrides given in code or on the command line.
+///
+/// String values may then refer to environment variables with `${NAME}` or
+/// `${NAME:default}`.
+#[derive(Debug, Default, Clone)]
+pub struct ConfigLoader {
+    defaults: Option<RootConfig>,
+    files: Vec<PathBuf>,
+    profiles: Vec<String>,
+    env_prefix: Option<String>,
+    overrides: Vec<(String, String)>,
+}
+
+impl ConfigLoader {
+    pub fn new() -> Self {
+        ConfigLoader::default()
+    }
+
+    /// The layers read by [`RootConfig::load`]: the files of
+    /// `DUBBO_CONFIG_PATH` (comma separated, `application.yaml` of the app
+    /// root by default), the profiles of `DUBBO_PROFILE` and the `DUBBO_`
+    /// environment variables.
+    pub fn from_env() -> Self {
+        let files = match env::var(DUBBO_CONFIG_PATH_ENV) {
+            Ok(paths) => split_list(&paths).map(PathBuf::from).collect(),
+            Err(_) => vec![crate::app_root_dir().join(super::DUBBO_CONFIG_PATH)],
+        };
+        let profiles = env::var(DUBBO_PROFILE_ENV)
+            .map(|profiles| split_list(&profiles).map(String::from).collect())
+            .unwrap_or_default();
+        ConfigLoader {
+            files,
+            profiles,
+            env_prefix: Some(DUBBO_ENV_PREFIX.to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_defaults(self, defaults: RootConfig) -> Self {
+        Self {
+            defaults: Some(defaults),
+            ..self
+        }
+    }
+
+    pub fn with_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.files.push(path.into());
+        self
+    }
+
+    pub fn with_profile(mut self, profile: impl Into<String>) -> Self {
+        self.profiles.push(profile.into());
+        self
+    }
+
+    /// Read the environment variables starting with `prefix`, the rest of
+    /// the name being the key path with `_` for separator.
+    pub fn with_env(self, prefix: impl Into<String>) -> Self {
+        Self {
+            env_prefix: Some(prefix.into()),
+            ..self
+        }
+    }
+
+    /// Override the value at `key`, a path such as `protocols.triple.port`.
+    pub fn with_override(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.overrides.push((key.into(), value.into()));
+        self
+    }
+
+    /// Override keys from command line arguments of the form
+    /// `--dubbo.<key>=<value>`, other arguments are ignored.
+    pub fn with_args<I>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        for arg in args {
+            let Some((key, value)) = arg
+                .strip_prefix(ARG_PREFIX)
+                .and_then(|arg| arg.split_once('='))
+            else {
+                continue;
+            };
+            self.overrides.push((key.to_string(), value.to_string()));
+        }
+        self
+    }
+
+    pub fn load(&self) -> Result<RootConfig, ConfigError> {
+        let schema = schema();
+        let mut tree = match &self.defaults {
+            Some(defaults) => to_value(defaults),
+            None => Value::Mapping(Mapping::new()),
+        };
+
+        for file in self.files.iter() {
+            merge(&mut tree, read_file(file)?);
+            for profile in self.profiles.iter() {
+                let path = profile_path(file, profile);
+                if path.is_file() {
+                    merge(&mut tree, read_file(&path)?);
+                }
+            }
+        }
+
+        if let Some(prefix) = &self.env_prefix {
+            let mut vars: Vec<(String, String)> = env::vars()
+                .filter(|(name, _)| name != DUBBO_CONFIG_PATH_ENV && name != DUBBO_PROFILE_ENV)
+                .filter_map(|(name, value)| Some((name.strip_prefix(prefix)?.to_string(), value)))
+                .filter(|(name, _)| !name.is_empty())
+                .collect();
+            vars.sort();
+            for (name, value) in vars {
+                let segments: Vec<&str> = name.split('_').filter(|s| !s.is_empty()).collect();
+                let path = resolve_env_path(&segments, Some(&tree), Some(&schema));
+                debug!(
+                    "config override from env {}{}: {}",
+                    prefix,
+                    name,
+                    path.join(".")
+                );
+                set_typed(&mut tree, &schema, &path, &value);
+            }
+        }
+
+        for (key, value) in self.overrides.iter() {
+            let path: Vec<String> = key.split('.').map(String::from).collect();
+            set_typed(&mut tree, &schema, &path, value);
+        }
+
+        interpolate(&mut tree, &schema, &mut Vec::new())?;
+
+        serde_path_to_error::deserialize(tree).map_err(|err| ConfigError::Invalid {
+            key: err.path().to_string(),
+            message: err.inner().to_string(),
+        })
+    }
+}
+
+fn split_list(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',').map(str::trim).filter(|s| !s.is_empty())
+}
+
+fn to_value<T: serde::Serialize>(value: &T) -> Value {
+    serde_yaml::to_value(value).unwrap_or(Value::Null)
+}
+
+/// The defaults of every key, map entries being described under `*`. Gives
+/// the type of the values read from strings.
+fn schema() -> Value {
+    let mut schema = to_value(&RootConfig::default());
+    let mut reference = to_value(&ReferenceConfig::default());
+    insert(
+        &mut reference,
+        &["methods", "*"],
+        to_value(&MethodConfig::default()),
+    );
+    let entries = [
+        (vec!["protocols", "*"], to_value(&Protocol::default())),
+        (
+            vec!["registries", "*"],
+            to_value(&RegistryConfig::default()),
+        ),
+        (
+            vec!["provider", "services", "*"],
+            to_value(&ServiceConfig::default()),
+        ),
+        (vec!["consumer", "references", "*"], reference),
+    ];
+    for (path, entry) in entries {
+        insert(&mut schema, &path, entry);
+    }
+    schema
+}
+
+fn insert<S: AsRef<str>>(tree: &mut Value, path: &[S], value: Value) {
+    let Some((first, rest)) = path.split_first() else {
+        *tree = value;
+        return;
+    };
+    if !tree.is_mapping() {
+        *tree = Value::Mapping(Mapping::new());
+    }
+    let Value::Mapping(map) = tree else {
+        return;
+    };
+    let key = Value::String(first.as_ref().to_string());
+    let child = map.entry(key).or_insert(Value::Null);
+    insert(child, rest, value);
+}
+
+fn lookup<'a, S: AsRef<str>>(tree: &'a Value, path: &[S]) -> Option<&'a Value> {
+    let Some((first, rest)) = path.split_first() else {
+        return Some(tree);
+    };
+    let map = tree.as_mapping()?;
+    let child = map.get(first.as_ref()).or_else(|| map.get("*"))?;
+    lookup(child, rest)
+}
+
+fn merge(base: &mut Value, layer: Value) {
+    match (base, layer) {
+        (Value::Mapping(base), Value::Mapping(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+fn read_file(path: &Path) -> Result<Value, ConfigError> {
+    let data = std::fs::read_to_string(path).map_err(|source| ConfigError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let parse_error = |message: String| ConfigError::Parse {
+        path: path.to_path_buf(),
+        message,
+    };
+    let value = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => {
+            let value: toml::Value =
+                toml::from_str(&data).map_err(|e| parse_error(e.to_string()))?;
+            serde_yaml::to_value(value).map_err(|e| parse_error(e.to_string()))?
+        }
+        _ => serde_yaml::from_str(&data).map_err(|e| parse_error(e.to_string()))?,
+    };
+    // the dubbo section of a shared application file
+    Ok(match value {
+        Value::Mapping(mut map) if map.contains_key(DUBBO_CONFIG_PREFIX) => {
+            map.remove(DUBBO_CONFIG_PREFIX).unwrap_or(Value::Null)
+        }
+        Value::Null => Value::Mapping(Mapping::new()),
+        value => value,
+    })
+}
+
+fn profile_path(file: &Path, profile: &str) -> PathBuf {
+    let stem = file
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    let name = match file.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => format!("{}-{}.{}", stem, profile, ext),
+        None => format!("{}-{}", stem, profile),
+    };
+    file.with_file_name(name)
+}
+
+/// Key path of an environment variable, matching the segments against the
+/// known keys so that `REGISTRY_IDS` finds `registry_ids` and `DEMOZK`
+/// finds `demoZK`.
+fn resolve_env_path(
+    segments: &[&str],
+    tree: Option<&Value>,
+    schema: Option<&Value>,
+) -> Vec<String> {
+    if segments.is_empty() {
+        return Vec::new();
+    }
+    let keys: Vec<&str> = [tree, schema]
+        .iter()
+        .flatten()
+        .filter_map(|value| value.as_mapping())
+        .flat_map(|map| map.keys().filter_map(Value::as_str))
+        .filter(|key| *key != "*")
+        .collect();
+    for n in (1..=segments.len()).rev() {
+        let joined = segments[..n].join("_");
+        if let Some(key) = keys
+            .iter()
+            .find(|key| key.replace('-', "_").eq_ignore_ascii_case(&joined))
+        {
+            let key = key.to_string();
+            let mut path = vec![key.clone()];
+            path.extend(resolve_env_path(
+                &segments[n..],
+                tree.and_then(|t| lookup(t, &[&key])),
+                schema.and_then(|s| lookup(s, &[&key])),
+            ));
+            return path;
+        }
+    }
+    let key = segments[0].to_lowercase();
+    let mut path = vec![key.clone()];
+    path.extend(resolve_env_path(
+        &segments[1..],
+        None,
+        schema.and_then(|s| lookup(s, &[&key])),
+    ));
+    path
+}
+
+fn set_typed(tree: &mut Value, schema: &Value, path: &[String], raw: &str) {
+    let value = typed_value(raw, lookup(schema, path).or_else(|| lookup(tree, path)));
+    insert(tree, path, value);
+}
+
+/// Value of a string given for a key, typed after `hint`, the default or
+/// current value of the key. Keys unknown to every layer stay strings.
+fn typed_value(raw: &str, hint: Option<&Value>) -> Value {
+    match hint {
+        None | Some(Value::String(_)) => Value::String(raw.to_string()),
+        Some(Value::Sequence(_)) if !raw.trim_start().starts_with('[') => Value::Sequence(
+            split_list(raw)
+                .map(|item| Value::String(item.to_string()))
+                .collect(),
+        ),
+        Some(_) => serde_yaml::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string())),
+    }
+}
+
+fn interpolate(
+    tree: &mut Value,
+    schema: &Value,
+    path: &mut Vec<String>,
+) -> Result<(), ConfigError> {
+    match tree {
+        Value::Mapping(map) => {
+            for (key, value) in map.iter_mut() {
+                path.push(key.as_str().map(String::from).unwrap_or_default());
+                interpolate(value, schema, path)?;
+                path.pop();
+            }
+        }
+        Value::Sequence(items) => {
+            for (i, value) in items.iter_mut().enumerate() {
+                path.push(i.to_string());
+                interpolate(value, schema, path)?;
+                path.pop();
+            }
+        }
+        Value::String(s) if s.contains("${") => {
+            let resolved = resolve_placeholders(s, &path.join("."))?;
+            *tree = typed_value(&resolved, lookup(schema, path));
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn resolve_placeholders(s: &str, key: &str) -> Result<String, ConfigError> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let placeholder = &rest[start + 2..start + end];
+        let (name, default) = match placeholder.split_once(':') {
+            Some((name, default)) => (name, Some(default)),
+            None => (placeholder, None),
+        };
+        match (env::var(name), default) {
+            (Ok(value), _) => out.push_str(&value),
+            (Err(_), Some(default)) => out.push_str(default),
+            (Err(_), None) => {
+                return Err(ConfigError::Placeholder {
+                    key: key.to_string(),
+                    name: name.to_string(),
+                })
+            }
+        }
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layers() {
+        let dir = env::temp_dir().join(format!("dubbo-config-loader-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("application.yaml"),
+            r#"
+logging:
+  level: debug
+dubbo:
+  protocols:
+    triple:
+      ip: 0.0.0.0
+      port: '8888'
+      name: tri
+  registries:
+    demoZK:
+      protocol: zookeeper
+      address: ${LOADER_TEST_DUBBO_UNSET:127.0.0.1:2181}
+  consumer:
+    references:
+      greeter:
+        interface: org.apache.dubbo.sample.tri.Greeter
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("application-test.yaml"),
+            "dubbo:\n  admin:\n    port: 9999\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("tracing.toml"), "[tracing]\nenabled = true\n").unwrap();
+
+        env::set_var("LOADER_TEST_DUBBO_REGISTRIES_DEMOZK_PROTOCOL", "nacos");
+        env::set_var(
+            "LOADER_TEST_DUBBO_CONSUMER_REFERENCES_GREETER_TIMEOUT",
+            "3000",
+        );
+        env::set_var("LOADER_TEST_DUBBO_CONSUMER_REGISTRY_IDS", "demoZK");
+
+        let config = ConfigLoader::new()
+            .with_file(dir.join("application.yaml"))
+            .with_file(dir.join("tracing.toml"))
+            .with_profile("test")
+            .with_env("LOADER_TEST_DUBBO_")
+            .with_args(vec!["--dubbo.protocols.triple.port=8889".to_string()])
+            .load()
+            .unwrap();
+        assert_eq!(config.protocols["triple"].port, "8889");
+        assert_eq!(config.registries["demoZK"].protocol, "nacos");
+        assert_eq!(config.registries["demoZK"].address, "127.0.0.1:2181");
+        assert_eq!(config.consumer.references["greeter"].timeout, Some(3000));
+        assert_eq!(config.consumer.registry_ids, vec!["demoZK".to_string()]);
+        assert_eq!(config.admin.port, 9999);
+        assert!(config.tracing.enabled);
+
+        let err = ConfigLoader::new()
+            .with_file(dir.join("application.yaml"))
+            .with_override("admin.port", "http")
+            .load()
+            .unwrap_err();
+        assert!(err.to_string().starts_with("admin.port: "), "{}", err);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
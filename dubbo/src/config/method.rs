@@ -0,0 +1,273 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    status::Code,
+    triple::compression::{CompressionEncoding, COMPRESSIONS, SUPPORTED_COMPRESSIONS},
+    Url,
+};
+
+/// Prefix of the url params of the methods, `methods.{name}.{key}`.
+pub const METHODS_KEY_PREFIX: &str = "methods.";
+
+/// Compressions a method may ask for, `identity` turning compression off.
+pub const METHOD_COMPRESSIONS: &[&str] = SUPPORTED_COMPRESSIONS;
+
+/// Most calls a hedged method sends, the first one included.
+pub const MAX_HEDGING_ATTEMPTS: u32 = 5;
+
+/// Overrides of a single method, keyed by the method name as sent on the
+/// wire.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct MethodConfig {
+    /// timeout of a call in milliseconds
+    #[serde(default)]
+    pub timeout: Option<u64>,
+    #[serde(default)]
+    pub retries: Option<u32>,
+    #[serde(default)]
+    pub loadbalance: String,
+    /// compression of the messages, see [`METHOD_COMPRESSIONS`]
+    #[serde(default)]
+    pub compression: String,
+    /// consumers return once the request is sent, without waiting for the
+    /// response
+    #[serde(default)]
+    pub oneway: bool,
+    /// largest message in bytes the method accepts
+    #[serde(default)]
+    pub max_message_size: Option<usize>,
+    /// largest message in bytes the method sends
+    #[serde(default)]
+    pub max_encode_message_size: Option<usize>,
+    /// hedging of the unary calls of consumers, replacing the retries
+    #[serde(default)]
+    pub hedging: Option<HedgingConfig>,
+}
+
+/// Hedging of a read-only method: the call is sent again to another provider
+/// when no response came after a delay, the first response winning and the
+/// other calls being cancelled.
+///
+/// An attempt is judged by the status of its response headers only. A
+/// provider failing after it sent the headers, with the status in the
+/// trailers, wins the race like a successful one.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct HedgingConfig {
+    /// calls sent at most, the first one included, up to
+    /// [`MAX_HEDGING_ATTEMPTS`]
+    #[serde(default)]
+    pub max_attempts: u32,
+    /// delay in milliseconds before sending the next call, the p95 latency
+    /// of the method when unset
+    #[serde(default)]
+    pub hedging_delay: Option<u64>,
+    /// status codes, such as `UNAVAILABLE` or `"14"`, failing a call without
+    /// failing the others. The next call is then sent right away.
+    #[serde(default)]
+    pub non_fatal_status_codes: Vec<String>,
+}
+
+impl HedgingConfig {
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            ..Default::default()
+        }
+    }
+
+    pub fn hedging_delay(self, hedging_delay: u64) -> Self {
+        Self {
+            hedging_delay: Some(hedging_delay),
+            ..self
+        }
+    }
+
+    pub fn non_fatal_status_codes(self, non_fatal_status_codes: Vec<String>) -> Self {
+        Self {
+            non_fatal_status_codes,
+            ..self
+        }
+    }
+
+    /// Parsed [`Self::non_fatal_status_codes`], failing on the first unknown
+    /// one.
+    pub fn non_fatal_codes(&self) -> Result<Vec<Code>, String> {
+        self.non_fatal_status_codes
+            .iter()
+            .map(|code| code.parse().map_err(|_| code.clone()))
+            .collect()
+    }
+}
+
+impl MethodConfig {
+    pub fn timeout(self, timeout: u64) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    pub fn retries(self, retries: u32) -> Self {
+        Self {
+            retries: Some(retries),
+            ..self
+        }
+    }
+
+    pub fn loadbalance(self, loadbalance: String) -> Self {
+        Self {
+            loadbalance,
+            ..self
+        }
+    }
+
+    pub fn compression(self, compression: String) -> Self {
+        Self {
+            compression,
+            ..self
+        }
+    }
+
+    pub fn oneway(self, oneway: bool) -> Self {
+        Self { oneway, ..self }
+    }
+
+    pub fn max_message_size(self, max_message_size: usize) -> Self {
+        Self {
+            max_message_size: Some(max_message_size),
+            ..self
+        }
+    }
+
+    pub fn max_encode_message_size(self, max_encode_message_size: usize) -> Self {
+        Self {
+            max_encode_message_size: Some(max_encode_message_size),
+            ..self
+        }
+    }
+
+    pub fn hedging(self, hedging: HedgingConfig) -> Self {
+        Self {
+            hedging: Some(hedging),
+            ..self
+        }
+    }
+
+    /// Compression of the method, `None` when unset and `Some(None)` when
+    /// turned off.
+    pub fn compression_encoding(&self) -> Option<Option<CompressionEncoding>> {
+        match self.compression.as_str() {
+            "" => None,
+            name => Some(COMPRESSIONS.get(name).copied().flatten()),
+        }
+    }
+
+    /// Url params of the method `name`, `&methods.{name}.{key}={value}` for
+    /// each value set.
+    pub fn to_url_params(&self, name: &str) -> String {
+        let params = [
+            ("timeout", self.timeout.map(|v| v.to_string())),
+            ("retries", self.retries.map(|v| v.to_string())),
+            ("loadbalance", Some(self.loadbalance.clone())),
+            ("compression", Some(self.compression.clone())),
+            ("oneway", self.oneway.then(|| "true".to_string())),
+            (
+                "max_message_size",
+                self.max_message_size.map(|v| v.to_string()),
+            ),
+            (
+                "max_encode_message_size",
+                self.max_encode_message_size.map(|v| v.to_string()),
+            ),
+        ];
+        params
+            .iter()
+            .filter_map(|(key, value)| Some((key, value.as_ref().filter(|v| !v.is_empty())?)))
+            .map(|(key, value)| {
+                format!(
+                    "&{}{}.{}={}",
+                    METHODS_KEY_PREFIX,
+                    name,
+                    key,
+                    urlencoding::encode(value)
+                )
+            })
+            .collect()
+    }
+
+    /// Settings of every method carried by `url`, keyed by method name.
+    pub fn from_url(url: &Url) -> HashMap<String, MethodConfig> {
+        let mut methods: HashMap<String, MethodConfig> = HashMap::new();
+        for (key, value) in url.all_query_params() {
+            let Some((name, key)) = key
+                .strip_prefix(METHODS_KEY_PREFIX)
+                .and_then(|key| key.rsplit_once('.'))
+            else {
+                continue;
+            };
+            let method = methods.entry(name.to_string()).or_default();
+            match key {
+                "timeout" => method.timeout = value.parse().ok(),
+                "retries" => method.retries = value.parse().ok(),
+                "loadbalance" => method.loadbalance = value,
+                "compression" => method.compression = value,
+                "oneway" => method.oneway = value == "true",
+                "max_message_size" => method.max_message_size = value.parse().ok(),
+                "max_encode_message_size" => method.max_encode_message_size = value.parse().ok(),
+                _ => {}
+            }
+        }
+        methods
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_params() {
+        let say_hello = MethodConfig::default()
+            .timeout(500)
+            .retries(0)
+            .compression("identity".to_string())
+            .max_message_size(1024)
+            .max_encode_message_size(2048);
+        let ping = MethodConfig::default().oneway(true);
+        let url: Url = format!(
+            "tri://127.0.0.1:20000/demo.Greeter?interface=demo.Greeter{}{}",
+            say_hello.to_url_params("SayHello"),
+            ping.to_url_params("Ping")
+        )
+        .parse()
+        .unwrap();
+
+        let methods = MethodConfig::from_url(&url);
+        assert_eq!(methods.len(), 2);
+        assert_eq!(methods["SayHello"], say_hello);
+        assert_eq!(methods["Ping"], ping);
+        assert!(matches!(
+            methods["SayHello"].compression_encoding(),
+            Some(None)
+        ));
+    }
+}
@@ -17,9 +17,22 @@
 
 pub use config::*;
 
+pub mod admin;
+pub mod application;
+pub mod auth;
 pub mod config;
+pub mod config_center;
+pub mod consumer;
+pub mod dynamic;
+pub mod health_check;
+pub mod http2;
+pub mod loader;
+pub mod method;
 pub mod protocol;
 pub mod provider;
 pub mod registry;
 pub mod router;
 pub mod service;
+pub mod tls;
+pub mod tracing;
+pub mod validation;
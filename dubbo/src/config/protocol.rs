@@ -19,6 +19,8 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
+use super::tls::TlsConfig;
+
 pub const DEFAULT_PROTOCOL: &str = "triple";
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +29,9 @@ pub struct Protocol {
     pub port: String,
     pub name: String,
 
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+
     #[serde(skip_serializing, skip_deserializing)]
     pub params: HashMap<String, String>,
 }
@@ -51,6 +56,13 @@ impl Protocol {
         Self { port, ..self }
     }
 
+    pub fn tls(self, tls: TlsConfig) -> Self {
+        Self {
+            tls: Some(tls),
+            ..self
+        }
+    }
+
     pub fn params(self, params: HashMap<String, String>) -> Self {
         Self { params, ..self }
     }
@@ -15,6 +15,8 @@
  * limitations under the License.
  */
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
@@ -59,8 +61,9 @@ pub struct TagMatchRule {
 }
 
 impl ConditionRouterConfig {
-    pub fn new(config: &String) -> Self {
-        serde_yaml::from_str(config).expect("parse error")
+    /// Parses the yaml of a condition router config.
+    pub fn new(config: &str) -> Result<Self, serde_yaml::Error> {
+        serde_yaml::from_str(config)
     }
 }
 
@@ -85,4 +88,62 @@ pub struct RouterConfig {
     pub nacos: Option<NacosConfig>,
     pub conditions: Option<Vec<ConditionRouterConfig>>,
     pub tags: Option<TagRouterConfig>,
+    pub zone: Option<ZoneRouterConfig>,
+}
+
+/// Zone aware routing: calls go to the providers whose `zone` url param is
+/// the zone of the consumer, and spill over to the other zones of its
+/// region, then to any zone, once less than `min_healthy_ratio` of the
+/// providers of its zone are healthy. `services` overrides it per service.
+///
+/// ```yaml
+/// routers:
+///   zone:
+///     enabled: true
+///     zone: hangzhou-a
+///     region: hangzhou
+///     min_healthy_ratio: 0.5
+///     services:
+///       org.apache.dubbo.sample.tri.Greeter:
+///         enabled: false
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ZoneRouterConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// zone of the consumer
+    #[serde(default)]
+    pub zone: String,
+    /// region of the consumer, preferred when calls spill over
+    #[serde(default)]
+    pub region: String,
+    #[serde(default = "default_min_healthy_ratio")]
+    pub min_healthy_ratio: f64,
+    #[serde(default)]
+    pub services: HashMap<String, ZoneServiceConfig>,
+}
+
+/// Zone aware routing of a service, unset fields keeping the global ones.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct ZoneServiceConfig {
+    #[serde(default)]
+    pub enabled: Option<bool>,
+    #[serde(default)]
+    pub min_healthy_ratio: Option<f64>,
+}
+
+impl Default for ZoneRouterConfig {
+    fn default() -> Self {
+        ZoneRouterConfig {
+            enabled: false,
+            zone: String::new(),
+            region: String::new(),
+            min_healthy_ratio: default_min_healthy_ratio(),
+            services: HashMap::new(),
+        }
+    }
+}
+
+fn default_min_healthy_ratio() -> f64 {
+    0.5
 }
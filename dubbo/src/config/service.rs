@@ -15,8 +15,12 @@
  * limitations under the License.
  */
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
+use super::method::MethodConfig;
+
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct ServiceConfig {
     pub version: String,
@@ -24,6 +28,31 @@ pub struct ServiceConfig {
     pub protocol: String,
     pub interface: String,
     pub tag: String,
+    /// `true` logs each call through the logger, any other value is the path
+    /// of the access log file
+    #[serde(default)]
+    pub accesslog: String,
+    #[serde(default)]
+    pub accesslog_args: bool,
+    /// `true` publishes a random token in the provider url, any other value
+    /// is used as the token itself
+    #[serde(default)]
+    pub token: String,
+    /// comma separated encodings the responses may be compressed with, or
+    /// `identity` to send them uncompressed
+    #[serde(default)]
+    pub compression: String,
+    /// size in bytes below which responses are sent uncompressed
+    #[serde(default)]
+    pub compression_min_size: Option<usize>,
+    /// largest request in bytes, 4 MiB by default
+    #[serde(default)]
+    pub max_message_size: Option<usize>,
+    /// largest response in bytes
+    #[serde(default)]
+    pub max_encode_message_size: Option<usize>,
+    #[serde(default)]
+    pub methods: HashMap<String, MethodConfig>,
 }
 
 impl ServiceConfig {
@@ -45,4 +74,62 @@ impl ServiceConfig {
     pub fn tag(self, tag: String) -> Self {
         Self { tag, ..self }
     }
+
+    pub fn accesslog(self, accesslog: String) -> Self {
+        Self { accesslog, ..self }
+    }
+
+    pub fn accesslog_args(self, accesslog_args: bool) -> Self {
+        Self {
+            accesslog_args,
+            ..self
+        }
+    }
+
+    pub fn token(self, token: String) -> Self {
+        Self { token, ..self }
+    }
+
+    pub fn compression(self, compression: String) -> Self {
+        Self {
+            compression,
+            ..self
+        }
+    }
+
+    pub fn compression_min_size(self, compression_min_size: usize) -> Self {
+        Self {
+            compression_min_size: Some(compression_min_size),
+            ..self
+        }
+    }
+
+    pub fn max_message_size(self, max_message_size: usize) -> Self {
+        Self {
+            max_message_size: Some(max_message_size),
+            ..self
+        }
+    }
+
+    pub fn max_encode_message_size(self, max_encode_message_size: usize) -> Self {
+        Self {
+            max_encode_message_size: Some(max_encode_message_size),
+            ..self
+        }
+    }
+
+    pub fn method(mut self, name: String, method: MethodConfig) -> Self {
+        self.methods.insert(name, method);
+        self
+    }
+
+    /// Url params of the methods, see [`MethodConfig::to_url_params`].
+    pub fn methods_url_params(&self) -> String {
+        let mut names: Vec<_> = self.methods.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .map(|name| self.methods[name].to_url_params(name))
+            .collect()
+    }
 }
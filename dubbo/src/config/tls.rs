@@ -0,0 +1,175 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{collections::HashMap, sync::RwLock, time::Duration};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::{params::constants::TLS_KEY, Url};
+
+// settings of the local listeners served over tls by address, the urls
+// published to the registries only carry a flag
+static LISTENERS: Lazy<RwLock<HashMap<String, TlsConfig>>> = Lazy::new(Default::default);
+
+/// Verification of client certificates by a server configured with a CA.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClientAuth {
+    None,
+    Optional,
+    #[default]
+    Require,
+}
+
+/// TLS settings of a protocol, used by both providers and consumers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TlsConfig {
+    /// PEM certificate chain presented to the peer, optional for consumers
+    #[serde(default)]
+    pub cert: String,
+    /// PEM private key of `cert`
+    #[serde(default)]
+    pub key: String,
+    /// PEM CA bundle verifying the peer, consumers fall back to the native
+    /// roots if empty and providers then accept any client
+    #[serde(default)]
+    pub ca: String,
+    #[serde(default)]
+    pub client_auth: ClientAuth,
+    /// SNI name sent by consumers, the host of the provider url if empty
+    #[serde(default)]
+    pub server_name: String,
+    #[serde(default = "default_alpn")]
+    pub alpn: Vec<String>,
+    /// seconds between checks of the files for changes, 0 never reloads
+    #[serde(default)]
+    pub reload_interval: u64,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        TlsConfig {
+            cert: String::new(),
+            key: String::new(),
+            ca: String::new(),
+            client_auth: ClientAuth::default(),
+            server_name: String::new(),
+            alpn: default_alpn(),
+            reload_interval: 0,
+        }
+    }
+}
+
+impl TlsConfig {
+    pub fn cert(self, cert: String) -> Self {
+        Self { cert, ..self }
+    }
+
+    pub fn key(self, key: String) -> Self {
+        Self { key, ..self }
+    }
+
+    pub fn ca(self, ca: String) -> Self {
+        Self { ca, ..self }
+    }
+
+    pub fn client_auth(self, client_auth: ClientAuth) -> Self {
+        Self {
+            client_auth,
+            ..self
+        }
+    }
+
+    pub fn server_name(self, server_name: String) -> Self {
+        Self {
+            server_name,
+            ..self
+        }
+    }
+
+    pub fn alpn(self, alpn: Vec<String>) -> Self {
+        Self { alpn, ..self }
+    }
+
+    pub fn reload_interval(self, reload_interval: u64) -> Self {
+        Self {
+            reload_interval,
+            ..self
+        }
+    }
+
+    pub fn reload_duration(&self) -> Option<Duration> {
+        match self.reload_interval {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    /// Query param flagging a service url as served over TLS, the settings
+    /// being kept by the process for the listener at `addr`.
+    pub fn export(&self, addr: &str) -> String {
+        LISTENERS
+            .write()
+            .expect("tls listeners lock poisoned")
+            .insert(addr.to_string(), self.clone());
+        format!("&{}=true", TLS_KEY)
+    }
+
+    /// Settings of the listener of a service url, `None` if it is not
+    /// served over TLS.
+    pub fn from_url(url: &Url) -> Option<Self> {
+        if url.query_param_by_key(TLS_KEY)? != "true" {
+            return None;
+        }
+        LISTENERS
+            .read()
+            .expect("tls listeners lock poisoned")
+            .get(url.authority())
+            .cloned()
+    }
+}
+
+fn default_alpn() -> Vec<String> {
+    vec!["h2".to_string()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export() {
+        let tls = TlsConfig::default()
+            .cert("/etc/dubbo/server.crt".to_string())
+            .key("/etc/dubbo/server.key".to_string());
+        let url: Url = format!(
+            "tri://127.0.0.1:18443/Greeter?interface=Greeter{}",
+            tls.export("127.0.0.1:18443")
+        )
+        .parse()
+        .unwrap();
+        assert_eq!(url.query_param_by_key(TLS_KEY).as_deref(), Some("true"));
+        assert!(!url.as_str().contains("server.key"));
+        assert_eq!(TlsConfig::from_url(&url), Some(tls));
+
+        let url: Url = "tri://127.0.0.1:18444/Greeter?interface=Greeter"
+            .parse()
+            .unwrap();
+        assert_eq!(TlsConfig::from_url(&url), None);
+    }
+}
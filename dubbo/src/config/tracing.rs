@@ -0,0 +1,79 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TracingConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// otlp grpc collector endpoint
+    #[serde(default = "default_endpoint")]
+    pub endpoint: String,
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+    /// ratio of root spans being sampled, child spans follow their parent
+    #[serde(default = "default_sampling_ratio")]
+    pub sampling_ratio: f64,
+}
+
+impl Default for TracingConfig {
+    fn default() -> Self {
+        TracingConfig {
+            enabled: false,
+            endpoint: default_endpoint(),
+            service_name: default_service_name(),
+            sampling_ratio: default_sampling_ratio(),
+        }
+    }
+}
+
+impl TracingConfig {
+    pub fn enabled(self, enabled: bool) -> Self {
+        Self { enabled, ..self }
+    }
+
+    pub fn endpoint(self, endpoint: String) -> Self {
+        Self { endpoint, ..self }
+    }
+
+    pub fn service_name(self, service_name: String) -> Self {
+        Self {
+            service_name,
+            ..self
+        }
+    }
+
+    pub fn sampling_ratio(self, sampling_ratio: f64) -> Self {
+        Self {
+            sampling_ratio,
+            ..self
+        }
+    }
+}
+
+fn default_endpoint() -> String {
+    "http://localhost:4317".to_string()
+}
+
+fn default_service_name() -> String {
+    "dubbo-rust".to_string()
+}
+
+fn default_sampling_ratio() -> f64 {
+    1.0
+}
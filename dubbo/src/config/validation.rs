@@ -0,0 +1,684 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{collections::HashMap, fmt, path::Path};
+
+use thiserror::Error;
+
+use super::{
+    config_center::{ConfigCenterConfig, CONFIG_CENTERS},
+    health_check::{HealthCheckConfig, HEALTH_CHECKS},
+    http2::Http2Config,
+    method::{MethodConfig, MAX_HEDGING_ATTEMPTS, METHOD_COMPRESSIONS},
+    router::RouterConfig,
+    tls::TlsConfig,
+    RootConfig,
+};
+use crate::{
+    cluster::CLUSTERS,
+    extension::EXTENSIONS,
+    filter::auth::JwtFilter,
+    loadbalancer::LOADBALANCES,
+    params::extension_param::ExtensionType,
+    triple::compression::{CompressionConfig, SUPPORTED_COMPRESSIONS},
+};
+
+/// A problem found in the config, `key` being the path of the offending
+/// value such as `provider.services.GreeterProvider.protocol`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    pub key: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.key, self.message)
+    }
+}
+
+/// Every problem found by [`RootConfig::validate`].
+#[derive(Debug, Error)]
+pub struct ValidationError {
+    pub issues: Vec<ConfigIssue>,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} config error(s)", self.issues.len())?;
+        for issue in self.issues.iter() {
+            write!(f, "\n  {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+impl RootConfig {
+    /// Check the config as a whole, registry schemes excepted, see
+    /// [`RootConfig::validate_with_extensions`].
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        into_result(Validator::default().validate(self))
+    }
+
+    /// [`RootConfig::validate`], checking as well that the scheme of each
+    /// registry is a registered registry extension.
+    pub async fn validate_with_extensions(&self) -> Result<(), ValidationError> {
+        let mut validator = Validator::default();
+        match EXTENSIONS.names(ExtensionType::Registry).await {
+            Ok(names) => validator.registry_schemes = Some(names),
+            Err(err) => {
+                validator.issue("registries", format!("failed to list extensions: {}", err))
+            }
+        }
+        into_result(validator.validate(self))
+    }
+}
+
+fn into_result(issues: Vec<ConfigIssue>) -> Result<(), ValidationError> {
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationError { issues })
+    }
+}
+
+#[derive(Default)]
+struct Validator {
+    registry_schemes: Option<Vec<String>>,
+    issues: Vec<ConfigIssue>,
+}
+
+impl Validator {
+    fn issue(&mut self, key: impl Into<String>, message: impl Into<String>) {
+        self.issues.push(ConfigIssue {
+            key: key.into(),
+            message: message.into(),
+        });
+    }
+
+    fn validate(mut self, config: &RootConfig) -> Vec<ConfigIssue> {
+        for (id, protocol) in sorted(&config.protocols) {
+            let key = format!("protocols.{}", id);
+            if !matches!(protocol.port.parse::<u16>(), Ok(port) if port > 0) {
+                self.issue(
+                    format!("{}.port", key),
+                    format!("invalid port {:?}, expected 1-65535", protocol.port),
+                );
+            }
+            if let Some(tls) = &protocol.tls {
+                self.tls(&format!("{}.tls", key), tls, true);
+            }
+        }
+
+        for (id, registry) in sorted(&config.registries) {
+            let key = format!("registries.{}", id);
+            if registry.address.is_empty() {
+                self.issue(format!("{}.address", key), "must not be empty");
+            }
+            match &self.registry_schemes {
+                _ if registry.protocol.is_empty() => {
+                    self.issue(format!("{}.protocol", key), "must not be empty")
+                }
+                Some(schemes) if !schemes.contains(&registry.protocol) => self.issue(
+                    format!("{}.protocol", key),
+                    format!(
+                        "no registry extension {:?} is registered, known: {}",
+                        registry.protocol,
+                        schemes.join(", ")
+                    ),
+                ),
+                _ => {}
+            }
+        }
+
+        self.ids(
+            "provider.protocol_ids",
+            &config.provider.protocol_ids,
+            &config.protocols,
+            "protocol",
+        );
+        self.ids(
+            "provider.registry_ids",
+            &config.provider.registry_ids,
+            &config.registries,
+            "registry",
+        );
+        let mut exported = HashMap::new();
+        for (name, service) in sorted(&config.provider.services) {
+            let key = format!("provider.services.{}", name);
+            if service.interface.is_empty() {
+                self.issue(format!("{}.interface", key), "must not be empty");
+            } else if let Some(other) =
+                exported.insert((&service.interface, &service.group, &service.version), name)
+            {
+                self.issue(
+                    format!("{}.interface", key),
+                    format!(
+                        "{} (group {:?}, version {:?}) is already exported by provider.services.{}",
+                        service.interface, service.group, service.version, other
+                    ),
+                );
+            }
+            self.compression(&key, &service.compression);
+            self.message_sizes(
+                &key,
+                service.max_message_size,
+                service.max_encode_message_size,
+            );
+            for (method, config) in sorted(&service.methods) {
+                self.method(&format!("{}.methods.{}", key, method), config);
+            }
+            if !config.protocols.contains_key(&service.protocol) {
+                self.issue(
+                    format!("{}.protocol", key),
+                    format!("undefined protocol {:?}", service.protocol),
+                );
+            }
+        }
+
+        self.ids(
+            "consumer.registry_ids",
+            &config.consumer.registry_ids,
+            &config.registries,
+            "registry",
+        );
+        for (name, reference) in sorted(&config.consumer.references) {
+            let key = format!("consumer.references.{}", name);
+            if reference.interface.is_empty() {
+                self.issue(format!("{}.interface", key), "must not be empty");
+            }
+            if !reference.url.is_empty() {
+                let scheme = reference.url.split_once("://").map(|(scheme, _)| scheme);
+                if !matches!(scheme, Some("tri" | "triple" | "http" | "https")) {
+                    self.issue(
+                        format!("{}.url", key),
+                        format!("unsupported url {:?}", reference.url),
+                    );
+                }
+            } else if reference.registry_ids.is_empty() && config.consumer.registry_ids.is_empty() {
+                self.issue(key.clone(), "neither url nor registry_ids is set");
+            }
+            self.ids(
+                &format!("{}.registry_ids", key),
+                &reference.registry_ids,
+                &config.registries,
+                "registry",
+            );
+            if !reference.cluster.is_empty() && !CLUSTERS.contains(&reference.cluster.as_str()) {
+                self.issue(
+                    format!("{}.cluster", key),
+                    format!(
+                        "unknown cluster {:?}, known: {}",
+                        reference.cluster,
+                        CLUSTERS.join(", ")
+                    ),
+                );
+            }
+            self.loadbalance(&format!("{}.loadbalance", key), &reference.loadbalance);
+            self.compression(&key, &reference.compression);
+            self.message_sizes(
+                &key,
+                reference.max_message_size,
+                reference.max_encode_message_size,
+            );
+            self.connections(&key, reference.min_connections, reference.max_connections);
+            for (method, config) in sorted(&reference.methods) {
+                self.method(&format!("{}.methods.{}", key, method), config);
+            }
+            if let Some(tls) = &reference.tls {
+                self.tls(&format!("{}.tls", key), tls, false);
+            }
+            if let Some(http2) = &reference.http2 {
+                self.http2(&format!("{}.http2", key), http2);
+            }
+            if let Some(health_check) = &reference.health_check {
+                self.health_check(&format!("{}.health_check", key), health_check);
+            }
+        }
+
+        if config.admin.enabled && config.admin.port == 0 {
+            self.issue("admin.port", "must not be 0");
+        }
+        if config.tracing.enabled && config.tracing.endpoint.is_empty() {
+            self.issue("tracing.endpoint", "must not be empty");
+        }
+        if !(0.0..=1.0).contains(&config.tracing.sampling_ratio) {
+            self.issue("tracing.sampling_ratio", "expected a ratio between 0 and 1");
+        }
+        if let Some(jwt) = &config.auth.jwt {
+            if let Err(err) = JwtFilter::new(jwt) {
+                self.issue("auth.jwt", err.to_string());
+            }
+        }
+        self.routers(&config.routers);
+        self.config_center(&config.config_center);
+
+        self.issues
+    }
+
+    fn ids<V>(&mut self, key: &str, ids: &[String], defined: &HashMap<String, V>, kind: &str) {
+        for (i, id) in ids.iter().enumerate() {
+            if !defined.contains_key(id) {
+                self.issue(
+                    format!("{}.{}", key, i),
+                    format!("undefined {} {:?}", kind, id),
+                );
+            }
+        }
+    }
+
+    fn config_center(&mut self, config_center: &ConfigCenterConfig) {
+        if config_center.protocol.is_empty() {
+            return;
+        }
+        if !CONFIG_CENTERS.contains(&config_center.protocol.as_str()) {
+            self.issue(
+                "config_center.protocol",
+                format!(
+                    "unsupported config center {:?}, known: {}",
+                    config_center.protocol,
+                    CONFIG_CENTERS.join(", ")
+                ),
+            );
+        }
+        if config_center.address.is_empty() {
+            self.issue("config_center.address", "must not be empty");
+        }
+    }
+
+    fn method(&mut self, key: &str, method: &MethodConfig) {
+        self.loadbalance(&format!("{}.loadbalance", key), &method.loadbalance);
+        if !method.compression.is_empty()
+            && !METHOD_COMPRESSIONS.contains(&method.compression.as_str())
+        {
+            self.issue(
+                format!("{}.compression", key),
+                format!(
+                    "unknown compression {:?}, known: {}",
+                    method.compression,
+                    METHOD_COMPRESSIONS.join(", ")
+                ),
+            );
+        }
+        self.message_sizes(key, method.max_message_size, method.max_encode_message_size);
+        if let Some(hedging) = &method.hedging {
+            if !(1..=MAX_HEDGING_ATTEMPTS).contains(&hedging.max_attempts) {
+                self.issue(
+                    format!("{}.hedging.max_attempts", key),
+                    format!(
+                        "invalid attempts {}, expected 1-{}",
+                        hedging.max_attempts, MAX_HEDGING_ATTEMPTS
+                    ),
+                );
+            }
+            if let Err(code) = hedging.non_fatal_codes() {
+                self.issue(
+                    format!("{}.hedging.non_fatal_status_codes", key),
+                    format!("unknown status code {:?}", code),
+                );
+            }
+        }
+    }
+
+    fn connections(&mut self, key: &str, min: Option<usize>, max: Option<usize>) {
+        if max == Some(0) {
+            self.issue(format!("{}.max_connections", key), "must not be 0");
+        }
+        if min.zip(max).is_some_and(|(min, max)| min > max) {
+            self.issue(
+                format!("{}.min_connections", key),
+                "must not exceed max_connections",
+            );
+        }
+    }
+
+    fn message_sizes(&mut self, key: &str, decode: Option<usize>, encode: Option<usize>) {
+        if decode == Some(0) {
+            self.issue(format!("{}.max_message_size", key), "must not be 0");
+        }
+        if encode == Some(0) {
+            self.issue(format!("{}.max_encode_message_size", key), "must not be 0");
+        }
+    }
+
+    /// The comma separated encodings of the `compression` of a service or a
+    /// reference.
+    fn compression(&mut self, key: &str, compression: &str) {
+        if compression.is_empty() {
+            return;
+        }
+        if let Err(encoding) = CompressionConfig::parse(compression) {
+            self.issue(
+                format!("{}.compression", key),
+                format!(
+                    "unknown compression {:?}, known: {}",
+                    encoding,
+                    SUPPORTED_COMPRESSIONS.join(", ")
+                ),
+            );
+        }
+    }
+
+    fn loadbalance(&mut self, key: &str, loadbalance: &str) {
+        if !loadbalance.is_empty() && !LOADBALANCES.contains(&loadbalance) {
+            self.issue(
+                key,
+                format!(
+                    "unknown loadbalance {:?}, known: {}",
+                    loadbalance,
+                    LOADBALANCES.join(", ")
+                ),
+            );
+        }
+    }
+
+    fn http2(&mut self, key: &str, http2: &Http2Config) {
+        const MAX_WINDOW_SIZE: u32 = (1 << 31) - 1;
+        for (name, size) in [
+            (
+                "initial_stream_window_size",
+                http2.initial_stream_window_size,
+            ),
+            (
+                "initial_connection_window_size",
+                http2.initial_connection_window_size,
+            ),
+        ] {
+            if size.is_some_and(|size| size > MAX_WINDOW_SIZE) {
+                self.issue(
+                    format!("{}.{}", key, name),
+                    format!("must not exceed {}", MAX_WINDOW_SIZE),
+                );
+            }
+        }
+        if let Some(size) = http2.max_frame_size {
+            if !(16_384..=16_777_215).contains(&size) {
+                self.issue(
+                    format!("{}.max_frame_size", key),
+                    format!("invalid frame size {}, expected 16384-16777215", size),
+                );
+            }
+        }
+        for (name, value) in [
+            ("keepalive_interval", http2.keepalive_interval),
+            ("keepalive_timeout", http2.keepalive_timeout),
+            ("connect_timeout", http2.connect_timeout),
+            (
+                "max_concurrent_streams",
+                http2.max_concurrent_streams.map(|v| v as u64),
+            ),
+        ] {
+            if value == Some(0) {
+                self.issue(format!("{}.{}", key, name), "must not be 0");
+            }
+        }
+        if http2.keepalive_interval.is_none()
+            && (http2.keepalive_timeout.is_some() || http2.keepalive_while_idle)
+        {
+            self.issue(
+                format!("{}.keepalive_interval", key),
+                "must be set to send keepalive pings",
+            );
+        }
+    }
+
+    fn health_check(&mut self, key: &str, health_check: &HealthCheckConfig) {
+        if !health_check.check.is_empty() && !HEALTH_CHECKS.contains(&health_check.check.as_str()) {
+            self.issue(
+                format!("{}.check", key),
+                format!(
+                    "unknown check {:?}, known: {}",
+                    health_check.check,
+                    HEALTH_CHECKS.join(", ")
+                ),
+            );
+        }
+        for (name, value) in [
+            ("interval", health_check.interval),
+            ("timeout", health_check.timeout),
+            (
+                "unhealthy_threshold",
+                health_check.unhealthy_threshold as u64,
+            ),
+            ("healthy_threshold", health_check.healthy_threshold as u64),
+        ] {
+            if value == 0 {
+                self.issue(format!("{}.{}", key, name), "must not be 0");
+            }
+        }
+    }
+
+    fn tls(&mut self, key: &str, tls: &TlsConfig, server: bool) {
+        if server && (tls.cert.is_empty() || tls.key.is_empty()) {
+            self.issue(key, "a provider needs both cert and key");
+        }
+        if tls.cert.is_empty() != tls.key.is_empty() {
+            self.issue(key, "cert and key must be set together");
+        }
+        for (name, path) in [("cert", &tls.cert), ("key", &tls.key), ("ca", &tls.ca)] {
+            if !path.is_empty() && !Path::new(path).is_file() {
+                self.issue(
+                    format!("{}.{}", key, name),
+                    format!("file {:?} does not exist", path),
+                );
+            }
+        }
+    }
+
+    fn routers(&mut self, routers: &RouterConfig) {
+        for (i, router) in routers.conditions.iter().flatten().enumerate() {
+            for (j, rule) in router.conditions.iter().enumerate() {
+                if let Err(message) = check_condition(rule) {
+                    self.issue(
+                        format!("routers.conditions.{}.conditions.{}", i, j),
+                        format!("{} in {:?}", message, rule),
+                    );
+                }
+            }
+        }
+        if let Some(tags) = &routers.tags {
+            for (i, tag) in tags.tags.iter().enumerate() {
+                if tag.name.is_empty() {
+                    self.issue(format!("routers.tags.tags.{}.name", i), "must not be empty");
+                }
+                for (j, rule) in tag.matches.iter().enumerate() {
+                    if rule.key.is_empty() {
+                        self.issue(
+                            format!("routers.tags.tags.{}.match.{}.key", i, j),
+                            "must not be empty",
+                        );
+                    }
+                }
+            }
+        }
+        if let Some(zone) = &routers.zone {
+            let enabled = zone.enabled
+                || zone
+                    .services
+                    .values()
+                    .any(|service| service.enabled == Some(true));
+            if enabled && zone.zone.is_empty() {
+                self.issue("routers.zone.zone", "must not be empty");
+            }
+            let ratios = std::iter::once(("routers.zone".to_string(), zone.min_healthy_ratio))
+                .chain(
+                    sorted(&zone.services)
+                        .into_iter()
+                        .filter_map(|(name, service)| {
+                            Some((
+                                format!("routers.zone.services.{}", name),
+                                service.min_healthy_ratio?,
+                            ))
+                        }),
+                );
+            for (key, ratio) in ratios {
+                if !(0.0..=1.0).contains(&ratio) {
+                    self.issue(
+                        format!("{}.min_healthy_ratio", key),
+                        "expected a ratio between 0 and 1",
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn sorted<V>(map: &HashMap<String, V>) -> Vec<(&String, &V)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
+/// Syntax of a condition rule, `when => then` where both sides are `&`
+/// separated `key = v1,v2` or `key != v` clauses.
+fn check_condition(rule: &str) -> Result<(), String> {
+    let rule = rule.trim();
+    if rule.is_empty() {
+        return Err("empty rule".to_string());
+    }
+    let mut sides = rule.split("=>");
+    let when = sides.next().unwrap_or_default();
+    let then = sides.next().unwrap_or_default();
+    if sides.next().is_some() {
+        return Err("more than one `=>`".to_string());
+    }
+    for (side, constant) in [(when, "true"), (then, "false")] {
+        let side = side.trim();
+        if side.is_empty() || side == constant {
+            continue;
+        }
+        for clause in side.split('&') {
+            let (key, values) = match clause.split_once("!=") {
+                Some(split) => split,
+                None => clause
+                    .split_once('=')
+                    .ok_or_else(|| format!("missing `=` in clause {:?}", clause.trim()))?,
+            };
+            let key = key.trim();
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                return Err(format!("invalid key {:?}", key));
+            }
+            if values.split(',').any(|value| value.trim().is_empty()) {
+                return Err(format!("empty value for key {:?}", key));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let config: RootConfig = serde_yaml::from_str(
+            r#"
+protocols:
+  triple:
+    ip: 0.0.0.0
+    port: '70000'
+    name: tri
+    tls:
+      cert: /not/found.pem
+provider:
+  registry_ids: [demoZK]
+  services:
+    GreeterProvider:
+      version: 1.0.0
+      group: test
+      protocol: tri
+      interface: org.apache.dubbo.sample.tri.Greeter
+      tag: ''
+    GreeterProvider2:
+      version: 1.0.0
+      group: test
+      protocol: triple
+      interface: org.apache.dubbo.sample.tri.Greeter
+      tag: ''
+consumer:
+  references:
+    greeter:
+      interface: org.apache.dubbo.sample.tri.Greeter
+      url: tri://127.0.0.1:8888
+      loadbalance: roundrobin
+      compression: zstd,br
+      min_connections: 4
+      max_connections: 2
+      methods:
+        sayHello:
+          hedging:
+            max_attempts: 8
+            non_fatal_status_codes: [UNAVAILABLE, '14', NOPE]
+      http2:
+        max_frame_size: 1024
+        keepalive_timeout: 1000
+      health_check:
+        check: http
+        healthy_threshold: 0
+routers:
+  conditions:
+    - configVersion: v3.0
+      scope: service
+      force: false
+      enabled: true
+      key: org.apache.dubbo.sample.tri.Greeter
+      conditions:
+        - method = sayHello => port = 8888
+        - method sayHello => port = 8888
+  zone:
+    min_healthy_ratio: 2
+    services:
+      org.apache.dubbo.sample.tri.Greeter:
+        enabled: true
+"#,
+        )
+        .unwrap();
+
+        let issues: Vec<String> = config
+            .validate()
+            .unwrap_err()
+            .issues
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                "protocols.triple.port: invalid port \"70000\", expected 1-65535",
+                "protocols.triple.tls: a provider needs both cert and key",
+                "protocols.triple.tls: cert and key must be set together",
+                "protocols.triple.tls.cert: file \"/not/found.pem\" does not exist",
+                "provider.registry_ids.0: undefined registry \"demoZK\"",
+                "provider.services.GreeterProvider.protocol: undefined protocol \"tri\"",
+                "provider.services.GreeterProvider2.interface: org.apache.dubbo.sample.tri.Greeter (group \"test\", version \"1.0.0\") is already exported by provider.services.GreeterProvider",
+                "consumer.references.greeter.loadbalance: unknown loadbalance \"roundrobin\", known: p2c, random",
+                "consumer.references.greeter.compression: unknown compression \"br\", known: gzip, zstd, deflate, snappy, identity",
+                "consumer.references.greeter.min_connections: must not exceed max_connections",
+                "consumer.references.greeter.methods.sayHello.hedging.max_attempts: invalid attempts 8, expected 1-5",
+                "consumer.references.greeter.methods.sayHello.hedging.non_fatal_status_codes: unknown status code \"NOPE\"",
+                "consumer.references.greeter.http2.max_frame_size: invalid frame size 1024, expected 16384-16777215",
+                "consumer.references.greeter.http2.keepalive_interval: must be set to send keepalive pings",
+                "consumer.references.greeter.health_check.check: unknown check \"http\", known: tcp, h2, grpc",
+                "consumer.references.greeter.health_check.healthy_threshold: must not be 0",
+                "routers.conditions.0.conditions.1: missing `=` in clause \"method sayHello\" in \"method sayHello => port = 8888\"",
+                "routers.zone.zone: must not be empty",
+                "routers.zone.min_healthy_ratio: expected a ratio between 0 and 1",
+            ]
+        );
+    }
+}
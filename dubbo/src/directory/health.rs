@@ -0,0 +1,529 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Health of the providers of a directory, from active checks and from the
+//! results of the calls, the unhealthy providers being left out of the load
+//! balancing.
+
+use std::sync::{Arc, Mutex, Weak};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use http::HeaderValue;
+use http_body::Body;
+use once_cell::sync::Lazy;
+use prost::Message;
+use tokio::{task::JoinHandle, time::Instant};
+use tower::ServiceExt;
+
+use crate::{
+    codegen::TripleInvoker,
+    config::health_check::HealthCheckConfig,
+    invoker::{clone_body::CloneBody, clone_invoker::CloneInvoker},
+    logger::tracing::{debug, info, warn},
+    status::Code,
+    triple::{decode::failed_status, transport::io::BoxIO},
+    utils::boxed_clone::BoxCloneService,
+    StdError,
+};
+
+/// Path of the check of the `grpc.health.v1` protocol.
+pub const GRPC_HEALTH_CHECK_PATH: &str = "/grpc.health.v1.Health/Check";
+
+static PROVIDERS: Lazy<Mutex<Vec<Weak<State>>>> = Lazy::new(Default::default);
+
+/// Providers of the consumers, as `(service, url, healthy)`.
+pub fn providers() -> Vec<(String, String, bool)> {
+    let mut providers = PROVIDERS.lock().unwrap();
+    providers.retain(|state| state.strong_count() > 0);
+    let mut statuses: Vec<_> = providers
+        .iter()
+        .filter_map(Weak::upgrade)
+        .map(|state| {
+            let healthy = state.counters.lock().unwrap().healthy;
+            (state.service.clone(), state.url.clone(), healthy)
+        })
+        .collect();
+    statuses.sort();
+    statuses
+}
+
+/// Whether the provider failed a call, as the passive checks count it: not
+/// reached, or answering that it is unavailable or too slow.
+pub fn provider_failed<B>(result: &Result<http::Response<B>, StdError>) -> bool {
+    match result {
+        Err(_) => true,
+        Ok(resp) => failed_status(resp.headers()).is_some_and(|status| {
+            matches!(status.code(), Code::Unavailable | Code::DeadlineExceeded)
+        }),
+    }
+}
+
+/// Health of a provider, shared by its invoker and its checks.
+#[derive(Clone)]
+pub struct ProviderHealth {
+    state: Arc<State>,
+}
+
+struct State {
+    service: String,
+    url: String,
+    config: Option<HealthCheckConfig>,
+    counters: Mutex<Counters>,
+}
+
+struct Counters {
+    healthy: bool,
+    failures: u32,
+    successes: u32,
+    down_since: Option<Instant>,
+}
+
+impl ProviderHealth {
+    /// Health of the provider `url` of `service`, which stays healthy
+    /// without `config`.
+    pub fn new(service: String, url: String, config: Option<HealthCheckConfig>) -> Self {
+        let state = Arc::new(State {
+            service,
+            url,
+            config,
+            counters: Mutex::new(Counters {
+                healthy: true,
+                failures: 0,
+                successes: 0,
+                down_since: None,
+            }),
+        });
+        let mut providers = PROVIDERS.lock().unwrap();
+        // pruned here too, the providers coming and going without any read
+        providers.retain(|state| state.strong_count() > 0);
+        providers.push(Arc::downgrade(&state));
+        ProviderHealth { state }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        let mut counters = self.state.counters.lock().unwrap();
+        if counters.healthy {
+            return true;
+        }
+        let Some(config) = &self.state.config else {
+            return true;
+        };
+        // without active checks the provider is tried again after a while,
+        // the next failure taking it out again
+        let retry = config.check.is_empty()
+            && counters
+                .down_since
+                .is_some_and(|since| since.elapsed() >= config.interval_duration());
+        if retry {
+            info!("trying provider {} again", self.state.url);
+            counters.healthy = true;
+            counters.failures = config.unhealthy_threshold.saturating_sub(1);
+            counters.down_since = None;
+        }
+        retry
+    }
+
+    /// Record the result of a call, as the passive checks do.
+    pub fn report_call(&self, success: bool) {
+        if self.state.config.as_ref().is_some_and(|c| c.passive) {
+            self.report(success);
+        }
+    }
+
+    /// Record the result of an active check.
+    pub fn report_check(&self, success: bool) {
+        self.report(success);
+    }
+
+    fn report(&self, success: bool) {
+        let Some(config) = &self.state.config else {
+            return;
+        };
+        let mut counters = self.state.counters.lock().unwrap();
+        if success {
+            counters.failures = 0;
+            if !counters.healthy {
+                counters.successes += 1;
+                if counters.successes >= config.healthy_threshold {
+                    info!("provider {} is healthy again", self.state.url);
+                    counters.healthy = true;
+                    counters.successes = 0;
+                    counters.down_since = None;
+                }
+            }
+        } else {
+            counters.successes = 0;
+            if counters.healthy {
+                counters.failures += 1;
+                if counters.failures >= config.unhealthy_threshold {
+                    warn!(
+                        "provider {} is unhealthy after {} failures",
+                        self.state.url, counters.failures
+                    );
+                    counters.healthy = false;
+                    counters.failures = 0;
+                    counters.down_since = Some(Instant::now());
+                }
+            }
+        }
+    }
+}
+
+/// Marks the calls of the health checks, which do not count as passive
+/// checks.
+#[derive(Clone, Copy)]
+pub(crate) struct HealthProbe;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Check {
+    Tcp,
+    /// http/2 handshake and ping on a connection of its own
+    H2,
+    Grpc,
+}
+
+/// Active check of a provider, run every `interval` of its config.
+pub(crate) struct HealthCheck {
+    check: Check,
+    host: http::Uri,
+    service: String,
+    invoker: CloneInvoker<TripleInvoker>,
+    connector: BoxCloneService<http::Uri, BoxIO, crate::Error>,
+}
+
+/// The checks of a provider, stopped when dropped.
+pub(crate) struct HealthCheckTask(JoinHandle<()>);
+
+impl Drop for HealthCheckTask {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+impl HealthCheck {
+    /// Check of the provider `host` of `service`, none unless its config
+    /// names one.
+    pub(crate) fn new(
+        config: &HealthCheckConfig,
+        host: http::Uri,
+        service: String,
+        invoker: CloneInvoker<TripleInvoker>,
+        connector: BoxCloneService<http::Uri, BoxIO, crate::Error>,
+    ) -> Option<Self> {
+        let check = match config.check.as_str() {
+            "tcp" => Check::Tcp,
+            "h2" => Check::H2,
+            "grpc" => Check::Grpc,
+            _ => return None,
+        };
+        Some(HealthCheck {
+            check,
+            host,
+            service,
+            invoker,
+            connector,
+        })
+    }
+
+    pub(crate) fn spawn(self, health: ProviderHealth) -> HealthCheckTask {
+        let config = health.state.config.clone().unwrap_or_default();
+        HealthCheckTask(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(config.interval_duration());
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let result = tokio::time::timeout(config.timeout_duration(), self.check())
+                    .await
+                    .unwrap_or_else(|_| Err("health check timed out".into()));
+                if let Err(err) = &result {
+                    debug!("{:?} check of {} failed: {}", self.check, self.host, err);
+                }
+                health.report_check(result.is_ok());
+            }
+        }))
+    }
+
+    async fn check(&self) -> Result<(), StdError> {
+        match self.check {
+            Check::Tcp => {
+                self.connector.clone().oneshot(self.host.clone()).await?;
+                Ok(())
+            }
+            Check::H2 => {
+                let io = self.connector.clone().oneshot(self.host.clone()).await?;
+                let (_send, mut conn) = h2::client::handshake(io).await?;
+                let mut ping_pong = conn.ping_pong().ok_or("ping already taken")?;
+                tokio::select! {
+                    pong = ping_pong.ping(h2::Ping::opaque()) => {
+                        pong?;
+                        Ok(())
+                    }
+                    closed = &mut conn => {
+                        closed?;
+                        Err("connection closed before the ping".into())
+                    }
+                }
+            }
+            Check::Grpc => self.grpc_check().await,
+        }
+    }
+
+    async fn grpc_check(&self) -> Result<(), StdError> {
+        let msg = HealthCheckRequest {
+            service: self.service.clone(),
+        }
+        .encode_to_vec();
+        let mut frame = BytesMut::with_capacity(5 + msg.len());
+        frame.put_u8(0);
+        frame.put_u32(msg.len() as u32);
+        frame.put_slice(&msg);
+
+        let mut req = http::Request::new(CloneBody::new(hyper::Body::from(frame.freeze())));
+        let headers = req.headers_mut();
+        headers.insert("path", HeaderValue::from_static(GRPC_HEALTH_CHECK_PATH));
+        headers.insert("grpc-accept-encoding", HeaderValue::from_static("identity"));
+        req.extensions_mut().insert(HealthProbe);
+
+        let resp = self.invoker.clone().oneshot(req).await?;
+        if let Some(status) = failed_status(resp.headers()) {
+            return Err(status.into());
+        }
+        let mut body = resp.into_body();
+        let mut data = BytesMut::new();
+        while let Some(chunk) = body.data().await {
+            data.extend_from_slice(&chunk?);
+        }
+        if let Some(status) = body.trailers().await?.as_ref().and_then(failed_status) {
+            return Err(status.into());
+        }
+        if data.len() < 5 || data[0] != 0 {
+            return Err("invalid health check response".into());
+        }
+        let resp = HealthCheckResponse::decode(Bytes::from(data).slice(5..))?;
+        if resp.status != SERVING {
+            return Err(format!("provider is not serving, status {}", resp.status).into());
+        }
+        Ok(())
+    }
+}
+
+/// `grpc.health.v1.HealthCheckRequest`
+#[derive(Clone, PartialEq, prost::Message)]
+struct HealthCheckRequest {
+    #[prost(string, tag = "1")]
+    service: String,
+}
+
+/// `grpc.health.v1.HealthCheckResponse`
+#[derive(Clone, PartialEq, prost::Message)]
+struct HealthCheckResponse {
+    #[prost(int32, tag = "1")]
+    status: i32,
+}
+
+const SERVING: i32 = 1;
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, net::TcpListener, time::Duration};
+
+    use hyper::{
+        service::{make_service_fn, service_fn},
+        Body, Request, Response, Server,
+    };
+    use tokio_stream::wrappers::ReceiverStream;
+    use tower::discover::Change;
+    use tower_service::Service;
+
+    use super::*;
+    use crate::{directory::Directory, invoker::NewInvoker, svc::NewService};
+
+    /// Http/2 server answering the health checks, serving `test.Serving`
+    /// only. Returns its address.
+    fn health_server() -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let make_svc = make_service_fn(|_conn| async {
+            Ok::<_, Infallible>(service_fn(|req: Request<Body>| async move {
+                assert_eq!(req.uri().path(), GRPC_HEALTH_CHECK_PATH);
+                let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+                let service = HealthCheckRequest::decode(&body[5..]).unwrap().service;
+                let status = if service == "test.Serving" {
+                    SERVING
+                } else {
+                    2
+                };
+                let msg = HealthCheckResponse { status }.encode_to_vec();
+                let mut frame = vec![0];
+                frame.extend((msg.len() as u32).to_be_bytes());
+                frame.extend(msg);
+                let resp = Response::builder()
+                    .header("content-type", "application/grpc")
+                    .header("grpc-status", "0")
+                    .body(Body::from(frame))
+                    .unwrap();
+                Ok::<_, Infallible>(resp)
+            }))
+        });
+        let server = Server::from_tcp(listener)
+            .unwrap()
+            .http2_only(true)
+            .serve(make_svc);
+        tokio::spawn(server);
+        addr
+    }
+
+    fn closed_addr() -> std::net::SocketAddr {
+        TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+    }
+
+    fn health_check(check: &str, url: &str, service: &str) -> HealthCheck {
+        let new_invoker = NewInvoker::new();
+        let host: http::Uri = url.parse().unwrap();
+        let connector = new_invoker.connector(&host);
+        let config = HealthCheckConfig::default().check(check.to_string());
+        let invoker = new_invoker.new_service(url.to_string());
+        HealthCheck::new(&config, host, service.to_string(), invoker, connector).unwrap()
+    }
+
+    #[test]
+    fn test_dropped_providers_pruned() {
+        for port in 0..1000 {
+            let url = format!("tri://127.0.0.1:{}/test.Pruned", port);
+            ProviderHealth::new("test.Pruned".to_string(), url, None);
+        }
+        assert!(PROVIDERS.lock().unwrap().len() < 100);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_passive_checks() {
+        let config = HealthCheckConfig::default()
+            .thresholds(2, 1)
+            .interval(Duration::from_secs(10));
+        let url = "tri://127.0.0.1:1/test.Passive".to_string();
+        let health = ProviderHealth::new("test.Passive".to_string(), url.clone(), Some(config));
+        health.report_call(false);
+        health.report_call(true);
+        health.report_call(false);
+        assert!(health.is_healthy());
+        health.report_call(false);
+        assert!(!health.is_healthy());
+        assert!(providers().contains(&("test.Passive".to_string(), url, false)));
+
+        // tried again after the interval, a single failure taking it out
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert!(health.is_healthy());
+        health.report_call(false);
+        assert!(!health.is_healthy());
+
+        // active checks alone decide when passive checks are off
+        let config = HealthCheckConfig::default()
+            .check("tcp".to_string())
+            .thresholds(1, 2)
+            .passive(false);
+        let health = ProviderHealth::new(
+            "test.Active".to_string(),
+            "tri://127.0.0.1:2/test.Active".to_string(),
+            Some(config),
+        );
+        health.report_call(false);
+        assert!(health.is_healthy());
+        health.report_check(false);
+        tokio::time::advance(Duration::from_secs(60)).await;
+        assert!(!health.is_healthy());
+        health.report_check(true);
+        assert!(!health.is_healthy());
+        health.report_check(true);
+        assert!(health.is_healthy());
+    }
+
+    #[test]
+    fn test_provider_failed() {
+        let response = |status: Option<&'static str>| {
+            let mut builder = http::Response::builder();
+            if let Some(status) = status {
+                builder = builder.header("grpc-status", status);
+            }
+            Ok(builder.body(()).unwrap())
+        };
+        assert!(!provider_failed(&response(None)));
+        assert!(!provider_failed(&response(Some("0"))));
+        // the provider answered, whatever the call failed of
+        assert!(!provider_failed(&response(Some("5"))));
+        assert!(provider_failed(&response(Some("14"))));
+        assert!(provider_failed(&response(Some("4"))));
+        assert!(provider_failed::<()>(&Err("connection refused".into())));
+    }
+
+    #[tokio::test]
+    async fn test_active_checks() {
+        let up = format!("tri://{}/test.Serving", health_server());
+        let down = format!("tri://{}/test.Serving", closed_addr());
+        for check in ["tcp", "h2", "grpc"] {
+            assert!(health_check(check, &up, "test.Serving")
+                .check()
+                .await
+                .is_ok());
+            assert!(health_check(check, &down, "test.Serving")
+                .check()
+                .await
+                .is_err());
+        }
+        let err = health_check("grpc", &up, "test.Stopped")
+            .check()
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "provider is not serving, status 2");
+    }
+
+    #[tokio::test]
+    async fn test_directory_leaves_out_unhealthy() {
+        let up = format!("tri://{}/test.Directory", health_server());
+        let down = format!("tri://{}/test.Directory", closed_addr());
+        let (tx, rx) = tokio::sync::mpsc::channel::<Result<_, StdError>>(4);
+        tx.send(Ok(Change::Insert(up.clone(), ()))).await.unwrap();
+        tx.send(Ok(Change::Insert(down.clone(), ()))).await.unwrap();
+        let config = HealthCheckConfig::default()
+            .check("tcp".to_string())
+            .interval(Duration::from_millis(50))
+            .thresholds(1, 1);
+        let mut directory = Directory::new("test.Directory".to_string(), ReceiverStream::new(rx))
+            .with_health_check(Some(config));
+        directory.ready().await.unwrap();
+        assert_eq!(directory.call(()).await.unwrap().len(), 2);
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let invokers = directory.call(()).await.unwrap();
+        let urls: Vec<_> = invokers
+            .iter()
+            .map(|invoker| invoker.url().unwrap().to_string())
+            .collect();
+        assert_eq!(urls, vec![up.clone()]);
+        let statuses = providers();
+        assert!(statuses.contains(&("test.Directory".to_string(), up, true)));
+        assert!(statuses.contains(&("test.Directory".to_string(), down.clone(), false)));
+
+        // the checks stop with the provider
+        tx.send(Ok(Change::Remove(down))).await.unwrap();
+        directory.ready().await.unwrap();
+        assert!(directory.checks.len() == 1);
+    }
+}
@@ -15,6 +15,8 @@
  * limitations under the License.
  */
 
+pub mod health;
+
 use std::{
     collections::HashMap,
     pin::Pin,
@@ -23,11 +25,15 @@ use std::{
 };
 
 use crate::{
+    cluster::router::zone::zone_router::ZoneProviders,
     codegen::{RpcInvocation, TripleInvoker},
+    config::health_check::HealthCheckConfig,
     invocation::Invocation,
     invoker::{clone_invoker::CloneInvoker, NewInvoker},
-    logger::tracing::{debug, error},
+    logger::tracing::{debug, error, warn},
+    metrics::{self, REGISTRY_NOTIFY_TOTAL, REGISTRY_PROVIDERS, REGISTRY_SUBSCRIBE_TOTAL},
     param::Param,
+    protocol::triple::service_key::ServiceSelector,
     svc::NewService,
     StdError, Url,
 };
@@ -46,6 +52,8 @@ use crate::{
 };
 use tower_service::Service;
 
+use self::health::{HealthCheck, HealthCheckTask, ProviderHealth};
+
 type BufferedDirectory =
     Buffer<Directory<ReceiverStream<Result<Change<String, ()>, StdError>>>, ()>;
 
@@ -69,12 +77,20 @@ where
 pub struct NewDirectory<N> {
     // registry
     inner: N,
+    new_invoker: NewInvoker,
+    selector: ServiceSelector,
+    health_check: Option<HealthCheckConfig>,
 }
 
 pub struct Directory<D> {
+    service_name: String,
     directory: HashMap<String, CloneInvoker<TripleInvoker>>,
+    checks: HashMap<String, HealthCheckTask>,
+    zone_providers: ZoneProviders,
     discover: D,
     new_invoker: NewInvoker,
+    selector: ServiceSelector,
+    health_check: Option<HealthCheckConfig>,
 }
 
 impl<N> NewCachedDirectory<N>
@@ -83,10 +99,42 @@ where
     <N as Service<()>>::Future: Send + 'static,
 {
     pub fn layer() -> impl tower_layer::Layer<N, Service = Self> {
-        tower_layer::layer_fn(|inner: N| {
+        Self::layer_with_invoker(NewInvoker::new())
+    }
+
+    /// Like [`NewCachedDirectory::layer`], creating the invokers of the
+    /// providers with `new_invoker`.
+    pub fn layer_with_invoker(
+        new_invoker: NewInvoker,
+    ) -> impl tower_layer::Layer<N, Service = Self> {
+        Self::layer_with_selector(new_invoker, ServiceSelector::default())
+    }
+
+    /// Like [`NewCachedDirectory::layer_with_invoker`], keeping only the
+    /// providers of the groups and versions accepted by `selector`.
+    pub fn layer_with_selector(
+        new_invoker: NewInvoker,
+        selector: ServiceSelector,
+    ) -> impl tower_layer::Layer<N, Service = Self> {
+        Self::layer_with_health_check(new_invoker, selector, None)
+    }
+
+    /// Like [`NewCachedDirectory::layer_with_selector`], checking the health
+    /// of the providers as `health_check` says.
+    pub fn layer_with_health_check(
+        new_invoker: NewInvoker,
+        selector: ServiceSelector,
+        health_check: Option<HealthCheckConfig>,
+    ) -> impl tower_layer::Layer<N, Service = Self> {
+        tower_layer::layer_fn(move |inner: N| {
             NewCachedDirectory {
                 // inner is registry
-                inner: CachedDirectory::new(NewDirectory::new(inner)),
+                inner: CachedDirectory::new(
+                    NewDirectory::new(inner)
+                        .with_new_invoker(new_invoker.clone())
+                        .with_selector(selector.clone())
+                        .with_health_check(health_check.clone()),
+                ),
             }
         })
     }
@@ -148,7 +196,30 @@ impl<N> NewDirectory<N> {
     const MAX_DIRECTORY_BUFFER_SIZE: usize = 16;
 
     pub fn new(inner: N) -> Self {
-        NewDirectory { inner }
+        NewDirectory {
+            inner,
+            new_invoker: NewInvoker::new(),
+            selector: ServiceSelector::default(),
+            health_check: None,
+        }
+    }
+
+    pub fn with_new_invoker(self, new_invoker: NewInvoker) -> Self {
+        Self {
+            new_invoker,
+            ..self
+        }
+    }
+
+    pub fn with_selector(self, selector: ServiceSelector) -> Self {
+        Self { selector, ..self }
+    }
+
+    pub fn with_health_check(self, health_check: Option<HealthCheckConfig>) -> Self {
+        Self {
+            health_check,
+            ..self
+        }
     }
 }
 
@@ -168,12 +239,19 @@ where
 
         let (tx, rx) = channel(Self::MAX_DIRECTORY_BUFFER_SIZE);
 
+        let directory = Directory::new(service_name.clone(), ReceiverStream::new(rx))
+            .with_new_invoker(self.new_invoker.clone())
+            .with_selector(self.selector.clone())
+            .with_health_check(self.health_check.clone());
+
+        let selector = self.selector.clone();
         tokio::spawn(async move {
             // todo use dubbo url model generate subscribe url
             // category:serviceInterface:version:group
             let consumer_url = format!("consumer://{}/{}", "127.0.0.1:8888", service_name);
             let mut subscribe_url: Url = consumer_url.parse().unwrap();
-            subscribe_url.add_query_param(InterfaceName::new(service_name));
+            subscribe_url.add_query_param(InterfaceName::new(service_name.clone()));
+            selector.add_url_params(&mut subscribe_url);
 
             let Ok(registry) = fut.await else {
                 error!("registry extension load failed.");
@@ -182,6 +260,18 @@ where
 
             let receiver = registry.subscribe(subscribe_url).await;
             debug!("discover start!");
+            let result = if receiver.is_ok() {
+                "success"
+            } else {
+                "failure"
+            };
+            metrics::increment_counter(
+                REGISTRY_SUBSCRIBE_TOTAL,
+                &vec![
+                    ("interface", service_name.clone()),
+                    ("result", result.to_string()),
+                ],
+            );
             match receiver {
                 Err(_e) => {
                     // error!("discover stream error: {}", e);
@@ -196,6 +286,18 @@ where
                             break;
                         }
                         Some(change) => {
+                            let change_type = match change {
+                                Ok(Change::Insert(..)) => "insert",
+                                Ok(Change::Remove(..)) => "remove",
+                                Err(_) => "error",
+                            };
+                            metrics::increment_counter(
+                                REGISTRY_NOTIFY_TOTAL,
+                                &vec![
+                                    ("interface", service_name.clone()),
+                                    ("type", change_type.to_string()),
+                                ],
+                            );
                             let _ = tx.send(change).await;
                         }
                     }
@@ -203,21 +305,88 @@ where
             }
         });
 
-        Buffer::new(
-            Directory::new(ReceiverStream::new(rx)),
-            Self::MAX_DIRECTORY_BUFFER_SIZE,
-        )
+        Buffer::new(directory, Self::MAX_DIRECTORY_BUFFER_SIZE)
     }
 }
 
 impl<D> Directory<D> {
-    pub fn new(discover: D) -> Self {
+    pub fn new(service_name: String, discover: D) -> Self {
         Directory {
+            service_name,
             directory: Default::default(),
+            checks: Default::default(),
+            zone_providers: Default::default(),
             discover,
-            new_invoker: NewInvoker,
+            new_invoker: NewInvoker::new(),
+            selector: ServiceSelector::default(),
+            health_check: None,
         }
     }
+
+    pub fn with_new_invoker(self, new_invoker: NewInvoker) -> Self {
+        Self {
+            new_invoker,
+            ..self
+        }
+    }
+
+    /// Keep only the providers accepted by `selector`.
+    pub fn with_selector(self, selector: ServiceSelector) -> Self {
+        Self { selector, ..self }
+    }
+
+    /// Check the health of the providers, leaving the unhealthy ones out.
+    pub fn with_health_check(self, health_check: Option<HealthCheckConfig>) -> Self {
+        Self {
+            health_check,
+            ..self
+        }
+    }
+}
+
+impl<D> Directory<D> {
+    fn record_providers(&self) {
+        metrics::set_gauge(
+            REGISTRY_PROVIDERS,
+            &vec![("interface", self.service_name.clone())],
+            self.directory.len() as f64,
+        );
+    }
+
+    fn forget_zone(&self, invoker: &CloneInvoker<TripleInvoker>) {
+        if let Some(url) = invoker.url() {
+            self.zone_providers.remove(url);
+        }
+    }
+
+    /// Invoker of the provider `url`, its health checked from now on.
+    fn new_invoker(&mut self, url: String) -> CloneInvoker<TripleInvoker> {
+        let health = ProviderHealth::new(
+            self.service_name.clone(),
+            url.clone(),
+            self.health_check.clone(),
+        );
+        let invoker = self
+            .new_invoker
+            .new_service(url.clone())
+            .with_health(health.clone())
+            .with_zone_providers(self.zone_providers.clone());
+        let check = self.health_check.as_ref().and_then(|config| {
+            let host: http::Uri = url.parse().ok()?;
+            let connector = self.new_invoker.connector(&host);
+            HealthCheck::new(
+                config,
+                host,
+                self.service_name.clone(),
+                invoker.clone(),
+                connector,
+            )
+        });
+        if let Some(check) = check {
+            self.checks.insert(url, check.spawn(health));
+        }
+        invoker
+    }
 }
 
 impl<D> Service<()> for Directory<D>
@@ -249,12 +418,29 @@ where
                     match change {
                         Some(Change::Remove(key)) => {
                             debug!("remove key: {}", key);
-                            self.directory.remove(&key);
+                            if let Some(invoker) = self.directory.remove(&key) {
+                                self.forget_zone(&invoker);
+                            }
+                            self.checks.remove(&key);
+                            self.record_providers();
                         }
                         Some(Change::Insert(key, _)) => {
                             debug!("insert key: {}", key);
-                            let invoker = self.new_invoker.new_service(key.clone());
-                            self.directory.insert(key, invoker);
+                            let accepted = key
+                                .parse::<Url>()
+                                .map_or(true, |url| self.selector.matches_url(&url));
+                            if !accepted {
+                                debug!("skip provider of other group or version: {}", key);
+                                continue;
+                            }
+                            let invoker = self.new_invoker(key.clone());
+                            if let Some(url) = invoker.url() {
+                                self.zone_providers.add(url);
+                            }
+                            if let Some(old) = self.directory.insert(key, invoker) {
+                                self.forget_zone(&old);
+                            }
+                            self.record_providers();
                         }
                         None => {
                             debug!("stream closed");
@@ -270,8 +456,14 @@ where
         let vec = self
             .directory
             .values()
+            .filter(|val| val.is_healthy())
             .map(|val| val.clone())
             .collect::<Vec<CloneInvoker<TripleInvoker>>>();
+        if vec.is_empty() && !self.directory.is_empty() {
+            // better a call to an unhealthy provider than none
+            warn!("no healthy provider of {}", self.service_name);
+            return future::ok(self.directory.values().cloned().collect());
+        }
         future::ok(vec)
     }
 }
@@ -198,6 +198,10 @@ impl InvokerExtensionLoader {
         self.factories.remove(&extension_name);
     }
 
+    pub fn names(&self) -> Vec<String> {
+        self.factories.keys().cloned().collect()
+    }
+
     pub fn load(&mut self, url: Url) -> Result<LoadExtensionPromise<InvokerProxy>, StdError> {
         let extension_name = url.query::<ExtensionName>();
         let Some(extension_name) = extension_name else {
@@ -78,6 +78,9 @@ impl ExtensionDirectory {
                     ExtensionOpt::Load(url, extension_type, tx) => {
                         let _ = extension_directory.load(url, extension_type, tx);
                     }
+                    ExtensionOpt::Names(extension_type, tx) => {
+                        let _ = tx.send(extension_directory.names(extension_type));
+                    }
                 }
             }
         });
@@ -128,6 +131,15 @@ impl ExtensionDirectory {
         }
     }
 
+    fn names(&self, extension_type: ExtensionType) -> Vec<String> {
+        let mut names = match extension_type {
+            ExtensionType::Registry => self.registry_extension_loader.names(),
+            ExtensionType::Invoker => self.invoker_extension_loader.names(),
+        };
+        names.sort();
+        names
+    }
+
     fn load(
         &mut self,
         url: Url,
@@ -363,6 +375,20 @@ impl ExtensionDirectoryCommander {
         }
     }
 
+    /// Names of the registered extensions of the given type.
+    pub async fn names(&self, extension_type: ExtensionType) -> Result<Vec<String>, StdError> {
+        let (tx, rx) = oneshot::channel();
+        let send = self
+            .sender
+            .send(ExtensionOpt::Names(extension_type, tx))
+            .await;
+        let Ok(_) = send else {
+            return Err(LoadExtensionError::new("list extensions failed".to_string()).into());
+        };
+        rx.await
+            .map_err(|_| LoadExtensionError::new("list extensions failed".to_string()).into())
+    }
+
     pub async fn load_registry(&self, url: Url) -> Result<RegistryProxy, StdError> {
         let url_str = url.to_string();
         info!("load registry extension: {}", url_str);
@@ -449,6 +475,7 @@ enum ExtensionOpt {
         ExtensionType,
         oneshot::Sender<Result<Extensions, StdError>>,
     ),
+    Names(ExtensionType, oneshot::Sender<Vec<String>>),
 }
 
 #[allow(private_bounds)]
@@ -59,6 +59,16 @@ pub trait Registry {
 
     async fn unsubscribe(&self, url: Url) -> Result<(), StdError>;
 
+    /// Keys of the services registered, formatted as `group/interface:version`,
+    /// for the registries able to list them.
+    async fn services(&self) -> Result<Vec<String>, StdError> {
+        Err(RegistryExtensionLoaderError::new(format!(
+            "registry {} can not list its services",
+            self.url()
+        ))
+        .into())
+    }
+
     fn url(&self) -> &Url;
 }
 
@@ -100,6 +110,10 @@ impl RegistryExtensionLoader {
         self.factories.remove(&extension_name);
     }
 
+    pub(crate) fn names(&self) -> Vec<String> {
+        self.factories.keys().cloned().collect()
+    }
+
     pub(crate) fn load(
         &mut self,
         url: Url,
@@ -141,9 +155,9 @@ impl RegistryExtensionFactory {
         &mut self,
         url: Url,
     ) -> Result<LoadExtensionPromise<RegistryProxy>, StdError> {
-        let registry_url = url.query::<RegistryUrl>().unwrap();
-        let registry_url = registry_url.value();
-        let url_str = registry_url.as_str().to_string();
+        // keyed by the whole extension url, static registries of the same
+        // registry url differ by their provider urls
+        let url_str = url.as_str().to_string();
         match self.instances.get(&url_str) {
             Some(proxy) => {
                 let proxy = proxy.clone();
@@ -202,6 +216,7 @@ pub mod proxy {
         Unregister(Url, oneshot::Sender<Result<(), StdError>>),
         Subscribe(Url, oneshot::Sender<Result<DiscoverStream, StdError>>),
         UnSubscribe(Url, oneshot::Sender<Result<(), StdError>>),
+        Services(oneshot::Sender<Result<Vec<String>, StdError>>),
     }
 
     #[derive(Clone)]
@@ -330,6 +345,29 @@ pub mod proxy {
             }
         }
 
+        async fn services(&self) -> Result<Vec<String>, StdError> {
+            let (tx, rx) = oneshot::channel();
+            match self.sender.send(RegistryOpt::Services(tx)).await {
+                Ok(_) => match rx.await {
+                    Ok(result) => result,
+                    Err(_) => {
+                        error!(
+                            "registry proxy error: receive services response failed, url: {}",
+                            self.url
+                        );
+                        Err(RegistryProxyError::new("receive services response failed").into())
+                    }
+                },
+                Err(_) => {
+                    error!(
+                        "registry proxy error: send services request failed, url: {}",
+                        self.url
+                    );
+                    Err(RegistryProxyError::new("send services opt failed").into())
+                }
+            }
+        }
+
         fn url(&self) -> &Url {
             &self.url
         }
@@ -368,6 +406,12 @@ pub mod proxy {
                                 error!("registry proxy error: send unsubscribe response failed");
                             }
                         }
+                        RegistryOpt::Services(tx) => {
+                            let services = registry.services().await;
+                            if tx.send(services).is_err() {
+                                error!("registry proxy error: send services response failed");
+                            }
+                        }
                     }
                 }
             });
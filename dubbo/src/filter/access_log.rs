@@ -0,0 +1,416 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use chrono::{DateTime, Local};
+use futures_util::future::BoxFuture;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tower_service::Service;
+
+use crate::{
+    invocation::RemoteAddr,
+    logger::tracing::{info, warn},
+    metrics::{
+        self,
+        rpc::{RequestSize, CONSUMER_SIDE, PROVIDER_SIDE},
+        Labels, ACCESS_LOG_DROPPED_TOTAL,
+    },
+    protocol::triple::service_key::{TRI_SERVICE_GROUP, TRI_SERVICE_VERSION},
+    status::Code,
+    utils::observed_body::{ObservedBody, ResponseObserver},
+};
+
+pub const ACCESS_LOG_QUEUE_SIZE: usize = 4096;
+
+/// tracing target of access log entries written to the logger
+pub const ACCESS_LOG_TARGET: &str = "dubbo::accesslog";
+
+/// Writers shared by every access log with the same output, keyed by output.
+static WRITERS: Lazy<Mutex<HashMap<String, mpsc::Sender<AccessLogEntry>>>> =
+    Lazy::new(Default::default);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccessLogEntry {
+    pub timestamp: String,
+    pub side: &'static str,
+    pub remote: String,
+    pub interface: String,
+    pub method: String,
+    pub group: String,
+    pub version: String,
+    pub status: String,
+    pub latency_ms: f64,
+    pub request_size: u64,
+    pub response_size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<serde_json::Value>,
+}
+
+/// Marker in the request extensions asking the handler to report the
+/// arguments of the call.
+#[derive(Debug, Clone, Copy)]
+pub struct LogArguments;
+
+/// Arguments of the call as json, in the extensions of provider responses
+/// and consumer requests.
+#[derive(Debug, Clone)]
+pub struct AccessLogArguments(pub serde_json::Value);
+
+/// Access log of a service, `output` follows the `accesslog` url parameter:
+/// `true` or `default` writes to the logger, anything else is a file path
+/// rotated daily.
+///
+/// Entries are handed to a background writer through a bounded queue, they
+/// are dropped instead of blocking the call when the queue is full.
+#[derive(Clone)]
+pub struct AccessLog {
+    tx: mpsc::Sender<AccessLogEntry>,
+    arguments: bool,
+}
+
+impl AccessLog {
+    pub fn new(output: &str) -> io::Result<Self> {
+        let mut writers = WRITERS.lock().expect("access log writers lock poisoned");
+        let tx = match writers.get(output) {
+            Some(tx) => tx.clone(),
+            None => {
+                let tx = spawn_writer(output)?;
+                writers.insert(output.to_string(), tx.clone());
+                tx
+            }
+        };
+        Ok(AccessLog {
+            tx,
+            arguments: false,
+        })
+    }
+
+    /// Also record the arguments of unary calls as json.
+    pub fn with_arguments(self, arguments: bool) -> Self {
+        Self { arguments, ..self }
+    }
+
+    pub fn arguments(&self) -> bool {
+        self.arguments
+    }
+
+    pub fn log(&self, entry: AccessLogEntry) {
+        match self.tx.try_send(entry) {
+            Ok(_) => {}
+            Err(TrySendError::Full(_)) => {
+                metrics::increment_counter(ACCESS_LOG_DROPPED_TOTAL, &Labels::new());
+            }
+            Err(TrySendError::Closed(_)) => warn!("access log writer is closed"),
+        }
+    }
+}
+
+fn spawn_writer(output: &str) -> io::Result<mpsc::Sender<AccessLogEntry>> {
+    let (tx, mut rx) = mpsc::channel::<AccessLogEntry>(ACCESS_LOG_QUEUE_SIZE);
+    let mut sink = match output {
+        "true" | "default" => Sink::Logger,
+        path => Sink::File(RollingFile::open(PathBuf::from(path))?),
+    };
+
+    std::thread::Builder::new()
+        .name("dubbo-accesslog".to_string())
+        .spawn(move || {
+            while let Some(entry) = rx.blocking_recv() {
+                sink.write(&entry);
+                while let Ok(entry) = rx.try_recv() {
+                    sink.write(&entry);
+                }
+                sink.flush();
+            }
+        })?;
+    Ok(tx)
+}
+
+enum Sink {
+    Logger,
+    File(RollingFile),
+}
+
+impl Sink {
+    fn write(&mut self, entry: &AccessLogEntry) {
+        let line = serde_json::to_string(entry).unwrap_or_default();
+        match self {
+            Sink::Logger => info!(target: ACCESS_LOG_TARGET, "{}", line),
+            Sink::File(file) => {
+                if let Err(err) = file.write_line(&line) {
+                    warn!("write access log error: {:?}", err);
+                }
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Sink::File(file) = self {
+            let _ = file.writer.flush();
+        }
+    }
+}
+
+/// Append only file renamed to `{path}.{yyyy-MM-dd}` when the day changes.
+struct RollingFile {
+    path: PathBuf,
+    date: String,
+    writer: BufWriter<File>,
+}
+
+impl RollingFile {
+    fn open(path: PathBuf) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        // roll a file left over from a previous day before appending to it
+        if let Ok(modified) = fs::metadata(&path).and_then(|m| m.modified()) {
+            let date = DateTime::<Local>::from(modified)
+                .format("%Y-%m-%d")
+                .to_string();
+            if date != today {
+                fs::rename(&path, rolled_path(&path, &date))?;
+            }
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(RollingFile {
+            path,
+            date: today,
+            writer: BufWriter::new(file),
+        })
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        if today != self.date {
+            self.writer.flush()?;
+            fs::rename(&self.path, rolled_path(&self.path, &self.date))?;
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+            self.writer = BufWriter::new(file);
+            self.date = today;
+        }
+        writeln!(self.writer, "{}", line)
+    }
+}
+
+fn rolled_path(path: &Path, date: &str) -> PathBuf {
+    let mut rolled = path.as_os_str().to_owned();
+    rolled.push(".");
+    rolled.push(date);
+    PathBuf::from(rolled)
+}
+
+struct AccessLogObserver {
+    log: AccessLog,
+    entry: AccessLogEntry,
+    start: Instant,
+    request_size: RequestSize,
+}
+
+impl ResponseObserver for AccessLogObserver {
+    fn on_data(&mut self, len: usize) {
+        self.entry.response_size += len as u64;
+    }
+
+    fn on_end(&mut self, code: Code) {
+        self.entry.status = format!("{:?}", code);
+        self.entry.latency_ms = self.start.elapsed().as_secs_f64() * 1000.0;
+        self.entry.request_size = self.request_size.get();
+        self.log.log(self.entry.clone());
+    }
+}
+
+/// Writes an access log entry for every call, once its response ends.
+#[derive(Clone)]
+pub struct AccessLogService<S> {
+    inner: S,
+    log: AccessLog,
+    side: &'static str,
+}
+
+impl<S> AccessLogService<S> {
+    pub fn new(inner: S, log: AccessLog, side: &'static str) -> Self {
+        AccessLogService { inner, log, side }
+    }
+
+    pub fn provider(inner: S, log: AccessLog) -> Self {
+        Self::new(inner, log, PROVIDER_SIDE)
+    }
+
+    pub fn consumer(inner: S, log: AccessLog) -> Self {
+        Self::new(inner, log, CONSUMER_SIDE)
+    }
+}
+
+impl<S> Service<http::Request<hyper::Body>> for AccessLogService<S>
+where
+    S: Service<http::Request<hyper::Body>, Response = http::Response<crate::BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<crate::BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<hyper::Body>) -> Self::Future {
+        let start = Instant::now();
+        let header = |key: &str| {
+            req.headers()
+                .get(key)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_string()
+        };
+        // consumer requests carry the path in a header until the invoker
+        // builds the uri
+        let mut path = req.uri().path().to_string();
+        if path.is_empty() || path == "/" {
+            path = header("path");
+        }
+        let mut segments = path.trim_start_matches('/').splitn(2, '/');
+
+        let mut entry = AccessLogEntry {
+            timestamp: Local::now().to_rfc3339(),
+            side: self.side,
+            remote: req
+                .extensions()
+                .get::<RemoteAddr>()
+                .map(|addr| addr.0.clone())
+                .unwrap_or_default(),
+            interface: segments.next().unwrap_or_default().to_string(),
+            method: segments.next().unwrap_or_default().to_string(),
+            group: header(TRI_SERVICE_GROUP),
+            version: header(TRI_SERVICE_VERSION),
+            status: String::new(),
+            latency_ms: 0.0,
+            request_size: 0,
+            response_size: 0,
+            arguments: None,
+        };
+        if self.log.arguments {
+            entry.arguments = req
+                .extensions_mut()
+                .remove::<AccessLogArguments>()
+                .map(|args| args.0);
+            req.extensions_mut().insert(LogArguments);
+        }
+
+        let request_size = RequestSize::default();
+        let req = req.map(|body| request_size.count(body));
+        let log = self.log.clone();
+        let fut = self.inner.call(req);
+
+        Box::pin(async move {
+            let mut resp = fut.await?;
+            if let Some(addr) = resp.extensions().get::<RemoteAddr>() {
+                entry.remote = addr.0.clone();
+            }
+            if let Some(args) = resp.extensions_mut().remove::<AccessLogArguments>() {
+                entry.arguments = Some(args.0);
+            }
+
+            let observer = AccessLogObserver {
+                log,
+                entry,
+                start,
+                request_size,
+            };
+            let (parts, body) = resp.into_parts();
+            let body = ObservedBody::new(body, observer, &parts.headers);
+            Ok(http::Response::from_parts(parts, crate::BoxBody::new(body)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, time::Duration};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_access_log_file() {
+        let dir = std::env::temp_dir().join(format!("dubbo-accesslog-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("access.log");
+
+        let log = AccessLog::new(path.to_str().unwrap())
+            .unwrap()
+            .with_arguments(true);
+        let inner = tower::service_fn(|req: http::Request<hyper::Body>| async move {
+            assert!(req.extensions().get::<LogArguments>().is_some());
+            let _ = hyper::body::to_bytes(req.into_body()).await;
+            let mut resp = http::Response::new(crate::boxed(hyper::Body::from("hello")));
+            resp.extensions_mut()
+                .insert(AccessLogArguments(serde_json::json!({"name": "dubbo"})));
+            Ok::<_, Infallible>(resp)
+        });
+        let mut svc = AccessLogService::provider(inner, log);
+
+        let mut req = http::Request::builder()
+            .uri("/helloworld.Greeter/SayHello")
+            .header(TRI_SERVICE_GROUP, "test")
+            .header(TRI_SERVICE_VERSION, "1.0.0")
+            .body(hyper::Body::from("request"))
+            .unwrap();
+        req.extensions_mut()
+            .insert(RemoteAddr("127.0.0.1:50000".to_string()));
+        let resp = svc.call(req).await.unwrap();
+        let _ = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+
+        let mut content = String::new();
+        for _ in 0..50 {
+            content = fs::read_to_string(&path).unwrap_or_default();
+            if !content.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let entry: serde_json::Value = serde_json::from_str(content.trim()).unwrap();
+        assert_eq!(entry["side"], "provider");
+        assert_eq!(entry["remote"], "127.0.0.1:50000");
+        assert_eq!(entry["interface"], "helloworld.Greeter");
+        assert_eq!(entry["method"], "SayHello");
+        assert_eq!(entry["group"], "test");
+        assert_eq!(entry["version"], "1.0.0");
+        assert_eq!(entry["status"], "Ok");
+        assert_eq!(entry["request_size"], 7);
+        assert_eq!(entry["response_size"], 5);
+        assert_eq!(entry["arguments"]["name"], "dubbo");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,252 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{str::FromStr, sync::Arc};
+
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use once_cell::sync::OnceCell;
+use rand::Rng;
+
+use crate::{
+    codegen::Request,
+    config::auth::JwtConfig,
+    logger::tracing::debug,
+    params::constants::TOKEN_KEY,
+    status::{Code, Status},
+    StdError,
+};
+
+use super::Filter;
+
+pub const AUTHORIZATION: &str = "authorization";
+const BEARER: &str = "Bearer ";
+
+/// Random token published in the provider url for `token: true`.
+pub fn generate_token() -> String {
+    format!("{:032x}", rand::thread_rng().gen::<u128>())
+}
+
+/// Rejects calls not carrying the token of the provider, as sent by
+/// consumers from the `token` parameter of the provider url.
+#[derive(Clone)]
+pub struct TokenFilter {
+    token: String,
+}
+
+impl TokenFilter {
+    pub fn new(token: String) -> Self {
+        TokenFilter { token }
+    }
+}
+
+impl Filter for TokenFilter {
+    fn call(&mut self, req: Request<()>) -> Result<Request<()>, Status> {
+        match req.metadata.get(TOKEN_KEY) {
+            Some(token) if constant_time_eq(token.as_bytes(), self.token.as_bytes()) => Ok(req),
+            _ => Err(Status::new(
+                Code::Unauthenticated,
+                "invalid token".to_string(),
+            )),
+        }
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Claims of a caller authenticated by [`JwtFilter`], handlers read them from
+/// the request extensions.
+#[derive(Debug, Clone)]
+pub struct JwtClaims(pub serde_json::Value);
+
+struct JwtKey {
+    kid: Option<String>,
+    key: DecodingKey,
+    validation: Validation,
+}
+
+/// Verifies the bearer jwt in the `authorization` header against the
+/// configured keys, issuers and audiences.
+#[derive(Clone)]
+pub struct JwtFilter {
+    keys: Arc<Vec<JwtKey>>,
+}
+
+impl JwtFilter {
+    pub fn new(config: &JwtConfig) -> Result<Self, StdError> {
+        let mut keys = Vec::with_capacity(config.keys.len());
+        for key_config in config.keys.iter() {
+            let algorithm = Algorithm::from_str(&key_config.algorithm)?;
+            let key = match algorithm {
+                Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+                    DecodingKey::from_secret(key_config.key.as_bytes())
+                }
+                Algorithm::ES256 | Algorithm::ES384 => {
+                    DecodingKey::from_ec_pem(key_config.key.as_bytes())?
+                }
+                Algorithm::EdDSA => DecodingKey::from_ed_pem(key_config.key.as_bytes())?,
+                _ => DecodingKey::from_rsa_pem(key_config.key.as_bytes())?,
+            };
+
+            let mut validation = Validation::new(algorithm);
+            if !config.issuers.is_empty() {
+                validation.set_issuer(&config.issuers);
+            }
+            if config.audiences.is_empty() {
+                validation.validate_aud = false;
+            } else {
+                validation.set_audience(&config.audiences);
+            }
+
+            keys.push(JwtKey {
+                kid: key_config.kid.clone(),
+                key,
+                validation,
+            });
+        }
+        if keys.is_empty() {
+            return Err("jwt auth requires at least one key".into());
+        }
+
+        Ok(JwtFilter {
+            keys: Arc::new(keys),
+        })
+    }
+
+    /// Claims of the token, if one of the keys verifies it.
+    pub fn verify(&self, token: &str) -> Result<serde_json::Value, Status> {
+        let unauthenticated = |message: String| Status::new(Code::Unauthenticated, message);
+        let header = jsonwebtoken::decode_header(token)
+            .map_err(|err| unauthenticated(format!("invalid jwt: {}", err)))?;
+
+        let mut last_err = None;
+        for key in self.keys.iter() {
+            if key.validation.algorithms[0] != header.alg {
+                continue;
+            }
+            if key.kid.is_some() && key.kid != header.kid {
+                continue;
+            }
+            match jsonwebtoken::decode::<serde_json::Value>(token, &key.key, &key.validation) {
+                Ok(data) => return Ok(data.claims),
+                Err(err) => last_err = Some(err),
+            }
+        }
+
+        Err(match last_err {
+            Some(err) => unauthenticated(format!("invalid jwt: {}", err)),
+            None => unauthenticated("no key matches the jwt".to_string()),
+        })
+    }
+}
+
+impl Filter for JwtFilter {
+    fn call(&mut self, mut req: Request<()>) -> Result<Request<()>, Status> {
+        let token = req
+            .metadata
+            .get(AUTHORIZATION)
+            .and_then(|value| value.strip_prefix(BEARER))
+            .ok_or_else(|| {
+                Status::new(Code::Unauthenticated, "missing bearer token".to_string())
+            })?;
+
+        let claims = self.verify(token).map_err(|status| {
+            debug!("jwt rejected: {:?}", status);
+            status
+        })?;
+        req.extensions_mut().insert(JwtClaims(claims));
+        Ok(req)
+    }
+}
+
+static JWT_FILTER: OnceCell<JwtFilter> = OnceCell::new();
+
+/// Install the jwt verifier applied to every service served afterwards.
+pub fn set_jwt_filter(filter: JwtFilter) -> Result<(), JwtFilter> {
+    JWT_FILTER.set(filter)
+}
+
+pub fn jwt_filter() -> Option<&'static JwtFilter> {
+    JWT_FILTER.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use jsonwebtoken::{encode, EncodingKey, Header};
+
+    use super::*;
+    use crate::{config::auth::JwtKeyConfig, invocation::Metadata};
+
+    fn request(key: &str, value: &str) -> Request<()> {
+        Request::from_parts(
+            Metadata::new().insert(key.to_string(), value.to_string()),
+            (),
+        )
+    }
+
+    #[test]
+    fn test_token_and_jwt() {
+        let mut token_filter = TokenFilter::new("secret-token".to_string());
+        assert!(token_filter
+            .call(request(TOKEN_KEY, "secret-token"))
+            .is_ok());
+        let err = token_filter
+            .call(request(TOKEN_KEY, "other"))
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), Code::Unauthenticated);
+
+        let config =
+            JwtConfig::default()
+                .issuers(vec!["dubbo".to_string()])
+                .key(JwtKeyConfig::new(
+                    "HS256".to_string(),
+                    "jwt-secret".to_string(),
+                ));
+        let mut jwt_filter = JwtFilter::new(&config).unwrap();
+        let exp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 60;
+        let sign = |iss: &str| {
+            encode(
+                &Header::default(),
+                &serde_json::json!({"sub": "alice", "iss": iss, "exp": exp}),
+                &EncodingKey::from_secret(b"jwt-secret"),
+            )
+            .unwrap()
+        };
+
+        let req = jwt_filter
+            .call(request(AUTHORIZATION, &format!("Bearer {}", sign("dubbo"))))
+            .unwrap();
+        let claims = req.extensions().get::<JwtClaims>().unwrap();
+        assert_eq!(claims.0["sub"], "alice");
+
+        let err = jwt_filter
+            .call(request(AUTHORIZATION, &format!("Bearer {}", sign("other"))))
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), Code::Unauthenticated);
+        let err = jwt_filter.call(Request::new(())).err().unwrap();
+        assert_eq!(err.code(), Code::Unauthenticated);
+    }
+}
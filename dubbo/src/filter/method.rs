@@ -0,0 +1,139 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_util::future::BoxFuture;
+use tower_service::Service;
+
+use crate::{
+    config::{dynamic, method::MethodConfig},
+    status::{Code, Status},
+    triple::{
+        compression::CompressionConfig, decode::MaxMessageSize, encode::MaxEncodeMessageSize,
+    },
+};
+
+/// Applies the [`MethodConfig`] of the called method on the provider: its
+/// timeout, compression of the response and largest messages.
+#[derive(Clone)]
+pub struct MethodConfigService<S> {
+    inner: S,
+    methods: Arc<HashMap<String, MethodConfig>>,
+    compression: Option<CompressionConfig>,
+    max_message_size: Option<usize>,
+    max_encode_message_size: Option<usize>,
+}
+
+impl<S> MethodConfigService<S> {
+    pub fn new(inner: S, methods: HashMap<String, MethodConfig>) -> Self {
+        MethodConfigService {
+            inner,
+            methods: Arc::new(methods),
+            compression: None,
+            max_message_size: None,
+            max_encode_message_size: None,
+        }
+    }
+
+    /// Largest request and response messages of the methods that set none.
+    pub fn with_max_message_sizes(self, decode: Option<usize>, encode: Option<usize>) -> Self {
+        Self {
+            max_message_size: decode,
+            max_encode_message_size: encode,
+            ..self
+        }
+    }
+
+    /// Compression of the responses of the methods that set none.
+    pub fn with_compression(self, compression: Option<CompressionConfig>) -> Self {
+        Self {
+            compression,
+            ..self
+        }
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for MethodConfigService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<crate::BoxBody>>,
+    S::Error: Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<crate::BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        let path = req.uri().path().to_string();
+        let (_, name) = dynamic::service_method(&path);
+        let method = self.methods.get(name);
+        let compression = match method.and_then(MethodConfig::compression_encoding) {
+            Some(encoding) => Some(
+                self.compression
+                    .clone()
+                    .unwrap_or_default()
+                    .encodings(encoding.into_iter().collect()),
+            ),
+            None => self.compression.clone(),
+        };
+        if let Some(compression) = compression {
+            req.extensions_mut().insert(compression);
+        }
+        let max_message_size = method
+            .and_then(|method| method.max_message_size)
+            .or(self.max_message_size);
+        if let Some(max) = max_message_size {
+            req.extensions_mut().insert(MaxMessageSize(max));
+        }
+        let max_encode_message_size = method
+            .and_then(|method| method.max_encode_message_size)
+            .or(self.max_encode_message_size);
+        if let Some(max) = max_encode_message_size {
+            req.extensions_mut().insert(MaxEncodeMessageSize(max));
+        }
+        let Some(method) = method else {
+            return Box::pin(self.inner.call(req));
+        };
+
+        let timeout = method.timeout;
+
+        let fut = self.inner.call(req);
+        match timeout {
+            Some(timeout) => Box::pin(async move {
+                match tokio::time::timeout(Duration::from_millis(timeout), fut).await {
+                    Ok(resp) => resp,
+                    Err(_) => Ok(Status::new(
+                        Code::DeadlineExceeded,
+                        format!("{} timed out after {}ms", path, timeout),
+                    )
+                    .to_http()),
+                }
+            }),
+            None => Box::pin(fut),
+        }
+    }
+}
@@ -15,9 +15,13 @@
  * limitations under the License.
  */
 
+pub mod access_log;
+pub mod auth;
 pub mod context;
+pub mod method;
 pub mod service;
 pub mod timeout;
+pub mod tps_limit;
 
 use crate::invocation::Request;
 
@@ -28,3 +32,14 @@ pub const TIMEOUT_DEFAULT: u128 = 1000;
 pub trait Filter {
     fn call(&mut self, req: Request<()>) -> Result<Request<()>, crate::status::Status>;
 }
+
+/// Closures adding metadata to the requests or rejecting them, such as the
+/// interceptors of the generated clients.
+impl<F> Filter for F
+where
+    F: FnMut(Request<()>) -> Result<Request<()>, crate::status::Status>,
+{
+    fn call(&mut self, req: Request<()>) -> Result<Request<()>, crate::status::Status> {
+        self(req)
+    }
+}
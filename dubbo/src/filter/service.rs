@@ -61,15 +61,13 @@ where
         let version = req.version();
         let (parts, msg) = req.into_parts();
 
-        let res = self.f.call(Request::from_parts(
-            Metadata::from_headers(parts.headers),
-            (),
-        ));
+        let mut req = Request::from_parts(Metadata::from_headers(parts.headers), ());
+        req.extensions = parts.extensions;
+
+        let res = self.f.call(req);
         match res {
             Ok(req) => {
-                let (metadata, _) = req.into_parts();
-                let req = Request::from_parts(Metadata::from_headers(metadata.into_headers()), msg);
-                let http_req = req.into_http(uri, method, version);
+                let http_req = req.map(|_| msg).into_http(uri, method, version);
 
                 let resp = self.inner.call(http_req);
                 Box::pin(resp)
@@ -0,0 +1,163 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use futures_util::future::{self, BoxFuture};
+use tower_service::Service;
+
+use crate::{
+    config::dynamic::{self, Side, TPS_INTERVAL_KEY, TPS_KEY},
+    status::{Code, Status},
+};
+
+/// interval in milliseconds of a `tps` without `tps.interval`
+pub const DEFAULT_TPS_INTERVAL: u64 = 60 * 1000;
+
+/// Counts calls in fixed windows of `interval`, a key at a time.
+#[derive(Clone, Default)]
+pub struct TpsLimiter {
+    windows: Arc<Mutex<HashMap<String, Window>>>,
+}
+
+struct Window {
+    rate: u64,
+    interval: Duration,
+    start: Instant,
+    count: u64,
+}
+
+impl TpsLimiter {
+    /// Take one of the `rate` calls of the current window of `key`, false
+    /// when they are all taken.
+    pub fn acquire(&self, key: &str, rate: u64, interval: Duration) -> bool {
+        let mut windows = self.windows.lock().expect("tps limiter lock poisoned");
+        let now = Instant::now();
+        let window = windows.entry(key.to_string()).or_insert(Window {
+            rate,
+            interval,
+            start: now,
+            count: 0,
+        });
+        // a changed rule starts a new window
+        if window.rate != rate
+            || window.interval != interval
+            || now.duration_since(window.start) >= interval
+        {
+            *window = Window {
+                rate,
+                interval,
+                start: now,
+                count: 0,
+            };
+        }
+        if window.count >= rate {
+            return false;
+        }
+        window.count += 1;
+        true
+    }
+}
+
+/// Rejects calls beyond the dynamic `tps` of a provider, `{method}.tps`
+/// limiting a single method and `tps` the whole service.
+#[derive(Clone)]
+pub struct TpsLimitService<S> {
+    inner: S,
+    limiter: TpsLimiter,
+}
+
+impl<S> TpsLimitService<S> {
+    pub fn new(inner: S) -> Self {
+        TpsLimitService {
+            inner,
+            limiter: TpsLimiter::default(),
+        }
+    }
+
+    fn allow(&self, path: &str) -> bool {
+        let (service, method) = dynamic::service_method(path);
+        let config = dynamic::dynamic_config();
+        let lookup = |key: &str| config.parameter_as::<u64>(Side::Provider, service, "", "", key);
+
+        let method_rate = lookup(&format!("{}.{}", method, TPS_KEY));
+        let (key, prefix, rate) = match method_rate {
+            Some(rate) => (path, format!("{}.", method), rate),
+            None => match lookup(TPS_KEY) {
+                Some(rate) => (service, String::new(), rate),
+                None => return true,
+            },
+        };
+        let interval =
+            lookup(&format!("{}{}", prefix, TPS_INTERVAL_KEY)).unwrap_or(DEFAULT_TPS_INTERVAL);
+        self.limiter
+            .acquire(key, rate, Duration::from_millis(interval.max(1)))
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for TpsLimitService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<crate::BoxBody>>,
+    S::Error: Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<crate::BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        if self.allow(req.uri().path()) {
+            return Box::pin(self.inner.call(req));
+        }
+        let status = Status::new(
+            Code::ResourceExhausted,
+            format!("{} exceeds the max tps of the service", req.uri().path()),
+        );
+        Box::pin(future::ok(status.to_http()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_acquire() {
+        let limiter = TpsLimiter::default();
+        let interval = Duration::from_secs(60);
+        assert!(limiter.acquire("svc", 2, interval));
+        assert!(limiter.acquire("svc", 2, interval));
+        assert!(!limiter.acquire("svc", 2, interval));
+        assert!(limiter.acquire("other", 2, interval));
+        // a changed rule starts a new window
+        assert!(limiter.acquire("svc", 3, interval));
+
+        let short = Duration::from_millis(1);
+        assert!(limiter.acquire("short", 1, short));
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(limiter.acquire("short", 1, short));
+    }
+}
@@ -18,13 +18,20 @@
 use std::{collections::HashMap, error::Error, pin::Pin};
 
 use crate::{
-    config::{get_global_config, protocol::ProtocolRetrieve, RootConfig},
+    admin::AdminServer,
+    config::{dynamic, protocol::ProtocolRetrieve, try_get_global_config, RootConfig},
     extension,
     extension::registry_extension::Registry,
-    logger::tracing::{debug, info},
+    filter::auth::{self, JwtFilter},
+    logger::tracing::{debug, error, info, warn},
+    metrics::{self, REGISTRY_REGISTER_TOTAL},
+    params::constants::{
+        ACCESS_LOG_ARGS_KEY, ACCESS_LOG_KEY, COMPRESSION_KEY, COMPRESSION_MIN_SIZE_KEY,
+        MAX_ENCODE_MESSAGE_SIZE_KEY, MAX_MESSAGE_SIZE_KEY, TOKEN_KEY,
+    },
     protocol::{BoxExporter, Protocol},
-    registry::protocol::RegistryProtocol,
-    Url,
+    registry::{self, protocol::RegistryProtocol},
+    telemetry, Url,
 };
 use futures::{future, Future};
 
@@ -62,11 +69,19 @@ impl Dubbo {
 
     pub fn init(&mut self) -> Result<(), Box<dyn Error>> {
         if self.config.is_none() {
-            self.config = Some(get_global_config())
+            self.config = Some(try_get_global_config()?)
         }
 
         let root_config = self.config.as_ref().unwrap();
         debug!("global conf: {:?}", root_config);
+        root_config.validate()?;
+        if let Some(jwt) = &root_config.auth.jwt {
+            // no provider starts without the jwt check it is configured with
+            let filter = JwtFilter::new(jwt).map_err(|err| format!("invalid auth.jwt: {}", err))?;
+            if auth::set_jwt_filter(filter).is_err() {
+                warn!("jwt auth installed already, keeping the first one");
+            }
+        }
         // env::set_var("ZOOKEEPER_SERVERS",root_config);
         for (_, service_config) in root_config.provider.services.iter() {
             info!("init service name: {}", service_config.interface);
@@ -78,12 +93,68 @@ impl Dubbo {
                     .protocols
                     .get_protocol_or_default(service_config.protocol.as_str());
                 let interface_name = service_config.interface.clone();
-                let protocol_url = format!(
+                let mut protocol_url = format!(
                     "{}/{}?interface={}",
-                    protocol.to_url(),
+                    protocol.clone().to_url(),
                     interface_name,
                     interface_name
                 );
+                if !service_config.accesslog.is_empty() {
+                    protocol_url.push_str(&format!(
+                        "&{}={}&{}={}",
+                        ACCESS_LOG_KEY,
+                        urlencoding::encode(&service_config.accesslog),
+                        ACCESS_LOG_ARGS_KEY,
+                        service_config.accesslog_args
+                    ));
+                }
+                let token = match service_config.token.as_str() {
+                    "" | "false" => None,
+                    "true" | "default" => Some(auth::generate_token()),
+                    token => Some(token.to_string()),
+                };
+                if let Some(token) = token {
+                    protocol_url.push_str(&format!(
+                        "&{}={}",
+                        TOKEN_KEY,
+                        urlencoding::encode(&token)
+                    ));
+                }
+                if !service_config.compression.is_empty() {
+                    protocol_url.push_str(&format!(
+                        "&{}={}",
+                        COMPRESSION_KEY,
+                        urlencoding::encode(&service_config.compression)
+                    ));
+                }
+                for (key, value) in [
+                    (
+                        COMPRESSION_MIN_SIZE_KEY,
+                        service_config.compression_min_size,
+                    ),
+                    (MAX_MESSAGE_SIZE_KEY, service_config.max_message_size),
+                    (
+                        MAX_ENCODE_MESSAGE_SIZE_KEY,
+                        service_config.max_encode_message_size,
+                    ),
+                ] {
+                    if let Some(value) = value {
+                        protocol_url.push_str(&format!("&{}={}", key, value));
+                    }
+                }
+                if let Some(tls) = &protocol.tls {
+                    protocol_url
+                        .push_str(&tls.export(&format!("{}:{}", protocol.ip, protocol.port)));
+                }
+                for (key, value) in [
+                    ("group", &service_config.group),
+                    ("version", &service_config.version),
+                ] {
+                    if !value.is_empty() {
+                        protocol_url.push_str(&format!("&{}={}", key, urlencoding::encode(value)));
+                    }
+                }
+                protocol_url.push_str(&service_config.methods_url_params());
                 info!("protocol_url: {:?}", protocol_url);
                 protocol_url.parse().ok()
             } else {
@@ -110,6 +181,36 @@ impl Dubbo {
     pub async fn start(&mut self) {
         self.init().unwrap();
         info!("starting...");
+
+        let tracing = &self.config.as_ref().unwrap().tracing;
+        if tracing.enabled {
+            if let Err(err) = telemetry::init_otlp(tracing) {
+                error!("init otlp tracing error: {:?}", err);
+            }
+        }
+
+        let config = self.config.as_ref().unwrap();
+        dynamic::set_application(&config.application.name);
+        if config.config_center.watch().is_some() {
+            info!(
+                "watching dynamic config from {}://{}",
+                config.config_center.protocol, config.config_center.address
+            );
+        }
+
+        let admin = &self.config.as_ref().unwrap().admin;
+        if admin.enabled {
+            match admin.to_addr().parse() {
+                Ok(addr) => {
+                    tokio::spawn(async move {
+                        if let Err(err) = AdminServer::new(addr).serve().await {
+                            error!("admin server error: {:?}", err);
+                        }
+                    });
+                }
+                Err(err) => error!("invalid admin address {}: {:?}", admin.to_addr(), err),
+            }
+        }
         // TODO: server registry
 
         let mut registry_extensions = Vec::new();
@@ -136,8 +237,19 @@ impl Dubbo {
 
                 //TODO multiple registry
                 for registry_extension in &registry_extensions {
-                    let _ = registry_extension.register(url.clone()).await;
+                    let result = match registry_extension.register(url.clone()).await {
+                        Ok(_) => "success",
+                        Err(_) => "failure",
+                    };
+                    metrics::increment_counter(
+                        REGISTRY_REGISTER_TOTAL,
+                        &vec![
+                            ("interface", url.path().trim_start_matches('/').to_string()),
+                            ("result", result.to_string()),
+                        ],
+                    );
                 }
+                registry::exported::add(url.clone(), registry_extensions.clone());
             }
         }
 
@@ -154,3 +266,18 @@ impl Drop for Dubbo {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::config::auth::JwtConfig;
+
+    use super::*;
+
+    #[test]
+    fn test_bad_jwt_config_fails_init() {
+        let mut config = RootConfig::new();
+        config.auth.jwt = Some(JwtConfig::default());
+        let err = Dubbo::new().with_config(config).init().err().unwrap();
+        assert!(err.to_string().contains("auth.jwt"), "{}", err);
+    }
+}
@@ -0,0 +1,217 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use futures_util::stream;
+use prost_reflect::MessageDescriptor;
+use serde_json::Value;
+
+use super::{
+    from_json, from_protobuf, to_protobuf, Descriptors, DynamicMessage, JSON_CONTENT_TYPE,
+    PROTOBUF_CONTENT_TYPE,
+};
+use crate::{
+    codegen::{ClientBuilder, Decoding, Metadata, Request, Response, RpcInvocation, TripleClient},
+    status::{Code, Status},
+    triple::codec::raw::{RawCodec, RawMessage},
+};
+use prost_reflect::prost::Message;
+
+/// Client calling any method of any service, see [`crate::generic`].
+#[derive(Clone)]
+pub struct GenericClient {
+    inner: TripleClient<RawCodec>,
+    descriptors: Descriptors,
+}
+
+impl GenericClient {
+    pub fn new(builder: ClientBuilder) -> Self {
+        Self {
+            inner: TripleClient::new(builder),
+            descriptors: Descriptors::default(),
+        }
+    }
+
+    /// Call the methods known by `descriptors` with protobuf.
+    pub fn with_descriptors(self, descriptors: Descriptors) -> Self {
+        Self {
+            descriptors,
+            ..self
+        }
+    }
+
+    /// Call `method` of `interface` with the json `args`, returning the json
+    /// of the response. The call sends protobuf when the descriptors know the
+    /// method, and `args` as json otherwise.
+    pub async fn invoke(
+        &mut self,
+        interface: &str,
+        method: &str,
+        args: Value,
+    ) -> Result<Value, Status> {
+        match self.descriptors.method(interface, method) {
+            Some(desc) => {
+                let message = RawMessage(to_protobuf(desc.input(), args)?);
+                let response = self
+                    .call(interface, method, PROTOBUF_CONTENT_TYPE, message)
+                    .await?;
+                from_protobuf(desc.output(), response.0)
+            }
+            None => {
+                let message = serde_json::to_vec(&args)
+                    .map_err(|err| Status::new(Code::InvalidArgument, err.to_string()))?;
+                let response = self
+                    .call(
+                        interface,
+                        method,
+                        JSON_CONTENT_TYPE,
+                        RawMessage(message.into()),
+                    )
+                    .await?;
+                from_json(&response.0)
+            }
+        }
+    }
+
+    /// Call `method` of `interface` with a dynamic protobuf message, the
+    /// descriptors having to know the method.
+    pub async fn invoke_dynamic(
+        &mut self,
+        interface: &str,
+        method: &str,
+        args: DynamicMessage,
+    ) -> Result<DynamicMessage, Status> {
+        let desc = self.descriptors.method(interface, method).ok_or_else(|| {
+            Status::new(
+                Code::Unimplemented,
+                format!("no descriptor of method {}/{}", interface, method),
+            )
+        })?;
+        let message = RawMessage(args.encode_to_vec().into());
+        let response = self
+            .call(interface, method, PROTOBUF_CONTENT_TYPE, message)
+            .await?;
+        DynamicMessage::decode(desc.output(), response.0)
+            .map_err(|err| Status::new(Code::Internal, err.to_string()))
+    }
+
+    /// Call `method` of `interface` of any kind with the json `args`, a
+    /// single one for the methods taking a single message, and the
+    /// attachments of the request metadata. Returns the response headers and
+    /// the stream of the json responses, ended by the trailers.
+    pub async fn invoke_streaming(
+        &mut self,
+        interface: &str,
+        method: &str,
+        args: Request<Vec<Value>>,
+    ) -> Result<Response<GenericStreaming>, Status> {
+        let desc = self.descriptors.method(interface, method);
+        let (metadata, args) = args.into_parts();
+        let (content_type, messages) = match &desc {
+            Some(desc) => (
+                PROTOBUF_CONTENT_TYPE,
+                args.into_iter()
+                    .map(|args| to_protobuf(desc.input(), args).map(RawMessage))
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            None => (
+                JSON_CONTENT_TYPE,
+                args.iter()
+                    .map(|args| {
+                        serde_json::to_vec(args)
+                            .map(|message| RawMessage(message.into()))
+                            .map_err(|err| Status::new(Code::InvalidArgument, err.to_string()))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+        };
+
+        let path = path(interface, method)?;
+        let invocation = invocation(interface, method);
+        let metadata = metadata.insert(
+            http::header::CONTENT_TYPE.to_string(),
+            content_type.to_string(),
+        );
+        let response = self
+            .inner
+            .bidi_streaming(
+                Request::from_parts(metadata, stream::iter(messages)),
+                path,
+                invocation,
+            )
+            .await?;
+        Ok(response.map(|inner| GenericStreaming {
+            inner,
+            output: desc.map(|desc| desc.output()),
+        }))
+    }
+
+    async fn call(
+        &mut self,
+        interface: &str,
+        method: &str,
+        content_type: &str,
+        message: RawMessage,
+    ) -> Result<RawMessage, Status> {
+        let path = path(interface, method)?;
+        let invocation = invocation(interface, method);
+        let metadata = Metadata::new().insert(
+            http::header::CONTENT_TYPE.to_string(),
+            content_type.to_string(),
+        );
+        let response = self
+            .inner
+            .unary(Request::from_parts(metadata, message), path, invocation)
+            .await?;
+        Ok(response.into_inner())
+    }
+}
+
+/// Json responses of [`GenericClient::invoke_streaming`].
+pub struct GenericStreaming {
+    inner: Decoding<RawMessage>,
+    output: Option<MessageDescriptor>,
+}
+
+impl GenericStreaming {
+    /// The next response, `None` at the end of the stream.
+    pub async fn message(&mut self) -> Result<Option<Value>, Status> {
+        match self.inner.message().await? {
+            Some(message) => match &self.output {
+                Some(desc) => from_protobuf(desc.clone(), message.0).map(Some),
+                None => from_json(&message.0).map(Some),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Trailers of the response, once all the messages are read.
+    pub async fn trailer(&mut self) -> Result<Option<Metadata>, Status> {
+        self.inner.trailer().await
+    }
+}
+
+fn path(interface: &str, method: &str) -> Result<http::uri::PathAndQuery, Status> {
+    format!("/{}/{}", interface, method)
+        .parse()
+        .map_err(|_| Status::new(Code::InvalidArgument, format!("invalid method {}", method)))
+}
+
+fn invocation(interface: &str, method: &str) -> RpcInvocation {
+    RpcInvocation::default()
+        .with_service_unique_name(interface.to_string())
+        .with_method_name(method.to_string())
+}
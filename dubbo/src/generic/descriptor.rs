@@ -0,0 +1,137 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{fs, io, path::Path};
+
+use prost_reflect::{DescriptorPool, MethodDescriptor};
+
+/// Failure loading [`Descriptors`].
+#[derive(Debug, thiserror::Error)]
+pub enum DescriptorError {
+    #[error("error reading descriptor set: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid descriptors: {0}")]
+    Invalid(#[from] prost_reflect::DescriptorError),
+    #[error("server reflection failed: {0}")]
+    Reflection(String),
+}
+
+/// Protobuf descriptors of the services called or served generically.
+#[derive(Debug, Clone, Default)]
+pub struct Descriptors {
+    pool: DescriptorPool,
+}
+
+impl Descriptors {
+    pub fn new(pool: DescriptorPool) -> Self {
+        Self { pool }
+    }
+
+    /// Descriptors of an encoded `FileDescriptorSet`, as written by
+    /// `protoc --include_imports --descriptor_set_out`.
+    pub fn decode(bytes: &[u8]) -> Result<Self, DescriptorError> {
+        Ok(Self::new(DescriptorPool::decode(bytes)?))
+    }
+
+    /// Descriptors of the `FileDescriptorSet` file at `path`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, DescriptorError> {
+        Self::decode(&fs::read(path)?)
+    }
+
+    pub fn pool(&self) -> &DescriptorPool {
+        &self.pool
+    }
+
+    /// Descriptor of `method` of the service `interface`, named by the full
+    /// name of the protobuf service.
+    pub fn method(&self, interface: &str, method: &str) -> Option<MethodDescriptor> {
+        self.pool
+            .get_service_by_name(interface)?
+            .methods()
+            .find(|m| m.name() == method)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_reflect::{
+        prost::Message,
+        prost_types::{
+            field_descriptor_proto::{Label, Type},
+            DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+            MethodDescriptorProto, ServiceDescriptorProto,
+        },
+    };
+    use serde_json::json;
+
+    use super::*;
+    use crate::generic::{from_protobuf, to_protobuf};
+
+    /// Descriptors of `test.Greeter`, whose `SayHello` takes a
+    /// `HelloRequest { name }` and returns a `HelloReply { message }`.
+    fn greeter() -> Descriptors {
+        let message = |name: &str, field: &str| DescriptorProto {
+            name: Some(name.to_string()),
+            field: vec![FieldDescriptorProto {
+                name: Some(field.to_string()),
+                number: Some(1),
+                label: Some(Label::Optional as i32),
+                r#type: Some(Type::String as i32),
+                json_name: Some(field.to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("greeter.proto".to_string()),
+            package: Some("test".to_string()),
+            message_type: vec![
+                message("HelloRequest", "name"),
+                message("HelloReply", "message"),
+            ],
+            service: vec![ServiceDescriptorProto {
+                name: Some("Greeter".to_string()),
+                method: vec![MethodDescriptorProto {
+                    name: Some("SayHello".to_string()),
+                    input_type: Some(".test.HelloRequest".to_string()),
+                    output_type: Some(".test.HelloReply".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            syntax: Some("proto3".to_string()),
+            ..Default::default()
+        };
+        let set = FileDescriptorSet { file: vec![file] };
+        Descriptors::decode(&set.encode_to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_descriptors() {
+        let descriptors = greeter();
+        assert!(descriptors.method("test.Greeter", "Unknown").is_none());
+        let method = descriptors.method("test.Greeter", "SayHello").unwrap();
+
+        let bytes = to_protobuf(method.input(), json!({"name": "dubbo"})).unwrap();
+        assert_eq!(&bytes[..], b"\x0a\x05dubbo");
+        assert_eq!(
+            from_protobuf(method.input(), bytes).unwrap(),
+            json!({"name": "dubbo"})
+        );
+        assert!(to_protobuf(method.input(), json!({"age": 3})).is_err());
+    }
+}
@@ -0,0 +1,76 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Generic invocation, calling and serving any service without its compiled
+//! types.
+//!
+//! Messages are given as json. With the protobuf [`Descriptors`] of a
+//! service, loaded from a descriptor set file or the server reflection of
+//! its providers, they are sent as protobuf like by the code generated from
+//! `.proto` files. Without them they are sent as json, like by the services
+//! defined with `#[dubbo::service]`. Clients call methods of any kind, services
+//! only serve unary methods.
+
+pub mod client;
+pub mod descriptor;
+pub mod reflection;
+pub mod service;
+
+use bytes::Bytes;
+use prost_reflect::{prost::Message, MessageDescriptor};
+use serde_json::Value;
+
+pub use self::{
+    client::{GenericClient, GenericStreaming},
+    descriptor::{DescriptorError, Descriptors},
+    service::{GenericServer, GenericService},
+};
+pub use prost_reflect::DynamicMessage;
+
+use crate::status::{Code, Status};
+
+/// Content type of the generic calls sending protobuf.
+pub const PROTOBUF_CONTENT_TYPE: &str = "application/grpc+proto";
+/// Content type of the generic calls sending json.
+pub const JSON_CONTENT_TYPE: &str = "application/grpc+json";
+
+/// Whether a call of `content_type` sends json.
+fn is_json(content_type: Option<&String>) -> bool {
+    content_type.is_some_and(|v| v.ends_with("json"))
+}
+
+/// Protobuf encoding of the json `value` of a message of `desc`.
+fn to_protobuf(desc: MessageDescriptor, value: Value) -> Result<Bytes, Status> {
+    let message = DynamicMessage::deserialize(desc, value)
+        .map_err(|err| Status::new(Code::InvalidArgument, err.to_string()))?;
+    Ok(message.encode_to_vec().into())
+}
+
+/// Json of the protobuf encoded message of `desc`.
+fn from_protobuf(desc: MessageDescriptor, bytes: Bytes) -> Result<Value, Status> {
+    let message = DynamicMessage::decode(desc, bytes)
+        .map_err(|err| Status::new(Code::InvalidArgument, err.to_string()))?;
+    serde_json::to_value(&message).map_err(|err| Status::new(Code::Internal, err.to_string()))
+}
+
+/// Json of a message sent as json, `null` when empty.
+fn from_json(bytes: &[u8]) -> Result<Value, Status> {
+    if bytes.is_empty() {
+        return Ok(Value::Null);
+    }
+    serde_json::from_slice(bytes).map_err(|err| Status::new(Code::InvalidArgument, err.to_string()))
+}
@@ -0,0 +1,164 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Client of the gRPC server reflection, loading the [`Descriptors`] of the
+//! services of a provider.
+
+use std::collections::{HashMap, HashSet};
+
+use prost_reflect::{prost::Message as _, prost_types::FileDescriptorProto, DescriptorPool};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+use super::{DescriptorError, Descriptors};
+use crate::codegen::{ClientBuilder, DefaultCodec, RpcInvocation, TripleClient};
+
+/// Service of the server reflection.
+pub const REFLECTION_SERVICE: &str = "grpc.reflection.v1alpha.ServerReflection";
+
+const REFLECTION_METHOD: &str = "ServerReflectionInfo";
+
+#[derive(Clone, PartialEq, prost::Message, Serialize, Deserialize)]
+struct ServerReflectionRequest {
+    #[prost(string, tag = "1")]
+    host: String,
+    #[prost(oneof = "MessageRequest", tags = "3, 4")]
+    message_request: Option<MessageRequest>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof, Serialize, Deserialize)]
+enum MessageRequest {
+    #[prost(string, tag = "3")]
+    FileByFilename(String),
+    #[prost(string, tag = "4")]
+    FileContainingSymbol(String),
+}
+
+#[derive(Clone, PartialEq, prost::Message, Serialize, Deserialize)]
+struct ServerReflectionResponse {
+    #[prost(oneof = "MessageResponse", tags = "4, 7")]
+    message_response: Option<MessageResponse>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof, Serialize, Deserialize)]
+enum MessageResponse {
+    #[prost(message, tag = "4")]
+    FileDescriptorResponse(FileDescriptorResponse),
+    #[prost(message, tag = "7")]
+    ErrorResponse(ErrorResponse),
+}
+
+#[derive(Clone, PartialEq, prost::Message, Serialize, Deserialize)]
+struct FileDescriptorResponse {
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    file_descriptor_proto: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, prost::Message, Serialize, Deserialize)]
+struct ErrorResponse {
+    #[prost(int32, tag = "1")]
+    error_code: i32,
+    #[prost(string, tag = "2")]
+    error_message: String,
+}
+
+impl ServerReflectionRequest {
+    fn new(message_request: MessageRequest) -> Self {
+        Self {
+            host: String::new(),
+            message_request: Some(message_request),
+        }
+    }
+}
+
+impl Descriptors {
+    /// Descriptors of the file defining `symbol`, such as the full name of a
+    /// service, and of its dependencies, fetched from the server reflection
+    /// of the providers of `builder`.
+    pub async fn from_reflection(
+        builder: ClientBuilder,
+        symbol: &str,
+    ) -> Result<Self, DescriptorError> {
+        let reflection_err =
+            |err: crate::status::Status| DescriptorError::Reflection(err.to_string());
+        let mut client = TripleClient::<DefaultCodec>::new(builder);
+        let (tx, rx) = mpsc::unbounded_channel();
+        let _ = tx.send(ServerReflectionRequest::new(
+            MessageRequest::FileContainingSymbol(symbol.to_string()),
+        ));
+
+        let invocation = RpcInvocation::default()
+            .with_service_unique_name(REFLECTION_SERVICE.to_string())
+            .with_method_name(REFLECTION_METHOD.to_string());
+        let path = format!("/{}/{}", REFLECTION_SERVICE, REFLECTION_METHOD)
+            .parse()
+            .unwrap();
+        let mut responses = client
+            .bidi_streaming(UnboundedReceiverStream::new(rx), path, invocation)
+            .await
+            .map_err(reflection_err)?
+            .into_inner();
+
+        let mut files: HashMap<String, FileDescriptorProto> = HashMap::new();
+        let mut requested = HashSet::new();
+        let mut pending = 1;
+        while pending > 0 {
+            let response: ServerReflectionResponse = responses
+                .message()
+                .await
+                .map_err(reflection_err)?
+                .ok_or_else(|| DescriptorError::Reflection("stream closed".to_string()))?;
+            pending -= 1;
+
+            let file_descriptors = match response.message_response {
+                Some(MessageResponse::FileDescriptorResponse(response)) => {
+                    response.file_descriptor_proto
+                }
+                Some(MessageResponse::ErrorResponse(err)) => {
+                    return Err(DescriptorError::Reflection(err.error_message))
+                }
+                None => return Err(DescriptorError::Reflection("empty response".to_string())),
+            };
+            for bytes in file_descriptors {
+                let file = FileDescriptorProto::decode(&bytes[..]).map_err(|err| {
+                    DescriptorError::Reflection(format!("invalid file descriptor: {}", err))
+                })?;
+                files.insert(file.name().to_string(), file);
+            }
+            // ask for the dependencies not sent along
+            let missing: Vec<String> = files
+                .values()
+                .flat_map(|file| file.dependency.iter())
+                .filter(|dep| !files.contains_key(*dep))
+                .cloned()
+                .collect();
+            for dep in missing {
+                if requested.insert(dep.clone()) {
+                    let _ = tx.send(ServerReflectionRequest::new(
+                        MessageRequest::FileByFilename(dep),
+                    ));
+                    pending += 1;
+                }
+            }
+        }
+
+        let mut pool = DescriptorPool::new();
+        pool.add_file_descriptor_protos(files.into_values())?;
+        Ok(Self::new(pool))
+    }
+}
@@ -0,0 +1,173 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{
+    convert::Infallible,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use async_trait::async_trait;
+use http_body::Body;
+use prost_reflect::MethodDescriptor;
+use serde_json::Value;
+use tower_service::Service;
+
+use super::{from_json, from_protobuf, is_json, to_protobuf, Descriptors};
+use crate::{
+    codegen::{Request, Response, TripleServer, UnarySvc},
+    status::{Code, Status},
+    triple::codec::raw::{RawCodec, RawMessage},
+    utils::boxed_clone::BoxCloneService,
+    BoxBody, BoxFuture, StdError,
+};
+
+/// Provider of any method of a service, see [`crate::generic`].
+#[async_trait]
+pub trait GenericService: Send + Sync + 'static {
+    /// Handle a call of `method`. Protobuf arguments are decoded to their
+    /// canonical json, and the json returned is encoded to the response
+    /// message of the method; json arguments are passed as sent.
+    async fn invoke(&self, method: String, args: Value) -> Result<Value, Status>;
+}
+
+/// Serves the calls of an interface with a [`GenericService`].
+pub struct GenericServer<T> {
+    inner: Arc<T>,
+    interface: Arc<str>,
+    descriptors: Descriptors,
+}
+
+impl<T: GenericService> GenericServer<T> {
+    pub fn new(interface: &str, inner: T) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            interface: interface.into(),
+            descriptors: Descriptors::default(),
+        }
+    }
+
+    /// Accept protobuf calls of the methods known by `descriptors`.
+    pub fn with_descriptors(self, descriptors: Descriptors) -> Self {
+        Self {
+            descriptors,
+            ..self
+        }
+    }
+
+    /// Register the service to be exported by the triple protocol.
+    pub fn register(self) {
+        crate::protocol::triple::TRIPLE_SERVICES
+            .write()
+            .unwrap()
+            .insert(self.interface.to_string(), BoxCloneService::new(self));
+    }
+}
+
+impl<T> Clone for GenericServer<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            interface: self.interface.clone(),
+            descriptors: self.descriptors.clone(),
+        }
+    }
+}
+
+impl<T, B> Service<http::Request<B>> for GenericServer<T>
+where
+    T: GenericService,
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let method = req
+            .uri()
+            .path()
+            .strip_prefix('/')
+            .and_then(|path| path.strip_prefix(&*self.interface))
+            .and_then(|path| path.strip_prefix('/'))
+            .filter(|method| !method.is_empty() && !method.contains('/'))
+            .map(str::to_string);
+        let Some(method) = method else {
+            let status = Status::new(
+                Code::Unimplemented,
+                format!("unknown method {}", req.uri().path()),
+            );
+            return Box::pin(async move { Ok(status.to_http()) });
+        };
+
+        let svc = GenericSvc {
+            inner: self.inner.clone(),
+            descriptor: self.descriptors.method(&self.interface, &method),
+            method,
+        };
+        Box::pin(async move {
+            let mut server = TripleServer::<RawMessage, RawMessage, RawCodec>::new();
+            Ok(server.unary(svc, req).await)
+        })
+    }
+}
+
+/// A unary call of `method`.
+struct GenericSvc<T> {
+    inner: Arc<T>,
+    method: String,
+    descriptor: Option<MethodDescriptor>,
+}
+
+impl<T: GenericService> UnarySvc<RawMessage> for GenericSvc<T> {
+    type Response = RawMessage;
+    type Future = BoxFuture<Response<Self::Response>, Status>;
+
+    fn call(&mut self, request: Request<RawMessage>) -> Self::Future {
+        let inner = self.inner.clone();
+        let method = self.method.clone();
+        let descriptor = self.descriptor.clone();
+        let json = is_json(request.metadata.get(http::header::CONTENT_TYPE.as_str()));
+        Box::pin(async move {
+            let message = request.message.0;
+            if json {
+                let result = inner.invoke(method, from_json(&message)?).await?;
+                let bytes = serde_json::to_vec(&result)
+                    .map_err(|err| Status::new(Code::Internal, err.to_string()))?;
+                return Ok(Response::new(RawMessage(bytes.into())));
+            }
+
+            let descriptor = descriptor.ok_or_else(|| {
+                Status::new(
+                    Code::Unimplemented,
+                    format!("no descriptor of method {}, call it with json", method),
+                )
+            })?;
+            let args = from_protobuf(descriptor.input(), message)?;
+            let result = inner.invoke(method, args).await?;
+            Ok(Response::new(RawMessage(to_protobuf(
+                descriptor.output(),
+                result,
+            )?)))
+        })
+    }
+}
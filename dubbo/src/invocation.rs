@@ -19,9 +19,12 @@ use std::{collections::HashMap, fmt::Debug, str::FromStr};
 
 use futures_core::Stream;
 
+use crate::cluster::router::zone::zone_router::ZoneProviders;
+
 pub struct Request<T> {
     pub message: T,
     pub metadata: Metadata,
+    pub extensions: http::Extensions,
 }
 
 impl<T> Request<T> {
@@ -29,6 +32,7 @@ impl<T> Request<T> {
         Self {
             message,
             metadata: Metadata::new(),
+            extensions: http::Extensions::new(),
         }
     }
 
@@ -41,7 +45,11 @@ impl<T> Request<T> {
     }
 
     pub fn from_parts(metadata: Metadata, message: T) -> Self {
-        Request { message, metadata }
+        Request {
+            message,
+            metadata,
+            extensions: http::Extensions::new(),
+        }
     }
 
     pub fn from_http(req: http::Request<T>) -> Self {
@@ -49,6 +57,7 @@ impl<T> Request<T> {
         Request {
             metadata: Metadata::from_headers(parts.headers),
             message: body,
+            extensions: parts.extensions,
         }
     }
 
@@ -63,10 +72,21 @@ impl<T> Request<T> {
         *http_req.uri_mut() = uri;
         *http_req.method_mut() = method;
         *http_req.headers_mut() = self.metadata.into_headers();
+        *http_req.extensions_mut() = self.extensions;
 
         http_req
     }
 
+    /// Values attached to the request by filters, such as the claims of
+    /// an authenticated caller.
+    pub fn extensions(&self) -> &http::Extensions {
+        &self.extensions
+    }
+
+    pub fn extensions_mut(&mut self) -> &mut http::Extensions {
+        &mut self.extensions
+    }
+
     pub fn map<F, U>(self, f: F) -> Request<U>
     where
         F: FnOnce(T) -> U,
@@ -75,6 +95,7 @@ impl<T> Request<T> {
         Request {
             message: m,
             metadata: self.metadata,
+            extensions: self.extensions,
         }
     }
 }
@@ -100,6 +121,10 @@ impl<T> Response<T> {
         (self.metadata, self.message)
     }
 
+    pub fn into_inner(self) -> T {
+        self.message
+    }
+
     pub fn into_http(self) -> http::Response<T> {
         let mut http_resp = http::Response::new(self.message);
         *http_resp.version_mut() = http::Version::HTTP_2;
@@ -184,6 +209,10 @@ impl Metadata {
         self
     }
 
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.inner.get(key)
+    }
+
     pub fn from_headers(headers: http::HeaderMap) -> Self {
         let mut h: HashMap<String, String> = HashMap::new();
         for (k, v) in headers.into_iter() {
@@ -208,6 +237,11 @@ impl Metadata {
     }
 }
 
+/// Address of the peer on the other side of a call, set by the transport in
+/// the extensions of provider requests and consumer responses.
+#[derive(Debug, Clone)]
+pub struct RemoteAddr(pub String);
+
 pub trait Invocation {
     fn get_target_service_unique_name(&self) -> String;
     fn get_method_name(&self) -> String;
@@ -219,6 +253,7 @@ pub struct RpcInvocation {
     method_name: String,
 
     metadata: Metadata,
+    zone_providers: Option<ZoneProviders>,
 }
 
 impl RpcInvocation {
@@ -236,6 +271,12 @@ impl RpcInvocation {
         self
     }
 
+    /// The providers of the called service in each zone, for the zone router.
+    pub fn with_zone_providers(mut self, zone_providers: ZoneProviders) -> Self {
+        self.zone_providers = Some(zone_providers);
+        self
+    }
+
     pub fn unique_fingerprint(&self) -> String {
         format!("{}#{}", self.target_service_unique_name, self.method_name)
     }
@@ -243,6 +284,10 @@ impl RpcInvocation {
     pub fn get_metadata(&self) -> Metadata {
         self.metadata.clone()
     }
+
+    pub fn get_zone_providers(&self) -> Option<&ZoneProviders> {
+        self.zone_providers.as_ref()
+    }
 }
 
 impl Invocation for RpcInvocation {
@@ -30,6 +30,9 @@ use http_body::Body;
 use pin_project::pin_project;
 use thiserror::Error;
 
+/// Bytes of a request body kept to replay it by default.
+pub const DEFAULT_RETRY_BUFFER_SIZE: usize = 64 * 1024;
+
 #[derive(Error, Debug)]
 #[error("buffered body reach max capacity.")]
 pub struct ReachMaxCapacityError;
@@ -61,6 +64,7 @@ impl BufferedBody {
                 buf: InnerBuffer {
                     bufs: Default::default(),
                     capacity: buf_size,
+                    capped: false,
                 },
             }),
             replay_body: false,
@@ -145,14 +149,16 @@ impl Body for BufferedBody {
 
         let len = data.remaining();
 
-        owned_body.buf.capacity = owned_body.buf.capacity.saturating_sub(len);
-
-        let data = if owned_body.buf.is_capped() {
+        // once the body outgrows the buffer it can no longer be replayed, so
+        // nothing more is kept
+        let data = if owned_body.buf.is_capped() || len > owned_body.buf.capacity {
+            owned_body.buf.capped = true;
             if owned_body.buf.has_remaining() {
                 owned_body.buf.bufs = VecDeque::default();
             }
             data.copy_to_bytes(len)
         } else {
+            owned_body.buf.capacity -= len;
             owned_body.buf.push_bytes(data.copy_to_bytes(len))
         };
 
@@ -216,6 +222,7 @@ impl Body for BufferedBody {
 pub struct InnerBuffer {
     bufs: VecDeque<Bytes>,
     capacity: usize,
+    capped: bool,
 }
 
 impl InnerBuffer {
@@ -225,7 +232,7 @@ impl InnerBuffer {
     }
 
     pub fn is_capped(&self) -> bool {
-        self.capacity == 0
+        self.capped
     }
 }
 
@@ -329,8 +336,12 @@ pub struct CloneBody(#[pin] BufferedBody);
 
 impl CloneBody {
     pub fn new(inner_body: hyper::Body) -> Self {
-        let inner_body = BufferedBody::new(inner_body, 1024 * 64);
-        CloneBody(inner_body)
+        Self::with_capacity(inner_body, DEFAULT_RETRY_BUFFER_SIZE)
+    }
+
+    /// Body replayed by its clones as long as it fits in `capacity` bytes.
+    pub fn with_capacity(inner_body: hyper::Body, capacity: usize) -> Self {
+        CloneBody(BufferedBody::new(inner_body, capacity))
     }
 }
 
@@ -363,3 +374,43 @@ impl Clone for CloneBody {
         Self(self.0.clone())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn read(body: &mut CloneBody) -> Result<Vec<u8>, StdError> {
+        let mut data = Vec::new();
+        while let Some(chunk) =
+            futures_util::future::poll_fn(|cx| Pin::new(&mut *body).poll_data(cx)).await
+        {
+            let mut chunk = chunk?;
+            data.extend_from_slice(&chunk.copy_to_bytes(chunk.remaining()));
+        }
+        Ok(data)
+    }
+
+    #[tokio::test]
+    async fn test_retry_buffer() {
+        let chunks: Vec<Result<_, std::io::Error>> = vec![Ok("hello "), Ok("dubbo")];
+        let mut body = CloneBody::with_capacity(
+            hyper::Body::wrap_stream(futures_util::stream::iter(chunks)),
+            11,
+        );
+        let mut retry = body.clone();
+        assert_eq!(read(&mut body).await.unwrap(), b"hello dubbo");
+        drop(body);
+        assert_eq!(read(&mut retry).await.unwrap(), b"hello dubbo");
+
+        let chunks: Vec<Result<_, std::io::Error>> = vec![Ok("hello "), Ok("dubbo!")];
+        let mut body = CloneBody::with_capacity(
+            hyper::Body::wrap_stream(futures_util::stream::iter(chunks)),
+            11,
+        );
+        let mut retry = body.clone();
+        assert_eq!(read(&mut body).await.unwrap(), b"hello dubbo!");
+        drop(body);
+        let err = read(&mut retry).await.unwrap_err();
+        assert!(err.is::<ReachMaxCapacityError>());
+    }
+}
@@ -16,7 +16,12 @@
  */
 use std::{mem, pin::Pin, task::Poll};
 
-use crate::{logger::tracing::debug, StdError};
+use crate::{
+    cluster::{hedging::AttemptedProviders, router::zone::zone_router::ZoneProviders},
+    directory::health::{self, HealthProbe, ProviderHealth},
+    logger::tracing::debug,
+    StdError, Url,
+};
 use futures_core::{future::BoxFuture, ready, Future, TryFuture};
 use futures_util::FutureExt;
 use pin_project::pin_project;
@@ -176,6 +181,9 @@ where
     rx: Receiver<ObserveState>,
     poll: ReusableBoxFuture<'static, ObserveState>,
     polling: bool,
+    url: Option<Url>,
+    health: Option<ProviderHealth>,
+    zone_providers: Option<ZoneProviders>,
 }
 
 impl<Inv> CloneInvoker<Inv>
@@ -197,13 +205,69 @@ where
             rx,
             polling: false,
             poll: ReusableBoxFuture::new(futures::future::pending()),
+            url: None,
+            health: None,
+            zone_providers: None,
+        }
+    }
+
+    /// Remember the provider `url` the invoker calls.
+    pub fn with_url(self, url: Url) -> Self {
+        Self {
+            url: Some(url),
+            ..self
+        }
+    }
+
+    pub fn url(&self) -> Option<&Url> {
+        self.url.as_ref()
+    }
+
+    /// Track the health of the provider, the calls failing to reach it
+    /// counting as passive checks.
+    pub fn with_health(self, health: ProviderHealth) -> Self {
+        Self {
+            health: Some(health),
+            ..self
+        }
+    }
+
+    pub fn health(&self) -> Option<&ProviderHealth> {
+        self.health.as_ref()
+    }
+
+    /// Share the count of the providers of the directory in each zone.
+    pub fn with_zone_providers(self, zone_providers: ZoneProviders) -> Self {
+        Self {
+            zone_providers: Some(zone_providers),
+            ..self
+        }
+    }
+
+    pub fn zone_providers(&self) -> Option<&ZoneProviders> {
+        self.zone_providers.as_ref()
+    }
+
+    /// Whether the provider may be called, true when its health is not
+    /// tracked.
+    pub fn is_healthy(&self) -> bool {
+        self.health
+            .as_ref()
+            .map_or(true, ProviderHealth::is_healthy)
+    }
+
+    /// `ip:port` of the provider, empty when unknown.
+    pub fn address(&self) -> String {
+        match self.url.as_ref().and_then(|url| url.host().zip(url.port())) {
+            Some((host, port)) => format!("{}:{}", host, port),
+            None => String::new(),
         }
     }
 }
 
-impl<Inv> Service<http::Request<CloneBody>> for CloneInvoker<Inv>
+impl<Inv, B> Service<http::Request<CloneBody>> for CloneInvoker<Inv>
 where
-    Inv: Service<http::Request<CloneBody>> + Send + 'static,
+    Inv: Service<http::Request<CloneBody>, Response = http::Response<B>> + Send + 'static,
     Inv::Error: Into<StdError> + Send + Sync + 'static,
     Inv::Future: Send,
 {
@@ -249,7 +313,22 @@ where
     }
 
     fn call(&mut self, req: http::Request<CloneBody>) -> Self::Future {
-        Box::pin(self.inner.call(req))
+        if let Some(attempted) = req.extensions().get::<AttemptedProviders>() {
+            attempted.add(self.address());
+        }
+        let health = self
+            .health
+            .clone()
+            .filter(|_| req.extensions().get::<HealthProbe>().is_none());
+        let fut = self.inner.call(req);
+        match health {
+            Some(health) => Box::pin(async move {
+                let result = fut.await;
+                health.report_call(!health::provider_failed(&result));
+                result
+            }),
+            None => Box::pin(fut),
+        }
     }
 }
 
@@ -265,6 +344,9 @@ where
             rx: self.rx.clone(),
             polling: false,
             poll: ReusableBoxFuture::new(futures::future::pending()),
+            url: self.url.clone(),
+            health: self.health.clone(),
+            zone_providers: self.zone_providers.clone(),
         }
     }
 }
@@ -14,12 +14,56 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
-use crate::{codegen::TripleInvoker, invoker::clone_invoker::CloneInvoker, svc::NewService};
+use crate::{
+    codegen::TripleInvoker,
+    config::http2::Http2Config,
+    invoker::clone_invoker::CloneInvoker,
+    svc::NewService,
+    triple::transport::{
+        connection::{self, Connection},
+        io::BoxIO,
+        pool::PoolConfig,
+        tls::ClientTls,
+    },
+    utils::boxed_clone::BoxCloneService,
+    Url,
+};
 
 pub mod clone_body;
 pub mod clone_invoker;
 
-pub struct NewInvoker;
+#[derive(Clone, Default)]
+pub struct NewInvoker {
+    tls: Option<ClientTls>,
+    pool: PoolConfig,
+    http2: Http2Config,
+}
+
+impl NewInvoker {
+    pub fn new() -> Self {
+        NewInvoker::default()
+    }
+
+    pub fn with_tls(self, tls: Option<ClientTls>) -> Self {
+        Self { tls, ..self }
+    }
+
+    pub fn with_pool(self, pool: PoolConfig) -> Self {
+        Self { pool, ..self }
+    }
+
+    pub fn with_http2(self, http2: Http2Config) -> Self {
+        Self { http2, ..self }
+    }
+
+    /// Connector reaching the provider `host` the way its invoker does.
+    pub(crate) fn connector(
+        &self,
+        host: &http::Uri,
+    ) -> BoxCloneService<http::Uri, BoxIO, crate::Error> {
+        connection::connector(host, "http", self.tls.clone())
+    }
+}
 
 impl NewService<String> for NewInvoker {
     type Service = CloneInvoker<TripleInvoker>;
@@ -27,7 +71,13 @@ impl NewService<String> for NewInvoker {
     fn new_service(&self, url: String) -> Self::Service {
         // todo create another invoker by url protocol
 
-        let url = url.parse().unwrap();
-        CloneInvoker::new(TripleInvoker::new(url))
+        let url: Url = url.parse().unwrap();
+        let mut conn = Connection::new()
+            .with_pool(self.pool.clone())
+            .with_http2(self.http2.clone());
+        if let Some(tls) = self.tls.clone() {
+            conn = conn.with_tls(tls);
+        }
+        CloneInvoker::new(TripleInvoker::new_with_connection(url.clone(), conn)).with_url(url)
     }
 }
@@ -15,6 +15,11 @@
  * limitations under the License.
  */
 
+// lets the paths of `#[dubbo::service]` resolve in the tests of the crate
+#[cfg(test)]
+extern crate self as dubbo;
+
+pub mod admin;
 pub mod cluster;
 pub mod codegen;
 pub mod config;
@@ -23,10 +28,12 @@ pub mod directory;
 pub mod extension;
 pub mod filter;
 mod framework;
+pub mod generic;
 pub mod invocation;
 pub mod invoker;
 pub mod loadbalancer;
 pub mod logger;
+pub mod metrics;
 pub mod param;
 pub mod params;
 pub mod protocol;
@@ -34,6 +41,8 @@ pub mod registry;
 pub mod route;
 pub mod status;
 pub mod svc;
+pub mod telemetry;
+pub mod testing;
 pub mod triple;
 pub mod url;
 pub mod utils;
@@ -42,6 +51,7 @@ use http_body::Body;
 use std::{env, future::Future, path::PathBuf, pin::Pin};
 
 pub use crate::url::Url;
+pub use dubbo_macro::service;
 pub use framework::Dubbo;
 
 pub type BoxFuture<T, E> = self::Pin<Box<dyn self::Future<Output = Result<T, E>> + Send + 'static>>;
@@ -18,37 +18,64 @@
 pub mod random;
 
 use futures_core::future::BoxFuture;
-use std::error::Error;
+use std::{collections::HashSet, error::Error, sync::Arc};
 use tokio::time::Duration;
 use tower::{discover::ServiceList, ServiceExt};
 use tower_service::Service;
 use tracing::debug;
 
 use crate::{
+    cluster::{hedging::AttemptedProviders, router::router_chain::RouterChains},
     codegen::RpcInvocation,
+    config::{dynamic, method::MethodConfig, router::ZoneRouterConfig},
     invocation::Metadata,
     invoker::{clone_body::CloneBody, clone_invoker::CloneInvoker},
     loadbalancer::random::RandomLoadBalancer,
     param::Param,
     protocol::triple::triple_invoker::TripleInvoker,
     svc::NewService,
-    StdError,
+    StdError, Url,
 };
 
+/// Names accepted by the `loadbalance` of a reference.
+pub const LOADBALANCES: &[&str] = &["p2c", "random"];
+
 pub struct NewLoadBalancer<N> {
     inner: N,
+    loadbalance: String,
+    chains: RouterChains,
 }
 
 #[derive(Clone)]
 pub struct LoadBalancerSvc<S> {
     inner: S, // Routes service
+    loadbalance: String,
+    chains: RouterChains,
 }
 
 impl<N> NewLoadBalancer<N> {
     pub fn layer() -> impl tower_layer::Layer<N, Service = Self> {
-        tower_layer::layer_fn(|inner| {
+        Self::layer_with_loadbalance(String::new())
+    }
+
+    /// `loadbalance` names the balancer of each call, see `get_loadbalancer`.
+    pub fn layer_with_loadbalance(
+        loadbalance: String,
+    ) -> impl tower_layer::Layer<N, Service = Self> {
+        Self::layer_with_zone(loadbalance, None)
+    }
+
+    /// Like [`NewLoadBalancer::layer_with_loadbalance`], the providers being
+    /// routed by `zone` first, see [`ZoneRouterConfig`].
+    pub fn layer_with_zone(
+        loadbalance: String,
+        zone: Option<ZoneRouterConfig>,
+    ) -> impl tower_layer::Layer<N, Service = Self> {
+        tower_layer::layer_fn(move |inner| {
             NewLoadBalancer {
                 inner, // NewRoutes
+                loadbalance: loadbalance.clone(),
+                chains: RouterChains::new(zone.clone()),
             }
         })
     }
@@ -66,7 +93,11 @@ where
         // Routes service
         let svc = self.inner.new_service(target);
 
-        LoadBalancerSvc { inner: svc }
+        LoadBalancerSvc {
+            inner: svc,
+            loadbalance: self.loadbalance.clone(),
+            chains: self.chains.clone(),
+        }
     }
 }
 
@@ -92,6 +123,13 @@ where
 
     fn call(&mut self, req: http::Request<CloneBody>) -> Self::Future {
         let routes = self.inner.call(());
+        let loadbalance = req
+            .extensions()
+            .get::<MethodConfig>()
+            .map(|method| method.loadbalance.clone())
+            .filter(|loadbalance| !loadbalance.is_empty())
+            .unwrap_or_else(|| self.loadbalance.clone());
+        let chains = self.chains.clone();
 
         let fut = async move {
             let routes = routes.await;
@@ -100,6 +138,19 @@ where
                 Err(e) => return Err(Into::<StdError>::into(e)),
                 Ok(routes) => routes,
             };
+            let metadata = Metadata::from_headers(req.headers().clone());
+            let routes = route(routes, &req, metadata.clone(), &chains)?;
+
+            // hedged attempts go to the providers not called yet
+            let routes = match req.extensions().get::<AttemptedProviders>() {
+                Some(attempted) if routes.iter().any(|r| !attempted.contains(&r.address())) => {
+                    routes
+                        .into_iter()
+                        .filter(|r| !attempted.contains(&r.address()))
+                        .collect()
+                }
+                _ => routes,
+            };
 
             // let service_list: Vec<_> = routes
             //     .into_iter()
@@ -107,14 +158,13 @@ where
             //     .collect();
 
             // let rdm = RandomLoadBalancer::default();
-            let metadata = Metadata::from_headers(req.headers().clone());
             // let invks = rdm.select_invokers(service_list, metadata);
             // invks.oneshot(req).await
             // let service_list = ServiceList::new(service_list);
 
             // let p2c = tower::balance::p2c::Balance::new(service_list);
             // let p: Box<dyn LoadBalancer<Invoker = BoxService<http::Request<CloneBody>, http::Response<UnsyncBoxBody<bytes::Bytes, status::Status>>, Box<dyn std::error::Error + std::marker::Send + std::marker::Sync>>> + std::marker::Send + std::marker::Sync> = get_loadbalancer("p2c").into();
-            let p = get_loadbalancer("p2c");
+            let p = get_loadbalancer(&loadbalance);
             // let ivk = p.select_invokers(invokers, metadata);
             let ivk = p.select_invokers(routes, metadata);
 
@@ -125,6 +175,49 @@ where
     }
 }
 
+/// Providers kept by the router chain of the service called by `req`.
+fn route(
+    invokers: Vec<CloneInvoker<TripleInvoker>>,
+    req: &http::Request<CloneBody>,
+    metadata: Metadata,
+    chains: &RouterChains,
+) -> Result<Vec<CloneInvoker<TripleInvoker>>, StdError> {
+    // the path header stays when the uri is rewritten for the provider
+    let path = req
+        .headers()
+        .get("path")
+        .and_then(|path| path.to_str().ok())
+        .unwrap_or(req.uri().path());
+    let (service, method) = dynamic::service_method(path);
+    let chain = chains.get(service);
+    if chain.routers.is_empty() {
+        return Ok(invokers);
+    }
+    let mut invocation = RpcInvocation::default()
+        .with_service_unique_name(service.to_string())
+        .with_method_name(method.to_string())
+        .with_metadata(metadata);
+    if let Some(zones) = invokers.iter().find_map(|invoker| invoker.zone_providers()) {
+        invocation = invocation.with_zone_providers(zones.clone());
+    }
+    let urls = invokers
+        .iter()
+        .filter_map(|invoker| invoker.url().cloned())
+        .collect();
+    let routed: HashSet<Url> = chain
+        .route(urls, Arc::new(invocation))
+        .into_iter()
+        .collect();
+    let invokers: Vec<_> = invokers
+        .into_iter()
+        .filter(|invoker| invoker.url().map_or(true, |url| routed.contains(url)))
+        .collect();
+    if invokers.is_empty() {
+        return Err(format!("no provider of {} left by the routers", service).into());
+    }
+    Ok(invokers)
+}
+
 type DubboBoxService = tower::util::BoxService<
     http::Request<CloneBody>,
     http::Response<crate::BoxBody>,
@@ -145,10 +238,7 @@ fn get_loadbalancer(
     loadbalancer: &str,
 ) -> Box<dyn LoadBalancer<Invoker = DubboBoxService> + Send + Sync + 'static> {
     match loadbalancer {
-        "random" => {
-            println!("random!");
-            Box::new(RandomLoadBalancer::default())
-        }
+        "random" => Box::new(RandomLoadBalancer::default()),
         "p2c" => Box::new(P2cBalancer::default()),
         _ => Box::new(P2cBalancer::default()),
     }
@@ -185,3 +275,53 @@ impl LoadBalancer for P2cBalancer {
         svc
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cluster::router::zone::zone_router::FORCE_ZONE_HEADER, invoker::NewInvoker, svc::NewService,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_route_by_zone() {
+        let invokers: Vec<_> = ["hz-b", "hz-a"]
+            .iter()
+            .enumerate()
+            .map(|(i, zone)| {
+                NewInvoker::new().new_service(format!(
+                    "tri://127.0.0.1:{}/test.LoadBalancerZone?zone={}",
+                    9100 + i,
+                    zone
+                ))
+            })
+            .collect();
+        let mut req = http::Request::new(CloneBody::new(hyper::Body::empty()));
+        req.headers_mut().insert(
+            "path",
+            http::HeaderValue::from_static("/test.LoadBalancerZone/Call"),
+        );
+
+        let metadata = Metadata::from_headers(req.headers().clone());
+        let chains = RouterChains::new(Some(ZoneRouterConfig {
+            enabled: true,
+            zone: "hz-a".to_string(),
+            ..Default::default()
+        }));
+        let addresses = |metadata: Metadata| -> Vec<_> {
+            let routed = route(invokers.clone(), &req, metadata, &chains).unwrap();
+            routed.iter().map(|invoker| invoker.address()).collect()
+        };
+        assert_eq!(addresses(metadata.clone()), vec!["127.0.0.1:9101"]);
+
+        let forced = metadata.insert(FORCE_ZONE_HEADER.to_string(), "bj-a".to_string());
+        let err = route(invokers.clone(), &req, forced, &chains)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "no provider of test.LoadBalancerZone left by the routers"
+        );
+    }
+}
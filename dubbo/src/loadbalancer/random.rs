@@ -20,10 +20,29 @@ use tracing::debug;
 
 use super::{DubboBoxService, LoadBalancer};
 use crate::{
-    invocation::Metadata, loadbalancer::CloneInvoker,
+    config::dynamic::{self, Side, WEIGHT_KEY},
+    invocation::Metadata,
+    loadbalancer::CloneInvoker,
     protocol::triple::triple_invoker::TripleInvoker,
 };
 
+pub const DEFAULT_WEIGHT: u32 = 100;
+
+/// Weight of a provider, the dynamic `weight` of its address, then the
+/// `weight` of its url.
+fn weight(invoker: &CloneInvoker<TripleInvoker>) -> u32 {
+    let Some(url) = invoker.url() else {
+        return DEFAULT_WEIGHT;
+    };
+    let service = url
+        .query_param_by_key("interface")
+        .unwrap_or_else(|| url.path().trim_start_matches('/').to_string());
+    dynamic::dynamic_config()
+        .parameter_as(Side::Provider, &service, "", &invoker.address(), WEIGHT_KEY)
+        .or_else(|| url.query_param_by_key(WEIGHT_KEY)?.parse().ok())
+        .unwrap_or(DEFAULT_WEIGHT)
+}
+
 #[derive(Clone, Default)]
 pub struct RandomLoadBalancer {}
 
@@ -36,7 +55,11 @@ impl LoadBalancer for RandomLoadBalancer {
         metadata: Metadata,
     ) -> Self::Invoker {
         debug!("random loadbalance {:?}", metadata);
-        let ivk = invokers.choose(&mut rand::thread_rng()).unwrap().clone();
+        let ivk = invokers
+            .choose_weighted(&mut rand::thread_rng(), weight)
+            .or_else(|_| invokers.choose(&mut rand::thread_rng()).ok_or(()))
+            .unwrap()
+            .clone();
         DubboBoxService::new(ivk)
     }
 }
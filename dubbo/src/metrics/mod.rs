@@ -0,0 +1,134 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+pub mod prometheus;
+pub mod rpc;
+
+use std::sync::Arc;
+
+use once_cell::sync::OnceCell;
+
+pub use self::prometheus::PrometheusRecorder;
+
+pub const RPC_REQUESTS_TOTAL: &str = "dubbo_rpc_requests_total";
+pub const RPC_REQUESTS_IN_FLIGHT: &str = "dubbo_rpc_requests_in_flight";
+pub const RPC_LATENCY_SECONDS: &str = "dubbo_rpc_latency_seconds";
+pub const RPC_REQUEST_SIZE_BYTES: &str = "dubbo_rpc_request_size_bytes";
+pub const RPC_RESPONSE_SIZE_BYTES: &str = "dubbo_rpc_response_size_bytes";
+
+pub const REGISTRY_SUBSCRIBE_TOTAL: &str = "dubbo_registry_subscribe_total";
+pub const REGISTRY_NOTIFY_TOTAL: &str = "dubbo_registry_notify_total";
+pub const REGISTRY_REGISTER_TOTAL: &str = "dubbo_registry_register_total";
+pub const REGISTRY_PROVIDERS: &str = "dubbo_registry_providers";
+
+pub const ACCESS_LOG_DROPPED_TOTAL: &str = "dubbo_accesslog_dropped_total";
+
+pub const CONNECTIONS_ACTIVE: &str = "dubbo_connections_active";
+pub const RUNTIME_WORKERS: &str = "dubbo_runtime_workers";
+pub const RUNTIME_ALIVE_TASKS: &str = "dubbo_runtime_alive_tasks";
+pub const RUNTIME_GLOBAL_QUEUE_DEPTH: &str = "dubbo_runtime_global_queue_depth";
+
+pub type Labels = Vec<(&'static str, String)>;
+
+/// Sink for every metric emitted by dubbo.
+///
+/// The default recorder keeps the values in memory and renders them in the
+/// prometheus text format, install another one with [`set_recorder`] to export
+/// them elsewhere.
+pub trait MetricsRecorder: Send + Sync + 'static {
+    fn increment_counter(&self, name: &'static str, labels: &Labels, value: u64);
+
+    fn add_gauge(&self, name: &'static str, labels: &Labels, delta: f64);
+
+    fn set_gauge(&self, name: &'static str, labels: &Labels, value: f64);
+
+    fn record_histogram(&self, name: &'static str, labels: &Labels, value: f64);
+
+    /// Text served by the `/metrics` endpoint of the admin server, `None` if
+    /// the recorder does not support scraping.
+    fn render(&self) -> Option<String> {
+        None
+    }
+}
+
+static RECORDER: OnceCell<Arc<dyn MetricsRecorder>> = OnceCell::new();
+
+/// Install the global recorder, must be called before the first metric is
+/// recorded. Returns the given recorder back if one is already installed.
+pub fn set_recorder(recorder: Arc<dyn MetricsRecorder>) -> Result<(), Arc<dyn MetricsRecorder>> {
+    RECORDER.set(recorder)
+}
+
+pub fn recorder() -> &'static Arc<dyn MetricsRecorder> {
+    RECORDER.get_or_init(|| Arc::new(PrometheusRecorder::new()))
+}
+
+pub fn increment_counter(name: &'static str, labels: &Labels) {
+    recorder().increment_counter(name, labels, 1);
+}
+
+pub fn add_gauge(name: &'static str, labels: &Labels, delta: f64) {
+    recorder().add_gauge(name, labels, delta);
+}
+
+pub fn set_gauge(name: &'static str, labels: &Labels, value: f64) {
+    recorder().set_gauge(name, labels, value);
+}
+
+pub fn record_histogram(name: &'static str, labels: &Labels, value: f64) {
+    recorder().record_histogram(name, labels, value);
+}
+
+/// Sample the gauges of the current tokio runtime, called before each scrape.
+pub fn record_runtime_metrics() {
+    let Ok(handle) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    let metrics = handle.metrics();
+    let labels = Labels::new();
+    set_gauge(RUNTIME_WORKERS, &labels, metrics.num_workers() as f64);
+    set_gauge(
+        RUNTIME_ALIVE_TASKS,
+        &labels,
+        metrics.num_alive_tasks() as f64,
+    );
+    set_gauge(
+        RUNTIME_GLOBAL_QUEUE_DEPTH,
+        &labels,
+        metrics.global_queue_depth() as f64,
+    );
+}
+
+pub(crate) fn describe(name: &str) -> &'static str {
+    match name {
+        RPC_REQUESTS_TOTAL => "Total number of finished rpc requests.",
+        RPC_REQUESTS_IN_FLIGHT => "Number of rpc requests currently being processed.",
+        RPC_LATENCY_SECONDS => "Latency of rpc requests in seconds.",
+        RPC_REQUEST_SIZE_BYTES => "Size of rpc request payloads in bytes.",
+        RPC_RESPONSE_SIZE_BYTES => "Size of rpc response payloads in bytes.",
+        REGISTRY_SUBSCRIBE_TOTAL => "Total number of registry subscriptions.",
+        REGISTRY_NOTIFY_TOTAL => "Total number of registry change notifications.",
+        REGISTRY_REGISTER_TOTAL => "Total number of service registrations.",
+        REGISTRY_PROVIDERS => "Number of providers discovered per interface.",
+        ACCESS_LOG_DROPPED_TOTAL => "Total number of access log entries dropped on a full queue.",
+        CONNECTIONS_ACTIVE => "Number of open connections.",
+        RUNTIME_WORKERS => "Number of worker threads of the tokio runtime.",
+        RUNTIME_ALIVE_TASKS => "Number of alive tasks of the tokio runtime.",
+        RUNTIME_GLOBAL_QUEUE_DEPTH => "Number of tasks in the global queue of the tokio runtime.",
+        _ => "",
+    }
+}
@@ -0,0 +1,254 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex};
+
+use super::{describe, Labels, MetricsRecorder};
+
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+const SIZE_BUCKETS: &[f64] = &[
+    64.0, 256.0, 1024.0, 4096.0, 16384.0, 65536.0, 262144.0, 1048576.0, 4194304.0,
+];
+
+enum Metric {
+    Counter(u64),
+    Gauge(f64),
+    Histogram {
+        buckets: &'static [f64],
+        counts: Vec<u64>,
+        sum: f64,
+        count: u64,
+    },
+}
+
+impl Metric {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Metric::Counter(_) => "counter",
+            Metric::Gauge(_) => "gauge",
+            Metric::Histogram { .. } => "histogram",
+        }
+    }
+}
+
+/// In memory recorder rendering the prometheus text exposition format.
+#[derive(Default)]
+pub struct PrometheusRecorder {
+    // metric name -> rendered labels -> value
+    families: Mutex<BTreeMap<&'static str, BTreeMap<String, Metric>>>,
+}
+
+impl PrometheusRecorder {
+    pub fn new() -> Self {
+        PrometheusRecorder::default()
+    }
+
+    fn update<F>(&self, name: &'static str, labels: &Labels, init: F, f: impl FnOnce(&mut Metric))
+    where
+        F: FnOnce() -> Metric,
+    {
+        let mut families = self.families.lock().expect("metrics lock poisoned");
+        let metric = families
+            .entry(name)
+            .or_default()
+            .entry(render_labels(labels))
+            .or_insert_with(init);
+        f(metric);
+    }
+}
+
+impl MetricsRecorder for PrometheusRecorder {
+    fn increment_counter(&self, name: &'static str, labels: &Labels, value: u64) {
+        self.update(
+            name,
+            labels,
+            || Metric::Counter(0),
+            |metric| {
+                if let Metric::Counter(v) = metric {
+                    *v += value;
+                }
+            },
+        );
+    }
+
+    fn add_gauge(&self, name: &'static str, labels: &Labels, delta: f64) {
+        self.update(
+            name,
+            labels,
+            || Metric::Gauge(0.0),
+            |metric| {
+                if let Metric::Gauge(v) = metric {
+                    *v += delta;
+                }
+            },
+        );
+    }
+
+    fn set_gauge(&self, name: &'static str, labels: &Labels, value: f64) {
+        self.update(
+            name,
+            labels,
+            || Metric::Gauge(0.0),
+            |metric| {
+                if let Metric::Gauge(v) = metric {
+                    *v = value;
+                }
+            },
+        );
+    }
+
+    fn record_histogram(&self, name: &'static str, labels: &Labels, value: f64) {
+        let buckets = if name.ends_with("_seconds") {
+            LATENCY_BUCKETS
+        } else {
+            SIZE_BUCKETS
+        };
+        let init = || Metric::Histogram {
+            buckets,
+            counts: vec![0; buckets.len()],
+            sum: 0.0,
+            count: 0,
+        };
+        self.update(name, labels, init, |metric| {
+            if let Metric::Histogram {
+                buckets,
+                counts,
+                sum,
+                count,
+            } = metric
+            {
+                for (bound, bucket) in buckets.iter().zip(counts.iter_mut()) {
+                    if value <= *bound {
+                        *bucket += 1;
+                    }
+                }
+                *sum += value;
+                *count += 1;
+            }
+        });
+    }
+
+    fn render(&self) -> Option<String> {
+        let families = self.families.lock().expect("metrics lock poisoned");
+        let mut out = String::new();
+        for (name, series) in families.iter() {
+            let Some(first) = series.values().next() else {
+                continue;
+            };
+            let _ = writeln!(out, "# HELP {} {}", name, describe(name));
+            let _ = writeln!(out, "# TYPE {} {}", name, first.type_name());
+            for (labels, metric) in series.iter() {
+                match metric {
+                    Metric::Counter(v) => {
+                        let _ = writeln!(out, "{}{} {}", name, braced(labels), v);
+                    }
+                    Metric::Gauge(v) => {
+                        let _ = writeln!(out, "{}{} {}", name, braced(labels), v);
+                    }
+                    Metric::Histogram {
+                        buckets,
+                        counts,
+                        sum,
+                        count,
+                    } => {
+                        for (bound, bucket) in buckets.iter().zip(counts.iter()) {
+                            let le = with_label(labels, "le", &bound.to_string());
+                            let _ = writeln!(out, "{}_bucket{{{}}} {}", name, le, bucket);
+                        }
+                        let le = with_label(labels, "le", "+Inf");
+                        let _ = writeln!(out, "{}_bucket{{{}}} {}", name, le, count);
+                        let _ = writeln!(out, "{}_sum{} {}", name, braced(labels), sum);
+                        let _ = writeln!(out, "{}_count{} {}", name, braced(labels), count);
+                    }
+                }
+            }
+        }
+        Some(out)
+    }
+}
+
+fn render_labels(labels: &Labels) -> String {
+    labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, escape(v)))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn braced(labels: &str) -> String {
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", labels)
+    }
+}
+
+fn with_label(labels: &str, key: &str, value: &str) -> String {
+    if labels.is_empty() {
+        format!("{}=\"{}\"", key, value)
+    } else {
+        format!("{},{}=\"{}\"", labels, key, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{RPC_LATENCY_SECONDS, RPC_REQUESTS_IN_FLIGHT, RPC_REQUESTS_TOTAL};
+
+    #[test]
+    fn test_render() {
+        let recorder = PrometheusRecorder::new();
+        let labels = vec![
+            ("interface", "helloworld.Greeter".to_string()),
+            ("method", "SayHello".to_string()),
+        ];
+        recorder.increment_counter(RPC_REQUESTS_TOTAL, &labels, 1);
+        recorder.increment_counter(RPC_REQUESTS_TOTAL, &labels, 2);
+        recorder.add_gauge(RPC_REQUESTS_IN_FLIGHT, &labels, 1.0);
+        recorder.add_gauge(RPC_REQUESTS_IN_FLIGHT, &labels, -1.0);
+        recorder.record_histogram(RPC_LATENCY_SECONDS, &labels, 0.02);
+
+        let text = recorder.render().unwrap();
+        assert!(text.contains("# TYPE dubbo_rpc_requests_total counter"));
+        assert!(text.contains(
+            "dubbo_rpc_requests_total{interface=\"helloworld.Greeter\",method=\"SayHello\"} 3"
+        ));
+        assert!(text.contains(
+            "dubbo_rpc_requests_in_flight{interface=\"helloworld.Greeter\",method=\"SayHello\"} 0"
+        ));
+        assert!(text.contains(
+            "dubbo_rpc_latency_seconds_bucket{interface=\"helloworld.Greeter\",method=\"SayHello\",le=\"0.01\"} 0"
+        ));
+        assert!(text.contains(
+            "dubbo_rpc_latency_seconds_bucket{interface=\"helloworld.Greeter\",method=\"SayHello\",le=\"0.025\"} 1"
+        ));
+        assert!(text.contains(
+            "dubbo_rpc_latency_seconds_count{interface=\"helloworld.Greeter\",method=\"SayHello\"} 1"
+        ));
+    }
+}
@@ -0,0 +1,227 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use futures_util::{future::BoxFuture, StreamExt};
+use tower_service::Service;
+
+use super::{
+    add_gauge, increment_counter, record_histogram, Labels, RPC_LATENCY_SECONDS,
+    RPC_REQUESTS_IN_FLIGHT, RPC_REQUESTS_TOTAL, RPC_REQUEST_SIZE_BYTES, RPC_RESPONSE_SIZE_BYTES,
+};
+use crate::{
+    status::Code,
+    utils::observed_body::{ObservedBody, ResponseObserver},
+};
+
+pub const PROVIDER_SIDE: &str = "provider";
+pub const CONSUMER_SIDE: &str = "consumer";
+
+/// Bytes read from a request body, shared between the body and the
+/// metrics of the call it belongs to.
+#[derive(Clone, Default, Debug)]
+pub struct RequestSize(Arc<AtomicU64>);
+
+impl RequestSize {
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Wrap the body so that every data frame read from it is counted.
+    ///
+    /// The body is rebuilt from its data frames, triple requests carry no
+    /// trailers so nothing is lost.
+    pub fn count(&self, body: hyper::Body) -> hyper::Body {
+        let size = self.0.clone();
+        hyper::Body::wrap_stream(body.inspect(move |data| {
+            if let Ok(data) = data {
+                size.fetch_add(data.len() as u64, Ordering::Relaxed);
+            }
+        }))
+    }
+}
+
+/// Metrics of a single rpc, from the request headers to the end of the
+/// response body.
+pub struct RpcMetrics {
+    labels: Labels,
+    start: Instant,
+    request_size: RequestSize,
+    response_size: u64,
+    finished: bool,
+}
+
+impl RpcMetrics {
+    pub fn start(side: &'static str, path: &str, peer: String, request_size: RequestSize) -> Self {
+        let mut segments = path.trim_start_matches('/').splitn(2, '/');
+        let interface = segments.next().unwrap_or_default().to_string();
+        let method = segments.next().unwrap_or_default().to_string();
+        let labels = vec![
+            ("interface", interface),
+            ("method", method),
+            ("side", side.to_string()),
+            ("peer", peer),
+        ];
+        add_gauge(RPC_REQUESTS_IN_FLIGHT, &labels, 1.0);
+
+        RpcMetrics {
+            labels,
+            start: Instant::now(),
+            request_size,
+            response_size: 0,
+            finished: false,
+        }
+    }
+
+    pub fn finish(&mut self, code: Code) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+
+        add_gauge(RPC_REQUESTS_IN_FLIGHT, &self.labels, -1.0);
+        record_histogram(
+            RPC_LATENCY_SECONDS,
+            &self.labels,
+            self.start.elapsed().as_secs_f64(),
+        );
+        record_histogram(
+            RPC_REQUEST_SIZE_BYTES,
+            &self.labels,
+            self.request_size.get() as f64,
+        );
+        record_histogram(
+            RPC_RESPONSE_SIZE_BYTES,
+            &self.labels,
+            self.response_size as f64,
+        );
+
+        let mut labels = self.labels.clone();
+        labels.push(("code", format!("{:?}", code)));
+        increment_counter(RPC_REQUESTS_TOTAL, &labels);
+    }
+}
+
+impl ResponseObserver for RpcMetrics {
+    fn on_data(&mut self, len: usize) {
+        self.response_size += len as u64;
+    }
+
+    fn on_end(&mut self, code: Code) {
+        self.finish(code);
+    }
+}
+
+impl Drop for RpcMetrics {
+    fn drop(&mut self) {
+        // the call went away before any response
+        self.finish(Code::Cancelled);
+    }
+}
+
+/// Records provider side metrics of every request served on a connection.
+#[derive(Clone)]
+pub struct MetricsService<S> {
+    inner: S,
+    // ip of the consumer, its ephemeral port left out of the labels
+    peer: String,
+}
+
+impl<S> MetricsService<S> {
+    pub fn new(inner: S, peer: String) -> Self {
+        MetricsService { inner, peer }
+    }
+}
+
+impl<S> Service<http::Request<hyper::Body>> for MetricsService<S>
+where
+    S: Service<http::Request<hyper::Body>, Response = http::Response<crate::BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<crate::BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<hyper::Body>) -> Self::Future {
+        let request_size = RequestSize::default();
+        let metrics = RpcMetrics::start(
+            PROVIDER_SIDE,
+            req.uri().path(),
+            self.peer.clone(),
+            request_size.clone(),
+        );
+        let req = req.map(|body| request_size.count(body));
+        let fut = self.inner.call(req);
+
+        Box::pin(async move {
+            let resp = fut.await?;
+            let (parts, body) = resp.into_parts();
+            let body = ObservedBody::new(body, metrics, &parts.headers);
+            Ok(http::Response::from_parts(parts, crate::BoxBody::new(body)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::recorder;
+
+    #[tokio::test]
+    async fn test_metrics_body() {
+        let request_size = RequestSize::default();
+        let body = request_size.count(hyper::Body::from("hello"));
+        let _ = hyper::body::to_bytes(body).await.unwrap();
+        assert_eq!(request_size.get(), 5);
+
+        let metrics = RpcMetrics::start(
+            CONSUMER_SIDE,
+            "/org.apache.dubbo.sample.MetricsTest/Ping",
+            "127.0.0.1:8888".to_string(),
+            request_size,
+        );
+        let body = ObservedBody::new(
+            hyper::Body::from("world!"),
+            metrics,
+            &http::HeaderMap::new(),
+        );
+        let _ = hyper::body::to_bytes(body).await.unwrap();
+
+        let text = recorder().render().unwrap();
+        assert!(text.contains(
+            "dubbo_rpc_requests_total{interface=\"org.apache.dubbo.sample.MetricsTest\",method=\"Ping\",side=\"consumer\",peer=\"127.0.0.1:8888\",code=\"Ok\"} 1"
+        ));
+        assert!(text.contains(
+            "dubbo_rpc_response_size_bytes_sum{interface=\"org.apache.dubbo.sample.MetricsTest\",method=\"Ping\",side=\"consumer\",peer=\"127.0.0.1:8888\"} 6"
+        ));
+        assert!(text.contains(
+            "dubbo_rpc_requests_in_flight{interface=\"org.apache.dubbo.sample.MetricsTest\",method=\"Ping\",side=\"consumer\",peer=\"127.0.0.1:8888\"} 0"
+        ));
+    }
+}
@@ -29,3 +29,11 @@ pub const INTERFACE_KEY: &str = "interface";
 pub const ANYHOST_KEY: &str = "anyhost";
 pub const SIDE_KEY: &str = "side";
 pub const TIMESTAMP_KEY: &str = "timestamp";
+pub const ACCESS_LOG_KEY: &str = "accesslog";
+pub const ACCESS_LOG_ARGS_KEY: &str = "accesslog.args";
+pub const TOKEN_KEY: &str = "token";
+pub const COMPRESSION_KEY: &str = "compression";
+pub const COMPRESSION_MIN_SIZE_KEY: &str = "compression.min_size";
+pub const MAX_MESSAGE_SIZE_KEY: &str = "max_message_size";
+pub const MAX_ENCODE_MESSAGE_SIZE_KEY: &str = "max_encode_message_size";
+pub const TLS_KEY: &str = "tls";
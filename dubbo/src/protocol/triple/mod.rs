@@ -15,6 +15,7 @@
  * limitations under the License.
  */
 
+pub mod service_key;
 pub mod triple_exporter;
 pub mod triple_invoker;
 pub mod triple_protocol;
@@ -25,6 +26,8 @@ use std::{collections::HashMap, sync::RwLock};
 
 use crate::{utils::boxed_clone::BoxCloneService, BoxBody};
 
+use self::service_key::ServiceKey;
+
 pub type GrpcBoxCloneService =
     BoxCloneService<http::Request<hyper::Body>, http::Response<BoxBody>, std::convert::Infallible>;
 
@@ -32,3 +35,22 @@ lazy_static! {
     pub static ref TRIPLE_SERVICES: RwLock<HashMap<String, GrpcBoxCloneService>> =
         RwLock::new(HashMap::new());
 }
+
+/// Register `service` to be exported by the triple protocol under `key`,
+/// hosting several groups or versions of an interface.
+pub fn register_service(key: &ServiceKey, service: GrpcBoxCloneService) {
+    TRIPLE_SERVICES
+        .write()
+        .unwrap()
+        .insert(key.to_string(), service);
+}
+
+/// Service registered under `key`, or else the one registered for its
+/// interface alone.
+pub fn registered_service(key: &ServiceKey) -> Option<GrpcBoxCloneService> {
+    let services = TRIPLE_SERVICES.read().unwrap();
+    services
+        .get(&key.to_string())
+        .or_else(|| services.get(&key.interface))
+        .cloned()
+}
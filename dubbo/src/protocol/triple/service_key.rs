@@ -0,0 +1,216 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fmt;
+
+use http::HeaderMap;
+
+use crate::{
+    params::registry_param::{Group, InterfaceName, Version},
+    url::UrlParam,
+    Url,
+};
+
+/// Header of the group of the called service.
+pub const TRI_SERVICE_GROUP: &str = "tri-service-group";
+/// Header of the version of the called service.
+pub const TRI_SERVICE_VERSION: &str = "tri-service-version";
+/// Group or version matching any other.
+pub const ANY_VALUE: &str = "*";
+
+/// Identity of an exported service, its interface and optional group and
+/// version, formatted as dubbo does: `group/interface:version`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ServiceKey {
+    pub interface: String,
+    pub group: String,
+    pub version: String,
+}
+
+impl ServiceKey {
+    pub fn new(interface: impl Into<String>) -> Self {
+        Self {
+            interface: interface.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_group(self, group: impl Into<String>) -> Self {
+        Self {
+            group: group.into(),
+            ..self
+        }
+    }
+
+    pub fn with_version(self, version: impl Into<String>) -> Self {
+        Self {
+            version: version.into(),
+            ..self
+        }
+    }
+
+    /// Key of the service exported or referred with `url`.
+    pub fn from_url(url: &Url) -> Self {
+        let interface = url
+            .query::<InterfaceName>()
+            .map(|v| v.value())
+            .unwrap_or_else(|| url.path().trim_start_matches('/').to_string());
+        Self {
+            interface,
+            group: url.query::<Group>().map(|v| v.value()).unwrap_or_default(),
+            version: url
+                .query::<Version>()
+                .map(|v| v.value())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Parse `group/interface:version`, both group and version being
+    /// optional.
+    pub fn parse(key: &str) -> Self {
+        let (group, rest) = key.split_once('/').unwrap_or(("", key));
+        let (interface, version) = rest.split_once(':').unwrap_or((rest, ""));
+        Self::new(interface).with_group(group).with_version(version)
+    }
+}
+
+impl fmt::Display for ServiceKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.group.is_empty() {
+            write!(f, "{}/", self.group)?;
+        }
+        f.write_str(&self.interface)?;
+        if !self.version.is_empty() {
+            write!(f, ":{}", self.version)?;
+        }
+        Ok(())
+    }
+}
+
+/// Groups and versions of the providers a consumer accepts.
+///
+/// Like in dubbo, `*` accepts any value and a comma separated list of
+/// groups accepts each of them. An empty group or version leaves the
+/// providers unfiltered.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServiceSelector {
+    group: String,
+    version: String,
+}
+
+impl ServiceSelector {
+    pub fn new(group: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            group: group.into(),
+            version: version.into(),
+        }
+    }
+
+    /// Whether a provider of `group` and `version` is accepted.
+    pub fn matches(&self, group: &str, version: &str) -> bool {
+        let group_matches = match self.group.as_str() {
+            "" | ANY_VALUE => true,
+            groups => groups.split(',').any(|g| g.trim() == group),
+        };
+        let version_matches = match self.version.as_str() {
+            "" | ANY_VALUE => true,
+            v => v == version,
+        };
+        group_matches && version_matches
+    }
+
+    /// Whether the provider of `url` is accepted.
+    pub fn matches_url(&self, url: &Url) -> bool {
+        let key = ServiceKey::from_url(url);
+        self.matches(&key.group, &key.version)
+    }
+
+    /// Announce the group and version to the provider, unless they select
+    /// more than a single one.
+    pub fn insert_headers(&self, headers: &mut HeaderMap) {
+        for (name, value) in [
+            (TRI_SERVICE_GROUP, single(&self.group)),
+            (TRI_SERVICE_VERSION, single(&self.version)),
+        ] {
+            if let Some(Ok(value)) = value.map(str::parse) {
+                headers.insert(name, value);
+            }
+        }
+    }
+
+    /// Add the group and version to the url subscribing to the providers,
+    /// unless they select more than a single one.
+    pub fn add_url_params(&self, url: &mut Url) {
+        if let Some(group) = single(&self.group) {
+            url.add_query_param(Group::new(group.to_string()));
+        }
+        if let Some(version) = single(&self.version) {
+            url.add_query_param(Version::new(version.to_string()));
+        }
+    }
+}
+
+/// `value` if it selects a single group or version.
+fn single(value: &str) -> Option<&str> {
+    match value {
+        "" | ANY_VALUE => None,
+        value if value.contains(',') => None,
+        value => Some(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_selector() {
+        let key = ServiceKey::parse("g1/org.apache.dubbo.Greeter:1.0.0");
+        assert_eq!(key.group, "g1");
+        assert_eq!(key.interface, "org.apache.dubbo.Greeter");
+        assert_eq!(key.version, "1.0.0");
+        assert_eq!(key.to_string(), "g1/org.apache.dubbo.Greeter:1.0.0");
+        assert_eq!(
+            ServiceKey::parse("org.apache.dubbo.Greeter"),
+            ServiceKey::new("org.apache.dubbo.Greeter")
+        );
+
+        assert!(ServiceSelector::default().matches("g1", "1.0.0"));
+        assert!(ServiceSelector::new("*", "*").matches("g1", "1.0.0"));
+        let selector = ServiceSelector::new("g1, g2", "1.0.0");
+        assert!(selector.matches("g2", "1.0.0"));
+        assert!(!selector.matches("g3", "1.0.0"));
+        assert!(!selector.matches("g1", "2.0.0"));
+
+        let url: Url = "tri://127.0.0.1:8888/org.apache.dubbo.Greeter?interface=org.apache.dubbo.Greeter&group=g2&version=1.0.0"
+            .parse()
+            .unwrap();
+        assert!(selector.matches_url(&url));
+
+        let mut headers = HeaderMap::new();
+        selector.insert_headers(&mut headers);
+        assert!(!headers.contains_key(TRI_SERVICE_GROUP));
+        assert_eq!(headers[TRI_SERVICE_VERSION], "1.0.0");
+
+        let mut url: Url = "consumer://127.0.0.1/org.apache.dubbo.Greeter"
+            .parse()
+            .unwrap();
+        ServiceSelector::new("g1", "*").add_url_params(&mut url);
+        assert_eq!(url.query::<Group>().unwrap().value(), "g1");
+        assert!(url.query::<Version>().is_none());
+    }
+}
@@ -17,6 +17,7 @@
 
 use crate::Url;
 use http::{HeaderValue, Uri};
+use opentelemetry::{trace::SpanKind, Context};
 use std::{
     fmt::{Debug, Formatter},
     str::FromStr,
@@ -24,8 +25,15 @@ use std::{
 use tower_service::Service;
 
 use crate::{
+    invocation::RemoteAddr,
     invoker::clone_body::CloneBody,
-    triple::transport::{self, connection::Connection},
+    metrics::rpc::{RequestSize, RpcMetrics, CONSUMER_SIDE},
+    params::constants::TOKEN_KEY,
+    protocol::triple::service_key::{ServiceKey, TRI_SERVICE_GROUP, TRI_SERVICE_VERSION},
+    status::Code,
+    telemetry::{self, rpc::RpcSpan},
+    triple::transport::{self, connection::Connection, tls::ClientTls},
+    utils::observed_body::ObservedBody,
 };
 
 pub struct TripleInvoker {
@@ -35,10 +43,24 @@ pub struct TripleInvoker {
 
 impl TripleInvoker {
     pub fn new(url: Url) -> TripleInvoker {
+        Self::new_with_tls(url, None)
+    }
+
+    pub fn new_with_tls(url: Url, tls: Option<ClientTls>) -> TripleInvoker {
+        let mut conn = Connection::new();
+        if let Some(tls) = tls {
+            conn = conn.with_tls(tls);
+        }
+        Self::new_with_connection(url, conn)
+    }
+
+    /// Invoker calling the provider of `url` over `conn`, configured but not
+    /// built yet.
+    pub fn new_with_connection(url: Url, conn: Connection) -> TripleInvoker {
         let uri = http::Uri::from_str(url.as_str()).unwrap();
         Self {
             url,
-            conn: Connection::new().with_host(uri).build(),
+            conn: conn.with_host(uri).build(),
         }
     }
 }
@@ -88,35 +110,51 @@ impl TripleInvoker {
             "authority",
             HeaderValue::from_str(uri.authority().unwrap().as_str()).unwrap(),
         );
-        req.headers_mut().insert(
-            "content-type",
-            HeaderValue::from_static("application/grpc+proto"),
-        );
+        // the client sets the content type of its codec
+        if !req.headers().contains_key(http::header::CONTENT_TYPE) {
+            req.headers_mut().insert(
+                "content-type",
+                HeaderValue::from_static("application/grpc+proto"),
+            );
+        }
         req.headers_mut()
             .insert("user-agent", HeaderValue::from_static("dubbo-rust/0.1.0"));
         req.headers_mut()
             .insert("te", HeaderValue::from_static("trailers"));
-        req.headers_mut().insert(
-            "tri-service-version",
-            HeaderValue::from_static("dubbo-rust/0.1.0"),
-        );
-        req.headers_mut()
-            .insert("tri-service-group", HeaderValue::from_static("cluster"));
+        // group and version announced by the provider win over the ones
+        // asked by the client
+        let key = ServiceKey::from_url(&self.url);
+        for (name, value) in [
+            (TRI_SERVICE_GROUP, key.group),
+            (TRI_SERVICE_VERSION, key.version),
+        ] {
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                if !value.is_empty() {
+                    req.headers_mut().insert(name, value);
+                }
+            }
+        }
         req.headers_mut().insert(
             "tri-unit-info",
             HeaderValue::from_static("dubbo-rust/0.1.0"),
         );
-        // if let Some(_encoding) = self.send_compression_encoding {
-
-        // }
-
-        req.headers_mut()
-            .insert("grpc-encoding", http::HeaderValue::from_static("gzip"));
+        // the client sets the compression of the method
+        if !req.headers().contains_key("grpc-accept-encoding") {
+            req.headers_mut()
+                .insert("grpc-encoding", http::HeaderValue::from_static("gzip"));
+
+            req.headers_mut().insert(
+                "grpc-accept-encoding",
+                http::HeaderValue::from_static("gzip"),
+            );
+        }
 
-        req.headers_mut().insert(
-            "grpc-accept-encoding",
-            http::HeaderValue::from_static("gzip"),
-        );
+        // token published by the provider
+        if let Some(token) = self.url.query_param_by_key(TOKEN_KEY) {
+            if let Ok(token) = HeaderValue::from_str(&token) {
+                req.headers_mut().insert(TOKEN_KEY, token);
+            }
+        }
 
         // // const (
         // //     TripleContentType    = "application/grpc+proto"
@@ -152,8 +190,50 @@ impl Service<http::Request<CloneBody>> for TripleInvoker {
     }
 
     fn call(&mut self, req: http::Request<CloneBody>) -> Self::Future {
-        let req = self.map_request(req);
+        let request_size = req
+            .extensions()
+            .get::<RequestSize>()
+            .cloned()
+            .unwrap_or_default();
+        let parent = req
+            .extensions()
+            .get::<Context>()
+            .cloned()
+            .unwrap_or_else(Context::current);
+        let mut req = self.map_request(req);
+
+        let mut span = RpcSpan::start(
+            SpanKind::Client,
+            req.uri().path(),
+            self.url.host().unwrap_or_default().to_string(),
+            self.url.port(),
+            &parent,
+        );
+        telemetry::inject(span.context(), req.headers_mut());
+        let mut metrics = RpcMetrics::start(
+            CONSUMER_SIDE,
+            req.uri().path(),
+            self.url.authority().to_string(),
+            request_size,
+        );
 
-        self.conn.call(req)
+        let authority = self.url.authority().to_string();
+        let fut = self.conn.call(req);
+        Box::pin(async move {
+            match fut.await {
+                Ok(mut resp) => {
+                    resp.extensions_mut().insert(RemoteAddr(authority));
+                    let (parts, body) = resp.into_parts();
+                    let body = ObservedBody::new(body, metrics, &parts.headers);
+                    let body = ObservedBody::new(body, span, &parts.headers);
+                    Ok(http::Response::from_parts(parts, crate::BoxBody::new(body)))
+                }
+                Err(err) => {
+                    metrics.finish(Code::Unavailable);
+                    span.end(Code::Unavailable);
+                    Err(err)
+                }
+            }
+        })
     }
 }
@@ -0,0 +1,146 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Provider urls registered by the application, taken offline and back
+//! online by the admin server like the qos commands of dubbo.
+
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    extension::registry_extension::{proxy::RegistryProxy, Registry},
+    logger::tracing::info,
+    protocol::triple::service_key::ServiceKey,
+    StdError, Url,
+};
+
+struct Exported {
+    key: ServiceKey,
+    url: Url,
+    registries: Vec<RegistryProxy>,
+    online: bool,
+}
+
+static EXPORTED: Lazy<Mutex<Vec<Exported>>> = Lazy::new(Default::default);
+
+/// Record the provider `url` registered to `registries`.
+pub(crate) fn add(url: Url, registries: Vec<RegistryProxy>) {
+    let key = ServiceKey::from_url(&url);
+    EXPORTED.lock().unwrap().push(Exported {
+        key,
+        url,
+        registries,
+        online: true,
+    });
+}
+
+/// Keys of the exported services, with whether they are online.
+pub fn services() -> Vec<(String, bool)> {
+    EXPORTED
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|exported| (exported.key.to_string(), exported.online))
+        .collect()
+}
+
+/// Unregister the providers of `service`, named by its interface or its
+/// `group/interface:version` key, or all of them. Returns the keys of the
+/// services taken offline.
+pub async fn offline(service: Option<&str>) -> Result<Vec<String>, StdError> {
+    set_online(service, false).await
+}
+
+/// Register again the providers taken offline, returning the keys of the
+/// services back online.
+pub async fn online(service: Option<&str>) -> Result<Vec<String>, StdError> {
+    set_online(service, true).await
+}
+
+async fn set_online(service: Option<&str>, online: bool) -> Result<Vec<String>, StdError> {
+    let targets: Vec<_> = EXPORTED
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|exported| exported.online != online)
+        .filter(|exported| {
+            service.map_or(true, |service| {
+                exported.key.interface == service || exported.key.to_string() == service
+            })
+        })
+        .map(|exported| {
+            (
+                exported.key.to_string(),
+                exported.url.clone(),
+                exported.registries.clone(),
+            )
+        })
+        .collect();
+
+    let mut keys = Vec::with_capacity(targets.len());
+    for (key, url, registries) in targets {
+        for registry in registries {
+            if online {
+                registry.register(url.clone()).await?;
+            } else {
+                registry.unregister(url.clone()).await?;
+            }
+        }
+        info!(
+            "service {} is {}",
+            key,
+            if online { "online" } else { "offline" }
+        );
+
+        let mut exported = EXPORTED.lock().unwrap();
+        if let Some(exported) = exported.iter_mut().find(|exported| exported.url == url) {
+            exported.online = online;
+        }
+        keys.push(key);
+    }
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_offline_and_online() {
+        let url: Url = "tri://127.0.0.1:8888/org.apache.dubbo.exported.Greeter?interface=org.apache.dubbo.exported.Greeter&group=test"
+            .parse()
+            .unwrap();
+        add(url, Vec::new());
+        let key = "test/org.apache.dubbo.exported.Greeter".to_string();
+
+        assert!(offline(Some("org.apache.dubbo.exported.Unknown"))
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            offline(Some("org.apache.dubbo.exported.Greeter"))
+                .await
+                .unwrap(),
+            vec![key.clone()]
+        );
+        assert!(services().contains(&(key.clone(), false)));
+        assert!(offline(Some(&key)).await.unwrap().is_empty());
+        assert_eq!(online(Some(&key)).await.unwrap(), vec![key.clone()]);
+        assert!(services().contains(&(key, true)));
+    }
+}
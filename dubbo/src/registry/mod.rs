@@ -23,6 +23,7 @@ use std::{
 };
 use tower_service::Service;
 
+pub mod exported;
 pub mod integration;
 pub mod protocol;
 pub mod registry;
@@ -189,6 +189,16 @@ impl Registry for StaticRegistry {
         Ok(())
     }
 
+    async fn services(&self) -> Result<Vec<String>, StdError> {
+        let lock = self.urls.lock().await;
+        Ok(lock
+            .iter()
+            .filter(|(_, values)| !values.urls.is_empty())
+            .map(|(interface, _)| interface.clone())
+            .sorted()
+            .collect())
+    }
+
     fn url(&self) -> &Url {
         &self.self_url
     }
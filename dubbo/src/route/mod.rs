@@ -25,6 +25,8 @@ use tower_service::Service;
 
 use crate::{
     codegen::{RpcInvocation, TripleInvoker},
+    config::dynamic::{self, Side, DISABLED_KEY},
+    invocation::Invocation,
     invoker::clone_invoker::CloneInvoker,
     param::Param,
     svc::NewService,
@@ -56,7 +58,6 @@ pub enum RoutesFutureInnerState<S> {
 
 #[derive(Clone)]
 pub struct Routes<T> {
-    #[allow(dead_code)]
     target: T,
     invokers: Vec<CloneInvoker<TripleInvoker>>,
 }
@@ -163,6 +164,23 @@ where
     fn call(&mut self, _: ()) -> Self::Future {
         // some router operator
         // if new_invokers changed, send new invokers to routes_rx after router operator
-        futures_util::future::ok(self.invokers.clone())
+        let service = self.target.param().get_target_service_unique_name();
+        let config = dynamic::dynamic_config();
+        let invokers: Vec<_> = self
+            .invokers
+            .iter()
+            .filter(|invoker| {
+                let disabled = config.parameter_as::<bool>(
+                    Side::Provider,
+                    &service,
+                    "",
+                    &invoker.address(),
+                    DISABLED_KEY,
+                );
+                !disabled.unwrap_or(false)
+            })
+            .cloned()
+            .collect();
+        futures_util::future::ok(invokers)
     }
 }
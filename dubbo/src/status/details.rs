@@ -0,0 +1,367 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The standard `google.rpc` error detail messages, carried in the
+//! `grpc-status-details-bin` trailer as a packed `google.rpc.Status`.
+//!
+//! ```ignore
+//! let status = Status::with_error_details(
+//!     Code::InvalidArgument,
+//!     "invalid greeting".to_string(),
+//!     vec![BadRequest::new(vec![FieldViolation::new("name", "must not be empty")]).to_any()],
+//! );
+//! let bad_request = status.get_detail::<BadRequest>();
+//! ```
+
+use std::{collections::HashMap, time::Duration};
+
+pub use prost_types::Any;
+
+/// A `google.rpc` error detail message, packed as an `Any` in the details of
+/// a status.
+pub trait ErrorDetail: prost::Message + Default + Sized {
+    const TYPE_URL: &'static str;
+
+    fn to_any(&self) -> Any {
+        Any {
+            type_url: Self::TYPE_URL.to_string(),
+            value: self.encode_to_vec(),
+        }
+    }
+
+    /// The detail packed in `any`, if it is one of this type.
+    fn from_any(any: &Any) -> Option<Self> {
+        if any.type_url != Self::TYPE_URL {
+            return None;
+        }
+        Self::decode(any.value.as_slice()).ok()
+    }
+}
+
+/// `google.rpc.Status`, the payload of `grpc-status-details-bin`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RpcStatus {
+    #[prost(int32, tag = "1")]
+    pub code: i32,
+    #[prost(string, tag = "2")]
+    pub message: String,
+    #[prost(message, repeated, tag = "3")]
+    pub details: Vec<Any>,
+}
+
+/// The reason of an error, as a constant in the domain of the service.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ErrorInfo {
+    #[prost(string, tag = "1")]
+    pub reason: String,
+    #[prost(string, tag = "2")]
+    pub domain: String,
+    #[prost(map = "string, string", tag = "3")]
+    pub metadata: HashMap<String, String>,
+}
+
+impl ErrorInfo {
+    pub fn new(
+        reason: impl Into<String>,
+        domain: impl Into<String>,
+        metadata: HashMap<String, String>,
+    ) -> Self {
+        ErrorInfo {
+            reason: reason.into(),
+            domain: domain.into(),
+            metadata,
+        }
+    }
+}
+
+/// How long the client should wait before retrying the call.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RetryInfo {
+    #[prost(message, optional, tag = "1")]
+    pub retry_delay: Option<prost_types::Duration>,
+}
+
+impl RetryInfo {
+    pub fn new(retry_delay: Duration) -> Self {
+        RetryInfo {
+            retry_delay: Some(prost_types::Duration {
+                seconds: retry_delay.as_secs() as i64,
+                nanos: retry_delay.subsec_nanos() as i32,
+            }),
+        }
+    }
+
+    pub fn retry_delay(&self) -> Option<Duration> {
+        let delay = self.retry_delay.as_ref()?;
+        if delay.seconds < 0 || delay.nanos < 0 {
+            return None;
+        }
+        Some(Duration::new(delay.seconds as u64, delay.nanos as u32))
+    }
+}
+
+/// Debugging information of the server, such as a stack trace.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct DebugInfo {
+    #[prost(string, repeated, tag = "1")]
+    pub stack_entries: Vec<String>,
+    #[prost(string, tag = "2")]
+    pub detail: String,
+}
+
+impl DebugInfo {
+    pub fn new(stack_entries: Vec<String>, detail: impl Into<String>) -> Self {
+        DebugInfo {
+            stack_entries,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// The quotas exceeded by the call.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct QuotaFailure {
+    #[prost(message, repeated, tag = "1")]
+    pub violations: Vec<QuotaViolation>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct QuotaViolation {
+    #[prost(string, tag = "1")]
+    pub subject: String,
+    #[prost(string, tag = "2")]
+    pub description: String,
+}
+
+impl QuotaFailure {
+    pub fn new(violations: Vec<QuotaViolation>) -> Self {
+        QuotaFailure { violations }
+    }
+}
+
+impl QuotaViolation {
+    pub fn new(subject: impl Into<String>, description: impl Into<String>) -> Self {
+        QuotaViolation {
+            subject: subject.into(),
+            description: description.into(),
+        }
+    }
+}
+
+/// The preconditions the call failed.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PreconditionFailure {
+    #[prost(message, repeated, tag = "1")]
+    pub violations: Vec<PreconditionViolation>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PreconditionViolation {
+    #[prost(string, tag = "1")]
+    pub r#type: String,
+    #[prost(string, tag = "2")]
+    pub subject: String,
+    #[prost(string, tag = "3")]
+    pub description: String,
+}
+
+impl PreconditionFailure {
+    pub fn new(violations: Vec<PreconditionViolation>) -> Self {
+        PreconditionFailure { violations }
+    }
+}
+
+impl PreconditionViolation {
+    pub fn new(
+        r#type: impl Into<String>,
+        subject: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        PreconditionViolation {
+            r#type: r#type.into(),
+            subject: subject.into(),
+            description: description.into(),
+        }
+    }
+}
+
+/// The invalid fields of the request.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct BadRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub field_violations: Vec<FieldViolation>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct FieldViolation {
+    #[prost(string, tag = "1")]
+    pub field: String,
+    #[prost(string, tag = "2")]
+    pub description: String,
+}
+
+impl BadRequest {
+    pub fn new(field_violations: Vec<FieldViolation>) -> Self {
+        BadRequest { field_violations }
+    }
+}
+
+impl FieldViolation {
+    pub fn new(field: impl Into<String>, description: impl Into<String>) -> Self {
+        FieldViolation {
+            field: field.into(),
+            description: description.into(),
+        }
+    }
+}
+
+/// The request the client can quote when filing a bug.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RequestInfo {
+    #[prost(string, tag = "1")]
+    pub request_id: String,
+    #[prost(string, tag = "2")]
+    pub serving_data: String,
+}
+
+impl RequestInfo {
+    pub fn new(request_id: impl Into<String>, serving_data: impl Into<String>) -> Self {
+        RequestInfo {
+            request_id: request_id.into(),
+            serving_data: serving_data.into(),
+        }
+    }
+}
+
+/// The resource the call failed to access.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ResourceInfo {
+    #[prost(string, tag = "1")]
+    pub resource_type: String,
+    #[prost(string, tag = "2")]
+    pub resource_name: String,
+    #[prost(string, tag = "3")]
+    pub owner: String,
+    #[prost(string, tag = "4")]
+    pub description: String,
+}
+
+impl ResourceInfo {
+    pub fn new(
+        resource_type: impl Into<String>,
+        resource_name: impl Into<String>,
+        owner: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        ResourceInfo {
+            resource_type: resource_type.into(),
+            resource_name: resource_name.into(),
+            owner: owner.into(),
+            description: description.into(),
+        }
+    }
+}
+
+/// Links to documentation about the error.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Help {
+    #[prost(message, repeated, tag = "1")]
+    pub links: Vec<HelpLink>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct HelpLink {
+    #[prost(string, tag = "1")]
+    pub description: String,
+    #[prost(string, tag = "2")]
+    pub url: String,
+}
+
+impl Help {
+    pub fn new(links: Vec<HelpLink>) -> Self {
+        Help { links }
+    }
+}
+
+impl HelpLink {
+    pub fn new(description: impl Into<String>, url: impl Into<String>) -> Self {
+        HelpLink {
+            description: description.into(),
+            url: url.into(),
+        }
+    }
+}
+
+/// The error message, localized for the end user.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct LocalizedMessage {
+    #[prost(string, tag = "1")]
+    pub locale: String,
+    #[prost(string, tag = "2")]
+    pub message: String,
+}
+
+impl LocalizedMessage {
+    pub fn new(locale: impl Into<String>, message: impl Into<String>) -> Self {
+        LocalizedMessage {
+            locale: locale.into(),
+            message: message.into(),
+        }
+    }
+}
+
+macro_rules! error_detail {
+    ($($ty:ident),*) => {
+        $(
+            impl ErrorDetail for $ty {
+                const TYPE_URL: &'static str =
+                    concat!("type.googleapis.com/google.rpc.", stringify!($ty));
+            }
+        )*
+    };
+}
+
+error_detail!(
+    ErrorInfo,
+    RetryInfo,
+    DebugInfo,
+    QuotaFailure,
+    PreconditionFailure,
+    BadRequest,
+    RequestInfo,
+    ResourceInfo,
+    Help,
+    LocalizedMessage
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_detail_any() {
+        let bad_request = BadRequest::new(vec![FieldViolation::new("name", "must not be empty")]);
+        let any = bad_request.to_any();
+        assert_eq!(any.type_url, "type.googleapis.com/google.rpc.BadRequest");
+        assert_eq!(BadRequest::from_any(&any), Some(bad_request));
+        assert_eq!(DebugInfo::from_any(&any), None);
+
+        let retry = RetryInfo::new(Duration::from_millis(1500));
+        let retry = RetryInfo::from_any(&retry.to_any()).unwrap();
+        assert_eq!(retry.retry_delay(), Some(Duration::from_millis(1500)));
+    }
+}
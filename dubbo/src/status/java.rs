@@ -0,0 +1,159 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::{details::DebugInfo, Code, Status};
+
+/// Well known exceptions thrown by Java Dubbo providers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JavaExceptionKind {
+    IllegalArgument,
+    IllegalState,
+    NullPointer,
+    UnsupportedOperation,
+    Timeout,
+    Rpc,
+    Other,
+}
+
+impl JavaExceptionKind {
+    pub fn from_class_name(class_name: &str) -> Self {
+        match class_name {
+            "java.lang.IllegalArgumentException" => JavaExceptionKind::IllegalArgument,
+            "java.lang.IllegalStateException" => JavaExceptionKind::IllegalState,
+            "java.lang.NullPointerException" => JavaExceptionKind::NullPointer,
+            "java.lang.UnsupportedOperationException" => JavaExceptionKind::UnsupportedOperation,
+            "java.util.concurrent.TimeoutException"
+            | "org.apache.dubbo.remoting.TimeoutException" => JavaExceptionKind::Timeout,
+            "org.apache.dubbo.rpc.RpcException" => JavaExceptionKind::Rpc,
+            _ => JavaExceptionKind::Other,
+        }
+    }
+
+    /// The grpc code a rust provider would fail with for the same error.
+    pub fn code(&self) -> Code {
+        match self {
+            JavaExceptionKind::IllegalArgument => Code::InvalidArgument,
+            JavaExceptionKind::IllegalState => Code::FailedPrecondition,
+            JavaExceptionKind::NullPointer => Code::Internal,
+            JavaExceptionKind::UnsupportedOperation => Code::Unimplemented,
+            JavaExceptionKind::Timeout => Code::DeadlineExceeded,
+            JavaExceptionKind::Rpc | JavaExceptionKind::Other => Code::Unknown,
+        }
+    }
+}
+
+/// Exception thrown by a Java Dubbo provider. Triple reports it as the stack
+/// trace of a `DebugInfo` detail, starting with `class.Name: message`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JavaException {
+    pub kind: JavaExceptionKind,
+    pub class_name: String,
+    pub message: String,
+    pub stack_trace: Vec<String>,
+}
+
+impl JavaException {
+    pub fn from_status(status: &Status) -> Option<Self> {
+        let debug_info = status.get_detail::<DebugInfo>()?;
+        let (first, stack_trace) = debug_info.stack_entries.split_first()?;
+        let (class_name, message) = match first.split_once(':') {
+            Some((class_name, message)) => (class_name.trim(), message.trim()),
+            None => (first.trim(), ""),
+        };
+        if !is_class_name(class_name) {
+            return None;
+        }
+        let message = if message.is_empty() {
+            debug_info.detail.as_str()
+        } else {
+            message
+        };
+        Some(JavaException {
+            kind: JavaExceptionKind::from_class_name(class_name),
+            class_name: class_name.to_string(),
+            message: message.to_string(),
+            stack_trace: stack_trace.iter().map(|s| s.trim().to_string()).collect(),
+        })
+    }
+
+    /// The debug info detail a Java provider would report this exception
+    /// with.
+    pub fn to_debug_info(&self) -> DebugInfo {
+        let mut stack_entries = vec![format!("{}: {}", self.class_name, self.message)];
+        stack_entries.extend(self.stack_trace.iter().cloned());
+        DebugInfo::new(stack_entries, self.message.clone())
+    }
+}
+
+impl std::fmt::Display for JavaException {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.class_name, self.message)
+    }
+}
+
+impl std::error::Error for JavaException {}
+
+fn is_class_name(name: &str) -> bool {
+    name.contains('.')
+        && name.split('.').all(|part| {
+            part.chars()
+                .next()
+                .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+                && part
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::details::ErrorDetail;
+
+    #[test]
+    fn test_java_exception() {
+        let debug_info = DebugInfo::new(
+            vec![
+                "java.lang.IllegalArgumentException: name must not be empty".to_string(),
+                "\tat org.apache.dubbo.sample.GreeterImpl.greet(GreeterImpl.java:42)".to_string(),
+            ],
+            "",
+        );
+        let status = Status::with_error_details(
+            Code::Unknown,
+            "name must not be empty".to_string(),
+            vec![debug_info.to_any()],
+        );
+        let exception = status.java_exception().unwrap();
+        assert_eq!(exception.kind, JavaExceptionKind::IllegalArgument);
+        assert_eq!(exception.kind.code(), Code::InvalidArgument);
+        assert_eq!(exception.class_name, "java.lang.IllegalArgumentException");
+        assert_eq!(exception.message, "name must not be empty");
+        assert_eq!(
+            exception.stack_trace,
+            vec!["at org.apache.dubbo.sample.GreeterImpl.greet(GreeterImpl.java:42)"]
+        );
+        assert_eq!(exception.to_debug_info().stack_entries.len(), 2);
+
+        let status = Status::with_error_details(
+            Code::Unknown,
+            String::new(),
+            vec![DebugInfo::new(vec!["not a class".to_string()], "").to_any()],
+        );
+        assert_eq!(status.java_exception(), None);
+    }
+}
@@ -15,12 +15,37 @@
  * limitations under the License.
  */
 
+pub mod details;
+pub mod java;
+
 use std::{error::Error, fmt};
 
+use base64::{
+    alphabet,
+    engine::{general_purpose::GeneralPurpose, DecodePaddingMode, GeneralPurposeConfig},
+    Engine,
+};
+use bytes::Bytes;
 use http::HeaderValue;
+use http_body::Body;
+use prost::Message;
+
+use self::{
+    details::{Any, ErrorDetail, RpcStatus},
+    java::JavaException,
+};
 
 pub const GRPC_STATUS: &str = "grpc-status";
 pub const GRPC_MESSAGE: &str = "grpc-message";
+pub const GRPC_STATUS_DETAILS: &str = "grpc-status-details-bin";
+
+/// Binary headers are sent unpadded, but padded values are accepted too.
+const BINARY_HEADER: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new()
+        .with_encode_padding(false)
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
 
 /// error codes for grpc APIs
 /// https://github.com/googleapis/googleapis/blob/master/google/rpc/code.proto
@@ -219,6 +244,79 @@ impl Code {
     }
 }
 
+impl Code {
+    /// Status of the http response of a call failing with this code, for
+    /// the calls made without grpc.
+    pub fn to_http_status(&self) -> http::StatusCode {
+        use http::StatusCode;
+        match *self {
+            Code::Ok => StatusCode::OK,
+            Code::Cancelled => {
+                StatusCode::from_u16(499).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+            Code::Unknown | Code::Internal | Code::DataLoss => StatusCode::INTERNAL_SERVER_ERROR,
+            Code::InvalidArgument | Code::FailedPrecondition | Code::OutOfRange => {
+                StatusCode::BAD_REQUEST
+            }
+            Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+            Code::NotFound => StatusCode::NOT_FOUND,
+            Code::AlreadyExists | Code::Aborted => StatusCode::CONFLICT,
+            Code::PermissionDenied => StatusCode::FORBIDDEN,
+            Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+            Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+            Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        }
+    }
+
+    /// Snake case name of the code, as in `invalid_argument`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Code::Ok => "ok",
+            Code::Cancelled => "canceled",
+            Code::Unknown => "unknown",
+            Code::InvalidArgument => "invalid_argument",
+            Code::DeadlineExceeded => "deadline_exceeded",
+            Code::NotFound => "not_found",
+            Code::AlreadyExists => "already_exists",
+            Code::PermissionDenied => "permission_denied",
+            Code::ResourceExhausted => "resource_exhausted",
+            Code::FailedPrecondition => "failed_precondition",
+            Code::Aborted => "aborted",
+            Code::OutOfRange => "out_of_range",
+            Code::Unimplemented => "unimplemented",
+            Code::Internal => "internal",
+            Code::Unavailable => "unavailable",
+            Code::DataLoss => "data_loss",
+            Code::Unauthenticated => "unauthenticated",
+        }
+    }
+}
+
+/// Parses a code from its number, or its name in any case, as in `14`,
+/// `unavailable` or `UNAVAILABLE`.
+impl std::str::FromStr for Code {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(code) = s.parse::<i32>() {
+            return match code {
+                0..=16 => Ok(Code::from(code)),
+                _ => Err(()),
+            };
+        }
+        let name = s.to_ascii_lowercase();
+        // the grpc spelling of `canceled`
+        if name == "cancelled" {
+            return Ok(Code::Cancelled);
+        }
+        (0..=16)
+            .map(Code::from)
+            .find(|code| code.name() == name)
+            .ok_or(())
+    }
+}
+
 impl std::fmt::Display for Code {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         std::fmt::Display::fmt(self.description(), f)
@@ -258,11 +356,38 @@ pub struct Status {
 
     // grpc-message
     message: String,
+
+    // grpc-status-details-bin
+    details: Bytes,
 }
 
 impl Status {
     pub fn new(code: Code, message: String) -> Self {
-        Status { code, message }
+        Status {
+            code,
+            message,
+            details: Bytes::new(),
+        }
+    }
+
+    /// Status carrying `details`, an encoded `google.rpc.Status`.
+    pub fn with_details(code: Code, message: String, details: Bytes) -> Self {
+        Status {
+            code,
+            message,
+            details,
+        }
+    }
+
+    /// Status carrying the `google.rpc` error details, such as a
+    /// [`details::BadRequest`] packed with [`ErrorDetail::to_any`].
+    pub fn with_error_details(code: Code, message: String, details: Vec<Any>) -> Self {
+        let rpc_status = RpcStatus {
+            code: code as i32,
+            message: message.clone(),
+            details,
+        };
+        Status::with_details(code, message, rpc_status.encode_to_vec().into())
     }
 
     pub fn with_message(self, message: String) -> Self {
@@ -281,6 +406,93 @@ impl Status {
         self.code
     }
 
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn details(&self) -> &[u8] {
+        &self.details
+    }
+
+    /// The `google.rpc` error details, empty if the details are not an
+    /// encoded `google.rpc.Status`.
+    pub fn error_details(&self) -> Vec<Any> {
+        if self.details.is_empty() {
+            return Vec::new();
+        }
+        RpcStatus::decode(self.details.clone())
+            .map(|status| status.details)
+            .unwrap_or_default()
+    }
+
+    /// The first error detail of type `T`, as in
+    /// `status.get_detail::<BadRequest>()`.
+    pub fn get_detail<T: ErrorDetail>(&self) -> Option<T> {
+        self.error_details().iter().find_map(T::from_any)
+    }
+
+    /// The exception of a Java Dubbo provider this status reports, if any.
+    pub fn java_exception(&self) -> Option<JavaException> {
+        JavaException::from_status(self)
+    }
+
+    /// Status of the `grpc-status` and `grpc-message` headers, if any.
+    pub fn from_headers(headers: &http::HeaderMap) -> Option<Self> {
+        let code = headers
+            .get(GRPC_STATUS)?
+            .to_str()
+            .ok()?
+            .parse::<i32>()
+            .ok()?;
+        let message = headers
+            .get(GRPC_MESSAGE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| urlencoding::decode(v).map_or_else(|_| v.to_string(), |v| v.into_owned()))
+            .unwrap_or_default();
+        let details = headers
+            .get(GRPC_STATUS_DETAILS)
+            .and_then(|v| BINARY_HEADER.decode(v.as_bytes()).ok())
+            .unwrap_or_default();
+        Some(Status::with_details(
+            Code::from(code),
+            message,
+            details.into(),
+        ))
+    }
+
+    fn insert_headers(&self, headers: &mut http::HeaderMap) {
+        headers.insert(GRPC_STATUS, self.code.to_http_header_value());
+        headers.insert(
+            GRPC_MESSAGE,
+            http::HeaderValue::from_str(&self.message).unwrap(),
+        );
+        if !self.details.is_empty() {
+            if let Ok(details) = HeaderValue::from_str(&BINARY_HEADER.encode(&self.details)) {
+                headers.insert(GRPC_STATUS_DETAILS, details);
+            }
+        }
+    }
+
+    /// Response of a call made without grpc, failing with this status: the
+    /// http status of the code and a json body such as
+    /// `{"code": "not_found", "message": "..."}`.
+    pub fn to_json_http(&self) -> http::Response<crate::BoxBody> {
+        let body = serde_json::json!({
+            "code": self.code.name(),
+            "message": self.message,
+        });
+        let body = http_body::Full::new(bytes::Bytes::from(body.to_string()))
+            .map_err(|never| match never {})
+            .boxed_unsync();
+        let mut resp = http::Response::new(body);
+        *resp.status_mut() = self.code.to_http_status();
+        resp.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_static("application/json"),
+        );
+        resp
+    }
+
     pub fn to_http(&self) -> http::Response<crate::BoxBody> {
         let (mut parts, _) = http::Response::new(()).into_parts();
 
@@ -289,13 +501,7 @@ impl Status {
             http::HeaderValue::from_static("application/grpc"),
         );
 
-        parts
-            .headers
-            .insert(GRPC_STATUS, self.code.to_http_header_value());
-        parts.headers.insert(
-            GRPC_MESSAGE,
-            http::HeaderValue::from_str(&self.message).unwrap(),
-        );
+        self.insert_headers(&mut parts.headers);
 
         parts.headers.insert(
             "grpc-accept-encoding",
@@ -313,13 +519,7 @@ impl Status {
             http::HeaderValue::from_static("application/grpc"),
         );
 
-        parts
-            .headers
-            .insert(GRPC_STATUS, self.code.to_http_header_value());
-        parts.headers.insert(
-            GRPC_MESSAGE,
-            http::HeaderValue::from_str(&self.message).unwrap(),
-        );
+        self.insert_headers(&mut parts.headers);
 
         parts.headers.insert(
             "grpc-accept-encoding",
@@ -369,3 +569,29 @@ impl Error for DubboError {}
 unsafe impl Send for DubboError {}
 
 unsafe impl Sync for DubboError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::details::{ErrorDetail, RetryInfo};
+
+    #[test]
+    fn test_status_details_headers() {
+        let retry = RetryInfo::new(std::time::Duration::from_secs(3));
+        let status = Status::with_error_details(
+            Code::Unavailable,
+            "try later".to_string(),
+            vec![retry.to_any()],
+        );
+        let resp = status.to_http();
+        assert!(resp.headers().contains_key(GRPC_STATUS_DETAILS));
+
+        let decoded = Status::from_headers(resp.headers()).unwrap();
+        assert_eq!(decoded.code(), Code::Unavailable);
+        assert_eq!(decoded.details(), status.details());
+        assert_eq!(decoded.get_detail::<RetryInfo>(), Some(retry));
+        assert!(Status::new(Code::Ok, String::new())
+            .error_details()
+            .is_empty());
+    }
+}
@@ -0,0 +1,219 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{
+    net::SocketAddr,
+    task::{Context as TaskContext, Poll},
+};
+
+use futures_util::future::BoxFuture;
+use http_body::Body;
+use opentelemetry::{
+    trace::{FutureExt, SpanKind, Status as SpanStatus, TraceContextExt, Tracer},
+    Context, KeyValue,
+};
+use tower_service::Service;
+
+use super::{extract, tracer, RPC_SYSTEM};
+use crate::{
+    status::Code,
+    utils::observed_body::{ObservedBody, ResponseObserver},
+};
+
+/// Span of a single rpc, ended once the response body is fully read.
+pub struct RpcSpan {
+    cx: Context,
+    ended: bool,
+}
+
+impl RpcSpan {
+    pub fn start(
+        kind: SpanKind,
+        path: &str,
+        peer_name: String,
+        peer_port: Option<u16>,
+        parent: &Context,
+    ) -> Self {
+        let mut segments = path.trim_start_matches('/').splitn(2, '/');
+        let service = segments.next().unwrap_or_default().to_string();
+        let method = segments.next().unwrap_or_default().to_string();
+
+        let mut attributes = vec![
+            KeyValue::new("rpc.system", RPC_SYSTEM),
+            KeyValue::new("rpc.service", service.clone()),
+            KeyValue::new("rpc.method", method.clone()),
+            KeyValue::new("net.peer.name", peer_name),
+        ];
+        if let Some(port) = peer_port {
+            attributes.push(KeyValue::new("net.peer.port", port as i64));
+        }
+
+        let tracer = tracer();
+        let span = tracer
+            .span_builder(format!("{}/{}", service, method))
+            .with_kind(kind)
+            .with_attributes(attributes)
+            .start_with_context(&tracer, parent);
+
+        RpcSpan {
+            cx: parent.with_span(span),
+            ended: false,
+        }
+    }
+
+    /// Context carrying the span, to be propagated to the peer or used as
+    /// the parent of nested calls.
+    pub fn context(&self) -> &Context {
+        &self.cx
+    }
+
+    pub fn end(&mut self, code: Code) {
+        if self.ended {
+            return;
+        }
+        self.ended = true;
+
+        let span = self.cx.span();
+        span.set_attribute(KeyValue::new("rpc.grpc.status_code", code as i64));
+        if code != Code::Ok {
+            span.set_status(SpanStatus::error(format!("{:?}", code)));
+        }
+        span.end();
+    }
+}
+
+impl ResponseObserver for RpcSpan {
+    fn on_end(&mut self, code: Code) {
+        self.end(code);
+    }
+}
+
+impl Drop for RpcSpan {
+    fn drop(&mut self) {
+        // the call went away before any response
+        self.end(Code::Cancelled);
+    }
+}
+
+/// Starts a server span for every request served on a connection, the span
+/// is the current context while the service handles the request.
+#[derive(Clone)]
+pub struct TracingService<S> {
+    inner: S,
+    peer: SocketAddr,
+}
+
+impl<S> TracingService<S> {
+    pub fn new(inner: S, peer: SocketAddr) -> Self {
+        TracingService { inner, peer }
+    }
+}
+
+impl<S, B> Service<http::Request<hyper::Body>> for TracingService<S>
+where
+    S: Service<http::Request<hyper::Body>, Response = http::Response<B>>,
+    S::Future: Send + 'static,
+    B: Body<Data = bytes::Bytes, Error = crate::status::Status> + Send + 'static,
+{
+    type Response = http::Response<crate::BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<hyper::Body>) -> Self::Future {
+        let parent = extract(req.headers());
+        let span = RpcSpan::start(
+            SpanKind::Server,
+            req.uri().path(),
+            self.peer.ip().to_string(),
+            Some(self.peer.port()),
+            &parent,
+        );
+        let fut = self.inner.call(req).with_context(span.context().clone());
+
+        Box::pin(async move {
+            let resp = fut.await?;
+            let (parts, body) = resp.into_parts();
+            let body = ObservedBody::new(body, span, &parts.headers);
+            Ok(http::Response::from_parts(parts, crate::BoxBody::new(body)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_sdk::{testing::trace::InMemorySpanExporter, trace::TracerProvider};
+
+    use super::*;
+    use crate::telemetry::inject;
+
+    #[tokio::test]
+    async fn test_propagation() {
+        let exporter = InMemorySpanExporter::default();
+        let provider = TracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let _ = provider.tracer("test");
+        opentelemetry::global::set_tracer_provider(provider.clone());
+
+        let path = "/helloworld.Greeter/SayHello";
+        let mut client = RpcSpan::start(
+            SpanKind::Client,
+            path,
+            "127.0.0.1".to_string(),
+            Some(8888),
+            &Context::new(),
+        );
+        let mut headers = http::HeaderMap::new();
+        inject(client.context(), &mut headers);
+        assert!(headers.contains_key("traceparent"));
+
+        let mut server = RpcSpan::start(
+            SpanKind::Server,
+            path,
+            "127.0.0.1".to_string(),
+            Some(50000),
+            &extract(&headers),
+        );
+        server.end(Code::Ok);
+        client.end(Code::NotFound);
+
+        let _ = provider.force_flush();
+        let spans = exporter.get_finished_spans().unwrap();
+        assert_eq!(spans.len(), 2);
+        let (server, client) = (&spans[0], &spans[1]);
+        assert_eq!(server.name, "helloworld.Greeter/SayHello");
+        assert_eq!(server.span_kind, SpanKind::Server);
+        assert_eq!(client.span_kind, SpanKind::Client);
+        assert_eq!(
+            server.span_context.trace_id(),
+            client.span_context.trace_id()
+        );
+        assert_eq!(server.parent_span_id, client.span_context.span_id());
+        assert!(server
+            .attributes
+            .contains(&KeyValue::new("rpc.system", "apache_dubbo")));
+        assert!(client
+            .attributes
+            .contains(&KeyValue::new("rpc.method", "SayHello")));
+        assert_eq!(client.status, SpanStatus::error("NotFound"));
+    }
+}
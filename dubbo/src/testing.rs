@@ -0,0 +1,121 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Utilities for testing services end to end in a single process, the server
+//! and its clients talking http/2 over the in-memory `mem` transport.
+//!
+//! ```ignore
+//! #[test]
+//! fn test_greet() {
+//!     testing::block_on(async {
+//!         register_server(GreeterServerImpl::default());
+//!         let server = testing::serve(vec!["org.apache.dubbo.sample.tri.Greeter".to_string()]).await;
+//!         let mut client = GreeterClient::new(server.client("org.apache.dubbo.sample.tri.Greeter"));
+//!     })
+//! }
+//! ```
+
+use std::{
+    future::Future,
+    net::{Ipv4Addr, SocketAddr},
+    sync::atomic::{AtomicU16, Ordering},
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
+use tokio::{runtime::Runtime, task::JoinHandle};
+
+use crate::triple::{
+    client::builder::ClientBuilder, server::builder::ServerBuilder,
+    transport::listener::mem_listener,
+};
+
+static NEXT_PORT: AtomicU16 = AtomicU16::new(1);
+
+static RUNTIME: Lazy<Runtime> = Lazy::new(|| {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("failed to build the test runtime")
+});
+
+/// Run `future` on a runtime shared by the tests of the process.
+///
+/// The extension directory and the registries it loads live on the runtime
+/// of their first caller, which with `#[tokio::test]` stops with its test,
+/// leaving the clients of the next tests without a registry.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    RUNTIME.block_on(future)
+}
+
+/// Server spawned on an in-memory address, shut down when dropped.
+pub struct TestServer {
+    addr: SocketAddr,
+    handle: JoinHandle<Result<(), crate::Error>>,
+}
+
+impl TestServer {
+    /// The in-memory address the server listens on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Url of `interface` on the server, as in
+    /// `mem://127.0.0.1:1?interface=org.apache.dubbo.sample.tri.Greeter`.
+    pub fn url(&self, interface: &str) -> String {
+        format!("mem://{}?interface={}", self.addr, interface)
+    }
+
+    /// Client builder calling `interface` on the server directly.
+    pub fn client(&self, interface: &str) -> ClientBuilder {
+        ClientBuilder::from_static(&self.url(interface))
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Serve the services of `service_names`, registered beforehand with
+/// `register_server`, in memory.
+pub async fn serve(service_names: Vec<String>) -> TestServer {
+    serve_with(ServerBuilder::new().with_service_names(service_names)).await
+}
+
+/// Serve `builder` in memory, its listener and address being replaced.
+pub async fn serve_with(builder: ServerBuilder) -> TestServer {
+    let port = NEXT_PORT.fetch_add(1, Ordering::Relaxed);
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let mut builder = builder.with_listener("mem".to_string());
+    builder.addr = Some(addr);
+    let server = builder.build();
+    let handle = tokio::spawn(server.serve());
+
+    let name = addr.to_string();
+    while !mem_listener::is_bound(&name) {
+        if handle.is_finished() {
+            match handle.await {
+                Ok(Err(err)) => panic!("serving mem://{} failed: {}", name, err),
+                res => panic!("serving mem://{} stopped: {:?}", name, res.err()),
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+    TestServer { addr, handle }
+}
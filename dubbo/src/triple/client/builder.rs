@@ -15,15 +15,37 @@
  * limitations under the License.
  */
 
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use crate::{
-    cluster::NewCluster, directory::NewCachedDirectory, extension, loadbalancer::NewLoadBalancer,
-    route::NewRoutes, utils::boxed_clone::BoxCloneService,
+    cluster::{NewCluster, DEFAULT_RETRIES},
+    directory::NewCachedDirectory,
+    extension,
+    filter::access_log::AccessLog,
+    invoker::clone_body::DEFAULT_RETRY_BUFFER_SIZE,
+    loadbalancer::{NewLoadBalancer, LOADBALANCES},
+    protocol::triple::service_key::ServiceSelector,
+    route::NewRoutes,
+    utils::boxed_clone::BoxCloneService,
 };
 
 use crate::{
+    config::{
+        consumer::{ReferenceConfig, ReferenceError},
+        get_global_config,
+        health_check::{HealthCheckConfig, HEALTH_CHECKS},
+        http2::Http2Config,
+        method::{MethodConfig, METHOD_COMPRESSIONS},
+        router::ZoneRouterConfig,
+        tls::TlsConfig,
+        RootConfig,
+    },
+    invoker::NewInvoker,
     registry::{registry::StaticRegistry, MkRegistryService},
+    triple::{
+        compression::CompressionConfig,
+        transport::{pool::PoolConfig, tls::ClientTls},
+    },
     Url,
 };
 use aws_smithy_http::body::SdkBody;
@@ -41,6 +63,21 @@ pub struct ClientBuilder {
     pub connector: &'static str,
     registry_extension_url: Option<Url>,
     pub direct: bool,
+    pub(crate) access_log: Option<AccessLog>,
+    tls: Option<TlsConfig>,
+    retries: Option<u32>,
+    loadbalance: Option<String>,
+    pub(crate) methods: HashMap<String, MethodConfig>,
+    pub(crate) compression: Option<CompressionConfig>,
+    pub(crate) max_message_size: Option<usize>,
+    pub(crate) max_encode_message_size: Option<usize>,
+    pub(crate) retry_buffer_size: Option<usize>,
+    pool: Option<PoolConfig>,
+    http2: Option<Http2Config>,
+    health_check: Option<HealthCheckConfig>,
+    zone: Option<ZoneRouterConfig>,
+    group: String,
+    version: String,
 }
 
 impl ClientBuilder {
@@ -50,6 +87,21 @@ impl ClientBuilder {
             connector: "",
             registry_extension_url: None,
             direct: false,
+            access_log: None,
+            tls: None,
+            retries: None,
+            loadbalance: None,
+            methods: HashMap::new(),
+            compression: None,
+            max_message_size: None,
+            max_encode_message_size: None,
+            retry_buffer_size: None,
+            pool: None,
+            http2: None,
+            health_check: None,
+            zone: None,
+            group: String::new(),
+            version: String::new(),
         }
     }
 
@@ -60,6 +112,21 @@ impl ClientBuilder {
             connector: "",
             registry_extension_url: Some(registry_extension_url),
             direct: true,
+            access_log: None,
+            tls: None,
+            retries: None,
+            loadbalance: None,
+            methods: HashMap::new(),
+            compression: None,
+            max_message_size: None,
+            max_encode_message_size: None,
+            retry_buffer_size: None,
+            pool: None,
+            http2: None,
+            health_check: None,
+            zone: None,
+            group: String::new(),
+            version: String::new(),
         }
     }
 
@@ -95,19 +162,413 @@ impl ClientBuilder {
         Self { direct, ..self }
     }
 
+    /// Log every call made by the client, see [`AccessLog`].
+    pub fn with_access_log(self, access_log: AccessLog) -> Self {
+        Self {
+            access_log: Some(access_log),
+            ..self
+        }
+    }
+
+    /// Connect to the providers over TLS, see [`TlsConfig`].
+    pub fn with_tls(self, tls: TlsConfig) -> Self {
+        Self {
+            tls: Some(tls),
+            ..self
+        }
+    }
+
+    /// Times a failed call is retried on another provider, 2 by default.
+    pub fn with_retries(self, retries: u32) -> Self {
+        Self {
+            retries: Some(retries),
+            ..self
+        }
+    }
+
+    /// Load balancer picking the provider of each call, `p2c` by default.
+    pub fn with_loadbalance(self, loadbalance: String) -> Self {
+        Self {
+            loadbalance: Some(loadbalance),
+            ..self
+        }
+    }
+
+    /// Settings of the method `name`, picked by the calls of generated
+    /// clients to that method.
+    pub fn with_method(mut self, name: String, method: MethodConfig) -> Self {
+        self.methods.insert(name, method);
+        self
+    }
+
+    /// Compression of the requests and encodings accepted for the
+    /// responses, gzip first by default, see [`CompressionConfig`].
+    pub fn with_compression(self, compression: CompressionConfig) -> Self {
+        Self {
+            compression: Some(compression),
+            ..self
+        }
+    }
+
+    /// Largest response message in bytes, 4 MiB by default. Methods may set
+    /// their own limit, see [`MethodConfig`].
+    pub fn with_max_message_size(self, max_message_size: usize) -> Self {
+        Self {
+            max_message_size: Some(max_message_size),
+            ..self
+        }
+    }
+
+    /// Largest request message in bytes, unlimited by default.
+    pub fn with_max_encode_message_size(self, max_encode_message_size: usize) -> Self {
+        Self {
+            max_encode_message_size: Some(max_encode_message_size),
+            ..self
+        }
+    }
+
+    /// Bytes of a request body kept to retry it on another provider, see
+    /// [`DEFAULT_RETRY_BUFFER_SIZE`]. Requests with larger bodies are not
+    /// retried.
+    pub fn with_retry_buffer_size(self, retry_buffer_size: usize) -> Self {
+        Self {
+            retry_buffer_size: Some(retry_buffer_size),
+            ..self
+        }
+    }
+
+    /// Connections kept to every provider, a single one by default.
+    pub fn with_connection_pool(self, pool: PoolConfig) -> Self {
+        Self {
+            pool: Some(pool),
+            ..self
+        }
+    }
+
+    /// Http/2 settings of the connections, see [`Http2Config`]. Keepalive
+    /// pings detect dead connections, which the pool replaces.
+    pub fn with_http2(self, http2: Http2Config) -> Self {
+        Self {
+            http2: Some(http2),
+            ..self
+        }
+    }
+
+    /// Check the health of the providers, the unhealthy ones being left out
+    /// of the load balancing, see [`HealthCheckConfig`].
+    pub fn with_health_check(self, health_check: HealthCheckConfig) -> Self {
+        Self {
+            health_check: Some(health_check),
+            ..self
+        }
+    }
+
+    /// Prefer the providers in the zone of the consumer, see
+    /// [`ZoneRouterConfig`].
+    pub fn with_zone_routing(self, zone: ZoneRouterConfig) -> Self {
+        Self {
+            zone: Some(zone),
+            ..self
+        }
+    }
+
+    /// Call only the providers in `group`, `*` for any group or a comma
+    /// separated list of groups.
+    pub fn with_group(self, group: String) -> Self {
+        Self { group, ..self }
+    }
+
+    /// Call only the providers of `version`, `*` for any version.
+    pub fn with_version(self, version: String) -> Self {
+        Self { version, ..self }
+    }
+
+    /// Groups and versions of the providers called.
+    pub(crate) fn selector(&self) -> ServiceSelector {
+        ServiceSelector::new(self.group.clone(), self.version.clone())
+    }
+
+    /// Builder of the reference `name` of the global config, see
+    /// [`ReferenceConfig`].
+    pub fn from_reference(name: &str) -> Result<Self, ReferenceError> {
+        Self::from_reference_config(get_global_config(), name)
+    }
+
+    pub fn from_reference_config(root: &RootConfig, name: &str) -> Result<Self, ReferenceError> {
+        let reference = root
+            .consumer
+            .references
+            .get(name)
+            .ok_or_else(|| ReferenceError::NotFound(name.to_string()))?;
+        if reference.interface.is_empty() {
+            return Err(ReferenceError::invalid(name, "interface must not be empty"));
+        }
+
+        let mut builder = if !reference.url.is_empty() {
+            let url = direct_url(reference).ok_or_else(|| {
+                ReferenceError::invalid(name, format!("invalid url {:?}", reference.url))
+            })?;
+            Self::from_static(&url)
+        } else {
+            let registry_ids = if reference.registry_ids.is_empty() {
+                &root.consumer.registry_ids
+            } else {
+                &reference.registry_ids
+            };
+            let registry_id = match registry_ids.as_slice() {
+                [] => {
+                    return Err(ReferenceError::invalid(
+                        name,
+                        "neither url nor registry_ids is set",
+                    ))
+                }
+                [id] => id,
+                _ => {
+                    return Err(ReferenceError::invalid(
+                        name,
+                        "only a single registry is supported",
+                    ))
+                }
+            };
+            let registry = root.registries.get(registry_id).ok_or_else(|| {
+                ReferenceError::invalid(
+                    name,
+                    format!("registry {:?} is not configured", registry_id),
+                )
+            })?;
+            let url: Url = registry.to_url().parse().map_err(|_| {
+                ReferenceError::invalid(name, format!("invalid registry {:?}", registry_id))
+            })?;
+            Self::new().with_registry(url)
+        };
+
+        builder = builder
+            .with_group(reference.group.clone())
+            .with_version(reference.version.clone());
+        if let Some(timeout) = reference.timeout {
+            builder = builder.with_timeout(timeout);
+        }
+        match reference.cluster.as_str() {
+            "" | "failover" => {
+                if let Some(retries) = reference.retries {
+                    builder = builder.with_retries(retries);
+                }
+            }
+            "failfast" => builder = builder.with_retries(0),
+            cluster => {
+                return Err(ReferenceError::invalid(
+                    name,
+                    format!("unknown cluster {:?}", cluster),
+                ))
+            }
+        }
+        match reference.loadbalance.as_str() {
+            "" => {}
+            loadbalance if LOADBALANCES.contains(&loadbalance) => {
+                builder = builder.with_loadbalance(reference.loadbalance.clone())
+            }
+            loadbalance => {
+                return Err(ReferenceError::invalid(
+                    name,
+                    format!("unknown loadbalance {:?}", loadbalance),
+                ))
+            }
+        }
+        match CompressionConfig::from_settings(
+            &reference.compression,
+            reference.compression_min_size,
+        ) {
+            Ok(Some(compression)) => builder = builder.with_compression(compression),
+            Ok(None) => {}
+            Err(compression) => {
+                return Err(ReferenceError::invalid(
+                    name,
+                    format!("unknown compression {:?}", compression),
+                ))
+            }
+        }
+        if let Some(max) = reference.max_message_size {
+            builder = builder.with_max_message_size(max);
+        }
+        if let Some(max) = reference.max_encode_message_size {
+            builder = builder.with_max_encode_message_size(max);
+        }
+        if reference.min_connections.is_some() || reference.max_connections.is_some() {
+            let min = reference.min_connections.unwrap_or(1);
+            let max = reference.max_connections.unwrap_or(min.max(1));
+            if min > max {
+                return Err(ReferenceError::invalid(
+                    name,
+                    "min_connections exceeds max_connections".to_string(),
+                ));
+            }
+            builder = builder.with_connection_pool(
+                PoolConfig::default()
+                    .min_connections(min)
+                    .max_connections(max),
+            );
+        }
+        for (method, config) in reference.methods.iter() {
+            if !config.loadbalance.is_empty()
+                && !LOADBALANCES.contains(&config.loadbalance.as_str())
+            {
+                return Err(ReferenceError::invalid(
+                    name,
+                    format!(
+                        "unknown loadbalance {:?} of method {}",
+                        config.loadbalance, method
+                    ),
+                ));
+            }
+            if !config.compression.is_empty()
+                && !METHOD_COMPRESSIONS.contains(&config.compression.as_str())
+            {
+                return Err(ReferenceError::invalid(
+                    name,
+                    format!(
+                        "unknown compression {:?} of method {}",
+                        config.compression, method
+                    ),
+                ));
+            }
+            builder = builder.with_method(method.clone(), config.clone());
+        }
+        if let Some(http2) = &reference.http2 {
+            builder = builder.with_http2(http2.clone());
+        }
+        if let Some(health_check) = &reference.health_check {
+            if !health_check.check.is_empty()
+                && !HEALTH_CHECKS.contains(&health_check.check.as_str())
+            {
+                return Err(ReferenceError::invalid(
+                    name,
+                    format!("unknown health check {:?}", health_check.check),
+                ));
+            }
+            builder = builder.with_health_check(health_check.clone());
+        }
+        if let Some(zone) = &root.routers.zone {
+            builder = builder.with_zone_routing(zone.clone());
+        }
+        if let Some(tls) = &reference.tls {
+            ClientTls::new(tls).map_err(|err| {
+                ReferenceError::invalid(name, format!("invalid tls config: {}", err))
+            })?;
+            builder = builder.with_tls(tls.clone());
+        }
+        Ok(builder)
+    }
+
     pub fn build(mut self) -> ServiceMK {
         let registry = self
             .registry_extension_url
             .take()
             .expect("registry must not be empty");
+        let tls = self
+            .tls
+            .as_ref()
+            .map(|tls| ClientTls::new(tls).expect("invalid client tls config"));
+        let new_invoker = NewInvoker::new()
+            .with_tls(tls)
+            .with_pool(self.pool.take().unwrap_or_default())
+            .with_http2(self.http2.take().unwrap_or_default());
+        // direct providers are called whatever group they announce
+        let selector = if self.direct {
+            ServiceSelector::default()
+        } else {
+            self.selector()
+        };
 
         let mk_service = ServiceBuilder::new()
-            .layer(NewCluster::layer())
-            .layer(NewLoadBalancer::layer())
+            .layer(NewCluster::layer_with_retry_buffer(
+                self.retries.unwrap_or(DEFAULT_RETRIES),
+                self.retry_buffer_size.unwrap_or(DEFAULT_RETRY_BUFFER_SIZE),
+            ))
+            .layer(NewLoadBalancer::layer_with_zone(
+                self.loadbalance.take().unwrap_or_default(),
+                self.zone.take(),
+            ))
             .layer(NewRoutes::layer())
-            .layer(NewCachedDirectory::layer())
+            .layer(NewCachedDirectory::layer_with_health_check(
+                new_invoker,
+                selector,
+                self.health_check.take(),
+            ))
             .service(MkRegistryService::new(registry));
 
         Arc::new(mk_service)
     }
 }
+
+/// Static provider url of a reference, `tri://` being an alias of plain
+/// http/2.
+fn direct_url(reference: &ReferenceConfig) -> Option<String> {
+    let (scheme, rest) = reference.url.split_once("://")?;
+    let scheme = match scheme {
+        "tri" | "triple" | "http" => "http",
+        "https" => "https",
+        _ => return None,
+    };
+    let authority = rest.split(['/', '?']).next().filter(|a| !a.is_empty())?;
+    Some(format!(
+        "{}://{}?interface={}",
+        scheme, authority, reference.interface
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_reference_config() {
+        let root: RootConfig = serde_yaml::from_str(
+            r#"
+registries:
+  demoZK:
+    protocol: zookeeper
+    address: 127.0.0.1:2181
+consumer:
+  references:
+    direct:
+      interface: org.apache.dubbo.sample.tri.Greeter
+      url: tri://localhost:20000
+      cluster: failfast
+      timeout: 3000
+      methods:
+        SayHello:
+          timeout: 500
+          compression: identity
+    registry:
+      interface: org.apache.dubbo.sample.tri.Greeter
+      registry_ids: [demoZK]
+      loadbalance: random
+      group: g1,g2
+      version: 1.0.0
+    unknown_registry:
+      interface: org.apache.dubbo.sample.tri.Greeter
+      registry_ids: [nacos]
+"#,
+        )
+        .unwrap();
+
+        let builder = ClientBuilder::from_reference_config(&root, "direct").unwrap();
+        assert_eq!(builder.timeout, Some(3000));
+        assert_eq!(builder.retries, Some(0));
+        assert_eq!(builder.methods["SayHello"].timeout, Some(500));
+        assert!(builder.direct);
+
+        let builder = ClientBuilder::from_reference_config(&root, "registry").unwrap();
+        assert_eq!(builder.loadbalance.as_deref(), Some("random"));
+        assert!(!builder.direct);
+        assert_eq!(builder.selector(), ServiceSelector::new("g1,g2", "1.0.0"));
+
+        let err = ClientBuilder::from_reference_config(&root, "unknown_registry").err();
+        assert_eq!(
+            err.unwrap().to_string(),
+            "consumer.references.unknown_registry: registry \"nacos\" is not configured"
+        );
+        let err = ClientBuilder::from_reference_config(&root, "missing").err();
+        assert!(matches!(err, Some(ReferenceError::NotFound(_))));
+    }
+}
@@ -16,6 +16,8 @@
  */
 
 pub mod builder;
+pub mod options;
 pub mod triple;
 
+pub use options::CallOptions;
 pub use triple::TripleClient;
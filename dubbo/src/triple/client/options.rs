@@ -0,0 +1,128 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{collections::HashMap, time::Duration};
+
+use crate::{
+    config::method::MethodConfig,
+    invocation::Request,
+    triple::compression::{CompressionEncoding, IDENTITY},
+};
+
+/// Settings of a single call, winning over the ones of the dynamic config,
+/// the method config and the client.
+///
+/// They travel in the extensions of the request, so interceptors may set
+/// them too.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallOptions {
+    pub timeout: Option<Duration>,
+    /// compression of the request, `Some(None)` turning it off
+    pub compression: Option<Option<CompressionEncoding>>,
+    pub retries: Option<u32>,
+    /// sent as headers along with the metadata of the request
+    pub attachments: HashMap<String, String>,
+}
+
+impl CallOptions {
+    pub fn new() -> Self {
+        CallOptions::default()
+    }
+
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    pub fn compression(self, compression: Option<CompressionEncoding>) -> Self {
+        Self {
+            compression: Some(compression),
+            ..self
+        }
+    }
+
+    pub fn retries(self, retries: u32) -> Self {
+        Self {
+            retries: Some(retries),
+            ..self
+        }
+    }
+
+    pub fn attachment(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.attachments.insert(key.into(), value.into());
+        self
+    }
+
+    /// Take the options out of `request`, moving the attachments to its
+    /// metadata.
+    pub(crate) fn take<T>(request: &mut Request<T>) -> CallOptions {
+        let options = request
+            .extensions
+            .remove::<CallOptions>()
+            .unwrap_or_default();
+        for (key, value) in options.attachments.iter() {
+            request.metadata =
+                std::mem::take(&mut request.metadata).insert(key.clone(), value.clone());
+        }
+        options
+    }
+
+    /// `method` with the compression of the call.
+    pub(crate) fn apply(&self, method: MethodConfig) -> MethodConfig {
+        match self.compression {
+            Some(Some(encoding)) => method.compression(encoding.name().to_string()),
+            Some(None) => method.compression(IDENTITY.to_string()),
+            None => method,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_call_options() {
+        let mut request = Request::new(());
+        request.extensions_mut().insert(
+            CallOptions::new()
+                .timeout(Duration::from_millis(100))
+                .compression(None)
+                .attachment("tenant", "t1"),
+        );
+        let options = CallOptions::take(&mut request);
+        assert_eq!(options.timeout, Some(Duration::from_millis(100)));
+        assert_eq!(request.metadata.get("tenant").unwrap(), "t1");
+        assert!(request.extensions().get::<CallOptions>().is_none());
+
+        let method = MethodConfig::default().compression("gzip".to_string());
+        assert_eq!(
+            options.apply(method.clone()).compression_encoding(),
+            Some(None)
+        );
+        assert_eq!(CallOptions::new().apply(method.clone()), method);
+        assert_eq!(
+            CallOptions::new()
+                .compression(Some(CompressionEncoding::Zstd))
+                .apply(method)
+                .compression,
+            "zstd"
+        );
+    }
+}
@@ -15,51 +15,230 @@
  * limitations under the License.
  */
 
+use std::{
+    collections::HashMap,
+    future::Future,
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
 use aws_smithy_http::body::SdkBody;
-use futures_util::{future, stream, StreamExt, TryStreamExt};
+use bytes::{Bytes, BytesMut};
+use futures_util::{future, stream, Stream, StreamExt, TryFutureExt, TryStreamExt};
 use http::HeaderValue;
 use prost::Message;
 use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
 use tower_service::Service;
 
-use crate::codegen::{ProstCodec, RpcInvocation, SerdeCodec};
+use crate::codegen::RpcInvocation;
 
 use crate::{
+    cluster::hedging::UnaryCall,
+    config::{
+        dynamic::{self, Side, TIMEOUT_KEY},
+        method::MethodConfig,
+    },
+    filter::{
+        access_log::{AccessLog, AccessLogArguments, AccessLogService},
+        Filter,
+    },
     invocation::{IntoStreamingRequest, Metadata, Request, Response},
-    status::Status,
+    logger::tracing::warn,
+    protocol::triple::service_key::ServiceSelector,
     svc::NewService,
     triple::{
-        codec::{Codec, Decoder, Encoder},
-        compression::CompressionEncoding,
-        decode::Decoding,
+        codec::{BoxDecoder, BoxEncoder, DecodeBuf, DefaultCodec, MessageCodec},
+        compression::{
+            CompressionConfig, CompressionEncoding, GRPC_ACCEPT_ENCODING, GRPC_ENCODING,
+        },
+        decode::{failed_status, Decoding},
         encode::encode,
     },
 };
 
-use super::builder::{ClientBuilder, ServiceMK};
+use super::{
+    builder::{ClientBuilder, ServiceMK},
+    options::CallOptions,
+};
+
+/// Filter run on the requests of a client and its clones.
+type Interceptor = Arc<Mutex<dyn Filter + Send>>;
 
-#[derive(Clone)]
-pub struct TripleClient {
-    pub(crate) send_compression_encoding: Option<CompressionEncoding>,
+/// Client of the triple protocol, serializing messages with `C`.
+pub struct TripleClient<C = DefaultCodec> {
+    pub(crate) compression: CompressionConfig,
+    max_message_size: Option<usize>,
+    max_encode_message_size: Option<usize>,
     pub(crate) mk: ServiceMK,
+    pub(crate) access_log: Option<AccessLog>,
+    timeout: Option<Duration>,
+    methods: Arc<HashMap<String, MethodConfig>>,
+    selector: ServiceSelector,
+    interceptors: Vec<Interceptor>,
+    _codec: PhantomData<fn() -> C>,
 }
 
-impl TripleClient {
+impl<C> Clone for TripleClient<C> {
+    fn clone(&self) -> Self {
+        TripleClient {
+            compression: self.compression.clone(),
+            max_message_size: self.max_message_size,
+            max_encode_message_size: self.max_encode_message_size,
+            mk: self.mk.clone(),
+            access_log: self.access_log.clone(),
+            timeout: self.timeout,
+            methods: self.methods.clone(),
+            selector: self.selector.clone(),
+            interceptors: self.interceptors.clone(),
+            _codec: PhantomData,
+        }
+    }
+}
+
+impl<C> TripleClient<C> {
     pub fn connect(host: String) -> Self {
         let builder = ClientBuilder::from_static(&host).with_direct(true);
         let mk = builder.build();
 
         TripleClient {
-            send_compression_encoding: Some(CompressionEncoding::Gzip),
+            compression: CompressionConfig::default(),
+            max_message_size: None,
+            max_encode_message_size: None,
             mk,
+            access_log: None,
+            timeout: None,
+            methods: Default::default(),
+            selector: Default::default(),
+            interceptors: Vec::new(),
+            _codec: PhantomData,
         }
     }
 
     pub fn new(builder: ClientBuilder) -> Self {
         TripleClient {
-            send_compression_encoding: Some(CompressionEncoding::Gzip),
+            compression: builder.compression.clone().unwrap_or_default(),
+            max_message_size: builder.max_message_size,
+            max_encode_message_size: builder.max_encode_message_size,
+            access_log: builder.access_log.clone(),
+            timeout: builder.timeout.map(Duration::from_millis),
+            methods: Arc::new(builder.methods.clone()),
+            selector: builder.selector(),
             mk: builder.build(),
+            interceptors: Vec::new(),
+            _codec: PhantomData,
+        }
+    }
+
+    /// Run `interceptor` on the metadata of each request, after the ones
+    /// added before. It may add headers or reject the call.
+    pub fn with_interceptor<F>(mut self, interceptor: F) -> Self
+    where
+        F: Filter + Send + 'static,
+    {
+        self.interceptors.push(Arc::new(Mutex::new(interceptor)));
+        self
+    }
+
+    /// Run the interceptors on `req` and take its [`CallOptions`].
+    fn intercept<T>(
+        &self,
+        req: Request<T>,
+    ) -> Result<(Request<T>, CallOptions), crate::status::Status> {
+        let Request {
+            message,
+            metadata,
+            extensions,
+        } = req;
+        let mut parts = Request {
+            message: (),
+            metadata,
+            extensions,
+        };
+        for interceptor in self.interceptors.iter() {
+            parts = interceptor.lock().unwrap().call(parts)?;
+        }
+        let mut req = parts.map(|_| message);
+        let options = CallOptions::take(&mut req);
+        Ok((req, options))
+    }
+
+    /// Arguments of the call, serialized only when the access log asks for them.
+    fn log_arguments<M: Serialize>(&self, message: &M) -> Option<AccessLogArguments> {
+        self.access_log
+            .as_ref()
+            .filter(|log| log.arguments())
+            .and_then(|_| serde_json::to_value(message).ok())
+            .map(AccessLogArguments)
+    }
+
+    /// Settings of the method called on `path`, see [`MethodConfig`], with
+    /// the overrides of the call.
+    fn method_config(&self, path: &http::uri::PathAndQuery, options: &CallOptions) -> MethodConfig {
+        let (_, method) = dynamic::service_method(path.path());
+        options.apply(self.methods.get(method).cloned().unwrap_or_default())
+    }
+
+    /// Compression of the requests of `method`.
+    fn compression(&self, method: &MethodConfig) -> Option<CompressionEncoding> {
+        method
+            .compression_encoding()
+            .unwrap_or_else(|| self.compression.send_encoding())
+    }
+
+    /// Announce the settings of `method` and of the call to the cluster and
+    /// the provider, and return the deadline of a call started now.
+    fn prepare<B>(
+        &self,
+        request: &mut http::Request<B>,
+        path: &http::uri::PathAndQuery,
+        method: &MethodConfig,
+        options: CallOptions,
+    ) -> Option<Instant> {
+        let headers = request.headers_mut();
+        if let Some(encoding) = self.compression(method) {
+            headers.insert(GRPC_ENCODING, encoding.into_header_value());
         }
+        // a method turning compression off wants plain responses too
+        let accept = match method.compression_encoding() {
+            Some(None) => &[][..],
+            _ => &self.compression.encodings[..],
+        };
+        headers.insert(
+            GRPC_ACCEPT_ENCODING,
+            CompressionEncoding::accept_encoding_value(accept),
+        );
+        self.selector.insert_headers(headers);
+        request.extensions_mut().insert(method.clone());
+        let deadline = self.deadline(request, path, method, &options);
+        request.extensions_mut().insert(options);
+        deadline
+    }
+
+    /// Deadline of a call started now, announced to the provider with the
+    /// `grpc-timeout` header. The timeout of the call wins over a dynamic
+    /// `timeout` of the method, which wins over the one of the method config,
+    /// which wins over the one of the client.
+    fn deadline<B>(
+        &self,
+        request: &mut http::Request<B>,
+        path: &http::uri::PathAndQuery,
+        method: &MethodConfig,
+        options: &CallOptions,
+    ) -> Option<Instant> {
+        let (service, method_name) = dynamic::service_method(path.path());
+        let timeout = options.timeout.or_else(|| {
+            dynamic::dynamic_config()
+                .parameter_as(Side::Consumer, service, method_name, "", TIMEOUT_KEY)
+                .or(method.timeout)
+                .map(Duration::from_millis)
+                .or(self.timeout)
+        })?;
+        if let Ok(value) = HeaderValue::from_str(&format!("{}m", timeout.as_millis())) {
+            request.headers_mut().insert(GRPC_TIMEOUT, value);
+        }
+        Some(Instant::now() + timeout)
     }
 
     pub fn map_request(
@@ -100,24 +279,19 @@ impl TripleClient {
             .insert("user-agent", HeaderValue::from_static("dubbo-rust/0.1.0"));
         req.headers_mut()
             .insert("te", HeaderValue::from_static("trailers"));
-        req.headers_mut().insert(
-            "tri-service-version",
-            HeaderValue::from_static("dubbo-rust/0.1.0"),
-        );
-        req.headers_mut()
-            .insert("tri-service-group", HeaderValue::from_static("cluster"));
+        self.selector.insert_headers(req.headers_mut());
         req.headers_mut().insert(
             "tri-unit-info",
             HeaderValue::from_static("dubbo-rust/0.1.0"),
         );
-        if let Some(_encoding) = self.send_compression_encoding {
+        if let Some(encoding) = self.compression.send_encoding() {
             req.headers_mut()
-                .insert("grpc-encoding", http::HeaderValue::from_static("gzip"));
-            req.headers_mut().insert(
-                "grpc-accept-encoding",
-                http::HeaderValue::from_static("gzip"),
-            );
+                .insert(GRPC_ENCODING, encoding.into_header_value());
         }
+        req.headers_mut().insert(
+            GRPC_ACCEPT_ENCODING,
+            CompressionEncoding::accept_encoding_value(&self.compression.encodings),
+        );
         // const (
         //     TripleContentType    = "application/grpc+proto"
         //     TripleUserAgent      = "grpc-go/1.35.0-dev"
@@ -140,68 +314,115 @@ impl TripleClient {
         mut invocation: RpcInvocation,
     ) -> Result<Response<M2>, crate::status::Status>
     where
-        M1: Message + Send + Sync + 'static + Serialize,
-        M2: Message + Send + Sync + 'static + for<'a> Deserialize<'a> + Default,
+        C: MessageCodec<M1, M2>,
+        M1: Send + Sync + 'static + Serialize,
+        M2: Send + Sync + 'static,
     {
-        let (decoder, encoder): (
-            Box<dyn Decoder<Item = M2, Error = Status> + Send + 'static>,
-            Box<dyn Encoder<Error = Status, Item = M1> + Send + 'static>,
-        ) = get_codec("application/grpc+proto");
+        let (decoder, encoder) = C::codec(C::CONTENT_TYPE);
+        let (req, options) = self.intercept(req)?;
+        let method = self.method_config(&path, &options);
+        let compression = self.compression(&method);
 
         let mt = req.metadata.clone();
+        let arguments = self.log_arguments(&req.message);
 
         let req = req.map(|m| stream::once(future::ready(m)));
         let body_stream = encode(
             encoder,
             req.into_inner().map(Ok),
-            self.send_compression_encoding,
+            compression,
+            self.compression.min_size,
+            method
+                .max_encode_message_size
+                .or(self.max_encode_message_size),
             true,
         )
         .into_stream();
-        let body = hyper::Body::wrap_stream(body_stream);
+        let encode_error = EncodeError::default();
+        let body = hyper::Body::wrap_stream(encode_error.watch(body_stream));
 
         invocation = invocation.with_metadata(mt.clone());
         let mut invoker = self.mk.new_service(invocation);
 
         let mut request = http::Request::builder()
             .header("path", path.to_string())
+            .header(http::header::CONTENT_TYPE, C::CONTENT_TYPE)
             .body(body)
             .unwrap();
 
         for (k, v) in mt.into_headers().iter() {
             request.headers_mut().insert(k, v.to_owned());
         }
+        if let Some(arguments) = arguments {
+            request.extensions_mut().insert(arguments);
+        }
 
-        let response = invoker
-            .call(request)
-            .await
-            .map_err(|err| crate::status::Status::from_error(err.into()));
-
-        match response {
-            Ok(v) => {
-                let resp = v
-                    .map(|body| Decoding::new(body, decoder, self.send_compression_encoding, true));
-                let (mut parts, body) = Response::from_http(resp).into_parts();
-
-                futures_util::pin_mut!(body);
+        let deadline = self.prepare(&mut request, &path, &method, options);
+        request.extensions_mut().insert(UnaryCall);
+        if method.oneway {
+            let call: crate::BoxFuture<_, crate::status::Status> = match self.access_log.clone() {
+                Some(log) => Box::pin(
+                    AccessLogService::consumer(invoker, log)
+                        .call(request)
+                        .map_err(crate::status::Status::from_error),
+                ),
+                None => Box::pin(
+                    invoker
+                        .call(request)
+                        .map_err(crate::status::Status::from_error),
+                ),
+            };
+            tokio::spawn(async move {
+                if let Err(err) = with_deadline(deadline, call).await {
+                    warn!("oneway call failed: {:?}", err);
+                }
+            });
+            return oneway_response(decoder, &path);
+        }
 
-                let message = body.try_next().await?.ok_or_else(|| {
+        let response = with_deadline(deadline, async {
+            match self.access_log.clone() {
+                Some(log) => AccessLogService::consumer(invoker, log).call(request).await,
+                None => invoker.call(request).await,
+            }
+            .map_err(crate::status::Status::from_error)
+        })
+        .await;
+
+        let result = async {
+            let v = response?;
+            // trailers-only responses carry the status in the headers
+            if let Some(status) = failed_status(v.headers()) {
+                return Err(status);
+            }
+            let encoding = CompressionEncoding::from_encoding_header(v.headers())?;
+            let resp = v.map(|body| {
+                Decoding::new(body, decoder, encoding, true)
+                    .with_max_message_size(method.max_message_size.or(self.max_message_size))
+            });
+            let (mut parts, body) = Response::from_http(resp).into_parts();
+
+            futures_util::pin_mut!(body);
+
+            let message = with_deadline(deadline, body.try_next())
+                .await?
+                .ok_or_else(|| {
                     crate::status::Status::new(
                         crate::status::Code::Internal,
                         "Missing response message.".to_string(),
                     )
                 })?;
 
-                if let Some(trailers) = body.trailer().await? {
-                    let mut h = parts.into_headers();
-                    h.extend(trailers.into_headers());
-                    parts = Metadata::from_headers(h);
-                }
-
-                Ok(Response::from_parts(parts, message))
+            if let Some(trailers) = body.trailer().await? {
+                let mut h = parts.into_headers();
+                h.extend(trailers.into_headers());
+                parts = Metadata::from_headers(h);
             }
-            Err(err) => Err(err),
+
+            Ok(Response::from_parts(parts, message))
         }
+        .await;
+        result.map_err(|err| encode_error.or(err))
     }
 
     pub async fn bidi_streaming<M1, M2>(
@@ -211,21 +432,25 @@ impl TripleClient {
         mut invocation: RpcInvocation,
     ) -> Result<Response<Decoding<M2>>, crate::status::Status>
     where
-        M1: Message + Send + Sync + 'static + Serialize,
-        M2: Message + Send + Sync + 'static + for<'a> Deserialize<'a> + Default,
+        C: MessageCodec<M1, M2>,
+        M1: Send + Sync + 'static + Serialize,
+        M2: Send + Sync + 'static,
     {
-        let (decoder, encoder): (
-            Box<dyn Decoder<Item = M2, Error = Status> + Send + 'static>,
-            Box<dyn Encoder<Error = Status, Item = M1> + Send + 'static>,
-        ) = get_codec("application/grpc+proto");
+        let (decoder, encoder) = C::codec(C::CONTENT_TYPE);
+        let (req, options) = self.intercept(req.into_streaming_request())?;
+        let method = self.method_config(&path, &options);
+        let compression = self.compression(&method);
 
-        let req = req.into_streaming_request();
         let mt = req.metadata.clone();
 
         let en = encode(
             encoder,
             req.into_inner().map(Ok),
-            self.send_compression_encoding,
+            compression,
+            self.compression.min_size,
+            method
+                .max_encode_message_size
+                .or(self.max_encode_message_size),
             true,
         )
         .into_stream();
@@ -236,6 +461,7 @@ impl TripleClient {
 
         let mut request = http::Request::builder()
             .header("path", path.to_string())
+            .header(http::header::CONTENT_TYPE, C::CONTENT_TYPE)
             .body(body)
             .unwrap();
 
@@ -243,15 +469,27 @@ impl TripleClient {
             request.headers_mut().insert(k, v.to_owned());
         }
 
-        let response = invoker
-            .call(request)
-            .await
-            .map_err(|err| crate::status::Status::from_error(err.into()));
+        let deadline = self.prepare(&mut request, &path, &method, options);
+        let response = with_deadline(deadline, async {
+            match self.access_log.clone() {
+                Some(log) => AccessLogService::consumer(invoker, log).call(request).await,
+                None => invoker.call(request).await,
+            }
+            .map_err(crate::status::Status::from_error)
+        })
+        .await;
 
         match response {
             Ok(v) => {
-                let resp = v
-                    .map(|body| Decoding::new(body, decoder, self.send_compression_encoding, true));
+                // trailers-only responses carry the status in the headers
+                if let Some(status) = failed_status(v.headers()) {
+                    return Err(status);
+                }
+                let encoding = CompressionEncoding::from_encoding_header(v.headers())?;
+                let resp = v.map(|body| {
+                    Decoding::new(body, decoder, encoding, true)
+                        .with_max_message_size(method.max_message_size.or(self.max_message_size))
+                });
 
                 Ok(Response::from_http(resp))
             }
@@ -266,30 +504,36 @@ impl TripleClient {
         mut invocation: RpcInvocation,
     ) -> Result<Response<M2>, crate::status::Status>
     where
-        M1: Message + Send + Sync + 'static + Serialize,
-        M2: Message + Send + Sync + 'static + for<'a> Deserialize<'a> + Default,
+        C: MessageCodec<M1, M2>,
+        M1: Send + Sync + 'static + Serialize,
+        M2: Send + Sync + 'static,
     {
-        let (decoder, encoder): (
-            Box<dyn Decoder<Item = M2, Error = Status> + Send + 'static>,
-            Box<dyn Encoder<Error = Status, Item = M1> + Send + 'static>,
-        ) = get_codec("application/grpc+proto");
-        let req = req.into_streaming_request();
+        let (decoder, encoder) = C::codec(C::CONTENT_TYPE);
+        let (req, options) = self.intercept(req.into_streaming_request())?;
+        let method = self.method_config(&path, &options);
+        let compression = self.compression(&method);
         let mt = req.metadata.clone();
 
         let en = encode(
             encoder,
             req.into_inner().map(Ok),
-            self.send_compression_encoding,
+            compression,
+            self.compression.min_size,
+            method
+                .max_encode_message_size
+                .or(self.max_encode_message_size),
             true,
         )
         .into_stream();
-        let body = hyper::Body::wrap_stream(en);
+        let encode_error = EncodeError::default();
+        let body = hyper::Body::wrap_stream(encode_error.watch(en));
 
         invocation = invocation.with_metadata(mt.clone());
         let mut invoker = self.mk.new_service(invocation);
 
         let mut request = http::Request::builder()
             .header("path", path.to_string())
+            .header(http::header::CONTENT_TYPE, C::CONTENT_TYPE)
             .body(body)
             .unwrap();
 
@@ -297,36 +541,48 @@ impl TripleClient {
             request.headers_mut().insert(k, v.to_owned());
         }
 
-        let response = invoker
-            .call(request)
-            .await
-            .map_err(|err| crate::status::Status::from_error(err.into()));
-
-        match response {
-            Ok(v) => {
-                let resp = v
-                    .map(|body| Decoding::new(body, decoder, self.send_compression_encoding, true));
-                let (mut parts, body) = Response::from_http(resp).into_parts();
-
-                futures_util::pin_mut!(body);
-
-                let message = body.try_next().await?.ok_or_else(|| {
-                    crate::status::Status::new(
-                        crate::status::Code::Internal,
-                        "Missing response message.".to_string(),
-                    )
-                })?;
-
-                if let Some(trailers) = body.trailer().await? {
-                    let mut h = parts.into_headers();
-                    h.extend(trailers.into_headers());
-                    parts = Metadata::from_headers(h);
-                }
-
-                Ok(Response::from_parts(parts, message))
+        let deadline = self.prepare(&mut request, &path, &method, options);
+        let response = with_deadline(deadline, async {
+            match self.access_log.clone() {
+                Some(log) => AccessLogService::consumer(invoker, log).call(request).await,
+                None => invoker.call(request).await,
             }
-            Err(err) => Err(err),
+            .map_err(crate::status::Status::from_error)
+        })
+        .await;
+
+        let result = async {
+            let v = response?;
+            // trailers-only responses carry the status in the headers
+            if let Some(status) = failed_status(v.headers()) {
+                return Err(status);
+            }
+            let encoding = CompressionEncoding::from_encoding_header(v.headers())?;
+            let resp = v.map(|body| {
+                Decoding::new(body, decoder, encoding, true)
+                    .with_max_message_size(method.max_message_size.or(self.max_message_size))
+            });
+            let (mut parts, body) = Response::from_http(resp).into_parts();
+
+            futures_util::pin_mut!(body);
+
+            let message = body.try_next().await?.ok_or_else(|| {
+                crate::status::Status::new(
+                    crate::status::Code::Internal,
+                    "Missing response message.".to_string(),
+                )
+            })?;
+
+            if let Some(trailers) = body.trailer().await? {
+                let mut h = parts.into_headers();
+                h.extend(trailers.into_headers());
+                parts = Metadata::from_headers(h);
+            }
+
+            Ok(Response::from_parts(parts, message))
         }
+        .await;
+        result.map_err(|err| encode_error.or(err))
     }
 
     pub async fn server_streaming<M1, M2>(
@@ -336,21 +592,27 @@ impl TripleClient {
         mut invocation: RpcInvocation,
     ) -> Result<Response<Decoding<M2>>, crate::status::Status>
     where
-        M1: Message + Send + Sync + 'static + Serialize,
-        M2: Message + Send + Sync + 'static + for<'a> Deserialize<'a> + Default,
+        C: MessageCodec<M1, M2>,
+        M1: Send + Sync + 'static + Serialize,
+        M2: Send + Sync + 'static,
     {
-        let (decoder, encoder): (
-            Box<dyn Decoder<Item = M2, Error = Status> + Send + 'static>,
-            Box<dyn Encoder<Error = Status, Item = M1> + Send + 'static>,
-        ) = get_codec("application/grpc+proto");
+        let (decoder, encoder) = C::codec(C::CONTENT_TYPE);
+        let (req, options) = self.intercept(req)?;
+        let method = self.method_config(&path, &options);
+        let compression = self.compression(&method);
 
+        let arguments = self.log_arguments(&req.message);
         let req = req.map(|m| stream::once(future::ready(m)));
         let mt = req.metadata.clone();
 
         let en = encode(
             encoder,
             req.into_inner().map(Ok),
-            self.send_compression_encoding,
+            compression,
+            self.compression.min_size,
+            method
+                .max_encode_message_size
+                .or(self.max_encode_message_size),
             true,
         )
         .into_stream();
@@ -361,22 +623,38 @@ impl TripleClient {
 
         let mut request = http::Request::builder()
             .header("path", path.to_string())
+            .header(http::header::CONTENT_TYPE, C::CONTENT_TYPE)
             .body(body)
             .unwrap();
 
         for (k, v) in mt.into_headers().iter() {
             request.headers_mut().insert(k, v.to_owned());
         }
+        if let Some(arguments) = arguments {
+            request.extensions_mut().insert(arguments);
+        }
 
-        let response = invoker
-            .call(request)
-            .await
-            .map_err(|err| crate::status::Status::from_error(err.into()));
+        let deadline = self.prepare(&mut request, &path, &method, options);
+        let response = with_deadline(deadline, async {
+            match self.access_log.clone() {
+                Some(log) => AccessLogService::consumer(invoker, log).call(request).await,
+                None => invoker.call(request).await,
+            }
+            .map_err(crate::status::Status::from_error)
+        })
+        .await;
 
         match response {
             Ok(v) => {
-                let resp = v
-                    .map(|body| Decoding::new(body, decoder, self.send_compression_encoding, true));
+                // trailers-only responses carry the status in the headers
+                if let Some(status) = failed_status(v.headers()) {
+                    return Err(status);
+                }
+                let encoding = CompressionEncoding::from_encoding_header(v.headers())?;
+                let resp = v.map(|body| {
+                    Decoding::new(body, decoder, encoding, true)
+                        .with_max_message_size(method.max_message_size.or(self.max_message_size))
+                });
 
                 Ok(Response::from_http(resp))
             }
@@ -385,25 +663,72 @@ impl TripleClient {
     }
 }
 
-pub fn get_codec<M1, M2>(
-    content_type: &str,
-) -> (
-    Box<dyn Decoder<Item = M2, Error = Status> + Send + 'static>,
-    Box<dyn Encoder<Error = Status, Item = M1> + Send + 'static>,
-)
+pub fn get_codec<M1, M2>(content_type: &str) -> (BoxDecoder<M2>, BoxEncoder<M1>)
 where
     M1: Message + Send + Sync + 'static + Serialize,
     M2: Message + Send + Sync + 'static + for<'a> Deserialize<'a> + Default,
 {
-    //Determine whether to use JSON as the serialization method.
-    match content_type.ends_with("json") {
-        true => {
-            let mut codec = SerdeCodec::<M1, M2>::default();
-            (Box::new(codec.decoder()), Box::new(codec.encoder()))
-        }
-        false => {
-            let mut codec = ProstCodec::<M1, M2>::default();
-            (Box::new(codec.decoder()), Box::new(codec.encoder()))
-        }
+    DefaultCodec::codec(content_type)
+}
+
+const GRPC_TIMEOUT: &str = "grpc-timeout";
+
+/// Error ending the stream of request messages, such as a message over the
+/// size limit. It breaks the request body, so the call fails with it rather
+/// than with the broken stream.
+#[derive(Clone, Default)]
+struct EncodeError(Arc<Mutex<Option<crate::status::Status>>>);
+
+impl EncodeError {
+    fn watch<S>(&self, stream: S) -> impl Stream<Item = Result<Bytes, crate::status::Status>>
+    where
+        S: Stream<Item = Result<Bytes, crate::status::Status>>,
+    {
+        let slot = self.clone();
+        stream.inspect_err(move |err| {
+            slot.0.lock().unwrap().get_or_insert_with(|| err.clone());
+        })
+    }
+
+    fn or(&self, status: crate::status::Status) -> crate::status::Status {
+        self.0.lock().unwrap().take().unwrap_or(status)
+    }
+}
+
+/// Response of a oneway call, the message with an empty encoding such as a
+/// default protobuf message.
+fn oneway_response<M2>(
+    mut decoder: BoxDecoder<M2>,
+    path: &http::uri::PathAndQuery,
+) -> Result<Response<M2>, crate::status::Status> {
+    let mut buf = BytesMut::new();
+    match decoder.decode(&mut DecodeBuf::new(&mut buf, 0)) {
+        Ok(Some(message)) => Ok(Response::new(message)),
+        _ => Err(crate::status::Status::new(
+            crate::status::Code::FailedPrecondition,
+            format!(
+                "the response of oneway method {} has no empty encoding",
+                path
+            ),
+        )),
+    }
+}
+
+/// Fails with `DeadlineExceeded` if `fut` is not done by the deadline. For
+/// streaming calls the deadline only covers the response headers.
+async fn with_deadline<T, F>(deadline: Option<Instant>, fut: F) -> Result<T, crate::status::Status>
+where
+    F: Future<Output = Result<T, crate::status::Status>>,
+{
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, fut)
+            .await
+            .unwrap_or_else(|_| {
+                Err(crate::status::Status::new(
+                    crate::status::Code::DeadlineExceeded,
+                    "Deadline exceeded.".to_string(),
+                ))
+            }),
+        None => fut.await,
     }
 }
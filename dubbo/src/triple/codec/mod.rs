@@ -17,13 +17,86 @@
 
 pub mod buffer;
 pub mod prost;
+pub mod raw;
 pub mod serde_codec;
 
 use std::io;
 
+use serde::{de::DeserializeOwned, Serialize};
+
 pub use self::buffer::{DecodeBuf, EncodeBuf};
+use self::{
+    prost::ProstCodec,
+    raw::{RawCodec, RawMessage},
+    serde_codec::SerdeCodec,
+};
 use crate::status::Status;
 
+pub type BoxEncoder<T> = Box<dyn Encoder<Item = T, Error = Status> + Send + 'static>;
+pub type BoxDecoder<T> = Box<dyn Decoder<Item = T, Error = Status> + Send + 'static>;
+
+/// Serialization of the messages of a call, `E` being encoded and `D`
+/// decoded.
+pub trait MessageCodec<E, D> {
+    /// Content type of the requests sent by a client.
+    const CONTENT_TYPE: &'static str;
+
+    /// Codec for a call of the given content type.
+    fn codec(content_type: &str) -> (BoxDecoder<D>, BoxEncoder<E>);
+}
+
+/// Protobuf, or json for `application/grpc+json` calls. Used by the code
+/// generated from `.proto` files.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultCodec;
+
+impl<E, D> MessageCodec<E, D> for DefaultCodec
+where
+    E: ::prost::Message + Serialize + Send + Sync + 'static,
+    D: ::prost::Message + DeserializeOwned + Default + Send + Sync + 'static,
+{
+    const CONTENT_TYPE: &'static str = "application/grpc+proto";
+
+    fn codec(content_type: &str) -> (BoxDecoder<D>, BoxEncoder<E>) {
+        //Determine whether to use JSON as the serialization method.
+        match content_type.ends_with("json") {
+            true => JsonCodec::codec(content_type),
+            false => {
+                let mut codec = ProstCodec::<E, D>::default();
+                (Box::new(codec.decoder()), Box::new(codec.encoder()))
+            }
+        }
+    }
+}
+
+/// Json for any serde type, whatever the content type of the call. Used by
+/// services defined with `#[dubbo::service]`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonCodec;
+
+impl<E, D> MessageCodec<E, D> for JsonCodec
+where
+    E: Serialize + Send + 'static,
+    D: DeserializeOwned + Send + 'static,
+{
+    const CONTENT_TYPE: &'static str = "application/grpc+json";
+
+    fn codec(_content_type: &str) -> (BoxDecoder<D>, BoxEncoder<E>) {
+        let mut codec = SerdeCodec::<E, D>::default();
+        (Box::new(codec.decoder()), Box::new(codec.encoder()))
+    }
+}
+
+/// Messages serialized by the caller, whatever the content type of the
+/// call. Used by generic calls, see [`crate::generic`].
+impl MessageCodec<RawMessage, RawMessage> for RawCodec {
+    const CONTENT_TYPE: &'static str = "application/grpc+proto";
+
+    fn codec(_content_type: &str) -> (BoxDecoder<RawMessage>, BoxEncoder<RawMessage>) {
+        (Box::new(RawCodec), Box::new(RawCodec))
+    }
+}
+
 pub trait Codec {
     /// The encodable message.
     type Encode: Send + 'static;
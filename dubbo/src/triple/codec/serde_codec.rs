@@ -65,9 +65,9 @@ impl<T: Serialize> Encoder for SerdeEncoder<T> {
 
     fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
         item.serialize(&mut serde_json::Serializer::new(dst.writer()))
-            .expect("failed to searialize");
-
-        Ok(())
+            .map_err(|err| {
+                crate::status::Status::new(crate::status::Code::Internal, err.to_string())
+            })
     }
 }
 
@@ -84,6 +84,8 @@ impl<'a, U: Deserialize<'a>> Decoder for SerdeDecoder<U> {
         src.copy_to_slice(&mut msg);
 
         let mut de = serde_json::Deserializer::from_reader(msg.reader());
-        Ok(Some(U::deserialize(&mut de).unwrap()))
+        U::deserialize(&mut de).map(Some).map_err(|err| {
+            crate::status::Status::new(crate::status::Code::InvalidArgument, err.to_string())
+        })
     }
 }
@@ -15,51 +15,191 @@
  * limitations under the License.
  */
 
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+};
 
 use bytes::{Buf, BufMut, BytesMut};
 use flate2::{
-    read::{GzDecoder, GzEncoder},
+    read::{GzDecoder, GzEncoder, ZlibDecoder, ZlibEncoder},
     Compression,
 };
 use lazy_static::lazy_static;
 
+use crate::status::{Code, Status};
+
 pub const GRPC_ACCEPT_ENCODING: &str = "grpc-accept-encoding";
 pub const GRPC_ENCODING: &str = "grpc-encoding";
 
-#[derive(Debug, Clone, Copy)]
+/// Name of the encoding sending messages uncompressed.
+pub const IDENTITY: &str = "identity";
+
+/// Names of every supported encoding, `identity` last.
+pub const SUPPORTED_COMPRESSIONS: &[&str] = &["gzip", "zstd", "deflate", "snappy", IDENTITY];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CompressionEncoding {
     Gzip,
+    Zstd,
+    /// zlib wrapped deflate, as sent by the java implementation
+    Deflate,
+    /// raw snappy blocks, without the framing format
+    Snappy,
 }
 
 lazy_static! {
+    /// Encodings by name, `identity` mapping to no compression.
     pub static ref COMPRESSIONS: HashMap<String, Option<CompressionEncoding>> = {
         let mut v = HashMap::new();
-        v.insert("gzip".to_string(), Some(CompressionEncoding::Gzip));
+        for encoding in CompressionEncoding::ALL {
+            v.insert(encoding.name().to_string(), Some(encoding));
+        }
+        v.insert(IDENTITY.to_string(), None);
         v
     };
 }
 
 impl CompressionEncoding {
+    /// Every encoding, in the order of preference of a client.
+    pub const ALL: [CompressionEncoding; 4] = [
+        CompressionEncoding::Gzip,
+        CompressionEncoding::Zstd,
+        CompressionEncoding::Deflate,
+        CompressionEncoding::Snappy,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CompressionEncoding::Gzip => "gzip",
+            CompressionEncoding::Zstd => "zstd",
+            CompressionEncoding::Deflate => "deflate",
+            CompressionEncoding::Snappy => "snappy",
+        }
+    }
+
+    /// First encoding of the `grpc-accept-encoding` of the peer that is also
+    /// supported.
     pub fn from_accept_encoding(header: &http::HeaderMap) -> Option<CompressionEncoding> {
+        Self::negotiate(header, &Self::ALL)
+    }
+
+    /// First encoding of the `grpc-accept-encoding` of the peer that is also
+    /// in `enabled`, `None` when there is no such encoding.
+    pub fn negotiate(
+        header: &http::HeaderMap,
+        enabled: &[CompressionEncoding],
+    ) -> Option<CompressionEncoding> {
         let accept_encoding = header.get(GRPC_ACCEPT_ENCODING)?;
         let encodings = accept_encoding.to_str().ok()?;
 
         encodings
-            .trim()
             .split(',')
             .map(|s| s.trim())
-            .into_iter()
-            .find_map(|s| match s {
-                "gzip" => Some(CompressionEncoding::Gzip),
-                _ => None,
-            })
+            .find_map(|s| enabled.iter().copied().find(|e| e.name() == s))
+    }
+
+    /// Encoding of the messages given by the `grpc-encoding` header, failing
+    /// with `Unimplemented` on encodings that are not supported.
+    pub fn from_encoding_header(
+        header: &http::HeaderMap,
+    ) -> Result<Option<CompressionEncoding>, Status> {
+        let encoding = match header.get(GRPC_ENCODING) {
+            Some(val) => val.to_str().unwrap_or_default(),
+            None => return Ok(None),
+        };
+        match COMPRESSIONS.get(encoding) {
+            Some(val) => Ok(*val),
+            None => Err(Status::new(
+                Code::Unimplemented,
+                format!(
+                    "grpc-encoding {:?} is not supported, supported: {}",
+                    encoding,
+                    SUPPORTED_COMPRESSIONS.join(",")
+                ),
+            )),
+        }
+    }
+
+    /// `grpc-accept-encoding` announcing `encodings`, `identity` when empty.
+    pub fn accept_encoding_value(encodings: &[CompressionEncoding]) -> http::HeaderValue {
+        if encodings.is_empty() {
+            return http::HeaderValue::from_static(IDENTITY);
+        }
+        let names: Vec<_> = encodings.iter().map(|e| e.name()).collect();
+        http::HeaderValue::from_str(&names.join(",")).unwrap()
     }
 
     pub fn into_header_value(self) -> http::HeaderValue {
-        match self {
-            CompressionEncoding::Gzip => http::HeaderValue::from_static("gzip"),
+        http::HeaderValue::from_static(self.name())
+    }
+}
+
+/// Encodings a side compresses its messages with and the size in bytes below
+/// which messages are sent uncompressed.
+///
+/// Clients compress requests with the first encoding and accept responses
+/// in any of them. Servers answer in the first encoding accepted by the
+/// client that is also in `encodings`, and read it from the request
+/// extensions to configure a service or a method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressionConfig {
+    pub encodings: Vec<CompressionEncoding>,
+    pub min_size: usize,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        CompressionConfig {
+            encodings: CompressionEncoding::ALL.to_vec(),
+            min_size: 0,
+        }
+    }
+}
+
+impl CompressionConfig {
+    /// Config of the comma separated encoding names of `encodings`, `identity`
+    /// alone turning compression off. Fails with the first unknown name.
+    pub fn parse(encodings: &str) -> Result<Self, String> {
+        let mut parsed = Vec::new();
+        for name in encodings.split(',').map(str::trim) {
+            match COMPRESSIONS.get(name) {
+                Some(Some(encoding)) if !parsed.contains(encoding) => parsed.push(*encoding),
+                Some(_) => {}
+                None => return Err(name.to_string()),
+            }
         }
+        Ok(CompressionConfig {
+            encodings: parsed,
+            ..Default::default()
+        })
+    }
+
+    /// Config of the `compression` and `compression_min_size` settings of a
+    /// service or a reference, `None` when neither is set.
+    pub fn from_settings(encodings: &str, min_size: Option<usize>) -> Result<Option<Self>, String> {
+        let config = match encodings {
+            "" if min_size.is_none() => return Ok(None),
+            "" => CompressionConfig::default(),
+            encodings => CompressionConfig::parse(encodings)?,
+        };
+        Ok(Some(match min_size {
+            Some(min_size) => config.min_size(min_size),
+            None => config,
+        }))
+    }
+
+    pub fn encodings(self, encodings: Vec<CompressionEncoding>) -> Self {
+        Self { encodings, ..self }
+    }
+
+    pub fn min_size(self, min_size: usize) -> Self {
+        Self { min_size, ..self }
+    }
+
+    /// Encoding of the messages sent by a client.
+    pub fn send_encoding(&self) -> Option<CompressionEncoding> {
+        self.encodings.first().copied()
     }
 }
 
@@ -71,40 +211,93 @@ pub fn compress(
 ) -> Result<(), std::io::Error> {
     dst.reserve(len);
 
+    let src = src.split_to(len);
+    let mut dst_writer = dst.writer();
     match encoding {
         CompressionEncoding::Gzip => {
             let mut en = GzEncoder::new(src.reader(), Compression::default());
-
-            let mut dst_writer = dst.writer();
-
             std::io::copy(&mut en, &mut dst_writer)?;
         }
+        CompressionEncoding::Zstd => {
+            zstd::stream::copy_encode(src.reader(), &mut dst_writer, 0)?;
+        }
+        CompressionEncoding::Deflate => {
+            let mut en = ZlibEncoder::new(src.reader(), Compression::default());
+            std::io::copy(&mut en, &mut dst_writer)?;
+        }
+        CompressionEncoding::Snappy => {
+            let compressed = snap::raw::Encoder::new().compress_vec(&src)?;
+            dst_writer.write_all(&compressed)?;
+        }
     }
 
     Ok(())
 }
 
+/// A decompressed message larger than the limit of its receiver, the error
+/// of [`decompress_limited`].
+#[derive(Debug, thiserror::Error)]
+#[error("decompressed message exceeds the limit of {0} bytes")]
+pub struct MessageTooLarge(pub usize);
+
+/// Decompress the first `len` bytes of `src` into `dst`.
 pub fn decompress(
     encoding: CompressionEncoding,
     src: &mut BytesMut,
     dst: &mut BytesMut,
     len: usize,
 ) -> Result<(), std::io::Error> {
-    let capacity = len * 2;
+    decompress_limited(encoding, src, dst, len, usize::MAX)
+}
+
+/// Decompress the first `len` bytes of `src` into `dst`, failing with
+/// [`MessageTooLarge`] as soon as more than `max_size` bytes come out.
+pub fn decompress_limited(
+    encoding: CompressionEncoding,
+    src: &mut BytesMut,
+    dst: &mut BytesMut,
+    len: usize,
+    max_size: usize,
+) -> Result<(), std::io::Error> {
+    let capacity = len.saturating_mul(2).min(max_size);
     dst.reserve(capacity);
 
+    // the decoders buffer their input, so they must not see the next message
+    let src = src.split_to(len);
+    let start = dst.len();
+    let limit = (max_size as u64).saturating_add(1);
+    let mut dst_writer = dst.writer();
     match encoding {
         CompressionEncoding::Gzip => {
-            let mut de = GzDecoder::new(src.reader());
-
-            let mut dst_writer = dst.writer();
-
+            let mut de = GzDecoder::new(src.reader()).take(limit);
+            std::io::copy(&mut de, &mut dst_writer)?;
+        }
+        CompressionEncoding::Zstd => {
+            let mut de = zstd::stream::read::Decoder::new(src.reader())?.take(limit);
+            std::io::copy(&mut de, &mut dst_writer)?;
+        }
+        CompressionEncoding::Deflate => {
+            let mut de = ZlibDecoder::new(src.reader()).take(limit);
             std::io::copy(&mut de, &mut dst_writer)?;
         }
+        CompressionEncoding::Snappy => {
+            if snap::raw::decompress_len(&src)? > max_size {
+                return Err(too_large(max_size));
+            }
+            let decompressed = snap::raw::Decoder::new().decompress_vec(&src)?;
+            dst_writer.write_all(&decompressed)?;
+        }
+    }
+    if dst_writer.get_ref().len() - start > max_size {
+        return Err(too_large(max_size));
     }
     Ok(())
 }
 
+fn too_large(max_size: usize) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, MessageTooLarge(max_size))
+}
+
 #[test]
 fn test_compress() {
     let mut src = BytesMut::with_capacity(super::consts::BUFFER_SIZE);
@@ -122,3 +315,83 @@ fn test_compress() {
 
     println!("src: {:?}, dst: {:?}", dst, de_dst);
 }
+
+#[test]
+fn test_encodings() {
+    for encoding in CompressionEncoding::ALL {
+        let mut src = BytesMut::from(&b"test compress test compress"[..]);
+        let len = src.len();
+        let mut dst = BytesMut::new();
+        compress(encoding, &mut src, &mut dst, len).unwrap();
+
+        // the next message stays in the buffer
+        let compressed_len = dst.len();
+        dst.put(&b"next"[..]);
+        let mut de_dst = BytesMut::new();
+        decompress(encoding, &mut dst, &mut de_dst, compressed_len).unwrap();
+        assert_eq!(
+            &de_dst[..],
+            b"test compress test compress",
+            "{:?}",
+            encoding
+        );
+        assert_eq!(&dst[..], b"next");
+    }
+
+    let mut headers = http::HeaderMap::new();
+    headers.insert(
+        GRPC_ACCEPT_ENCODING,
+        http::HeaderValue::from_static("br, snappy,gzip"),
+    );
+    assert_eq!(
+        CompressionEncoding::from_accept_encoding(&headers),
+        Some(CompressionEncoding::Snappy)
+    );
+    assert_eq!(
+        CompressionEncoding::negotiate(&headers, &[CompressionEncoding::Gzip]),
+        Some(CompressionEncoding::Gzip)
+    );
+    assert_eq!(
+        CompressionEncoding::negotiate(&headers, &[CompressionEncoding::Zstd]),
+        None
+    );
+
+    headers.insert(GRPC_ENCODING, http::HeaderValue::from_static("br"));
+    let status = CompressionEncoding::from_encoding_header(&headers).unwrap_err();
+    assert_eq!(status.code(), Code::Unimplemented);
+    assert!(status
+        .message()
+        .ends_with("gzip,zstd,deflate,snappy,identity"));
+
+    let config = CompressionConfig::parse("zstd, identity").unwrap();
+    assert_eq!(config.encodings, vec![CompressionEncoding::Zstd]);
+    assert!(CompressionConfig::parse("identity")
+        .unwrap()
+        .encodings
+        .is_empty());
+    assert_eq!(CompressionConfig::parse("gzip,br"), Err("br".to_string()));
+}
+
+#[test]
+fn test_decompress_limited() {
+    for encoding in CompressionEncoding::ALL {
+        let mut src = BytesMut::from(&[0u8; 4096][..]);
+        let mut dst = BytesMut::new();
+        compress(encoding, &mut src, &mut dst, 4096).unwrap();
+
+        let len = dst.len();
+        let mut de_dst = BytesMut::new();
+        let err =
+            decompress_limited(encoding, &mut dst.clone(), &mut de_dst, len, 1024).unwrap_err();
+        assert!(
+            err.get_ref().unwrap().is::<MessageTooLarge>(),
+            "{:?}",
+            encoding
+        );
+        assert!(de_dst.len() <= 1025);
+
+        let mut de_dst = BytesMut::new();
+        decompress_limited(encoding, &mut dst, &mut de_dst, len, 4096).unwrap();
+        assert_eq!(de_dst.len(), 4096);
+    }
+}
@@ -22,7 +22,7 @@ use bytes::{Buf, BufMut, Bytes, BytesMut};
 use futures_util::{future, ready, Stream};
 use http_body::Body;
 
-use super::compression::{decompress, CompressionEncoding};
+use super::compression::{decompress_limited, CompressionEncoding, MessageTooLarge};
 use crate::{
     invocation::Metadata,
     triple::codec::{DecodeBuf, Decoder},
@@ -30,6 +30,14 @@ use crate::{
 
 type BoxBody = http_body::combinators::UnsyncBoxBody<Bytes, crate::status::Status>;
 
+/// Largest message in bytes decoded unless configured otherwise.
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+
+/// Largest message in bytes the handler of a request accepts, in the request
+/// extensions.
+#[derive(Debug, Clone, Copy)]
+pub struct MaxMessageSize(pub usize);
+
 pub struct Decoding<T> {
     state: State,
     body: BoxBody,
@@ -39,6 +47,7 @@ pub struct Decoding<T> {
     compress: Option<CompressionEncoding>,
     decompress_buf: BytesMut,
     decode_as_grpc: bool,
+    max_message_size: usize,
 }
 
 #[derive(PartialEq)]
@@ -78,6 +87,59 @@ impl<T> Decoding<T> {
             compress,
             decompress_buf: BytesMut::new(),
             decode_as_grpc,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+        }
+    }
+
+    /// Fail with `ResourceExhausted` on messages larger than
+    /// `max_message_size` bytes, before buffering them. `None` keeps the
+    /// limit of [`DEFAULT_MAX_MESSAGE_SIZE`].
+    pub fn with_max_message_size(self, max_message_size: Option<usize>) -> Self {
+        Self {
+            max_message_size: max_message_size.unwrap_or(self.max_message_size),
+            ..self
+        }
+    }
+
+    fn check_message_size(&mut self, len: usize) -> Result<(), crate::status::Status> {
+        if len <= self.max_message_size {
+            return Ok(());
+        }
+        self.state = State::Error;
+        Err(crate::status::Status::new(
+            crate::status::Code::ResourceExhausted,
+            format!(
+                "message of {} bytes exceeds the limit of {} bytes",
+                len, self.max_message_size
+            ),
+        ))
+    }
+
+    /// Decompress the `len` bytes at the front of the buffer into
+    /// `decompress_buf`, within the size limit.
+    fn decompress(
+        &mut self,
+        compress: CompressionEncoding,
+        len: usize,
+    ) -> Result<(), crate::status::Status> {
+        self.decompress_buf.clear();
+        let result = decompress_limited(
+            compress,
+            &mut self.buf,
+            &mut self.decompress_buf,
+            len,
+            self.max_message_size,
+        );
+        match result {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                self.state = State::Error;
+                let code = match err.get_ref().map(|err| err.is::<MessageTooLarge>()) {
+                    Some(true) => crate::status::Code::ResourceExhausted,
+                    _ => crate::status::Code::Internal,
+                };
+                Err(crate::status::Status::new(code, err.to_string()))
+            }
         }
     }
 
@@ -95,48 +157,31 @@ impl<T> Decoding<T> {
         }
         // while self.message().await?.is_some() {}
 
-        let trailer = future::poll_fn(|cx| Pin::new(&mut self.body).poll_trailers(cx)).await;
-        trailer.map(|data| data.map(Metadata::from_headers))
+        let trailer = future::poll_fn(|cx| Pin::new(&mut self.body).poll_trailers(cx)).await?;
+        if let Some(status) = trailer.as_ref().and_then(failed_status) {
+            return Err(status);
+        }
+        Ok(trailer.map(Metadata::from_headers))
     }
 
+    /// Decode the whole body of a call made without grpc framing as a single
+    /// message, once the body is fully read.
     pub fn decode_http(&mut self) -> Result<Option<T>, crate::status::Status> {
-        if self.state == State::ReadHeader {
-            self.state = State::ReadHttpBody;
+        if self.state != State::ReadHttpBody {
             return Ok(None);
         }
-        if let State::ReadHttpBody = self.state {
-            if self.buf.is_empty() {
-                return Ok(None);
-            }
-            match self.compress {
-                None => self.decompress_buf = self.buf.clone(),
-                Some(compress) => {
-                    let len = self.buf.len();
-                    if let Err(err) =
-                        decompress(compress, &mut self.buf, &mut self.decompress_buf, len)
-                    {
-                        return Err(crate::status::Status::new(
-                            crate::status::Code::Internal,
-                            err.to_string(),
-                        ));
-                    }
-                }
+        self.state = State::ReadHeader;
+        let len = self.buf.len();
+        self.check_message_size(len)?;
+        let mut buf = match self.compress {
+            None => self.buf.split(),
+            Some(compress) => {
+                self.decompress(compress, len)?;
+                self.decompress_buf.split()
             }
-            let len = self.decompress_buf.len();
-            let decoding_result = self
-                .decoder
-                .decode(&mut DecodeBuf::new(&mut self.decompress_buf, len));
-
-            return match decoding_result {
-                Ok(Some(r)) => {
-                    self.state = State::ReadHeader;
-                    Ok(Some(r))
-                }
-                Ok(None) => Ok(None),
-                Err(err) => Err(err),
-            };
-        }
-        Ok(None)
+        };
+        let len = buf.len();
+        self.decoder.decode(&mut DecodeBuf::new(&mut buf, len))
     }
 
     pub fn decode_grpc(&mut self) -> Result<Option<T>, crate::status::Status> {
@@ -169,7 +214,9 @@ impl<T> Decoding<T> {
                 }
             };
             let len = self.buf.get_u32() as usize;
-            self.buf.reserve(len as usize);
+            // the buffer grows as the message arrives, a length prefix alone
+            // allocates nothing
+            self.check_message_size(len)?;
 
             self.state = State::ReadBody { len, is_compressed }
         }
@@ -180,18 +227,7 @@ impl<T> Decoding<T> {
             }
 
             let decoding_result = if is_compressed {
-                self.decompress_buf.clear();
-                if let Err(err) = decompress(
-                    self.compress.unwrap(),
-                    &mut self.buf,
-                    &mut self.decompress_buf,
-                    len,
-                ) {
-                    return Err(crate::status::Status::new(
-                        crate::status::Code::Internal,
-                        err.to_string(),
-                    ));
-                }
+                self.decompress(self.compress.unwrap(), len)?;
 
                 let decompress_len = self.decompress_buf.len();
                 self.decoder.decode(&mut DecodeBuf::new(
@@ -224,6 +260,13 @@ impl<T> Decoding<T> {
     }
 }
 
+/// The status of trailers, or of the headers of a trailers-only response,
+/// reporting a failed call.
+pub(crate) fn failed_status(headers: &http::HeaderMap) -> Option<crate::status::Status> {
+    crate::status::Status::from_headers(headers)
+        .filter(|status| status.code() != crate::status::Code::Ok)
+}
+
 impl<T> Stream for Decoding<T> {
     type Item = Result<T, crate::status::Status>;
 
@@ -236,8 +279,11 @@ impl<T> Stream for Decoding<T> {
                 return Poll::Ready(None);
             }
 
-            if let Some(item) = self.decode_chunk()? {
-                return Poll::Ready(Some(Ok(item)));
+            // without grpc framing the body is a single message
+            if self.decode_as_grpc {
+                if let Some(item) = self.decode_chunk()? {
+                    return Poll::Ready(Some(Ok(item)));
+                }
             }
 
             let chunk = match ready!(Pin::new(&mut self.body).poll_data(cx)) {
@@ -254,7 +300,24 @@ impl<T> Stream for Decoding<T> {
             };
 
             if let Some(data) = chunk {
-                self.buf.put(data)
+                self.buf.put(data);
+                if !self.decode_as_grpc {
+                    let len = self.buf.len();
+                    self.check_message_size(len)?;
+                }
+            } else if !self.decode_as_grpc && self.state == State::ReadHeader {
+                self.state = State::ReadHttpBody;
+                if let Some(item) = self.decode_chunk()? {
+                    return Poll::Ready(Some(Ok(item)));
+                }
+            } else if self.decode_as_grpc
+                && (self.buf.has_remaining() || self.state != State::ReadHeader)
+            {
+                self.state = State::Error;
+                return Poll::Ready(Some(Err(crate::status::Status::new(
+                    crate::status::Code::Internal,
+                    "stream ended in the middle of a message".to_string(),
+                ))));
             } else {
                 break;
             }
@@ -262,7 +325,12 @@ impl<T> Stream for Decoding<T> {
 
         match ready!(Pin::new(&mut self.body).poll_trailers(cx)) {
             Ok(trailer) => {
+                let status = trailer.as_ref().and_then(failed_status);
                 self.trailers = trailer.map(Metadata::from_headers);
+                if let Some(status) = status {
+                    self.state = State::Error;
+                    return Poll::Ready(Some(Err(status)));
+                }
             }
             Err(err) => {
                 error!("poll_trailers, err: {}", err);
@@ -276,3 +344,88 @@ impl<T> Stream for Decoding<T> {
         (0, None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        status::Code,
+        triple::{
+            codec::raw::{RawCodec, RawMessage},
+            compression::compress,
+        },
+    };
+
+    fn raw_decoding(body: hyper::Body) -> Decoding<RawMessage> {
+        Decoding::new(body, Box::new(RawCodec), None, true)
+    }
+
+    fn frame(compressed: bool, len: u32, payload: &[u8]) -> Bytes {
+        let mut buf = BytesMut::new();
+        buf.put_u8(compressed as u8);
+        buf.put_u32(len);
+        buf.put_slice(payload);
+        buf.freeze()
+    }
+
+    #[tokio::test]
+    async fn test_oversized_length_prefix() {
+        let mut decoding = raw_decoding(hyper::Body::from(frame(false, u32::MAX, b"x")));
+        let status = decoding.message().await.unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+        assert!(decoding.buf.capacity() <= super::super::consts::BUFFER_SIZE);
+        assert!(decoding.message().await.unwrap().is_none());
+
+        let mut decoding = raw_decoding(hyper::Body::from(frame(false, 32, &[0; 32])))
+            .with_max_message_size(Some(16));
+        let status = decoding.message().await.unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+
+        // the limit applies to the decompressed message too
+        let mut src = BytesMut::from(&[0u8; 1024][..]);
+        let mut compressed = BytesMut::new();
+        compress(CompressionEncoding::Gzip, &mut src, &mut compressed, 1024).unwrap();
+        let body = hyper::Body::from(frame(true, compressed.len() as u32, &compressed));
+        let mut decoding = Decoding::new(
+            body,
+            Box::new(RawCodec),
+            Some(CompressionEncoding::Gzip),
+            true,
+        )
+        .with_max_message_size(Some(512));
+        let status = decoding.message().await.unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+    }
+
+    #[tokio::test]
+    async fn test_partial_frames() {
+        // a message trickling in a byte at a time is still decoded
+        let (mut tx, body) = hyper::Body::channel();
+        let mut decoding = raw_decoding(body);
+        tokio::spawn(async move {
+            for byte in frame(false, 5, b"dubbo") {
+                tx.send_data(Bytes::copy_from_slice(&[byte])).await.unwrap();
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        });
+        let message = decoding.message().await.unwrap().unwrap();
+        assert_eq!(&message.0[..], b"dubbo");
+        assert!(decoding.message().await.unwrap().is_none());
+
+        // a prefix claiming a large message allocates nothing until the
+        // message arrives, and a stream ending early fails
+        let (mut tx, body) = hyper::Body::channel();
+        let mut decoding = raw_decoding(body);
+        tx.send_data(frame(false, 1024 * 1024, b"partial"))
+            .await
+            .unwrap();
+        let pending = tokio::time::timeout(Duration::from_millis(50), decoding.message()).await;
+        assert!(pending.is_err());
+        assert!(decoding.buf.capacity() <= super::super::consts::BUFFER_SIZE);
+        drop(tx);
+        let status = decoding.message().await.unwrap_err();
+        assert_eq!(status.code(), Code::Internal);
+    }
+}
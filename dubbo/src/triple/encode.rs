@@ -26,11 +26,21 @@ use pin_project::pin_project;
 use super::compression::{compress, CompressionEncoding};
 use crate::triple::codec::{EncodeBuf, Encoder};
 
+/// Largest message in bytes the handler of a request may answer with, in the
+/// request extensions.
+#[derive(Debug, Clone, Copy)]
+pub struct MaxEncodeMessageSize(pub usize);
+
+/// Encode the messages of `resp_body`, compressing those of at least
+/// `min_compress_size` bytes with `compression_encoding`. A message larger
+/// than `max_message_size` bytes ends the stream with `ResourceExhausted`.
 #[allow(unused_must_use)]
 pub fn encode<E, B>(
     mut encoder: Box<dyn Encoder<Error = Status, Item = E> + Send + 'static>,
     resp_body: B,
     compression_encoding: Option<CompressionEncoding>,
+    min_compress_size: usize,
+    max_message_size: Option<usize>,
     encode_as_grpc: bool,
 ) -> impl TryStream<Ok = Bytes, Error = Status>
 where
@@ -40,9 +50,9 @@ where
         let mut buf = BytesMut::with_capacity(super::consts::BUFFER_SIZE);
         futures_util::pin_mut!(resp_body);
 
-        let (enable_compress, mut uncompression_buf) = match compression_encoding {
-            Some(CompressionEncoding::Gzip) => (true, BytesMut::with_capacity(super::consts::BUFFER_SIZE)),
-            None => (false, BytesMut::new())
+        let mut uncompression_buf = match compression_encoding {
+            Some(_) => BytesMut::with_capacity(super::consts::BUFFER_SIZE),
+            None => BytesMut::new(),
         };
 
         loop {
@@ -55,24 +65,40 @@ where
                         }
                     }
                     // 编码数据到缓冲中
-                    if enable_compress {
+                    let len = if compression_encoding.is_some() {
                         uncompression_buf.clear();
 
                         encoder.encode(item, &mut EncodeBuf::new(&mut uncompression_buf))
                             .map_err(|_e| crate::status::Status::new(crate::status::Code::Internal, "encode error".to_string()));
-
-                        let len = uncompression_buf.len();
-                        compress(compression_encoding.unwrap(), &mut uncompression_buf, &mut buf, len)
-                            .map_err(|_| crate::status::Status::new(crate::status::Code::Internal, "compress error".to_string()));
+                        uncompression_buf.len()
                     } else {
+                        let start = buf.len();
                         encoder.encode(item, &mut EncodeBuf::new(&mut buf)).map_err(|_e| crate::status::Status::new(crate::status::Code::Internal, "encode error".to_string()));
+                        buf.len() - start
+                    };
+                    if let Some(max) = max_message_size.filter(|max| len > *max) {
+                        yield Err(crate::status::Status::new(
+                            crate::status::Code::ResourceExhausted,
+                            format!("message of {} bytes exceeds the limit of {} bytes", len, max),
+                        ));
+                        break;
+                    }
+                    let mut compressed = false;
+                    if let Some(encoding) = compression_encoding {
+                        if len >= min_compress_size {
+                            compressed = true;
+                            compress(encoding, &mut uncompression_buf, &mut buf, len)
+                                .map_err(|_| crate::status::Status::new(crate::status::Code::Internal, "compress error".to_string()));
+                        } else {
+                            buf.put(uncompression_buf.split());
+                        }
                     }
                     let result=match encode_as_grpc{
                         true=>{
                             let len = buf.len() - super::consts::HEADER_SIZE;
                             {
                                 let mut buf = &mut buf[..super::consts::HEADER_SIZE];
-                                buf.put_u8(enable_compress as u8);
+                                buf.put_u8(compressed as u8);
                                 buf.put_u32(len as u32);
                             }
                             buf.split_to(len + super::consts::HEADER_SIZE)
@@ -94,12 +120,22 @@ pub fn encode_server<E, B>(
     encoder: Box<dyn Encoder<Error = Status, Item = E> + Send + 'static>,
     body: B,
     compression_encoding: Option<CompressionEncoding>,
+    min_compress_size: usize,
+    max_message_size: Option<usize>,
     encode_as_grpc: bool,
 ) -> EncodeBody<impl Stream<Item = Result<Bytes, Status>>>
 where
     B: Stream<Item = Result<E, Status>>,
 {
-    let s = encode(encoder, body, compression_encoding, encode_as_grpc).into_stream();
+    let s = encode(
+        encoder,
+        body,
+        compression_encoding,
+        min_compress_size,
+        max_message_size,
+        encode_as_grpc,
+    )
+    .into_stream();
     EncodeBody::new_server(s)
 }
 
@@ -107,12 +143,22 @@ pub fn encode_client<E, B>(
     encoder: Box<dyn Encoder<Error = Status, Item = E> + Send + 'static>,
     body: B,
     compression_encoding: Option<CompressionEncoding>,
+    min_compress_size: usize,
+    max_message_size: Option<usize>,
     is_grpc: bool,
 ) -> EncodeBody<impl Stream<Item = Result<Bytes, Status>>>
 where
     B: Stream<Item = E>,
 {
-    let s = encode(encoder, body.map(Ok), compression_encoding, is_grpc).into_stream();
+    let s = encode(
+        encoder,
+        body.map(Ok),
+        compression_encoding,
+        min_compress_size,
+        max_message_size,
+        is_grpc,
+    )
+    .into_stream();
     EncodeBody::new_client(s)
 }
 
@@ -16,14 +16,31 @@
  */
 
 use std::{
+    collections::HashMap,
     net::{SocketAddr, ToSocketAddrs},
     path::Path,
     str::FromStr,
 };
 
 use crate::{
+    config::{method::MethodConfig, tls::TlsConfig},
+    filter::{
+        access_log::{AccessLog, AccessLogService},
+        auth::{jwt_filter, TokenFilter},
+        method::MethodConfigService,
+        service::FilterService,
+        tps_limit::TpsLimitService,
+    },
     logger::tracing::{error, info, warn},
-    params::registry_param::InterfaceName,
+    params::{
+        constants::{
+            ACCESS_LOG_ARGS_KEY, ACCESS_LOG_KEY, COMPRESSION_KEY, COMPRESSION_MIN_SIZE_KEY,
+            MAX_ENCODE_MESSAGE_SIZE_KEY, MAX_MESSAGE_SIZE_KEY, TOKEN_KEY,
+        },
+        registry_param::{Group, InterfaceName, Version},
+    },
+    protocol::triple::{registered_service, service_key::ServiceKey},
+    triple::compression::CompressionConfig,
     url::UrlParam,
     Url,
 };
@@ -32,7 +49,7 @@ use hyper::body::Body;
 use tokio_rustls::rustls::{Certificate, PrivateKey};
 use tower_service::Service;
 
-use crate::{triple::transport::DubboServer, utils, BoxBody};
+use crate::{triple::transport::DubboServer, utils, utils::boxed_clone::BoxCloneService, BoxBody};
 
 #[derive(Clone, Default, Debug)]
 pub struct ServerBuilder {
@@ -41,6 +58,16 @@ pub struct ServerBuilder {
     pub certs: Vec<Certificate>,
     pub keys: Vec<PrivateKey>,
     pub service_names: Vec<String>,
+    pub access_log: Option<String>,
+    pub access_log_args: bool,
+    pub token: Option<String>,
+    pub tls: Option<TlsConfig>,
+    pub methods: HashMap<String, MethodConfig>,
+    pub compression: Option<CompressionConfig>,
+    pub max_message_size: Option<usize>,
+    pub max_encode_message_size: Option<usize>,
+    pub group: String,
+    pub version: String,
     server: DubboServer,
 }
 
@@ -73,6 +100,14 @@ impl ServerBuilder {
         }
     }
 
+    /// Serve over TLS from the files of `tls`, see [`TlsConfig`].
+    pub fn with_tls_config(self, tls: TlsConfig) -> ServerBuilder {
+        Self {
+            tls: Some(tls),
+            ..self
+        }
+    }
+
     pub fn with_addr(self, addr: &'static str) -> ServerBuilder {
         Self {
             addr: addr.to_socket_addrs().unwrap().next(),
@@ -80,6 +115,69 @@ impl ServerBuilder {
         }
     }
 
+    /// Log every call of the services, see [`AccessLog`] for `output`.
+    pub fn with_access_log(self, output: &str, arguments: bool) -> ServerBuilder {
+        Self {
+            access_log: Some(output.to_string()),
+            access_log_args: arguments,
+            ..self
+        }
+    }
+
+    /// Require callers to send `token`, see [`TokenFilter`].
+    pub fn with_token(self, token: String) -> ServerBuilder {
+        Self {
+            token: Some(token),
+            ..self
+        }
+    }
+
+    /// Settings of the method `name` of the services, see
+    /// [`MethodConfigService`].
+    pub fn with_method(mut self, name: String, method: MethodConfig) -> ServerBuilder {
+        self.methods.insert(name, method);
+        self
+    }
+
+    /// Encodings the responses of the services may be compressed with, every
+    /// supported one by default, see [`CompressionConfig`].
+    pub fn with_compression(self, compression: CompressionConfig) -> ServerBuilder {
+        Self {
+            compression: Some(compression),
+            ..self
+        }
+    }
+
+    /// Largest request message in bytes, 4 MiB by default. Methods may set
+    /// their own limit, see [`MethodConfigService`].
+    pub fn with_max_message_size(self, max_message_size: usize) -> ServerBuilder {
+        Self {
+            max_message_size: Some(max_message_size),
+            ..self
+        }
+    }
+
+    /// Largest response message in bytes, unlimited by default.
+    pub fn with_max_encode_message_size(self, max_encode_message_size: usize) -> ServerBuilder {
+        Self {
+            max_encode_message_size: Some(max_encode_message_size),
+            ..self
+        }
+    }
+
+    /// Group of the services, unless given by their names.
+    pub fn with_group(self, group: String) -> ServerBuilder {
+        Self { group, ..self }
+    }
+
+    /// Version of the services, unless given by their names.
+    pub fn with_version(self, version: String) -> ServerBuilder {
+        Self { version, ..self }
+    }
+
+    /// Services to serve, named by interface or by `group/interface:version`
+    /// to host several groups or versions of an interface, see
+    /// [`ServiceKey`].
     pub fn with_service_names(self, service_names: Vec<String>) -> ServerBuilder {
         Self {
             service_names,
@@ -96,16 +194,65 @@ impl ServerBuilder {
             }
         }
 
+        if let Some(tls) = &self.tls {
+            server = server.with_tls_config(tls.clone());
+        }
+
+        let access_log = self
+            .access_log
+            .as_ref()
+            .and_then(|output| match AccessLog::new(output) {
+                Ok(log) => Some(log.with_arguments(self.access_log_args)),
+                Err(err) => {
+                    error!("error opening access log {}: {:?}", output, err);
+                    None
+                }
+            });
+
         {
-            let lock = crate::protocol::triple::TRIPLE_SERVICES.read().unwrap();
             for name in self.service_names.iter() {
-                if lock.get(name).is_none() {
-                    warn!("service ({}) not register", name);
+                let mut key = ServiceKey::parse(name);
+                if key.group.is_empty() {
+                    key.group = self.group.clone();
+                }
+                if key.version.is_empty() {
+                    key.version = self.version.clone();
+                }
+                let Some(mut svc) = registered_service(&key) else {
+                    warn!("service ({}) not register", key);
                     continue;
+                };
+
+                if !self.methods.is_empty()
+                    || self.compression.is_some()
+                    || self.max_message_size.is_some()
+                    || self.max_encode_message_size.is_some()
+                {
+                    svc = BoxCloneService::new(
+                        MethodConfigService::new(svc, self.methods.clone())
+                            .with_compression(self.compression.clone())
+                            .with_max_message_sizes(
+                                self.max_message_size,
+                                self.max_encode_message_size,
+                            ),
+                    );
+                }
+                // only calls passing the auth filters count towards the tps
+                svc = BoxCloneService::new(TpsLimitService::new(svc));
+                if let Some(jwt) = jwt_filter() {
+                    svc = BoxCloneService::new(FilterService::new(svc, jwt.clone()));
+                }
+                if let Some(token) = &self.token {
+                    svc = BoxCloneService::new(FilterService::new(
+                        svc,
+                        TokenFilter::new(token.clone()),
+                    ));
+                }
+                if let Some(log) = &access_log {
+                    svc = BoxCloneService::new(AccessLogService::provider(svc, log.clone()));
                 }
-                let svc = lock.get(name).unwrap();
 
-                server = server.add_service(name.clone(), svc.clone());
+                server = server.add_keyed_service(key, svc);
             }
         }
 
@@ -118,6 +265,7 @@ impl ServerBuilder {
         S: Service<Request<Body>, Response = Response<BoxBody>, Error = std::convert::Infallible>
             + Clone
             + Send
+            + Sync
             + 'static,
         S::Future: Send + 'static,
         S::Error: Into<crate::Error> + Send + 'static,
@@ -154,9 +302,42 @@ impl From<Url> for ServerBuilder {
                 .unwrap_or("tcp".to_string()),
             addr: authority.to_string().to_socket_addrs().unwrap().next(),
             service_names: vec![service_name],
+            access_log: u
+                .query_param_by_key(ACCESS_LOG_KEY)
+                .filter(|v| !v.is_empty() && v != "false"),
+            access_log_args: u
+                .query_param_by_key(ACCESS_LOG_ARGS_KEY)
+                .map(|v| v == "true")
+                .unwrap_or_default(),
+            token: u.query_param_by_key(TOKEN_KEY).filter(|v| !v.is_empty()),
+            tls: TlsConfig::from_url(&u),
+            methods: MethodConfig::from_url(&u),
+            compression: compression_from_url(&u),
+            max_message_size: u
+                .query_param_by_key(MAX_MESSAGE_SIZE_KEY)
+                .and_then(|v| v.parse().ok()),
+            max_encode_message_size: u
+                .query_param_by_key(MAX_ENCODE_MESSAGE_SIZE_KEY)
+                .and_then(|v| v.parse().ok()),
+            group: u.query::<Group>().map(|v| v.value()).unwrap_or_default(),
+            version: u.query::<Version>().map(|v| v.value()).unwrap_or_default(),
             server: DubboServer::default(),
             certs: Vec::new(),
             keys: Vec::new(),
         }
     }
 }
+
+fn compression_from_url(url: &Url) -> Option<CompressionConfig> {
+    let encodings = url.query_param_by_key(COMPRESSION_KEY).unwrap_or_default();
+    let min_size = url
+        .query_param_by_key(COMPRESSION_MIN_SIZE_KEY)
+        .and_then(|v| v.parse().ok());
+    match CompressionConfig::from_settings(&encodings, min_size) {
+        Ok(compression) => compression,
+        Err(encoding) => {
+            error!("unknown compression {:?}, url: {:?}", encoding, url);
+            None
+        }
+    }
+}
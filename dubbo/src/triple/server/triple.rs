@@ -18,20 +18,20 @@
 use futures_util::{future, stream, StreamExt, TryStreamExt};
 use http::HeaderValue;
 use http_body::Body;
-use prost::Message;
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use std::marker::PhantomData;
 
 use crate::{
+    filter::access_log::{AccessLogArguments, LogArguments},
     invocation::Request,
-    status::Status,
+    status::{Code, Status},
     triple::{
-        client::triple::get_codec,
-        codec::{Decoder, Encoder},
-        compression::{CompressionEncoding, COMPRESSIONS},
-        decode::Decoding,
-        encode::encode_server,
+        codec::{DefaultCodec, MessageCodec},
+        compression::{CompressionConfig, CompressionEncoding},
+        decode::{Decoding, MaxMessageSize},
+        encode::{encode_server, MaxEncodeMessageSize},
         server::service::{ClientStreamingSvc, ServerStreamingSvc, StreamingSvc, UnarySvc},
+        transport::gateway::is_grpc,
     },
     BoxBody,
 };
@@ -39,24 +39,52 @@ use crate::{
 pub const GRPC_ACCEPT_ENCODING: &str = "grpc-accept-encoding";
 pub const GRPC_ENCODING: &str = "grpc-encoding";
 
-pub struct TripleServer<M1, M2> {
-    _pd: PhantomData<(M1, M2)>,
-    compression: Option<CompressionEncoding>,
+/// Server side of the triple protocol, serializing messages with `C`.
+pub struct TripleServer<M1, M2, C = DefaultCodec> {
+    _pd: PhantomData<(M1, M2, C)>,
+    compression: CompressionConfig,
 }
 
-impl<M1, M2> TripleServer<M1, M2> {
+impl<M1, M2, C> TripleServer<M1, M2, C> {
     pub fn new() -> Self {
         Self {
             _pd: PhantomData,
-            compression: Some(CompressionEncoding::Gzip),
+            compression: CompressionConfig::default(),
         }
     }
+
+    /// Encodings the responses may be compressed with, unless a
+    /// [`CompressionConfig`] in the request extensions says otherwise.
+    pub fn with_compression(self, compression: CompressionConfig) -> Self {
+        Self {
+            compression,
+            ..self
+        }
+    }
+
+    /// Encoding of the response to `req` and the size from which its
+    /// messages are compressed. The encoding is the first one accepted by
+    /// the client that is enabled for the service.
+    fn response_compression<B>(
+        &self,
+        req: &http::Request<B>,
+    ) -> (Option<CompressionEncoding>, usize) {
+        let config = req
+            .extensions()
+            .get::<CompressionConfig>()
+            .unwrap_or(&self.compression);
+        (
+            CompressionEncoding::negotiate(req.headers(), &config.encodings),
+            config.min_size,
+        )
+    }
 }
 
-impl<M1, M2> TripleServer<M1, M2>
+impl<M1, M2, C> TripleServer<M1, M2, C>
 where
-    M1: Message + for<'a> Deserialize<'a> + Default + 'static,
-    M2: Message + Serialize + Default + 'static,
+    C: MessageCodec<M2, M1>,
+    M1: Serialize + Send + 'static,
+    M2: Send + 'static,
 {
     pub async fn client_streaming<S, B>(
         &mut self,
@@ -68,28 +96,29 @@ where
         B: Body + Send + 'static,
         B::Error: Into<crate::Error> + Send,
     {
+        if let Some(resp) = reject_http(req.headers()) {
+            return resp;
+        }
         let content_type = req
             .headers()
             .get("content-type")
             .cloned()
             .unwrap_or(HeaderValue::from_str("application/grpc+proto").unwrap());
         let content_type_str = content_type.to_str().unwrap();
-        let (decoder, encoder): (
-            Box<dyn Decoder<Item = M1, Error = Status> + Send + 'static>,
-            Box<dyn Encoder<Error = Status, Item = M2> + Send + 'static>,
-        ) = get_codec(content_type_str);
-        let mut accept_encoding = CompressionEncoding::from_accept_encoding(req.headers());
-        if self.compression.is_none() || accept_encoding.is_none() {
-            accept_encoding = None;
-        }
+        let (decoder, encoder) = C::codec(content_type_str);
+        let (accept_encoding, min_compress_size) = self.response_compression(&req);
 
         // Get grpc_encoding from http_header, decompress message.
         let compression = match self.get_encoding_from_req(req.headers()) {
             Ok(val) => val,
-            Err(status) => return status.to_http(),
+            Err(status) => return unsupported_encoding(status),
         };
 
-        let req_stream = req.map(|body| Decoding::new(body, decoder, compression, true));
+        let max_message_size = max_message_size(&req);
+        let max_encode_message_size = max_encode_message_size(&req);
+        let req_stream = req.map(|body| {
+            Decoding::new(body, decoder, compression, true).with_max_message_size(max_message_size)
+        });
 
         let resp = service.call(Request::from_http(req_stream)).await;
 
@@ -102,6 +131,8 @@ where
             encoder,
             stream::once(future::ready(resp_body)).map(Ok).into_stream(),
             accept_encoding,
+            min_compress_size,
+            max_encode_message_size,
             true,
         );
 
@@ -128,30 +159,29 @@ where
         B: Body + Send + 'static,
         B::Error: Into<crate::Error> + Send,
     {
+        if let Some(resp) = reject_http(req.headers()) {
+            return resp;
+        }
         let content_type = req
             .headers()
             .get("content-type")
             .cloned()
             .unwrap_or(HeaderValue::from_str("application/grpc+proto").unwrap());
         let content_type_str = content_type.to_str().unwrap();
-        let (decoder, encoder): (
-            Box<dyn Decoder<Item = M1, Error = Status> + Send + 'static>,
-            Box<dyn Encoder<Error = Status, Item = M2> + Send + 'static>,
-        ) = get_codec(content_type_str);
-        // Firstly, get grpc_accept_encoding from http_header, get compression
-        // Secondly, if server enable compression and compression is valid, this method should compress response
-        let mut accept_encoding = CompressionEncoding::from_accept_encoding(req.headers());
-        if self.compression.is_none() || accept_encoding.is_none() {
-            accept_encoding = None;
-        }
+        let (decoder, encoder) = C::codec(content_type_str);
+        let (accept_encoding, min_compress_size) = self.response_compression(&req);
 
         // Get grpc_encoding from http_header, decompress message.
         let compression = match self.get_encoding_from_req(req.headers()) {
             Ok(val) => val,
-            Err(status) => return status.to_http(),
+            Err(status) => return unsupported_encoding(status),
         };
 
-        let req_stream = req.map(|body| Decoding::new(body, decoder, compression, true));
+        let max_message_size = max_message_size(&req);
+        let max_encode_message_size = max_encode_message_size(&req);
+        let req_stream = req.map(|body| {
+            Decoding::new(body, decoder, compression, true).with_max_message_size(max_message_size)
+        });
 
         let resp = service.call(Request::from_http(req_stream)).await;
 
@@ -159,7 +189,14 @@ where
             Ok(v) => v.into_http().into_parts(),
             Err(err) => return err.to_http(),
         };
-        let resp_body = encode_server(encoder, resp_body, compression, true);
+        let resp_body = encode_server(
+            encoder,
+            resp_body,
+            accept_encoding,
+            min_compress_size,
+            max_encode_message_size,
+            true,
+        );
 
         parts
             .headers
@@ -184,45 +221,64 @@ where
         B: Body + Send + 'static,
         B::Error: Into<crate::Error> + Send,
     {
+        if let Some(resp) = reject_http(req.headers()) {
+            return resp;
+        }
         let content_type = req
             .headers()
             .get("content-type")
             .cloned()
             .unwrap_or(HeaderValue::from_str("application/grpc+proto").unwrap());
         let content_type_str = content_type.to_str().unwrap();
-        let (decoder, encoder): (
-            Box<dyn Decoder<Item = M1, Error = Status> + Send + 'static>,
-            Box<dyn Encoder<Error = Status, Item = M2> + Send + 'static>,
-        ) = get_codec(content_type_str);
-        // Firstly, get grpc_accept_encoding from http_header, get compression
-        // Secondly, if server enable compression and compression is valid, this method should compress response
-        let mut accept_encoding = CompressionEncoding::from_accept_encoding(req.headers());
-        if self.compression.is_none() || accept_encoding.is_none() {
-            accept_encoding = None;
-        }
+        let (decoder, encoder) = C::codec(content_type_str);
+        let (accept_encoding, min_compress_size) = self.response_compression(&req);
 
         // Get grpc_encoding from http_header, decompress message.
         let compression = match self.get_encoding_from_req(req.headers()) {
             Ok(val) => val,
-            Err(status) => return status.to_http(),
+            Err(status) => return unsupported_encoding(status),
         };
-        let req_stream = req.map(|body| Decoding::new(body, decoder, compression, true));
-        let (parts, mut body) = Request::from_http(req_stream).into_parts();
-        let msg = body.try_next().await.unwrap().ok_or_else(|| {
-            crate::status::Status::new(crate::status::Code::Unknown, "request wrong".to_string())
+        let log_arguments = req.extensions().get::<LogArguments>().is_some();
+        let max_message_size = max_message_size(&req);
+        let max_encode_message_size = max_encode_message_size(&req);
+        let req_stream = req.map(|body| {
+            Decoding::new(body, decoder, compression, true).with_max_message_size(max_message_size)
+        });
+        let mut req = Request::from_http(req_stream);
+        let msg = req.message.try_next().await.and_then(|msg| {
+            msg.ok_or_else(|| {
+                crate::status::Status::new(
+                    crate::status::Code::Unknown,
+                    "request wrong".to_string(),
+                )
+            })
         });
         let msg = match msg {
             Ok(v) => v,
             Err(err) => return err.to_http(),
         };
+        let arguments = match log_arguments {
+            true => serde_json::to_value(&msg).ok(),
+            false => None,
+        };
 
-        let resp = service.call(Request::from_parts(parts, msg)).await;
+        let resp = service.call(req.map(|_| msg)).await;
 
         let (mut parts, resp_body) = match resp {
             Ok(v) => v.into_http().into_parts(),
             Err(err) => return err.to_http(),
         };
-        let resp_body = encode_server(encoder, resp_body, compression, true);
+        if let Some(arguments) = arguments {
+            parts.extensions.insert(AccessLogArguments(arguments));
+        }
+        let resp_body = encode_server(
+            encoder,
+            resp_body,
+            accept_encoding,
+            min_compress_size,
+            max_encode_message_size,
+            true,
+        );
 
         parts
             .headers
@@ -246,13 +302,10 @@ where
         B: Body + Send + 'static,
         B::Error: Into<crate::Error> + Send,
     {
-        let mut accept_encoding = CompressionEncoding::from_accept_encoding(req.headers());
-        if self.compression.is_none() || accept_encoding.is_none() {
-            accept_encoding = None;
-        }
+        let (mut accept_encoding, min_compress_size) = self.response_compression(&req);
         let compression = match self.get_encoding_from_req(req.headers()) {
             Ok(val) => val,
-            Err(status) => return status.to_http(),
+            Err(status) => return unsupported_encoding(status),
         };
         let content_type = req
             .headers()
@@ -262,31 +315,51 @@ where
         let content_type_str = content_type.to_str().unwrap();
         //Determine whether to use the gRPC mode to handle request data
         let handle_request_as_grpc = content_type_str.contains("grpc");
-        let (decoder, encoder): (
-            Box<dyn Decoder<Item = M1, Error = Status> + Send + 'static>,
-            Box<dyn Encoder<Error = Status, Item = M2> + Send + 'static>,
-        ) = get_codec(content_type_str);
-        let req_stream =
-            req.map(|body| Decoding::new(body, decoder, compression, handle_request_as_grpc));
-        let (parts, mut body) = Request::from_http(req_stream).into_parts();
-        let msg = body.try_next().await.unwrap().ok_or_else(|| {
-            crate::status::Status::new(crate::status::Code::Unknown, "request wrong".to_string())
+        if !handle_request_as_grpc {
+            // plain http calls are answered with a plain body
+            accept_encoding = None;
+        }
+        let (decoder, encoder) = C::codec(content_type_str);
+        let log_arguments = req.extensions().get::<LogArguments>().is_some();
+        let max_message_size = max_message_size(&req);
+        let max_encode_message_size = max_encode_message_size(&req);
+        let req_stream = req.map(|body| {
+            Decoding::new(body, decoder, compression, handle_request_as_grpc)
+                .with_max_message_size(max_message_size)
+        });
+        let mut req = Request::from_http(req_stream);
+        let msg = req.message.try_next().await.and_then(|msg| {
+            msg.ok_or_else(|| {
+                crate::status::Status::new(
+                    crate::status::Code::Unknown,
+                    "request wrong".to_string(),
+                )
+            })
         });
         let msg = match msg {
             Ok(v) => v,
             Err(err) => return err.to_http(),
         };
+        let arguments = match log_arguments {
+            true => serde_json::to_value(&msg).ok(),
+            false => None,
+        };
 
-        let resp = service.call(Request::from_parts(parts, msg)).await;
+        let resp = service.call(req.map(|_| msg)).await;
 
         let (mut parts, resp_body) = match resp {
             Ok(v) => v.into_http().into_parts(),
             Err(err) => return err.to_http(),
         };
+        if let Some(arguments) = arguments {
+            parts.extensions.insert(AccessLogArguments(arguments));
+        }
         let resp_body = encode_server(
             encoder,
             stream::once(future::ready(resp_body)).map(Ok).into_stream(),
             accept_encoding,
+            min_compress_size,
+            max_encode_message_size,
             handle_request_as_grpc,
         );
 
@@ -306,22 +379,40 @@ where
         &self,
         header: &http::HeaderMap,
     ) -> Result<Option<CompressionEncoding>, crate::status::Status> {
-        let encoding = match header.get(GRPC_ENCODING) {
-            Some(val) => val.to_str().unwrap(),
-            None => return Ok(None),
-        };
+        CompressionEncoding::from_encoding_header(header)
+    }
+}
 
-        let compression = match COMPRESSIONS.get(encoding) {
-            Some(val) => val.to_owned(),
-            None => {
-                let status = crate::status::Status::new(
-                    crate::status::Code::Unimplemented,
-                    format!("grpc-accept-encoding: {} not support!", encoding),
-                );
+/// Answer to a request in an encoding that is not supported, announcing the
+/// supported ones.
+fn unsupported_encoding(status: Status) -> http::Response<BoxBody> {
+    let mut resp = status.to_http();
+    resp.headers_mut().insert(
+        GRPC_ACCEPT_ENCODING,
+        CompressionEncoding::accept_encoding_value(&CompressionEncoding::ALL),
+    );
+    resp
+}
 
-                return Err(status);
-            }
-        };
-        Ok(compression)
+/// Plain http calls only reach unary methods, see
+/// [`HttpGateway`](crate::triple::transport::gateway::HttpGateway).
+fn reject_http(headers: &http::HeaderMap) -> Option<http::Response<BoxBody>> {
+    if is_grpc(headers) {
+        return None;
     }
+    let status = Status::new(
+        Code::Unimplemented,
+        "streaming methods are only served over grpc".to_string(),
+    );
+    Some(status.to_http())
+}
+
+fn max_message_size<B>(req: &http::Request<B>) -> Option<usize> {
+    req.extensions().get::<MaxMessageSize>().map(|max| max.0)
+}
+
+fn max_encode_message_size<B>(req: &http::Request<B>) -> Option<usize> {
+    req.extensions()
+        .get::<MaxEncodeMessageSize>()
+        .map(|max| max.0)
 }
@@ -15,23 +15,29 @@
  * limitations under the License.
  */
 
-use crate::StdError;
-use hyper::client::{conn::Builder, service::Connect};
+use hyper::client::conn::Builder;
 use tower_service::Service;
 
-use crate::{boxed, invoker::clone_body::CloneBody, triple::transport::connector::get_connector};
-
-type HyperConnect = Connect<
-    crate::utils::boxed_clone::BoxCloneService<http::Uri, super::io::BoxIO, StdError>,
-    CloneBody,
-    http::Uri,
->;
+use crate::{
+    config::http2::Http2Config,
+    invoker::clone_body::CloneBody,
+    triple::transport::{
+        connector::{get_connector, https_connector::HttpsConnector, Connector},
+        io::BoxIO,
+        pool::{ConnectionPool, PoolConfig},
+        tls::ClientTls,
+    },
+    utils::boxed_clone::BoxCloneService,
+};
 
 pub struct Connection {
     host: hyper::Uri,
     connector: String,
     builder: Builder,
-    connect: Option<HyperConnect>,
+    tls: Option<ClientTls>,
+    pool_config: PoolConfig,
+    http2: Http2Config,
+    pool: Option<ConnectionPool>,
 }
 
 impl Default for Connection {
@@ -46,7 +52,10 @@ impl Connection {
             host: hyper::Uri::default(),
             connector: "http".to_string(),
             builder: Builder::new(),
-            connect: None,
+            tls: None,
+            pool_config: PoolConfig::default(),
+            http2: Http2Config::default(),
+            pool: None,
         }
     }
 
@@ -65,14 +74,83 @@ impl Connection {
         self
     }
 
+    /// Connect over TLS, overriding the connector.
+    pub fn with_tls(mut self, tls: ClientTls) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    pub fn with_pool(mut self, pool_config: PoolConfig) -> Self {
+        self.pool_config = pool_config;
+        self
+    }
+
+    /// Tune the http/2 connections. The connect timeout and the streams a
+    /// connection carries override the ones of the pool.
+    pub fn with_http2(mut self, http2: Http2Config) -> Self {
+        self.http2 = http2;
+        self
+    }
+
     pub fn build(mut self) -> Self {
-        let builder = self.builder.clone().http2_only(true).to_owned();
-        let hyper_connect: HyperConnect = Connect::new(get_connector(&self.connector), builder);
-        self.connect = Some(hyper_connect);
+        let builder = http2_builder(self.builder.clone(), &self.http2);
+        let mut pool_config = self.pool_config.clone();
+        if let Some(streams) = self.http2.max_concurrent_streams {
+            pool_config = pool_config.max_streams_per_connection(streams);
+        }
+        if let Some(timeout) = self.http2.connect_timeout_duration() {
+            pool_config = pool_config.connect_timeout(timeout);
+        }
+        let connector = connector(&self.host, &self.connector, self.tls.take());
+        self.pool = Some(ConnectionPool::new(
+            self.host.clone(),
+            connector,
+            builder,
+            pool_config,
+        ));
         self
     }
 }
 
+/// Connector reaching `host`, over TLS when `tls` is set.
+pub(crate) fn connector(
+    host: &hyper::Uri,
+    connector: &str,
+    tls: Option<ClientTls>,
+) -> BoxCloneService<hyper::Uri, BoxIO, crate::Error> {
+    match tls {
+        Some(tls) => BoxCloneService::new(Connector::new(HttpsConnector::new().with_tls(tls))),
+        // providers in the same process are reached over `mem://`
+        None if host.scheme_str() == Some("mem") => get_connector("mem"),
+        None => get_connector(connector),
+    }
+}
+
+fn http2_builder(mut builder: Builder, http2: &Http2Config) -> Builder {
+    builder.http2_only(true);
+    if let Some(size) = http2.initial_stream_window_size {
+        builder.http2_initial_stream_window_size(size);
+    }
+    if let Some(size) = http2.initial_connection_window_size {
+        builder.http2_initial_connection_window_size(size);
+    }
+    if http2.adaptive_window {
+        builder.http2_adaptive_window(true);
+    }
+    if let Some(size) = http2.max_frame_size {
+        builder.http2_max_frame_size(size);
+    }
+    // connections that miss a ping close, and the pool reconnects
+    if let Some(interval) = http2.keepalive_interval_duration() {
+        builder.http2_keep_alive_interval(interval);
+        builder.http2_keep_alive_while_idle(http2.keepalive_while_idle);
+        if let Some(timeout) = http2.keepalive_timeout_duration() {
+            builder.http2_keep_alive_timeout(timeout);
+        }
+    }
+    builder
+}
+
 impl Service<http::Request<CloneBody>> for Connection {
     type Response = http::Response<crate::BoxBody>;
 
@@ -82,34 +160,104 @@ impl Service<http::Request<CloneBody>> for Connection {
 
     fn poll_ready(
         &mut self,
-        cx: &mut std::task::Context<'_>,
+        _cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Result<(), Self::Error>> {
-        match self.connect {
+        match self.pool {
             None => {
                 panic!("connection must be built before use")
             }
-            Some(ref mut connect) => connect.poll_ready(cx).map_err(|e| e.into()),
+            // the pool picks or opens a connection for every call
+            Some(_) => std::task::Poll::Ready(Ok(())),
         }
     }
 
     fn call(&mut self, req: http::Request<CloneBody>) -> Self::Future {
-        match self.connect {
+        match self.pool {
             None => {
                 panic!("connection must be built before use")
             }
-            Some(ref mut connect) => {
-                let uri = self.host.clone();
-                let call_fut = connect.call(uri);
-                let fut = async move {
-                    let mut con = call_fut.await.unwrap();
-                    con.call(req)
-                        .await
-                        .map_err(|err| err.into())
-                        .map(|res| res.map(boxed))
-                };
-
-                return Box::pin(fut);
+            Some(ref pool) => {
+                let pool = pool.clone();
+                Box::pin(async move { pool.send(req).await })
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, future, sync::Arc, time::Duration};
+
+    use tokio::{
+        net::{TcpListener, TcpStream},
+        sync::Notify,
+    };
+
+    use super::*;
+
+    /// Forward connections to `upstream` until `freeze` is notified, then
+    /// keep them open without forwarding anything, as a dead peer would.
+    async fn freezing_proxy(upstream: std::net::SocketAddr, freeze: Arc<Notify>) -> hyper::Uri {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut client, _)) = listener.accept().await {
+                let freeze = freeze.clone();
+                tokio::spawn(async move {
+                    let mut server = TcpStream::connect(upstream).await.unwrap();
+                    tokio::select! {
+                        _ = tokio::io::copy_bidirectional(&mut client, &mut server) => {}
+                        _ = freeze.notified() => future::pending::<()>().await,
+                    }
+                });
+            }
+        });
+        format!("http://{}", addr).parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_keepalive_reconnects() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((io, _)) = listener.accept().await {
+                let service = hyper::service::service_fn(|_| async {
+                    Ok::<_, Infallible>(http::Response::new(hyper::Body::from("ok")))
+                });
+                tokio::spawn(
+                    hyper::server::conn::Http::new()
+                        .http2_only(true)
+                        .serve_connection(io, service),
+                );
+            }
+        });
+        let freeze = Arc::new(Notify::new());
+        let host = freezing_proxy(upstream, freeze.clone()).await;
+
+        let mut conn = Connection::new()
+            .with_host(host.clone())
+            .with_pool(
+                PoolConfig::default()
+                    .reconnect_backoff(Duration::from_millis(10), Duration::from_millis(10)),
+            )
+            .with_http2(Http2Config::default().keepalive(
+                Duration::from_millis(50),
+                Duration::from_millis(50),
+                true,
+            ))
+            .build();
+        let mut call = || {
+            let req = http::Request::builder()
+                .uri(host.clone())
+                .body(CloneBody::new(hyper::Body::empty()))
+                .unwrap();
+            tokio::time::timeout(Duration::from_secs(1), conn.call(req))
+        };
+        call().await.unwrap().unwrap();
+
+        // the first connection misses its pings and is replaced
+        freeze.notify_waiters();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        call().await.unwrap().unwrap();
+    }
+}
@@ -24,7 +24,6 @@ use std::{
 use crate::logger::tracing::info;
 use http::Uri;
 use hyper::client::connect::dns::Name;
-use rustls_native_certs::load_native_certs;
 use tokio::net::TcpStream;
 use tokio_rustls::{
     client::TlsStream,
@@ -33,24 +32,41 @@ use tokio_rustls::{
 };
 use tower_service::Service;
 
-use crate::triple::transport::resolver::{dns::DnsResolver, Resolve};
+use crate::triple::transport::{
+    resolver::{dns::DnsResolver, Resolve},
+    tls::{native_roots, ClientTls},
+};
 
 #[derive(Clone, Default)]
 pub struct HttpsConnector<R = DnsResolver> {
     resolver: R,
+    tls: Option<ClientTls>,
 }
 
 impl HttpsConnector {
     pub fn new() -> Self {
         Self {
             resolver: DnsResolver::default(),
+            tls: None,
         }
     }
 }
 
 impl<R> HttpsConnector<R> {
     pub fn new_with_resolver(resolver: R) -> HttpsConnector<R> {
-        Self { resolver }
+        Self {
+            resolver,
+            tls: None,
+        }
+    }
+
+    /// Connect with the given client certificates and roots instead of the
+    /// native roots.
+    pub fn with_tls(self, tls: ClientTls) -> Self {
+        Self {
+            tls: Some(tls),
+            ..self
+        }
     }
 }
 
@@ -106,21 +122,21 @@ where
             addrs[0]
         };
 
-        let mut root_store = rustls::RootCertStore::empty();
-
-        for cert in load_native_certs()? {
-            root_store.add(&rustls::Certificate(cert.0))?;
-        }
-
-        let config = rustls::ClientConfig::builder()
-            .with_safe_defaults()
-            .with_root_certificates(root_store)
-            .with_no_client_auth();
+        let (config, server_name) = match &self.tls {
+            Some(tls) => (tls.config(), tls.server_name(host)),
+            None => {
+                let config = rustls::ClientConfig::builder()
+                    .with_safe_defaults()
+                    .with_root_certificates(native_roots()?)
+                    .with_no_client_auth();
+                (Arc::new(config), host)
+            }
+        };
 
-        let connector = TlsConnectorTokio::from(Arc::new(config));
+        let connector = TlsConnectorTokio::from(config);
 
         let stream = TcpStream::connect(&addr).await?;
-        let domain = rustls::ServerName::try_from(host).map_err(|err| {
+        let domain = rustls::ServerName::try_from(server_name).map_err(|err| {
             crate::status::Status::new(crate::status::Code::Internal, err.to_string())
         })?;
         let stream = connector.connect(domain, stream).await?;
@@ -0,0 +1,57 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use http::Uri;
+use tokio::io::DuplexStream;
+use tower_service::Service;
+
+use crate::triple::transport::listener::mem_listener;
+
+/// Connector to the `mem` listeners of the same process, the authority of
+/// `mem://ip:port` naming the listener.
+#[derive(Clone, Default)]
+pub struct MemConnector;
+
+impl MemConnector {
+    pub fn new() -> Self {
+        MemConnector
+    }
+}
+
+impl Service<Uri> for MemConnector {
+    type Response = DuplexStream;
+
+    type Error = crate::Error;
+
+    type Future = crate::BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        Box::pin(async move {
+            let name = uri
+                .authority()
+                .ok_or_else(|| format!("missing listener name in {}", uri))?;
+            Ok(mem_listener::connect(name.as_str())?)
+        })
+    }
+}
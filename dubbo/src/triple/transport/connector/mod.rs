@@ -17,6 +17,7 @@
 
 pub mod http_connector;
 pub mod https_connector;
+pub mod mem_connector;
 #[cfg(any(target_os = "macos", target_family = "unix"))]
 pub mod unix_connector;
 
@@ -84,6 +85,10 @@ pub fn get_connector(connector: &str) -> BoxCloneService<Uri, BoxIO, crate::Erro
             let c = https_connector::HttpsConnector::new();
             BoxCloneService::new(Connector::new(c))
         }
+        "mem" => {
+            let c = mem_connector::MemConnector::new();
+            BoxCloneService::new(Connector::new(c))
+        }
         #[cfg(any(target_os = "macos", target_family = "unix"))]
         "unix" => {
             let c = unix_connector::UnixConnector::new();
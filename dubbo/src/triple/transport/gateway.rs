@@ -0,0 +1,134 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::task::{Context, Poll};
+
+use futures_util::FutureExt;
+use http::{HeaderMap, Method, Request, Response, StatusCode};
+use tower_service::Service;
+
+use crate::{
+    status::{Code, Status},
+    BoxBody,
+};
+
+/// Whether a call is made with grpc, rather than plain http. Calls without a
+/// content type are taken as grpc, like the services do.
+pub fn is_grpc(headers: &HeaderMap) -> bool {
+    headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map_or(true, |v| v.starts_with("application/grpc"))
+}
+
+/// Serves the unary methods to plain http calls, `POST /{service}/{method}`
+/// with a body such as `application/json` and no grpc framing, over http/1.1
+/// or http/2.
+///
+/// The services answer failed calls with grpc statuses in the headers,
+/// turned here into the matching http status and a json body, see
+/// [`Status::to_json_http`].
+#[derive(Clone, Debug)]
+pub struct HttpGateway<S> {
+    inner: S,
+}
+
+impl<S> HttpGateway<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+}
+
+impl<S, B> Service<Request<B>> for HttpGateway<S>
+where
+    S: Service<Request<B>, Response = Response<BoxBody>>,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+{
+    type Response = Response<BoxBody>;
+    type Error = S::Error;
+    type Future = crate::BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        // grpc calls are always posted
+        if req.method() != Method::POST {
+            let status = Status::new(
+                Code::Unimplemented,
+                format!("method {} is not allowed, call with POST", req.method()),
+            );
+            let mut resp = status.to_json_http();
+            *resp.status_mut() = StatusCode::METHOD_NOT_ALLOWED;
+            return Box::pin(futures_util::future::ok(resp));
+        }
+        if is_grpc(req.headers()) {
+            return Box::pin(self.inner.call(req));
+        }
+        Box::pin(self.inner.call(req).map(|resp| {
+            resp.map(|resp| match Status::from_headers(resp.headers()) {
+                Some(status) if status.code() != Code::Ok => status.to_json_http(),
+                _ => resp,
+            })
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_http_gateway() {
+        let svc = tower::service_fn(|_: Request<()>| async {
+            Ok::<_, Infallible>(Status::new(Code::NotFound, "no greeting".to_string()).to_http())
+        });
+        let gateway = HttpGateway::new(svc);
+
+        let req = Request::post("/org.apache.dubbo.sample.tri.Greeter/greet")
+            .header(http::header::CONTENT_TYPE, "application/grpc")
+            .body(())
+            .unwrap();
+        let resp = gateway.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["grpc-status"], "5");
+
+        let req = Request::post("/org.apache.dubbo.sample.tri.Greeter/greet")
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .body(())
+            .unwrap();
+        let resp = gateway.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        assert_eq!(
+            &body[..],
+            br#"{"code":"not_found","message":"no greeting"}"#
+        );
+
+        let req = Request::get("/org.apache.dubbo.sample.tri.Greeter/greet")
+            .body(())
+            .unwrap();
+        let resp = gateway.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+}
@@ -0,0 +1,136 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Listener of the connections made in the same process by the `mem`
+//! connector, over in-memory pipes instead of sockets. Servers bind a
+//! `SocketAddr` only naming them, no port is opened, and clients reach them
+//! at `mem://ip:port`.
+
+use std::{collections::HashMap, io, net::SocketAddr, sync::Mutex};
+
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use tokio::{
+    io::DuplexStream,
+    sync::{mpsc, Mutex as AsyncMutex},
+};
+
+use super::Listener;
+
+/// Bytes buffered in each direction of an in-memory connection.
+pub const MEM_BUFFER_SIZE: usize = 64 * 1024;
+
+lazy_static! {
+    static ref MEM_LISTENERS: Mutex<HashMap<String, mpsc::UnboundedSender<DuplexStream>>> =
+        Mutex::new(HashMap::new());
+}
+
+pub struct MemListener {
+    addr: SocketAddr,
+    incoming: AsyncMutex<mpsc::UnboundedReceiver<DuplexStream>>,
+}
+
+impl MemListener {
+    pub fn bind(addr: SocketAddr) -> io::Result<MemListener> {
+        let mut listeners = MEM_LISTENERS.lock().unwrap();
+        let name = addr.to_string();
+        if listeners.get(&name).is_some_and(|tx| !tx.is_closed()) {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("mem://{} is already bound", name),
+            ));
+        }
+        let (tx, rx) = mpsc::unbounded_channel();
+        listeners.insert(name, tx);
+        Ok(MemListener {
+            addr,
+            incoming: AsyncMutex::new(rx),
+        })
+    }
+}
+
+impl Drop for MemListener {
+    fn drop(&mut self) {
+        self.incoming.get_mut().close();
+        let mut listeners = MEM_LISTENERS.lock().unwrap();
+        let name = self.addr.to_string();
+        if listeners.get(&name).is_some_and(|tx| tx.is_closed()) {
+            listeners.remove(&name);
+        }
+    }
+}
+
+#[async_trait]
+impl Listener for MemListener {
+    type Conn = DuplexStream;
+
+    async fn accept(&self) -> io::Result<(Self::Conn, SocketAddr)> {
+        match self.incoming.lock().await.recv().await {
+            Some(conn) => Ok((conn, self.addr)),
+            None => Err(io::ErrorKind::NotConnected.into()),
+        }
+    }
+}
+
+/// Whether a listener is bound to `name`, as in `127.0.0.1:8888`.
+pub fn is_bound(name: &str) -> bool {
+    let listeners = MEM_LISTENERS.lock().unwrap();
+    listeners.get(name).is_some_and(|tx| !tx.is_closed())
+}
+
+/// Connect to the listener bound to `name`.
+pub fn connect(name: &str) -> io::Result<DuplexStream> {
+    let listeners = MEM_LISTENERS.lock().unwrap();
+    let refused = || {
+        io::Error::new(
+            io::ErrorKind::ConnectionRefused,
+            format!("nothing listens on mem://{}", name),
+        )
+    };
+    let tx = listeners.get(name).ok_or_else(refused)?;
+    let (client, server) = tokio::io::duplex(MEM_BUFFER_SIZE);
+    tx.send(server).map_err(|_| refused())?;
+    Ok(client)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mem_listener() {
+        let addr: SocketAddr = "127.0.0.2:1".parse().unwrap();
+        assert!(connect("127.0.0.2:1").is_err());
+
+        let listener = MemListener::bind(addr).unwrap();
+        assert!(MemListener::bind(addr).is_err());
+        let mut client = connect("127.0.0.2:1").unwrap();
+        let (mut server, local) = listener.accept().await.unwrap();
+        assert_eq!(local, addr);
+
+        client.write_all(b"ping").await.unwrap();
+        let mut buf = [0; 4];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+
+        drop(listener);
+        assert!(!is_bound("127.0.0.2:1"));
+        assert!(connect("127.0.0.2:1").is_err());
+    }
+}
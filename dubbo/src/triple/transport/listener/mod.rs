@@ -15,6 +15,7 @@
  * limitations under the License.
  */
 
+pub mod mem_listener;
 pub mod tcp_listener;
 #[cfg(any(target_os = "macos", target_family = "unix"))]
 pub mod unix_listener;
@@ -65,6 +66,7 @@ impl<T: Listener> Listener for WrappedListener<T> {
 pub async fn get_listener(name: String, addr: SocketAddr) -> Result<BoxListener, crate::Error> {
     match name.as_str() {
         "tcp" => Ok(TcpListener::bind(addr).await?.boxed()),
+        "mem" => Ok(mem_listener::MemListener::bind(addr)?.boxed()),
         #[cfg(any(target_os = "macos", target_family = "unix"))]
         "unix" => Ok(unix_listener::UnixListener::bind(addr).await?.boxed()),
         _ => {
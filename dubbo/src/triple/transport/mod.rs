@@ -17,10 +17,13 @@
 
 pub mod connection;
 pub mod connector;
-mod io;
+pub mod gateway;
+pub(crate) mod io;
 pub mod listener;
+pub mod pool;
 pub mod resolver;
 pub mod router;
 pub mod service;
+pub mod tls;
 
 pub use service::DubboServer;
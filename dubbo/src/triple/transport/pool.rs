@@ -0,0 +1,612 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{
+    error::Error as _,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_util::future;
+use http_body::Body;
+use hyper::client::conn::{Builder, SendRequest};
+use thiserror::Error;
+use tokio::{sync::Notify, time::Instant};
+use tower::ServiceExt;
+
+use super::io::BoxIO;
+use crate::{
+    boxed,
+    invoker::clone_body::CloneBody,
+    logger::tracing::{debug, warn},
+    utils::boxed_clone::BoxCloneService,
+};
+
+/// Streams open on every connection before the pool opens another one.
+pub const DEFAULT_MAX_STREAMS_PER_CONNECTION: usize = 100;
+
+type BoxConnector = BoxCloneService<http::Uri, BoxIO, crate::Error>;
+
+/// Settings of the http/2 connections a client keeps to a provider.
+///
+/// Calls go to the connection with the fewest open streams. Once every
+/// connection carries `max_streams_per_connection` streams, another one is
+/// opened, up to `max_connections`. Connections that die are replaced to
+/// keep `min_connections` open, waiting `reconnect_backoff`, doubled on every
+/// failure up to `max_reconnect_backoff`, between attempts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolConfig {
+    pub min_connections: usize,
+    pub max_connections: usize,
+    pub max_streams_per_connection: usize,
+    pub reconnect_backoff: Duration,
+    pub max_reconnect_backoff: Duration,
+    pub connect_timeout: Option<Duration>,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        PoolConfig {
+            min_connections: 1,
+            max_connections: 1,
+            max_streams_per_connection: DEFAULT_MAX_STREAMS_PER_CONNECTION,
+            reconnect_backoff: Duration::from_millis(100),
+            max_reconnect_backoff: Duration::from_secs(10),
+            connect_timeout: None,
+        }
+    }
+}
+
+impl PoolConfig {
+    pub fn min_connections(self, min_connections: usize) -> Self {
+        Self {
+            min_connections,
+            ..self
+        }
+    }
+
+    pub fn max_connections(self, max_connections: usize) -> Self {
+        Self {
+            max_connections,
+            ..self
+        }
+    }
+
+    pub fn max_streams_per_connection(self, max_streams_per_connection: usize) -> Self {
+        Self {
+            max_streams_per_connection,
+            ..self
+        }
+    }
+
+    pub fn reconnect_backoff(self, reconnect_backoff: Duration, max: Duration) -> Self {
+        Self {
+            reconnect_backoff,
+            max_reconnect_backoff: max,
+            ..self
+        }
+    }
+
+    /// Fail the attempts to connect, including the http/2 handshake, that
+    /// take longer than `connect_timeout`.
+    pub fn connect_timeout(self, connect_timeout: Duration) -> Self {
+        Self {
+            connect_timeout: Some(connect_timeout),
+            ..self
+        }
+    }
+
+    fn max(&self) -> usize {
+        self.max_connections.max(self.min_connections).max(1)
+    }
+
+    fn backoff(&self, failures: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failures.saturating_sub(1));
+        self.reconnect_backoff
+            .saturating_mul(factor)
+            .min(self.max_reconnect_backoff)
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("connection to {host} failed {failures} times, retrying in {delay:?}: {error}")]
+pub struct ReconnectBackoffError {
+    pub host: http::Uri,
+    pub failures: u32,
+    pub delay: Duration,
+    pub error: String,
+}
+
+#[derive(Debug, Error)]
+#[error("connect to {host} timed out after {timeout:?}")]
+pub struct ConnectTimeoutError {
+    pub host: http::Uri,
+    pub timeout: Duration,
+}
+
+/// Pool of http/2 connections to a provider, see [`PoolConfig`].
+#[derive(Clone)]
+pub struct ConnectionPool {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    host: http::Uri,
+    connector: BoxConnector,
+    builder: Builder,
+    config: PoolConfig,
+    state: Mutex<State>,
+    changed: Notify,
+}
+
+#[derive(Default)]
+struct State {
+    conns: Vec<Arc<PooledConnection>>,
+    connecting: usize,
+    failures: u32,
+    retry_at: Option<Instant>,
+    last_error: String,
+}
+
+struct PooledConnection {
+    sender: tokio::sync::Mutex<SendRequest<CloneBody>>,
+    streams: AtomicUsize,
+    closed: AtomicBool,
+}
+
+impl ConnectionPool {
+    pub fn new(
+        host: http::Uri,
+        connector: BoxConnector,
+        builder: Builder,
+        config: PoolConfig,
+    ) -> Self {
+        ConnectionPool {
+            inner: Arc::new(Inner {
+                host,
+                connector,
+                builder,
+                config,
+                state: Mutex::new(State::default()),
+                changed: Notify::new(),
+            }),
+        }
+    }
+
+    /// Open connections, not counting the ones being established.
+    pub fn connections(&self) -> usize {
+        let mut state = self.inner.state.lock().unwrap();
+        state.prune();
+        state.conns.len()
+    }
+
+    /// Send `req` on the connection with the fewest open streams.
+    pub async fn send(
+        &self,
+        req: http::Request<CloneBody>,
+    ) -> Result<http::Response<crate::BoxBody>, crate::Error> {
+        let conn = self.acquire().await?;
+        let stream = OpenStream::open(conn.clone());
+        let resp = {
+            let mut sender = conn.sender.lock().await;
+            match future::poll_fn(|cx| sender.poll_ready(cx)).await {
+                Ok(()) => sender.send_request(req),
+                Err(err) => {
+                    self.inner.evict(&conn, &err);
+                    return Err(err.into());
+                }
+            }
+        };
+        match resp.await {
+            Ok(resp) => Ok(resp.map(|body| {
+                boxed(PooledBody {
+                    body,
+                    _stream: stream,
+                })
+            })),
+            Err(err) => {
+                if is_connection_error(&err) {
+                    self.inner.evict(&conn, &err);
+                }
+                Err(err.into())
+            }
+        }
+    }
+
+    async fn acquire(&self) -> Result<Arc<PooledConnection>, crate::Error> {
+        let config = &self.inner.config;
+        loop {
+            let changed = self.inner.changed.notified();
+            let (best, connect) = {
+                let mut state = self.inner.state.lock().unwrap();
+                state.prune();
+                let best = state.conns.iter().min_by_key(|c| c.streams()).cloned();
+                let open = state.conns.len() + state.connecting;
+                let busy = best
+                    .as_ref()
+                    .map_or(true, |c| c.streams() >= config.max_streams_per_connection);
+                let mut connect = config.min_connections.saturating_sub(open);
+                if busy && open < config.max() {
+                    connect = connect.max(1);
+                }
+                if state.backing_off() {
+                    connect = 0;
+                }
+                if best.is_none() && connect == 0 && state.connecting == 0 {
+                    return Err(state.backoff_error(&self.inner).into());
+                }
+                state.connecting += connect;
+                (best, connect)
+            };
+            match best {
+                Some(best) => {
+                    for _ in 0..connect {
+                        self.inner.spawn_connect();
+                    }
+                    return Ok(best);
+                }
+                None if connect > 0 => {
+                    for _ in 1..connect {
+                        self.inner.spawn_connect();
+                    }
+                    // connect in a task, which settles the count of the
+                    // connections being established even if this call is
+                    // dropped in the middle of the handshake
+                    let inner = self.inner.clone();
+                    return tokio::spawn(async move { inner.connect().await }).await?;
+                }
+                // wait for the connections being established
+                None => changed.await,
+            }
+        }
+    }
+}
+
+impl Inner {
+    async fn connect(self: &Arc<Self>) -> Result<Arc<PooledConnection>, crate::Error> {
+        let result = self.handshake().await;
+        let mut state = self.state.lock().unwrap();
+        state.connecting -= 1;
+        let result = match result {
+            Ok(conn) => {
+                state.failures = 0;
+                state.retry_at = None;
+                state.conns.push(conn.clone());
+                Ok(conn)
+            }
+            Err(err) => {
+                state.failures += 1;
+                let delay = self.config.backoff(state.failures);
+                state.retry_at = Some(Instant::now() + delay);
+                state.last_error = err.to_string();
+                warn!(
+                    "connect to {} failed {} times, retrying in {:?}: {}",
+                    self.host, state.failures, delay, err
+                );
+                Err(err)
+            }
+        };
+        drop(state);
+        self.changed.notify_waiters();
+        result
+    }
+
+    async fn handshake(self: &Arc<Self>) -> Result<Arc<PooledConnection>, crate::Error> {
+        let connect = async {
+            let io = self.connector.clone().oneshot(self.host.clone()).await?;
+            let handshake = self.builder.handshake::<_, CloneBody>(io).await?;
+            Ok::<_, crate::Error>(handshake)
+        };
+        let (sender, connection) = match self.config.connect_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, connect).await.map_err(|_| {
+                ConnectTimeoutError {
+                    host: self.host.clone(),
+                    timeout,
+                }
+            })??,
+            None => connect.await?,
+        };
+        let conn = Arc::new(PooledConnection {
+            sender: tokio::sync::Mutex::new(sender),
+            streams: AtomicUsize::new(0),
+            closed: AtomicBool::new(false),
+        });
+        let watched = Arc::downgrade(&conn);
+        let pool = Arc::downgrade(self);
+        tokio::spawn(async move {
+            if let Err(err) = connection.await {
+                debug!("connection closed: {}", err);
+            }
+            if let Some(conn) = watched.upgrade() {
+                conn.closed.store(true, Ordering::Release);
+            }
+            if let Some(pool) = pool.upgrade() {
+                pool.refill();
+            }
+        });
+        Ok(conn)
+    }
+
+    /// Connect in the background, after the backoff of the last failure.
+    fn spawn_connect(self: &Arc<Self>) {
+        let pool = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let retry_at = match pool.upgrade() {
+                Some(pool) => pool.state.lock().unwrap().retry_at,
+                None => return,
+            };
+            if let Some(retry_at) = retry_at {
+                tokio::time::sleep_until(retry_at).await;
+            }
+            let Some(pool) = pool.upgrade() else {
+                return;
+            };
+            if pool.connect().await.is_err() {
+                pool.refill();
+            }
+        });
+    }
+
+    /// Replace the connections closed below `min_connections`.
+    fn refill(self: &Arc<Self>) {
+        let missing = {
+            let mut state = self.state.lock().unwrap();
+            state.prune();
+            let open = state.conns.len() + state.connecting;
+            let missing = self.config.min_connections.saturating_sub(open);
+            state.connecting += missing;
+            missing
+        };
+        for _ in 0..missing {
+            self.spawn_connect();
+        }
+        self.changed.notify_waiters();
+    }
+
+    /// Stop using a connection that went away, letting its open streams end.
+    fn evict(self: &Arc<Self>, conn: &PooledConnection, err: &hyper::Error) {
+        if !conn.closed.swap(true, Ordering::AcqRel) {
+            debug!("evict connection to {}: {}", self.host, err);
+            self.refill();
+        }
+    }
+}
+
+impl State {
+    fn prune(&mut self) {
+        self.conns
+            .retain(|conn| !conn.closed.load(Ordering::Acquire));
+    }
+
+    fn backing_off(&self) -> bool {
+        self.retry_at.is_some_and(|at| at > Instant::now())
+    }
+
+    fn backoff_error(&self, pool: &Inner) -> ReconnectBackoffError {
+        ReconnectBackoffError {
+            host: pool.host.clone(),
+            failures: self.failures,
+            delay: self
+                .retry_at
+                .map(|at| at.saturating_duration_since(Instant::now()))
+                .unwrap_or_default(),
+            error: self.last_error.clone(),
+        }
+    }
+}
+
+impl PooledConnection {
+    fn streams(&self) -> usize {
+        self.streams.load(Ordering::Relaxed)
+    }
+}
+
+/// A stream counted on its connection until the response body is dropped.
+struct OpenStream(Arc<PooledConnection>);
+
+impl OpenStream {
+    fn open(conn: Arc<PooledConnection>) -> Self {
+        conn.streams.fetch_add(1, Ordering::Relaxed);
+        OpenStream(conn)
+    }
+}
+
+impl Drop for OpenStream {
+    fn drop(&mut self) {
+        self.0.streams.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+struct PooledBody {
+    body: hyper::Body,
+    // closed with the body
+    _stream: OpenStream,
+}
+
+impl Body for PooledBody {
+    type Data = bytes::Bytes;
+    type Error = hyper::Error;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        Pin::new(&mut self.body).poll_data(cx)
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+        Pin::new(&mut self.body).poll_trailers(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.body.size_hint()
+    }
+}
+
+/// Whether `err` means the connection can not carry new streams, as after a
+/// GOAWAY.
+fn is_connection_error(err: &hyper::Error) -> bool {
+    if err.is_closed() || err.is_canceled() {
+        return true;
+    }
+    let mut source = err.source();
+    while let Some(err) = source {
+        if let Some(err) = err.downcast_ref::<h2::Error>() {
+            return err.is_go_away() || err.is_io();
+        }
+        source = err.source();
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, net::SocketAddr};
+
+    use tokio::{net::TcpListener, task::JoinHandle};
+
+    use super::*;
+    use crate::triple::transport::connector::get_connector;
+
+    type Connections = Arc<Mutex<Vec<JoinHandle<()>>>>;
+
+    /// Serve http/2 requests answered after `delay`, keeping the tasks of
+    /// the connections to close them.
+    async fn serve(delay: Duration) -> (SocketAddr, Connections) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let connections = Connections::default();
+        let tasks = connections.clone();
+        tokio::spawn(async move {
+            while let Ok((io, _)) = listener.accept().await {
+                let service = hyper::service::service_fn(move |_| async move {
+                    tokio::time::sleep(delay).await;
+                    Ok::<_, Infallible>(http::Response::new(hyper::Body::from("ok")))
+                });
+                let task = tokio::spawn(async move {
+                    let _ = hyper::server::conn::Http::new()
+                        .http2_only(true)
+                        .serve_connection(io, service)
+                        .await;
+                });
+                tasks.lock().unwrap().push(task);
+            }
+        });
+        (addr, connections)
+    }
+
+    fn pool(addr: SocketAddr, config: PoolConfig) -> ConnectionPool {
+        pool_with(addr, get_connector("http"), config)
+    }
+
+    fn pool_with(addr: SocketAddr, connector: BoxConnector, config: PoolConfig) -> ConnectionPool {
+        let host: http::Uri = format!("http://{}", addr).parse().unwrap();
+        ConnectionPool::new(
+            host,
+            connector,
+            Builder::new().http2_only(true).to_owned(),
+            config,
+        )
+    }
+
+    async fn call(pool: &ConnectionPool, addr: SocketAddr) -> Result<(), crate::Error> {
+        let req = http::Request::builder()
+            .uri(format!("http://{}/", addr))
+            .body(CloneBody::new(hyper::Body::empty()))
+            .unwrap();
+        let resp = pool.send(req).await?;
+        hyper::body::to_bytes(resp.into_body()).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pool_grows_and_reconnects() {
+        let (addr, connections) = serve(Duration::from_millis(100)).await;
+        let pool = pool(
+            addr,
+            PoolConfig::default()
+                .max_connections(3)
+                .max_streams_per_connection(1)
+                .reconnect_backoff(Duration::from_millis(10), Duration::from_millis(50)),
+        );
+
+        let calls: Vec<_> = (0..4).map(|_| call(&pool, addr)).collect();
+        for result in future::join_all(calls).await {
+            result.unwrap();
+        }
+        assert_eq!(pool.connections(), 3);
+
+        // the provider drops the connections, the pool reopens one
+        for task in connections.lock().unwrap().drain(..) {
+            task.abort();
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(pool.connections(), 1);
+        call(&pool, addr).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pool_call_dropped_while_connecting() {
+        let (addr, _connections) = serve(Duration::ZERO).await;
+        // connections taking a while to open
+        let connector = BoxConnector::new(tower::service_fn(|uri: http::Uri| async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            get_connector("http").oneshot(uri).await
+        }));
+        let pool = pool_with(addr, connector, PoolConfig::default());
+
+        // as when a deadline or a hedged attempt cancels the call
+        let dropped = tokio::time::timeout(Duration::from_millis(10), call(&pool, addr)).await;
+        assert!(dropped.is_err());
+
+        tokio::time::timeout(Duration::from_secs(1), call(&pool, addr))
+            .await
+            .expect("the next call waits for a connection forever")
+            .unwrap();
+        assert_eq!(pool.connections(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_pool_backoff() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let pool = pool(
+            addr,
+            PoolConfig::default().reconnect_backoff(Duration::from_secs(5), Duration::from_secs(5)),
+        );
+
+        let err = call(&pool, addr).await.unwrap_err();
+        assert!(err.downcast_ref::<ReconnectBackoffError>().is_none());
+        let err = call(&pool, addr).await.unwrap_err();
+        let err = err.downcast_ref::<ReconnectBackoffError>().unwrap();
+        assert_eq!(err.failures, 1);
+        assert!(err.delay > Duration::from_secs(4));
+    }
+}
@@ -16,80 +16,190 @@
  */
 
 use std::{
+    collections::HashMap,
     fmt,
-    pin::Pin,
+    sync::{Arc, RwLock},
     task::{Context, Poll},
 };
 
-use axum::Router;
-use futures_core::Future;
+use futures_util::future;
 use hyper::{Body, Request, Response};
-use pin_project::pin_project;
 use tower::ServiceExt;
 use tower_service::Service;
 
-use crate::BoxBody;
+use crate::{
+    protocol::triple::{
+        service_key::{ServiceKey, ANY_VALUE, TRI_SERVICE_GROUP, TRI_SERVICE_VERSION},
+        GrpcBoxCloneService,
+    },
+    status::{Code, Status},
+    utils::boxed_clone::BoxCloneService,
+    BoxBody,
+};
+
+/// Services of each interface, with the keys of their groups and versions.
+type Services = HashMap<String, Vec<(ServiceKey, GrpcBoxCloneService)>>;
 
-#[derive(Debug, Clone, Default)]
+/// Routes the calls on `/{interface}/{method}` to the service of the
+/// interface whose group and version are asked by the `tri-service-group`
+/// and `tri-service-version` headers.
+///
+/// An absent header, or `*`, accepts any group or version, preferring the
+/// service exported without one. A call asking for a group or version which
+/// is not exported falls back to the service exported without both, like
+/// dubbo does.
+#[derive(Clone, Default)]
 pub struct DubboRouter {
-    pub router: Router,
+    services: Arc<RwLock<Services>>,
+}
+
+impl fmt::Debug for DubboRouter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let services = self.services.read().unwrap();
+        f.debug_list()
+            .entries(services.values().flatten().map(|(key, _)| key.to_string()))
+            .finish()
+    }
 }
 
 impl DubboRouter {
     pub fn new() -> DubboRouter {
-        Self {
-            router: Router::new(),
-        }
+        Self::default()
     }
 }
 
 impl DubboRouter {
-    pub fn add_service<S>(mut self, name: String, service: S) -> Self
+    pub fn add_service<S>(self, name: String, service: S) -> Self
     where
         S: Service<Request<Body>, Response = Response<BoxBody>, Error = std::convert::Infallible>
             + Clone
             + Send
+            + Sync
             + 'static,
         S::Future: Send + 'static,
     {
-        let svc = service.map_response(|res| res.map(axum::body::boxed));
-        // *{bubbo} represents wildcard router
-        self.router = self.router.route(&format!("/{}/*dubbo", name), svc);
+        self.add_keyed_service(ServiceKey::new(name), service)
+    }
 
+    /// Route the calls of the interface of `key` asking for its group and
+    /// version to `service`.
+    pub fn add_keyed_service<S>(self, key: ServiceKey, service: S) -> Self
+    where
+        S: Service<Request<Body>, Response = Response<BoxBody>, Error = std::convert::Infallible>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        S::Future: Send + 'static,
+    {
+        self.insert(key, BoxCloneService::new(service));
         self
     }
+
+    /// Route the calls of the services of `other` too, replacing the
+    /// services of the same keys.
+    pub fn merge(&self, other: &DubboRouter) {
+        let services = other.services.read().unwrap();
+        for (key, service) in services.values().flatten() {
+            self.insert(key.clone(), service.clone());
+        }
+    }
+
+    fn insert(&self, key: ServiceKey, service: GrpcBoxCloneService) {
+        let mut services = self.services.write().unwrap();
+        let entries = services.entry(key.interface.clone()).or_default();
+        entries.retain(|(k, _)| *k != key);
+        entries.push((key, service));
+    }
+
+    fn route(&self, req: &Request<Body>) -> Option<GrpcBoxCloneService> {
+        let interface = req.uri().path().trim_start_matches('/');
+        let interface = interface.split_once('/').map_or(interface, |(i, _)| i);
+        let header = |name| {
+            req.headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .filter(|v| !v.is_empty())
+        };
+
+        let services = self.services.read().unwrap();
+        select(
+            services.get(interface)?,
+            header(TRI_SERVICE_GROUP),
+            header(TRI_SERVICE_VERSION),
+        )
+        .cloned()
+    }
+}
+
+fn select<'a, T>(
+    entries: &'a [(ServiceKey, T)],
+    group: Option<&str>,
+    version: Option<&str>,
+) -> Option<&'a T> {
+    let accepts = |wanted: Option<&str>, value: &str| match wanted {
+        None | Some(ANY_VALUE) => true,
+        Some(wanted) => wanted == value,
+    };
+    entries
+        .iter()
+        .filter(|(key, _)| accepts(group, &key.group) && accepts(version, &key.version))
+        .min_by_key(|(key, _)| (!key.group.is_empty(), !key.version.is_empty()))
+        .or_else(|| {
+            entries
+                .iter()
+                .find(|(key, _)| key.group.is_empty() && key.version.is_empty())
+        })
+        .map(|(_, service)| service)
 }
 
 impl Service<Request<Body>> for DubboRouter {
     type Response = Response<BoxBody>;
     type Error = crate::Error;
-    type Future = RoutesFuture;
+    type Future = crate::BoxFuture<Self::Response, Self::Error>;
 
     fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         Poll::Ready(Ok(()))
     }
 
     fn call(&mut self, req: Request<Body>) -> Self::Future {
-        RoutesFuture(self.router.call(req))
+        match self.route(&req) {
+            Some(service) => Box::pin(async move {
+                match service.oneshot(req).await {
+                    Ok(res) => Ok(res),
+                    Err(err) => match err {},
+                }
+            }),
+            None => {
+                let status = Status::new(
+                    Code::Unimplemented,
+                    format!("service {} is not exported", req.uri().path()),
+                );
+                Box::pin(future::ok(status.to_http()))
+            }
+        }
     }
 }
 
-#[pin_project]
-pub struct RoutesFuture(#[pin] axum::routing::future::RouteFuture<Body, std::convert::Infallible>);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-impl fmt::Debug for RoutesFuture {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_tuple("RoutesFuture").finish()
-    }
-}
-
-impl Future for RoutesFuture {
-    type Output = Result<Response<BoxBody>, crate::Error>;
+    #[test]
+    fn test_select() {
+        let key = ServiceKey::new("org.apache.dubbo.Greeter");
+        let entries = vec![
+            (key.clone().with_group("g1").with_version("1.0.0"), 1),
+            (key.clone(), 2),
+            (key.clone().with_group("g1").with_version("2.0.0"), 3),
+        ];
 
-    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        match futures_util::ready!(self.project().0.poll(cx)) {
-            Ok(res) => Ok(res.map(crate::boxed)).into(),
-            Err(err) => match err {},
-        }
+        assert_eq!(select(&entries, Some("g1"), Some("2.0.0")), Some(&3));
+        assert_eq!(select(&entries, None, None), Some(&2));
+        assert_eq!(select(&entries, Some("g1"), None), Some(&1));
+        assert_eq!(select(&entries, Some("g1"), Some("*")), Some(&1));
+        // unknown group or version falls back to the default service
+        assert_eq!(select(&entries, Some("cluster"), None), Some(&2));
+        assert_eq!(select(&entries[..1], Some("g2"), None), None);
     }
 }
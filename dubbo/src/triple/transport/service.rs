@@ -15,26 +15,55 @@
  * limitations under the License.
  */
 
-use std::{io, net::SocketAddr, sync::Arc};
+use std::{collections::HashMap, io, net::SocketAddr, sync::Mutex};
 
 use crate::logger::tracing::{debug, error, info};
 use futures_core::Future;
 use http::{Request, Response};
 use hyper::body::Body;
+use once_cell::sync::Lazy;
 use tokio::time::Duration;
 use tokio_rustls::{
     rustls,
     rustls::{Certificate, PrivateKey},
     TlsAcceptor,
 };
+use tower::ServiceExt;
 use tower_service::Service;
 
-use super::{listener::get_listener, router::DubboRouter};
-use crate::{triple::transport::io::BoxIO, BoxBody};
+use super::{gateway::HttpGateway, listener::get_listener, router::DubboRouter};
+use crate::{
+    config::tls::TlsConfig,
+    invocation::RemoteAddr,
+    metrics::{
+        self,
+        rpc::{MetricsService, PROVIDER_SIDE},
+        CONNECTIONS_ACTIVE,
+    },
+    protocol::triple::service_key::ServiceKey,
+    telemetry::rpc::TracingService,
+    triple::transport::{
+        io::BoxIO,
+        tls::{PeerIdentity, ServerTls},
+    },
+    BoxBody,
+};
+
+/// Routers of the servers running in this process, keyed by address.
+static SERVING: Lazy<Mutex<HashMap<SocketAddr, DubboRouter>>> = Lazy::new(Default::default);
 
-#[derive(Default, Clone, Debug)]
+/// Forgets the router of a server once it stops.
+struct Serving(SocketAddr);
+
+impl Drop for Serving {
+    fn drop(&mut self) {
+        SERVING.lock().unwrap().remove(&self.0);
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct DubboServer {
-    accept_http2: bool,
+    accept_http1: bool,
     init_stream_window_size: Option<u32>,
     init_connection_window_size: Option<u32>,
     max_concurrent_streams: Option<u32>,
@@ -45,16 +74,24 @@ pub struct DubboServer {
     listener: Option<String>,
     certs: Vec<Certificate>,
     keys: Vec<PrivateKey>,
+    tls: Option<TlsConfig>,
 }
 
 impl DubboServer {
-    pub fn with_accpet_http1(self, accept_http2: bool) -> Self {
+    /// Accept http/1.1 connections beside http/2 ones, for the plain http
+    /// calls of [`HttpGateway`]. Enabled by default.
+    pub fn with_accept_http1(self, accept_http1: bool) -> Self {
         Self {
-            accept_http2,
+            accept_http1,
             ..self
         }
     }
 
+    #[deprecated(note = "use `with_accept_http1`, which takes the opposite flag")]
+    pub fn with_accpet_http1(self, http2_only: bool) -> Self {
+        self.with_accept_http1(!http2_only)
+    }
+
     pub fn with_init_stream_window_size(self, stream_window: u32) -> Self {
         Self {
             init_stream_window_size: Some(stream_window),
@@ -108,12 +145,27 @@ impl DubboServer {
             ..self
         }
     }
+
+    /// Serve over TLS from files, optionally verifying client certificates
+    /// and reloading the files when they change.
+    pub fn with_tls_config(self, tls: TlsConfig) -> Self {
+        Self {
+            tls: Some(tls),
+            ..self
+        }
+    }
+}
+
+impl Default for DubboServer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DubboServer {
     pub fn new() -> Self {
         Self {
-            accept_http2: true,
+            accept_http1: true,
             init_stream_window_size: None,
             init_connection_window_size: None,
             max_concurrent_streams: None,
@@ -124,6 +176,7 @@ impl DubboServer {
             listener: None,
             certs: Vec::new(),
             keys: Vec::new(),
+            tls: None,
         }
     }
 }
@@ -134,6 +187,7 @@ impl DubboServer {
         S: Service<Request<Body>, Response = Response<BoxBody>, Error = std::convert::Infallible>
             + Clone
             + Send
+            + Sync
             + 'static,
         S::Future: Send + 'static,
         S::Error: Into<crate::Error> + Send + 'static,
@@ -142,17 +196,47 @@ impl DubboServer {
         self
     }
 
+    /// Serve `service` to the calls asking for the group and version of
+    /// `key`, see [`DubboRouter`].
+    pub fn add_keyed_service<S>(mut self, key: ServiceKey, service: S) -> Self
+    where
+        S: Service<Request<Body>, Response = Response<BoxBody>, Error = std::convert::Infallible>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        S::Future: Send + 'static,
+    {
+        self.router = self.router.add_keyed_service(key, service);
+        self
+    }
+
     pub async fn serve(self, addr: SocketAddr) -> Result<(), crate::Error> {
         self.serve_with_graceful(addr, futures_util::future::pending())
             .await
     }
 
+    /// Serve on `addr` until `signal` completes.
+    ///
+    /// When a server of this process already runs on `addr`, the services
+    /// are added to it and this returns at once, so services exported on the
+    /// same port share a single server.
     pub async fn serve_with_graceful(
         self,
         addr: SocketAddr,
         signal: impl Future<Output = ()>,
     ) -> Result<(), crate::Error> {
-        let svc = self.router.clone();
+        let _serving = {
+            let mut serving = SERVING.lock().unwrap();
+            if let Some(router) = serving.get(&addr) {
+                router.merge(&self.router);
+                info!("services added to the server on {}", addr);
+                return Ok(());
+            }
+            serving.insert(addr, self.router.clone());
+            Serving(addr)
+        };
+        let svc = HttpGateway::new(self.router.clone());
         tokio::pin!(signal);
 
         let http2_keepalive_timeout = self
@@ -168,20 +252,21 @@ impl DubboServer {
             }
         };
 
-        let acceptor: Option<TlsAcceptor>;
-        if self.certs.len() != 0 && !self.keys.len() != 0 {
-            let mut keys = self.keys;
+        let tls = match self.tls {
+            Some(ref tls) => Some(ServerTls::server(tls)?),
+            None if !self.certs.is_empty() && !self.keys.is_empty() => {
+                let mut keys = self.keys;
 
-            let config = rustls::ServerConfig::builder()
-                .with_safe_defaults()
-                .with_no_client_auth()
-                .with_single_cert(self.certs, keys.remove(0))
-                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+                let config = rustls::ServerConfig::builder()
+                    .with_safe_defaults()
+                    .with_no_client_auth()
+                    .with_single_cert(self.certs, keys.remove(0))
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
 
-            acceptor = Some(TlsAcceptor::from(Arc::new(config)));
-        } else {
-            acceptor = None;
-        }
+                Some(ServerTls::fixed(config))
+            }
+            None => None,
+        };
 
         let listener = match get_listener(name, addr).await {
             Ok(v) => v,
@@ -198,26 +283,60 @@ impl DubboServer {
                     match res {
                         Ok(conn) => {
                             let (io, local_addr) = conn;
-                            let b :BoxIO;
-
-                            if !acceptor.is_none() {
-                                b = BoxIO::new(acceptor.as_ref().unwrap().clone().accept(io).await?);
-                            } else {
-                                b = io;
-                            }
 
                             debug!("hyper serve, local address: {:?}", local_addr);
-                            let c = hyper::server::conn::Http::new()
-                                .http2_only(self.accept_http2)
+                            let remote_addr = RemoteAddr(local_addr.to_string());
+                            let acceptor = tls.as_ref().map(|tls| TlsAcceptor::from(tls.current()));
+                            let svc = svc.clone();
+                            let http = hyper::server::conn::Http::new()
+                                .http2_only(!self.accept_http1)
                                 .http2_max_concurrent_streams(self.max_concurrent_streams)
                                 .http2_initial_connection_window_size(self.init_connection_window_size)
                                 .http2_initial_stream_window_size(self.init_stream_window_size)
                                 .http2_keep_alive_interval(self.http2_keepalive_interval)
                                 .http2_keep_alive_timeout(http2_keepalive_timeout)
                                 .http2_max_frame_size(self.max_frame_size)
-                                .serve_connection(b,svc.clone()).with_upgrades();
+                                .to_owned();
+
+                            let labels = vec![("side", PROVIDER_SIDE.to_string())];
+                            metrics::add_gauge(CONNECTIONS_ACTIVE, &labels, 1.0);
+                            tokio::spawn(async move {
+                                // handshake off the accept loop, a slow client must not block others
+                                let (io, peer) = match acceptor {
+                                    Some(acceptor) => match acceptor.accept(io).await {
+                                        Ok(stream) => {
+                                            let peer = stream
+                                                .get_ref()
+                                                .1
+                                                .peer_certificates()
+                                                .and_then(|certs| certs.first())
+                                                .and_then(PeerIdentity::from_certificate);
+                                            (BoxIO::new(stream), peer)
+                                        }
+                                        Err(err) => {
+                                            debug!("tls handshake, err: {:?}", err);
+                                            metrics::add_gauge(CONNECTIONS_ACTIVE, &labels, -1.0);
+                                            return;
+                                        }
+                                    },
+                                    None => (io, None),
+                                };
 
-                            tokio::spawn(c);
+                                let svc = svc.map_request(move |mut req: Request<Body>| {
+                                    req.extensions_mut().insert(remote_addr.clone());
+                                    if let Some(peer) = &peer {
+                                        req.extensions_mut().insert(peer.clone());
+                                    }
+                                    req
+                                });
+                                let c = http
+                                    .serve_connection(io, MetricsService::new(TracingService::new(svc, local_addr), local_addr.ip().to_string()))
+                                    .with_upgrades();
+                                if let Err(err) = c.await {
+                                    debug!("hyper serve connection, err: {:?}", err);
+                                }
+                                metrics::add_gauge(CONNECTIONS_ACTIVE, &labels, -1.0);
+                            });
                         },
                         Err(err) => error!("hyper serve, err: {:?}", err),
                     }
@@ -234,7 +353,7 @@ impl DubboServer {
 // impl BusinessConfig for DubboServer {
 //     fn init() -> Self {
 //         let conf = config::get_global_config();
-//         DubboServer::new().with_accpet_http1(conf.bool("dubbo.server.accept_http2".to_string()))
+//         DubboServer::new().with_accept_http1(conf.bool("dubbo.server.accept_http1".to_string()))
 //     }
 
 //     fn load() -> Result<(), std::convert::Infallible> {
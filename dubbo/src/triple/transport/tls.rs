@@ -0,0 +1,370 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{
+    fmt::Display,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock, Weak},
+    time::{Duration, SystemTime},
+};
+
+use rustls_native_certs::load_native_certs;
+use tokio_rustls::rustls::{
+    self,
+    server::{AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient},
+    Certificate, RootCertStore,
+};
+use x509_parser::extensions::GeneralName;
+
+use crate::{
+    config::tls::{ClientAuth, TlsConfig},
+    logger::tracing::{error, info, warn},
+    utils::tls::{load_certs, load_keys},
+};
+
+/// Identity in the certificate of the peer of a connection, set by the
+/// transport in the extensions of provider requests.
+#[derive(Debug, Clone, Default)]
+pub struct PeerIdentity {
+    pub subject: String,
+    pub dns_names: Vec<String>,
+    pub uris: Vec<String>,
+}
+
+impl PeerIdentity {
+    pub fn from_certificate(cert: &Certificate) -> Option<Self> {
+        let (_, cert) = x509_parser::parse_x509_certificate(&cert.0).ok()?;
+        let mut identity = PeerIdentity {
+            subject: cert.subject().to_string(),
+            ..Default::default()
+        };
+        if let Ok(Some(san)) = cert.subject_alternative_name() {
+            for name in san.value.general_names.iter() {
+                match name {
+                    GeneralName::DNSName(dns) => identity.dns_names.push(dns.to_string()),
+                    GeneralName::URI(uri) => identity.uris.push(uri.to_string()),
+                    _ => {}
+                }
+            }
+        }
+        Some(identity)
+    }
+}
+
+/// A rustls config rebuilt from its files whenever they change on disk.
+pub struct ReloadableConfig<T> {
+    current: Arc<RwLock<Arc<T>>>,
+}
+
+impl<T> Clone for ReloadableConfig<T> {
+    fn clone(&self) -> Self {
+        ReloadableConfig {
+            current: self.current.clone(),
+        }
+    }
+}
+
+impl<T> ReloadableConfig<T>
+where
+    T: Send + Sync + 'static,
+{
+    pub fn fixed(config: T) -> Self {
+        ReloadableConfig {
+            current: Arc::new(RwLock::new(Arc::new(config))),
+        }
+    }
+
+    fn watch<F>(files: Vec<PathBuf>, interval: Option<Duration>, build: F) -> io::Result<Self>
+    where
+        F: Fn() -> io::Result<T> + Send + 'static,
+    {
+        let config = Self::fixed(build()?);
+        if let Some(interval) = interval {
+            match tokio::runtime::Handle::try_current() {
+                Ok(handle) => {
+                    let current = Arc::downgrade(&config.current);
+                    handle.spawn(reload(current, files, interval, build));
+                }
+                Err(_) => warn!("no tokio runtime, tls certificates will not be reloaded"),
+            }
+        }
+        Ok(config)
+    }
+
+    /// The config to use for the next handshake.
+    pub fn current(&self) -> Arc<T> {
+        self.current
+            .read()
+            .expect("tls config lock poisoned")
+            .clone()
+    }
+}
+
+async fn reload<T, F>(
+    current: Weak<RwLock<Arc<T>>>,
+    files: Vec<PathBuf>,
+    interval: Duration,
+    build: F,
+) where
+    F: Fn() -> io::Result<T>,
+{
+    let mut modified = modified_times(&files);
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        // stop once every user of the config is gone
+        let Some(current) = current.upgrade() else {
+            break;
+        };
+        let latest = modified_times(&files);
+        if latest == modified {
+            continue;
+        }
+        match build() {
+            Ok(config) => {
+                *current.write().expect("tls config lock poisoned") = Arc::new(config);
+                modified = latest;
+                info!("reloaded tls certificates from {:?}", files);
+            }
+            // files may be half written, try again on the next tick
+            Err(err) => error!("error reloading tls certificates: {:?}", err),
+        }
+    }
+}
+
+fn modified_times(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    files
+        .iter()
+        .map(|file| fs::metadata(file).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+fn watched_files(config: &TlsConfig) -> Vec<PathBuf> {
+    [&config.cert, &config.key, &config.ca]
+        .iter()
+        .filter(|file| !file.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+pub type ServerTls = ReloadableConfig<rustls::ServerConfig>;
+
+impl ReloadableConfig<rustls::ServerConfig> {
+    pub fn server(config: &TlsConfig) -> io::Result<Self> {
+        let tls = config.clone();
+        Self::watch(watched_files(config), config.reload_duration(), move || {
+            server_config(&tls)
+        })
+    }
+}
+
+/// TLS settings of the connections of a consumer.
+#[derive(Clone)]
+pub struct ClientTls {
+    config: ReloadableConfig<rustls::ClientConfig>,
+    server_name: Option<String>,
+}
+
+impl ClientTls {
+    pub fn new(config: &TlsConfig) -> io::Result<Self> {
+        let tls = config.clone();
+        let reloadable =
+            ReloadableConfig::watch(watched_files(config), config.reload_duration(), move || {
+                client_config(&tls)
+            })?;
+        Ok(ClientTls {
+            config: reloadable,
+            server_name: Some(config.server_name.clone()).filter(|name| !name.is_empty()),
+        })
+    }
+
+    pub fn config(&self) -> Arc<rustls::ClientConfig> {
+        self.config.current()
+    }
+
+    /// Name sent in SNI and verified against the server certificate.
+    pub fn server_name<'a>(&'a self, host: &'a str) -> &'a str {
+        self.server_name.as_deref().unwrap_or(host)
+    }
+}
+
+fn server_config(tls: &TlsConfig) -> io::Result<rustls::ServerConfig> {
+    let certs = load_certs(Path::new(&tls.cert))?;
+    let key = load_keys(Path::new(&tls.key))?.remove(0);
+
+    let builder = rustls::ServerConfig::builder().with_safe_defaults();
+    let builder = if tls.ca.is_empty() || tls.client_auth == ClientAuth::None {
+        builder.with_no_client_auth()
+    } else {
+        let roots = load_roots(Path::new(&tls.ca))?;
+        let verifier = match tls.client_auth {
+            ClientAuth::Optional => AllowAnyAnonymousOrAuthenticatedClient::new(roots).boxed(),
+            _ => AllowAnyAuthenticatedClient::new(roots).boxed(),
+        };
+        builder.with_client_cert_verifier(verifier)
+    };
+
+    let mut config = builder.with_single_cert(certs, key).map_err(invalid)?;
+    config.alpn_protocols = alpn_protocols(&tls.alpn);
+    Ok(config)
+}
+
+fn client_config(tls: &TlsConfig) -> io::Result<rustls::ClientConfig> {
+    let roots = if tls.ca.is_empty() {
+        native_roots()?
+    } else {
+        load_roots(Path::new(&tls.ca))?
+    };
+
+    let builder = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots);
+    let mut config = if tls.cert.is_empty() {
+        builder.with_no_client_auth()
+    } else {
+        let certs = load_certs(Path::new(&tls.cert))?;
+        let key = load_keys(Path::new(&tls.key))?.remove(0);
+        builder.with_client_auth_cert(certs, key).map_err(invalid)?
+    };
+    config.alpn_protocols = alpn_protocols(&tls.alpn);
+    Ok(config)
+}
+
+pub(crate) fn native_roots() -> io::Result<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+    for cert in load_native_certs()? {
+        roots.add(&Certificate(cert.0)).map_err(invalid)?;
+    }
+    Ok(roots)
+}
+
+fn load_roots(path: &Path) -> io::Result<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(path)? {
+        roots.add(&cert).map_err(invalid)?;
+    }
+    Ok(roots)
+}
+
+fn alpn_protocols(alpn: &[String]) -> Vec<Vec<u8>> {
+    alpn.iter().map(|p| p.as_bytes().to_vec()).collect()
+}
+
+fn invalid(err: impl Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use rcgen::{BasicConstraints, CertificateParams, DnType, IsCa, SanType};
+    use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+    use super::*;
+
+    fn write_cert(dir: &Path, name: &str, params: CertificateParams, ca: &rcgen::Certificate) {
+        let cert = rcgen::Certificate::from_params(params).unwrap();
+        fs::write(
+            dir.join(format!("{}.pem", name)),
+            cert.serialize_pem_with_signer(ca).unwrap(),
+        )
+        .unwrap();
+        fs::write(
+            dir.join(format!("{}.key", name)),
+            cert.serialize_private_key_pem(),
+        )
+        .unwrap();
+    }
+
+    async fn handshake(server: &ServerTls, client: &ClientTls) -> io::Result<Option<PeerIdentity>> {
+        let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+        let acceptor = TlsAcceptor::from(server.current());
+        let connector = TlsConnector::from(client.config());
+        let name = rustls::ServerName::try_from(client.server_name("127.0.0.1")).unwrap();
+
+        let (accepted, _) = tokio::join!(
+            acceptor.accept(server_io),
+            connector.connect(name, client_io)
+        );
+        Ok(accepted?
+            .get_ref()
+            .1
+            .peer_certificates()
+            .and_then(|certs| certs.first())
+            .and_then(PeerIdentity::from_certificate))
+    }
+
+    #[tokio::test]
+    async fn test_mutual_tls() {
+        let dir = std::env::temp_dir().join(format!("dubbo-tls-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut ca_params = CertificateParams::new(vec![]);
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        ca_params
+            .distinguished_name
+            .push(DnType::CommonName, "dubbo test ca");
+        let ca = rcgen::Certificate::from_params(ca_params).unwrap();
+        fs::write(dir.join("ca.pem"), ca.serialize_pem().unwrap()).unwrap();
+
+        write_cert(
+            &dir,
+            "provider",
+            CertificateParams::new(vec!["provider.dubbo".to_string()]),
+            &ca,
+        );
+        let mut consumer = CertificateParams::new(vec!["consumer.dubbo".to_string()]);
+        consumer
+            .distinguished_name
+            .push(DnType::CommonName, "consumer");
+        consumer
+            .subject_alt_names
+            .push(SanType::URI("spiffe://dubbo/consumer".to_string()));
+        write_cert(&dir, "consumer", consumer, &ca);
+
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+        let server = ServerTls::server(
+            &TlsConfig::default()
+                .cert(path("provider.pem"))
+                .key(path("provider.key"))
+                .ca(path("ca.pem")),
+        )
+        .unwrap();
+        let client = TlsConfig::default()
+            .ca(path("ca.pem"))
+            .server_name("provider.dubbo".to_string());
+
+        let mtls_client = ClientTls::new(
+            &client
+                .clone()
+                .cert(path("consumer.pem"))
+                .key(path("consumer.key")),
+        )
+        .unwrap();
+        let identity = handshake(&server, &mtls_client).await.unwrap().unwrap();
+        assert!(identity.subject.contains("CN=consumer"));
+        assert_eq!(identity.dns_names, vec!["consumer.dubbo".to_string()]);
+        assert_eq!(identity.uris, vec!["spiffe://dubbo/consumer".to_string()]);
+
+        // the provider requires a client certificate
+        let anonymous_client = ClientTls::new(&client).unwrap();
+        assert!(handshake(&server, &anonymous_client).await.is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -17,5 +17,6 @@
 
 pub mod boxed;
 pub mod boxed_clone;
+pub mod observed_body;
 pub mod tls;
 pub mod yaml_utils;
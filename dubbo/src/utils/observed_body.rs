@@ -0,0 +1,137 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Buf;
+use http::HeaderMap;
+use http_body::Body;
+use pin_project::{pin_project, pinned_drop};
+
+use crate::status::{Code, GRPC_STATUS};
+
+/// Notified about the progress of a response body.
+pub trait ResponseObserver {
+    fn on_data(&mut self, _len: usize) {}
+
+    /// Called exactly once, when the body ends or is dropped.
+    fn on_end(&mut self, code: Code);
+}
+
+/// Response body reporting its size and final grpc status to an observer.
+///
+/// The status is taken from the response headers (trailers only responses)
+/// or the trailers, a body dropped before its end is reported as cancelled.
+#[pin_project(PinnedDrop)]
+pub struct ObservedBody<B, O: ResponseObserver> {
+    #[pin]
+    inner: B,
+    observer: O,
+    code: Option<Code>,
+    data_end: bool,
+    ended: bool,
+}
+
+impl<B, O: ResponseObserver> ObservedBody<B, O> {
+    pub fn new(inner: B, observer: O, headers: &HeaderMap) -> Self {
+        ObservedBody {
+            inner,
+            observer,
+            code: grpc_status(headers),
+            data_end: false,
+            ended: false,
+        }
+    }
+}
+
+fn end<O: ResponseObserver>(observer: &mut O, ended: &mut bool, code: Code) {
+    if !*ended {
+        *ended = true;
+        observer.on_end(code);
+    }
+}
+
+impl<B, O> Body for ObservedBody<B, O>
+where
+    B: Body,
+    O: ResponseObserver,
+{
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.project();
+        let data = futures_util::ready!(this.inner.poll_data(cx));
+        match &data {
+            Some(Ok(data)) => this.observer.on_data(data.remaining()),
+            Some(Err(_)) => end(this.observer, this.ended, Code::Internal),
+            // trailers may never be polled, e.g. by `hyper::body::to_bytes`
+            None => *this.data_end = true,
+        }
+        Poll::Ready(data)
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+        let this = self.project();
+        let trailers = futures_util::ready!(this.inner.poll_trailers(cx));
+        let code = match &trailers {
+            Ok(Some(trailers)) => this.code.or(grpc_status(trailers)).unwrap_or(Code::Unknown),
+            Ok(None) => this.code.unwrap_or(Code::Ok),
+            Err(_) => Code::Internal,
+        };
+        end(this.observer, this.ended, code);
+        Poll::Ready(trailers)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[pinned_drop]
+impl<B, O: ResponseObserver> PinnedDrop for ObservedBody<B, O> {
+    fn drop(self: Pin<&mut Self>) {
+        let this = self.project();
+        let code = match this.code {
+            Some(code) => *code,
+            None if *this.data_end => Code::Ok,
+            None => Code::Cancelled,
+        };
+        end(this.observer, this.ended, code);
+    }
+}
+
+fn grpc_status(headers: &HeaderMap) -> Option<Code> {
+    headers
+        .get(GRPC_STATUS)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<i32>().ok())
+        .map(Code::from)
+}
@@ -32,7 +32,7 @@ where
         return Err(anyhow::anyhow!("path is not a file: {:?}", path));
     }
     let data = fs::read(path.as_path())?;
-    Ok(from_slice(&data).unwrap())
+    Ok(from_slice(&data)?)
 }
 
 // read value by a key like: logging.level
@@ -35,11 +35,34 @@ pub mod echo_client {
                 inner: TripleClient::new(builder),
             }
         }
+        pub fn from_reference(name: &str) -> Result<Self, dubbo::config::consumer::ReferenceError> {
+            Ok(Self::new(ClientBuilder::from_reference(name)?))
+        }
+        /// Run `interceptor` on the metadata of each call, it may add
+        /// headers or reject the call.
+        pub fn with_interceptor<F>(self, interceptor: F) -> Self
+        where
+            F: Filter + Send + 'static,
+        {
+            Self {
+                inner: self.inner.with_interceptor(interceptor),
+            }
+        }
         /// UnaryEcho is unary echo.
         pub async fn unary_echo(
             &mut self,
             request: Request<super::EchoRequest>,
         ) -> Result<Response<super::EchoResponse>, dubbo::status::Status> {
+            self.unary_echo_with_options(request, CallOptions::default())
+                .await
+        }
+        ///Like [`Self::unary_echo`], with the options of this call.
+        pub async fn unary_echo_with_options(
+            &mut self,
+            mut request: Request<super::EchoRequest>,
+            options: CallOptions,
+        ) -> Result<Response<super::EchoResponse>, dubbo::status::Status> {
+            request.extensions_mut().insert(options);
             let invocation = RpcInvocation::default()
                 .with_service_unique_name(String::from("grpc.examples.echo.Echo"))
                 .with_method_name(String::from("UnaryEcho"));
@@ -51,6 +74,16 @@ pub mod echo_client {
             &mut self,
             request: Request<super::EchoRequest>,
         ) -> Result<Response<Decoding<super::EchoResponse>>, dubbo::status::Status> {
+            self.server_streaming_echo_with_options(request, CallOptions::default())
+                .await
+        }
+        ///Like [`Self::server_streaming_echo`], with the options of this call.
+        pub async fn server_streaming_echo_with_options(
+            &mut self,
+            mut request: Request<super::EchoRequest>,
+            options: CallOptions,
+        ) -> Result<Response<Decoding<super::EchoResponse>>, dubbo::status::Status> {
+            request.extensions_mut().insert(options);
             let invocation = RpcInvocation::default()
                 .with_service_unique_name(String::from("grpc.examples.echo.Echo"))
                 .with_method_name(String::from("ServerStreamingEcho"));
@@ -64,6 +97,17 @@ pub mod echo_client {
             &mut self,
             request: impl IntoStreamingRequest<Message = super::EchoRequest>,
         ) -> Result<Response<super::EchoResponse>, dubbo::status::Status> {
+            self.client_streaming_echo_with_options(request, CallOptions::default())
+                .await
+        }
+        ///Like [`Self::client_streaming_echo`], with the options of this call.
+        pub async fn client_streaming_echo_with_options(
+            &mut self,
+            request: impl IntoStreamingRequest<Message = super::EchoRequest>,
+            options: CallOptions,
+        ) -> Result<Response<super::EchoResponse>, dubbo::status::Status> {
+            let mut request = request.into_streaming_request();
+            request.extensions_mut().insert(options);
             let invocation = RpcInvocation::default()
                 .with_service_unique_name(String::from("grpc.examples.echo.Echo"))
                 .with_method_name(String::from("ClientStreamingEcho"));
@@ -77,6 +121,17 @@ pub mod echo_client {
             &mut self,
             request: impl IntoStreamingRequest<Message = super::EchoRequest>,
         ) -> Result<Response<Decoding<super::EchoResponse>>, dubbo::status::Status> {
+            self.bidirectional_streaming_echo_with_options(request, CallOptions::default())
+                .await
+        }
+        ///Like [`Self::bidirectional_streaming_echo`], with the options of this call.
+        pub async fn bidirectional_streaming_echo_with_options(
+            &mut self,
+            request: impl IntoStreamingRequest<Message = super::EchoRequest>,
+            options: CallOptions,
+        ) -> Result<Response<Decoding<super::EchoResponse>>, dubbo::status::Status> {
+            let mut request = request.into_streaming_request();
+            request.extensions_mut().insert(options);
             let invocation = RpcInvocation::default()
                 .with_service_unique_name(String::from("grpc.examples.echo.Echo"))
                 .with_method_name(String::from("BidirectionalStreamingEcho"));
@@ -299,4 +354,33 @@ pub mod echo_server {
                 dubbo::utils::boxed_clone::BoxCloneService::new(s),
             );
     }
+    /// Register `server` as the `version` of the service in `group`,
+    /// several of them can be exported on the same port.
+    pub fn register_versioned_server<T: Echo>(server: T, group: &str, version: &str) {
+        let s = EchoServer::new(server);
+        let key = dubbo::protocol::triple::service_key::ServiceKey::new("grpc.examples.echo.Echo")
+            .with_group(group)
+            .with_version(version);
+        dubbo::protocol::triple::register_service(
+            &key,
+            dubbo::utils::boxed_clone::BoxCloneService::new(s),
+        );
+    }
+    /// Register `server` behind `interceptor`, run on the metadata of
+    /// each call before it reaches the service. It may add to the
+    /// extensions of the request or reject the call.
+    pub fn register_server_with_interceptor<T, F>(server: T, interceptor: F)
+    where
+        T: Echo,
+        F: Filter + Clone + Send + Sync + 'static,
+    {
+        let s = EchoServer::with_filter(server, interceptor);
+        dubbo::protocol::triple::TRIPLE_SERVICES
+            .write()
+            .unwrap()
+            .insert(
+                "grpc.examples.echo.Echo".to_string(),
+                dubbo::utils::boxed_clone::BoxCloneService::new(s),
+            );
+    }
 }
@@ -35,7 +35,11 @@ async fn main() {
         .register::<RegistryExtension<NacosRegistry>>()
         .await;
 
-    let builder = ClientBuilder::new().with_registry("nacos://127.0.0.1:8848".parse().unwrap());
+    // group and version of the provider, see application.yaml
+    let builder = ClientBuilder::new()
+        .with_registry("nacos://127.0.0.1:8848".parse().unwrap())
+        .with_group("test".to_string())
+        .with_version("1.0.0".to_string());
 
     let mut cli = GreeterClient::new(builder);
     let mut mtdata = Metadata::default();
@@ -207,3 +207,204 @@ fn match_for_io_error(err_status: &dubbo::status::Status) -> Option<&std::io::Er
         };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use dubbo::{
+        testing::{self, TestServer},
+        triple::transport::listener::mem_listener,
+    };
+    use hyper::{Body, Method, Request, Response, StatusCode};
+
+    use super::*;
+
+    /// Send `req` to `server` over a new http/1 or http/2 connection.
+    async fn send(server: &TestServer, http2: bool, req: Request<Body>) -> Response<Body> {
+        let io = mem_listener::connect(&server.addr().to_string()).unwrap();
+        let (mut sender, conn) = hyper::client::conn::Builder::new()
+            .http2_only(http2)
+            .handshake(io)
+            .await
+            .unwrap();
+        tokio::spawn(conn);
+        sender.send_request(req).await.unwrap()
+    }
+
+    /// Post `body` to `method` of the greeter like `curl -d`, returning the
+    /// status and body of the response.
+    async fn post(
+        server: &TestServer,
+        http2: bool,
+        method: &str,
+        body: &'static str,
+    ) -> (StatusCode, String) {
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri(format!(
+                "http://{}/org.apache.dubbo.sample.tri.Greeter/{}",
+                server.addr(),
+                method
+            ))
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap();
+        let resp = send(server, http2, req).await;
+        let status = resp.status();
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[test]
+    fn test_http_json_calls() {
+        testing::block_on(async {
+            register_server(GreeterServerImpl::default());
+            let server =
+                testing::serve(vec!["org.apache.dubbo.sample.tri.Greeter".to_string()]).await;
+
+            for http2 in [false, true] {
+                let (status, body) = post(&server, http2, "greet", r#"{"name": "dubbo"}"#).await;
+                assert_eq!(status, StatusCode::OK);
+                assert!(body.contains("hello, dubbo-rust"), "{}", body);
+            }
+
+            let (status, body) = post(&server, false, "greet", "{").await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert!(body.contains(r#""code":"invalid_argument""#), "{}", body);
+
+            let (status, body) = post(&server, false, "unknown", "{}").await;
+            assert_eq!(status, StatusCode::NOT_IMPLEMENTED);
+            assert!(body.contains(r#""code":"unimplemented""#), "{}", body);
+
+            let (status, _) =
+                post(&server, false, "greetServerStream", r#"{"name": "dubbo"}"#).await;
+            assert_eq!(status, StatusCode::NOT_IMPLEMENTED);
+
+            let req = Request::get(format!(
+                "http://{}/org.apache.dubbo.sample.tri.Greeter/greet",
+                server.addr()
+            ))
+            .body(Body::empty())
+            .unwrap();
+            let resp = send(&server, false, req).await;
+            assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+        })
+    }
+
+    #[test]
+    fn test_in_memory_calls() {
+        use protos::greeter_client::GreeterClient;
+
+        const GREETER: &str = "org.apache.dubbo.sample.tri.Greeter";
+
+        testing::block_on(async {
+            register_server(GreeterServerImpl::default());
+            let server = testing::serve(vec![GREETER.to_string()]).await;
+            let mut cli = GreeterClient::new(server.client(GREETER));
+
+            let resp = cli
+                .greet(dubbo::invocation::Request::new(GreeterRequest {
+                    name: "dubbo".to_string(),
+                }))
+                .await
+                .unwrap();
+            assert_eq!(resp.into_parts().1.message, "hello, dubbo-rust");
+
+            let names = ["msg1", "msg2", "msg3"].map(|name| GreeterRequest {
+                name: name.to_string(),
+            });
+            let resp = cli
+                .greet_stream(futures_util::stream::iter(names))
+                .await
+                .unwrap();
+            let (_, mut body) = resp.into_parts();
+            let mut replies = Vec::new();
+            while let Some(reply) = body.next().await {
+                replies.push(reply.unwrap().message);
+            }
+            assert_eq!(
+                replies,
+                ["msg1", "msg2", "msg3"].map(|name| format!("server reply: {:?}", name))
+            );
+            assert!(body.trailer().await.unwrap().is_some());
+
+            // new connections are refused once the server is dropped
+            let url = server.url(GREETER);
+            drop(server);
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            let mut cli = GreeterClient::new(ClientBuilder::from_static(&url));
+            let resp = cli
+                .greet(dubbo::invocation::Request::new(GreeterRequest::default()))
+                .await;
+            assert!(resp.is_err());
+        })
+    }
+
+    #[test]
+    fn test_interceptors_and_call_options() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        use dubbo::{
+            codegen::CallOptions,
+            status::{Code, Status},
+            testing,
+        };
+        use protos::{
+            greeter_client::GreeterClient, greeter_server::register_server_with_interceptor,
+        };
+
+        const GREETER: &str = "org.apache.dubbo.sample.tri.Greeter";
+        type Req = dubbo::invocation::Request<GreeterRequest>;
+
+        testing::block_on(async {
+            // rejects the calls asking for it, letting the other tests through
+            register_server_with_interceptor(
+                GreeterServerImpl::default(),
+                |req: dubbo::invocation::Request<()>| match req.metadata.get("x-reject") {
+                    Some(_) => Err(Status::new(Code::PermissionDenied, "rejected".to_string())),
+                    None => Ok(req),
+                },
+            );
+            let server = testing::serve(vec![GREETER.to_string()]).await;
+
+            let calls = Arc::new(AtomicUsize::new(0));
+            let counter = calls.clone();
+            let mut cli = GreeterClient::new(server.client(GREETER)).with_interceptor(
+                move |req: dubbo::invocation::Request<()>| {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    Ok(req)
+                },
+            );
+            let resp = cli
+                .greet(Req::new(GreeterRequest::default()))
+                .await
+                .unwrap();
+            assert_eq!(resp.into_inner().message, "hello, dubbo-rust");
+
+            let options = CallOptions::new()
+                .compression(None)
+                .retries(0)
+                .attachment("x-reject", "true");
+            let err = cli
+                .greet_with_options(Req::new(GreeterRequest::default()), options)
+                .await
+                .err()
+                .unwrap();
+            assert_eq!(err.code(), Code::PermissionDenied);
+            assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+            let mut cli = cli.with_interceptor(|_: dubbo::invocation::Request<()>| {
+                Err(Status::new(Code::Unauthenticated, "no token".to_string()))
+            });
+            let err = cli
+                .greet(Req::new(GreeterRequest::default()))
+                .await
+                .err()
+                .unwrap();
+            assert_eq!(err.code(), Code::Unauthenticated);
+            assert_eq!(calls.load(Ordering::SeqCst), 3);
+        })
+    }
+}
@@ -26,9 +26,13 @@ use dubbo::{
         Extension,
     },
     logger::tracing::info,
-    params::registry_param::{
-        AppName, Category, Group, InterfaceName, RegistryUrl, ServiceNamespace, Version,
+    params::{
+        constants::PROVIDERS_KEY,
+        registry_param::{
+            AppName, Category, Group, InterfaceName, RegistryUrl, ServiceNamespace, Version,
+        },
     },
+    protocol::triple::service_key::ServiceKey,
 };
 use nacos_sdk::api::{
     naming::{NamingEventListener, NamingService, NamingServiceBuilder, ServiceInstance},
@@ -36,6 +40,9 @@ use nacos_sdk::api::{
 };
 use tokio::sync::{watch, Notify};
 
+/// Page size listing the services of a nacos group.
+const SERVICE_PAGE_SIZE: i32 = 100;
+
 pub struct NacosRegistry {
     url: Url,
     nacos_service: Arc<dyn NamingService + Send + Sync>,
@@ -242,6 +249,38 @@ impl Registry for NacosRegistry {
         Ok(())
     }
 
+    async fn services(&self) -> Result<Vec<String>, StdError> {
+        // the nacos group of the registry url, the default one otherwise
+        let group_name = self.url.query::<Group>().map(|group| group.value());
+
+        let mut services = Vec::new();
+        let mut page_no = 1;
+        loop {
+            let (names, count) = self
+                .nacos_service
+                .get_service_list(page_no, SERVICE_PAGE_SIZE, group_name.clone())
+                .await?;
+            if names.is_empty() {
+                break;
+            }
+            services.extend(
+                names
+                    .iter()
+                    .filter_map(|name| NacosServiceName::parse(name))
+                    // dubbo-rust providers register without a category
+                    .filter(|name| matches!(name.category(), "" | PROVIDERS_KEY))
+                    .map(|name| name.key().to_string()),
+            );
+            if page_no * SERVICE_PAGE_SIZE >= count {
+                break;
+            }
+            page_no += 1;
+        }
+        services.sort();
+        services.dedup();
+        Ok(services)
+    }
+
     fn url(&self) -> &Url {
         &self.url
     }
@@ -389,7 +428,28 @@ impl NacosServiceName {
         }
     }
 
-    #[allow(dead_code)]
+    /// Parse the `category:interface:version:group` name of a nacos service.
+    fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.splitn(4, ':');
+        let category = parts.next()?.to_string();
+        let interface = parts.next()?.to_string();
+        let version = parts.next()?.to_string();
+        let group = parts.next()?.to_string();
+        Some(Self {
+            category,
+            interface,
+            version,
+            group,
+            value: value.to_string(),
+        })
+    }
+
+    fn key(&self) -> ServiceKey {
+        ServiceKey::new(&self.interface)
+            .with_group(&self.group)
+            .with_version(&self.version)
+    }
+
     fn category(&self) -> &str {
         &self.category
     }
@@ -592,4 +652,17 @@ pub mod tests {
         let sleep_millis = time::Duration::from_secs(40);
         thread::sleep(sleep_millis);
     }
+
+    #[test]
+    fn test_parse_service_name() {
+        let name =
+            NacosServiceName::parse("providers:org.apache.dubbo.demo.GreeterService:1.0.0:test")
+                .unwrap();
+        assert_eq!(name.category(), PROVIDERS_KEY);
+        assert_eq!(
+            name.key().to_string(),
+            "test/org.apache.dubbo.demo.GreeterService:1.0.0"
+        );
+        assert!(NacosServiceName::parse("DEFAULT_GROUP@@service").is_none());
+    }
 }
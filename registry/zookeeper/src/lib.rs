@@ -392,6 +392,21 @@ impl Registry for ZookeeperRegistry {
         Ok(())
     }
 
+    async fn services(&self) -> Result<Vec<String>, StdError> {
+        let children = self
+            .zk_client
+            .get_children(&format!("/{}", DUBBO_KEY), false)?;
+        // beside the services, the root holds the config and metadata nodes
+        let mut services: Vec<String> = children
+            .into_iter()
+            .filter(|child| {
+                self.exists_path(&format!("/{}/{}/{}", DUBBO_KEY, child, PROVIDERS_KEY))
+            })
+            .collect();
+        services.sort();
+        Ok(services)
+    }
+
     fn url(&self) -> &Url {
         todo!()
     }